@@ -0,0 +1,114 @@
+//! Readers for the Token-2022 token accounts transferred through the
+//! transfer-tracking example program, without depending on the token program
+//! crate
+
+use {
+    crate::TOKEN_ACCOUNT_OWNER_OFFSET, solana_program_error::ProgramError, solana_pubkey::Pubkey,
+};
+
+/// Length of the base `Account` state
+const BASE_ACCOUNT_LENGTH: usize = 165;
+/// Length of a `Multisig`, which accounts with extensions are never sized to
+const MULTISIG_LENGTH: usize = 355;
+/// Account type of a token account, following the base state
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+/// Offset of the `state` of the base `Account`
+const ACCOUNT_STATE_OFFSET: usize = 108;
+/// `AccountState::Uninitialized`
+const ACCOUNT_STATE_UNINITIALIZED: u8 = 0;
+
+/// Reads the owner of a token account, with or without extensions, as
+/// `StateWithExtensions::<Account>::unpack` would.
+///
+/// The owner is what the transfer account is derived from, see
+/// [`transfer_tracking_extra_account_metas`](crate::transfer_tracking_extra_account_metas).
+pub fn get_token_account_owner(account_data: &[u8]) -> Result<Pubkey, ProgramError> {
+    if account_data.len() < BASE_ACCOUNT_LENGTH || account_data.len() == MULTISIG_LENGTH {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if account_data.len() > BASE_ACCOUNT_LENGTH
+        && account_data[BASE_ACCOUNT_LENGTH] != ACCOUNT_TYPE_ACCOUNT
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if account_data[ACCOUNT_STATE_OFFSET] == ACCOUNT_STATE_UNINITIALIZED {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let offset = TOKEN_ACCOUNT_OWNER_OFFSET as usize;
+    Pubkey::try_from(&account_data[offset..offset + 32])
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_program::program_pack::Pack,
+        spl_token_2022::{
+            extension::{
+                immutable_owner::ImmutableOwner, transfer_hook::TransferHookAccount,
+                BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType,
+                StateWithExtensions, StateWithExtensionsMut,
+            },
+            state::{Account, AccountState},
+        },
+    };
+
+    fn account(owner: &Pubkey) -> Account {
+        Account {
+            mint: solana_program::pubkey::Pubkey::new_unique(),
+            owner: solana_program::pubkey::Pubkey::new_from_array(owner.to_bytes()),
+            amount: 10,
+            state: AccountState::Initialized,
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn read_owner_of_plain_account() {
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0; Account::LEN];
+        account(&owner).pack_into_slice(&mut data);
+        assert_eq!(get_token_account_owner(&data), Ok(owner));
+
+        assert_eq!(
+            get_token_account_owner(&data[..Account::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        Account::default().pack_into_slice(&mut data);
+        assert_eq!(
+            get_token_account_owner(&data),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn read_owner_of_account_with_extensions() {
+        let owner = Pubkey::new_unique();
+        let account_size = ExtensionType::try_calculate_account_len::<Account>(&[
+            ExtensionType::ImmutableOwner,
+            ExtensionType::TransferHookAccount,
+        ])
+        .unwrap();
+        let mut data = vec![0; account_size];
+        let mut state = StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<ImmutableOwner>(true).unwrap();
+        state.init_extension::<TransferHookAccount>(true).unwrap();
+        state.base = account(&owner);
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        assert_eq!(get_token_account_owner(&data), Ok(owner));
+        // same owner as the token program reads
+        let state = StateWithExtensions::<Account>::unpack(&data).unwrap();
+        assert_eq!(state.base.owner.to_bytes(), owner.to_bytes());
+        assert!(state.get_extension::<TransferHookAccount>().is_ok());
+
+        // a mint with extensions isn't a token account
+        data[Account::LEN] = 1;
+        assert_eq!(
+            get_token_account_owner(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
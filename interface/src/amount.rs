@@ -0,0 +1,100 @@
+//! Conversion of human-readable token amounts to the base units stored in
+//! the limits of the transfer-tracking example program
+
+use solana_program_error::ProgramError;
+
+/// Offset of `decimals` in the base `Mint` state: mint authority (36) and
+/// supply (8)
+const MINT_DECIMALS_OFFSET: usize = 36 + 8;
+
+/// Reads the decimals from the data of a `Mint` account, with or without
+/// extensions.
+pub fn mint_decimals(mint_data: &[u8]) -> Result<u8, ProgramError> {
+    mint_data
+        .get(MINT_DECIMALS_OFFSET)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Converts a human-readable amount, such as `"12.5"`, to base units for a
+/// mint with the given decimals.
+///
+/// The conversion is exact: amounts with more fractional digits than the
+/// mint's decimals, or that overflow a `u64`, are rejected.
+pub fn ui_amount_to_base_units(ui_amount: &str, decimals: u8) -> Result<u64, ProgramError> {
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+        || fraction.len() > decimals as usize
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let digits = format!(
+        "{}{}{}",
+        whole,
+        fraction,
+        "0".repeat(decimals as usize - fraction.len())
+    );
+    digits.parse().map_err(|_| ProgramError::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_ui_amounts() {
+        for (ui_amount, decimals, base_units) in [
+            ("1", 0, 1),
+            ("1", 6, 1_000_000),
+            ("12.5", 2, 1_250),
+            ("0.000001", 6, 1),
+            (".5", 1, 5),
+            ("7.", 3, 7_000),
+            ("0", 9, 0),
+            ("18446744073709551615", 0, u64::MAX),
+            ("18446744073.709551615", 9, u64::MAX),
+        ] {
+            assert_eq!(
+                ui_amount_to_base_units(ui_amount, decimals).unwrap(),
+                base_units,
+                "{ui_amount} with {decimals} decimals"
+            );
+        }
+    }
+
+    #[test]
+    fn reject_invalid_ui_amounts() {
+        for (ui_amount, decimals) in [
+            ("", 2),
+            (".", 2),
+            ("1.234", 2),
+            ("1.5", 0),
+            ("-1", 2),
+            ("1e6", 2),
+            ("1,5", 2),
+            ("18446744073709551616", 0),
+            ("18446744073.709551616", 9),
+        ] {
+            assert_eq!(
+                ui_amount_to_base_units(ui_amount, decimals).unwrap_err(),
+                ProgramError::InvalidArgument,
+                "{ui_amount} with {decimals} decimals"
+            );
+        }
+    }
+
+    #[test]
+    fn read_mint_decimals() {
+        let mut mint_data = vec![0; 82];
+        mint_data[MINT_DECIMALS_OFFSET] = 9;
+        assert_eq!(mint_decimals(&mint_data).unwrap(), 9);
+        assert_eq!(
+            mint_decimals(&mint_data[..MINT_DECIMALS_OFFSET]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+}
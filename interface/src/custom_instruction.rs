@@ -0,0 +1,817 @@
+//! Custom instructions of the transfer-tracking example program, on top of
+//! the transfer-hook interface instructions
+
+use {
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+    spl_pod::bytemuck::{pod_slice_from_bytes, pod_slice_to_bytes},
+    spl_tlv_account_resolution::account::ExtraAccountMeta,
+};
+
+/// Custom instruction discriminators
+///
+/// These are a single byte, and must not collide with the first byte of any
+/// of the 8-byte transfer-hook interface discriminators.
+pub mod instruction_discriminator {
+    /// Initialize transfer account (custom instruction)
+    pub const INITIALIZE_TRANSFER_ACCOUNT: u8 = 255;
+    /// Initialize the recent transfer log of a mint (custom instruction)
+    pub const INITIALIZE_RECENT_TRANSFER_LOG: u8 = 254;
+    /// Initialize the config of a mint (custom instruction)
+    pub const INITIALIZE_MINT_CONFIG: u8 = 253;
+    /// Overwrite the config of a mint (custom instruction)
+    pub const UPDATE_MINT_CONFIG: u8 = 252;
+    /// Freeze a token account and blocklist its owner (custom instruction)
+    pub const FREEZE_OWNER: u8 = 251;
+    /// Set the label of a transfer account (custom instruction)
+    pub const SET_LABEL: u8 = 250;
+    /// Add an owner to the exempt owners of a mint (custom instruction)
+    pub const ADD_EXEMPT_OWNER: u8 = 249;
+    /// Remove an owner from the exempt owners of a mint (custom instruction)
+    pub const REMOVE_EXEMPT_OWNER: u8 = 248;
+    /// Report the setup status of a mint (custom instruction)
+    pub const HEALTH_CHECK: u8 = 247;
+    /// Move the tracked stats of a transfer account to a new owner (custom
+    /// instruction)
+    pub const REASSIGN_TRANSFER_ACCOUNT: u8 = 246;
+    /// Initialize the extra account metas and the config of a mint at once
+    /// (custom instruction)
+    pub const SETUP_MINT: u8 = 245;
+    /// Set whether an owner rejects incoming transfers (custom instruction)
+    pub const SET_INCOMING_POLICY: u8 = 244;
+    /// Initialize the transfer statistics of a mint (custom instruction)
+    pub const INITIALIZE_MINT_STATS: u8 = 243;
+    /// Initialize the fee ledger of a category (custom instruction)
+    pub const INITIALIZE_FEE_LEDGER: u8 = 242;
+    /// Report the totals of several transfer accounts (custom instruction)
+    pub const READ_TOTALS: u8 = 241;
+    /// Re-initialize the validation account of a mint with new extra account
+    /// metas (custom instruction)
+    pub const REPLACE_EXTRA_ACCOUNT_META_LIST: u8 = 240;
+    /// Migrate a mint config to the current layout version (custom
+    /// instruction)
+    pub const MIGRATE_MINT_CONFIG: u8 = 239;
+    /// Set or top up the transfer allowance of an owner (custom instruction)
+    pub const SET_ALLOWANCE: u8 = 238;
+    /// Snapshot the stats of a mint into its stats history (custom
+    /// instruction)
+    pub const SNAPSHOT_STATS: u8 = 237;
+    /// Initialize the burn ledger of an owner (custom instruction)
+    pub const INITIALIZE_BURN_LEDGER: u8 = 236;
+    /// Burn the amount owed by an owner (custom instruction)
+    pub const SETTLE_BURN: u8 = 235;
+    /// Add an owner to the allowlist of a mint (custom instruction)
+    pub const ADD_ALLOWLISTED_OWNER: u8 = 234;
+    /// Remove an owner from the allowlist of a mint (custom instruction)
+    pub const REMOVE_ALLOWLISTED_OWNER: u8 = 233;
+    /// Reset a transfer account to the current layout (custom instruction)
+    pub const REINITIALIZE_TRANSFER_ACCOUNT: u8 = 232;
+    /// Reset the totals of several transfer accounts (custom instruction)
+    pub const RESET_ACCOUNTS_BATCH: u8 = 231;
+    /// Report the config of a mint (custom instruction)
+    pub const GET_MINT_CONFIG: u8 = 230;
+    /// Let anyone transfer, not only the distributor of a mint (custom
+    /// instruction)
+    pub const ENABLE_TRADING: u8 = 229;
+    /// Schedule a pause of the transfers of a mint (custom instruction)
+    pub const SCHEDULE_PAUSE: u8 = 228;
+    /// Cancel the scheduled pause of a mint (custom instruction)
+    pub const CANCEL_PAUSE: u8 = 227;
+    /// Initialize the transfer account of the owner of a destination token
+    /// account (custom instruction)
+    pub const INITIALIZE_DESTINATION_ACCOUNT: u8 = 226;
+    /// Set the vesting schedule of an owner (custom instruction)
+    pub const SET_VESTING_SCHEDULE: u8 = 225;
+    /// Close the config, stats and validation account of a mint (custom
+    /// instruction)
+    pub const TEARDOWN_MINT: u8 = 224;
+    /// Withdraw lamports from the treasury of a mint (custom instruction)
+    pub const WITHDRAW_TREASURY: u8 = 223;
+    /// Set whether an owner can only receive transfers (custom instruction)
+    pub const SET_RECEIVE_ONLY: u8 = 222;
+    /// Verify the extra account meta list of a mint against the expected
+    /// template, optionally repairing it (custom instruction)
+    pub const VERIFY_EXTRA_ACCOUNT_META_LIST: u8 = 221;
+    /// Mark the setup of a mint as complete, accepting its transfers (custom
+    /// instruction)
+    pub const FINALIZE_SETUP: u8 = 220;
+    /// Initialize the running Merkle root of the transfers of a mint (custom
+    /// instruction)
+    pub const INITIALIZE_TRANSFER_ROOT: u8 = 219;
+    /// Migrate a transfer account to the current layout (custom instruction)
+    pub const MIGRATE_TRANSFER_ACCOUNT: u8 = 218;
+}
+
+/// Length of a transfer account label
+pub const LABEL_LEN: usize = 16;
+
+/// Length of the reason of a scheduled pause
+pub const PAUSE_REASON_LEN: usize = 32;
+
+/// Custom instructions supported by the transfer-tracking example program
+///
+/// Each is packed as its single-byte discriminator followed by its data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CustomInstruction {
+    /// Initialize the transfer account of an owner
+    InitializeTransferAccount,
+    /// Initialize the recent transfer log of a mint
+    InitializeRecentTransferLog,
+    /// Initialize the config of a mint
+    InitializeMintConfig,
+    /// Overwrite the config of a mint
+    UpdateMintConfig {
+        /// The config, packed by the program's `MintConfig::pack`
+        config: Vec<u8>,
+    },
+    /// Freeze a token account and blocklist its owner
+    FreezeOwner,
+    /// Set the label of a transfer account
+    SetLabel {
+        /// The new label
+        label: [u8; LABEL_LEN],
+    },
+    /// Add an owner to the exempt owners of a mint
+    AddExemptOwner {
+        /// The owner to exempt
+        owner: Pubkey,
+    },
+    /// Remove an owner from the exempt owners of a mint
+    RemoveExemptOwner {
+        /// The owner no longer exempt
+        owner: Pubkey,
+    },
+    /// Report the setup status of a mint
+    HealthCheck,
+    /// Move the tracked stats of a transfer account to a new owner
+    ReassignTransferAccount,
+    /// Initialize the extra account metas and the config of a mint at once
+    ///
+    /// Packed as a little-endian `u32` count of metas, the metas, then the
+    /// config.
+    SetupMint {
+        /// The extra account metas of the validation account
+        extra_account_metas: Vec<ExtraAccountMeta>,
+        /// The config, packed by the program's `MintConfig::pack`
+        config: Vec<u8>,
+    },
+    /// Set whether an owner rejects incoming transfers
+    SetIncomingPolicy {
+        /// Whether incoming transfers are rejected
+        reject_incoming: bool,
+    },
+    /// Initialize the transfer statistics of a mint
+    InitializeMintStats,
+    /// Initialize the fee ledger of a category
+    InitializeFeeLedger {
+        /// Category of the ledger
+        category: u8,
+    },
+    /// Report the totals of several transfer accounts
+    ReadTotals,
+    /// Re-initialize the validation account of a mint with new extra account
+    /// metas
+    ///
+    /// Packed as a little-endian `u32` count of metas, then the metas.
+    ReplaceExtraAccountMetaList {
+        /// The new extra account metas of the validation account
+        extra_account_metas: Vec<ExtraAccountMeta>,
+    },
+    /// Migrate a mint config to the current layout version
+    MigrateMintConfig,
+    /// Set or top up the transfer allowance of an owner
+    ///
+    /// Packed as a little-endian `u64` amount, then the `top_up` flag.
+    SetAllowance {
+        /// New allowance, or amount added to the remaining one
+        amount: u64,
+        /// Whether the amount is added to the remaining allowance instead of
+        /// replacing it
+        top_up: bool,
+    },
+    /// Snapshot the stats of a mint into its stats history
+    SnapshotStats,
+    /// Initialize the burn ledger of an owner
+    InitializeBurnLedger,
+    /// Burn the amount owed by an owner
+    SettleBurn,
+    /// Add an owner to the allowlist page of a mint it falls on
+    AddAllowlistedOwner {
+        /// The owner to allow
+        owner: Pubkey,
+        /// Unix timestamp the entry expires at, 0 if it never expires
+        expires_at: i64,
+    },
+    /// Remove an owner from the allowlist page of a mint it falls on
+    RemoveAllowlistedOwner {
+        /// The owner no longer allowed
+        owner: Pubkey,
+    },
+    /// Reset a corrupted transfer account to the current layout, dropping its
+    /// data
+    ReinitializeTransferAccount,
+    /// Reset the totals of several transfer accounts, e.g. for a new season
+    ResetAccountsBatch {
+        /// Number of transfer accounts, passed before the config signers
+        count: u8,
+    },
+    /// Report the config of a mint
+    GetMintConfig,
+    /// Let anyone transfer, not only the distributor of a mint
+    EnableTrading,
+    /// Schedule a pause of the transfers of a mint
+    SchedulePause {
+        /// Slot from which transfers are paused
+        slot: u64,
+        /// Reason of the pause, logged by failing transfers
+        reason: [u8; PAUSE_REASON_LEN],
+    },
+    /// Cancel the scheduled pause of a mint, or lift it once effective
+    CancelPause,
+    /// Initialize the transfer account of the owner of a destination token
+    /// account, paid by anyone
+    InitializeDestinationAccount,
+    /// Set the vesting schedule of an owner, locking the tokens not vested
+    /// yet
+    SetVestingSchedule {
+        /// Amount vested over the whole schedule
+        total: u64,
+        /// Unix timestamp from which the amount vests linearly
+        start_timestamp: i64,
+        /// Unix timestamp before which nothing is vested
+        cliff_timestamp: i64,
+        /// Seconds over which the amount vests from the start
+        duration_seconds: u64,
+    },
+    /// Close every account of a mint, draining their rent to a recipient
+    ///
+    /// Packed as a `u8` count of allowlist pages, the pages, then a `u8` count
+    /// of fee categories and the categories.
+    TeardownMint {
+        /// Allowlist pages of the mint to close
+        allowlist_pages: Vec<u8>,
+        /// Categories of the fee ledgers of the mint to close
+        fee_categories: Vec<u8>,
+    },
+    /// Withdraw lamports from the treasury of a mint, keeping it rent exempt
+    WithdrawTreasury {
+        /// Lamports to withdraw
+        amount: u64,
+    },
+    /// Set whether an owner can only receive transfers, as a lockbox
+    SetReceiveOnly {
+        /// Whether outbound transfers of the owner are rejected
+        receive_only: bool,
+    },
+    /// Verify the extra account meta list of a mint against the template the
+    /// program expects, reporting the differences as return data
+    VerifyExtraAccountMetaList {
+        /// Whether the list is repaired to match the template, which the mint
+        /// authority must sign for
+        repair: bool,
+    },
+    /// Mark the setup of a mint as complete, ending the grace period during
+    /// which its transfers are rejected
+    FinalizeSetup,
+    /// Initialize the running Merkle root of the transfers of a mint
+    InitializeTransferRoot,
+    /// Migrate a transfer account written with an older layout to the
+    /// current one
+    MigrateTransferAccount,
+}
+
+impl CustomInstruction {
+    /// Unpacks a byte buffer into a [`CustomInstruction`], returning `None`
+    /// if the first byte is not a custom instruction discriminator.
+    pub fn unpack(input: &[u8]) -> Result<Option<Self>, ProgramError> {
+        use instruction_discriminator::*;
+        let Some((&discriminator, rest)) = input.split_first() else {
+            return Ok(None);
+        };
+        let instruction = match discriminator {
+            INITIALIZE_TRANSFER_ACCOUNT => Self::InitializeTransferAccount,
+            INITIALIZE_RECENT_TRANSFER_LOG => Self::InitializeRecentTransferLog,
+            INITIALIZE_MINT_CONFIG => Self::InitializeMintConfig,
+            UPDATE_MINT_CONFIG => Self::UpdateMintConfig {
+                config: rest.to_vec(),
+            },
+            FREEZE_OWNER => Self::FreezeOwner,
+            SET_LABEL => Self::SetLabel {
+                label: rest
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?,
+            },
+            ADD_EXEMPT_OWNER => Self::AddExemptOwner {
+                owner: unpack_pubkey(rest)?,
+            },
+            REMOVE_EXEMPT_OWNER => Self::RemoveExemptOwner {
+                owner: unpack_pubkey(rest)?,
+            },
+            HEALTH_CHECK => Self::HealthCheck,
+            REASSIGN_TRANSFER_ACCOUNT => Self::ReassignTransferAccount,
+            SETUP_MINT => {
+                let (extra_account_metas, config) = unpack_extra_account_metas(rest)?;
+                Self::SetupMint {
+                    extra_account_metas,
+                    config: config.to_vec(),
+                }
+            }
+            SET_INCOMING_POLICY => Self::SetIncomingPolicy {
+                reject_incoming: match rest {
+                    [0] => false,
+                    [1] => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                },
+            },
+            INITIALIZE_MINT_STATS => Self::InitializeMintStats,
+            INITIALIZE_FEE_LEDGER => Self::InitializeFeeLedger {
+                category: match rest {
+                    [category] => *category,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                },
+            },
+            READ_TOTALS => Self::ReadTotals,
+            REPLACE_EXTRA_ACCOUNT_META_LIST => match unpack_extra_account_metas(rest)? {
+                (extra_account_metas, []) => Self::ReplaceExtraAccountMetaList {
+                    extra_account_metas,
+                },
+                _ => return Err(ProgramError::InvalidInstructionData),
+            },
+            MIGRATE_MINT_CONFIG => Self::MigrateMintConfig,
+            SET_ALLOWANCE => match rest {
+                [amount @ .., top_up] if amount.len() == 8 => Self::SetAllowance {
+                    amount: u64::from_le_bytes(
+                        amount
+                            .try_into()
+                            .map_err(|_| ProgramError::InvalidInstructionData)?,
+                    ),
+                    top_up: match top_up {
+                        0 => false,
+                        1 => true,
+                        _ => return Err(ProgramError::InvalidInstructionData),
+                    },
+                },
+                _ => return Err(ProgramError::InvalidInstructionData),
+            },
+            SNAPSHOT_STATS => Self::SnapshotStats,
+            INITIALIZE_BURN_LEDGER => Self::InitializeBurnLedger,
+            SETTLE_BURN => Self::SettleBurn,
+            ADD_ALLOWLISTED_OWNER => {
+                if rest.len() != 40 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::AddAllowlistedOwner {
+                    owner: unpack_pubkey(&rest[..32])?,
+                    expires_at: i64::from_le_bytes(unpack_word(&rest[32..], 0)?),
+                }
+            }
+            REMOVE_ALLOWLISTED_OWNER => Self::RemoveAllowlistedOwner {
+                owner: unpack_pubkey(rest)?,
+            },
+            REINITIALIZE_TRANSFER_ACCOUNT => Self::ReinitializeTransferAccount,
+            RESET_ACCOUNTS_BATCH => Self::ResetAccountsBatch {
+                count: match rest {
+                    [count] => *count,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                },
+            },
+            GET_MINT_CONFIG => Self::GetMintConfig,
+            ENABLE_TRADING => Self::EnableTrading,
+            SCHEDULE_PAUSE => {
+                let (slot, reason) = rest
+                    .split_at_checked(8)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                Self::SchedulePause {
+                    slot: u64::from_le_bytes(
+                        slot.try_into()
+                            .map_err(|_| ProgramError::InvalidInstructionData)?,
+                    ),
+                    reason: reason
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                }
+            }
+            CANCEL_PAUSE => Self::CancelPause,
+            INITIALIZE_DESTINATION_ACCOUNT => Self::InitializeDestinationAccount,
+            SET_VESTING_SCHEDULE => {
+                if rest.len() != 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::SetVestingSchedule {
+                    total: u64::from_le_bytes(unpack_word(rest, 0)?),
+                    start_timestamp: i64::from_le_bytes(unpack_word(rest, 1)?),
+                    cliff_timestamp: i64::from_le_bytes(unpack_word(rest, 2)?),
+                    duration_seconds: u64::from_le_bytes(unpack_word(rest, 3)?),
+                }
+            }
+            TEARDOWN_MINT => {
+                let (allowlist_pages, rest) = unpack_byte_list(rest)?;
+                let (fee_categories, rest) = unpack_byte_list(rest)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::TeardownMint {
+                    allowlist_pages: allowlist_pages.to_vec(),
+                    fee_categories: fee_categories.to_vec(),
+                }
+            }
+            WITHDRAW_TREASURY => {
+                if rest.len() != 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::WithdrawTreasury {
+                    amount: u64::from_le_bytes(unpack_word(rest, 0)?),
+                }
+            }
+            SET_RECEIVE_ONLY => Self::SetReceiveOnly {
+                receive_only: match rest {
+                    [0] => false,
+                    [1] => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                },
+            },
+            VERIFY_EXTRA_ACCOUNT_META_LIST => Self::VerifyExtraAccountMetaList {
+                repair: match rest {
+                    [0] => false,
+                    [1] => true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                },
+            },
+            FINALIZE_SETUP => Self::FinalizeSetup,
+            INITIALIZE_TRANSFER_ROOT => Self::InitializeTransferRoot,
+            MIGRATE_TRANSFER_ACCOUNT => Self::MigrateTransferAccount,
+            _ => return Ok(None),
+        };
+        Ok(Some(instruction))
+    }
+
+    /// Packs a [`CustomInstruction`] into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        use instruction_discriminator::*;
+        match self {
+            Self::InitializeTransferAccount => vec![INITIALIZE_TRANSFER_ACCOUNT],
+            Self::InitializeRecentTransferLog => vec![INITIALIZE_RECENT_TRANSFER_LOG],
+            Self::InitializeMintConfig => vec![INITIALIZE_MINT_CONFIG],
+            Self::UpdateMintConfig { config } => [&[UPDATE_MINT_CONFIG], &config[..]].concat(),
+            Self::FreezeOwner => vec![FREEZE_OWNER],
+            Self::SetLabel { label } => [&[SET_LABEL], &label[..]].concat(),
+            Self::AddExemptOwner { owner } => [&[ADD_EXEMPT_OWNER], owner.as_ref()].concat(),
+            Self::RemoveExemptOwner { owner } => [&[REMOVE_EXEMPT_OWNER], owner.as_ref()].concat(),
+            Self::HealthCheck => vec![HEALTH_CHECK],
+            Self::ReassignTransferAccount => vec![REASSIGN_TRANSFER_ACCOUNT],
+            Self::SetupMint {
+                extra_account_metas,
+                config,
+            } => [
+                &[SETUP_MINT],
+                &pack_extra_account_metas(extra_account_metas)[..],
+                config,
+            ]
+            .concat(),
+            Self::SetIncomingPolicy { reject_incoming } => {
+                vec![SET_INCOMING_POLICY, (*reject_incoming).into()]
+            }
+            Self::InitializeMintStats => vec![INITIALIZE_MINT_STATS],
+            Self::InitializeFeeLedger { category } => vec![INITIALIZE_FEE_LEDGER, *category],
+            Self::ReadTotals => vec![READ_TOTALS],
+            Self::ReplaceExtraAccountMetaList {
+                extra_account_metas,
+            } => [
+                &[REPLACE_EXTRA_ACCOUNT_META_LIST],
+                &pack_extra_account_metas(extra_account_metas)[..],
+            ]
+            .concat(),
+            Self::MigrateMintConfig => vec![MIGRATE_MINT_CONFIG],
+            Self::SetAllowance { amount, top_up } => [
+                &[SET_ALLOWANCE][..],
+                &amount.to_le_bytes(),
+                &[(*top_up).into()],
+            ]
+            .concat(),
+            Self::SnapshotStats => vec![SNAPSHOT_STATS],
+            Self::InitializeBurnLedger => vec![INITIALIZE_BURN_LEDGER],
+            Self::SettleBurn => vec![SETTLE_BURN],
+            Self::AddAllowlistedOwner { owner, expires_at } => [
+                &[ADD_ALLOWLISTED_OWNER],
+                owner.as_ref(),
+                &expires_at.to_le_bytes(),
+            ]
+            .concat(),
+            Self::RemoveAllowlistedOwner { owner } => {
+                [&[REMOVE_ALLOWLISTED_OWNER], owner.as_ref()].concat()
+            }
+            Self::ReinitializeTransferAccount => vec![REINITIALIZE_TRANSFER_ACCOUNT],
+            Self::ResetAccountsBatch { count } => vec![RESET_ACCOUNTS_BATCH, *count],
+            Self::GetMintConfig => vec![GET_MINT_CONFIG],
+            Self::EnableTrading => vec![ENABLE_TRADING],
+            Self::SchedulePause { slot, reason } => {
+                [&[SCHEDULE_PAUSE][..], &slot.to_le_bytes(), &reason[..]].concat()
+            }
+            Self::CancelPause => vec![CANCEL_PAUSE],
+            Self::InitializeDestinationAccount => vec![INITIALIZE_DESTINATION_ACCOUNT],
+            Self::SetVestingSchedule {
+                total,
+                start_timestamp,
+                cliff_timestamp,
+                duration_seconds,
+            } => [
+                &[SET_VESTING_SCHEDULE][..],
+                &total.to_le_bytes(),
+                &start_timestamp.to_le_bytes(),
+                &cliff_timestamp.to_le_bytes(),
+                &duration_seconds.to_le_bytes(),
+            ]
+            .concat(),
+            Self::TeardownMint {
+                allowlist_pages,
+                fee_categories,
+            } => [
+                &[TEARDOWN_MINT, allowlist_pages.len() as u8][..],
+                allowlist_pages,
+                &[fee_categories.len() as u8],
+                fee_categories,
+            ]
+            .concat(),
+            Self::WithdrawTreasury { amount } => {
+                [&[WITHDRAW_TREASURY][..], &amount.to_le_bytes()].concat()
+            }
+            Self::SetReceiveOnly { receive_only } => {
+                vec![SET_RECEIVE_ONLY, (*receive_only).into()]
+            }
+            Self::VerifyExtraAccountMetaList { repair } => {
+                vec![VERIFY_EXTRA_ACCOUNT_META_LIST, (*repair).into()]
+            }
+            Self::FinalizeSetup => vec![FINALIZE_SETUP],
+            Self::InitializeTransferRoot => vec![INITIALIZE_TRANSFER_ROOT],
+            Self::MigrateTransferAccount => vec![MIGRATE_TRANSFER_ACCOUNT],
+        }
+    }
+}
+
+/// Packs the metas as a little-endian `u32` count followed by the metas
+fn pack_extra_account_metas(extra_account_metas: &[ExtraAccountMeta]) -> Vec<u8> {
+    [
+        &(extra_account_metas.len() as u32).to_le_bytes()[..],
+        pod_slice_to_bytes(extra_account_metas),
+    ]
+    .concat()
+}
+
+/// Unpacks the metas packed by [`pack_extra_account_metas`], returning the
+/// rest of the input
+fn unpack_extra_account_metas(
+    input: &[u8],
+) -> Result<(Vec<ExtraAccountMeta>, &[u8]), ProgramError> {
+    let (length, rest) = input
+        .split_first_chunk::<4>()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let metas_len = (u32::from_le_bytes(*length) as usize)
+        .checked_mul(std::mem::size_of::<ExtraAccountMeta>())
+        .filter(|metas_len| *metas_len <= rest.len())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let (metas, rest) = rest.split_at(metas_len);
+    Ok((pod_slice_from_bytes(metas)?.to_vec(), rest))
+}
+
+/// Unpacks a list of bytes prefixed by its `u8` length, returning the rest of
+/// the input
+fn unpack_byte_list(input: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    let (length, rest) = input
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if rest.len() < *length as usize {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(rest.split_at(*length as usize))
+}
+
+fn unpack_pubkey(input: &[u8]) -> Result<Pubkey, ProgramError> {
+    Pubkey::try_from(input).map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+/// Unpacks the 8-byte word at `index` of the input
+fn unpack_word(input: &[u8], index: usize) -> Result<[u8; 8], ProgramError> {
+    input
+        .get(index * 8..index * 8 + 8)
+        .and_then(|word| word.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let instructions = [
+            CustomInstruction::InitializeTransferAccount,
+            CustomInstruction::InitializeRecentTransferLog,
+            CustomInstruction::InitializeMintConfig,
+            CustomInstruction::UpdateMintConfig {
+                config: vec![1, 2, 3],
+            },
+            CustomInstruction::FreezeOwner,
+            CustomInstruction::SetLabel {
+                label: *b"savings\0\0\0\0\0\0\0\0\0",
+            },
+            CustomInstruction::AddExemptOwner {
+                owner: Pubkey::new_unique(),
+            },
+            CustomInstruction::RemoveExemptOwner {
+                owner: Pubkey::new_unique(),
+            },
+            CustomInstruction::HealthCheck,
+            CustomInstruction::ReassignTransferAccount,
+            CustomInstruction::SetupMint {
+                extra_account_metas: vec![],
+                config: vec![4, 5],
+            },
+            CustomInstruction::SetupMint {
+                extra_account_metas: vec![
+                    ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
+                    ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, false).unwrap(),
+                ],
+                config: vec![6],
+            },
+            CustomInstruction::SetIncomingPolicy {
+                reject_incoming: true,
+            },
+            CustomInstruction::SetIncomingPolicy {
+                reject_incoming: false,
+            },
+            CustomInstruction::InitializeMintStats,
+            CustomInstruction::InitializeFeeLedger { category: 3 },
+            CustomInstruction::ReadTotals,
+            CustomInstruction::ReplaceExtraAccountMetaList {
+                extra_account_metas: vec![ExtraAccountMeta::new_with_pubkey(
+                    &Pubkey::new_unique(),
+                    true,
+                    true,
+                )
+                .unwrap()],
+            },
+            CustomInstruction::MigrateMintConfig,
+            CustomInstruction::SetAllowance {
+                amount: 1_000,
+                top_up: false,
+            },
+            CustomInstruction::SetAllowance {
+                amount: u64::MAX,
+                top_up: true,
+            },
+            CustomInstruction::SnapshotStats,
+            CustomInstruction::InitializeBurnLedger,
+            CustomInstruction::SettleBurn,
+            CustomInstruction::AddAllowlistedOwner {
+                owner: Pubkey::new_unique(),
+                expires_at: 0,
+            },
+            CustomInstruction::AddAllowlistedOwner {
+                owner: Pubkey::new_unique(),
+                expires_at: 1_700_000_000,
+            },
+            CustomInstruction::RemoveAllowlistedOwner {
+                owner: Pubkey::new_unique(),
+            },
+            CustomInstruction::ReinitializeTransferAccount,
+            CustomInstruction::ResetAccountsBatch { count: 3 },
+            CustomInstruction::GetMintConfig,
+            CustomInstruction::EnableTrading,
+            CustomInstruction::SchedulePause {
+                slot: 1_000,
+                reason: [b'x'; PAUSE_REASON_LEN],
+            },
+            CustomInstruction::CancelPause,
+            CustomInstruction::InitializeDestinationAccount,
+            CustomInstruction::SetVestingSchedule {
+                total: 1_000,
+                start_timestamp: 1_700_000_000,
+                cliff_timestamp: 1_700_086_400,
+                duration_seconds: 31_536_000,
+            },
+            CustomInstruction::TeardownMint {
+                allowlist_pages: vec![],
+                fee_categories: vec![],
+            },
+            CustomInstruction::TeardownMint {
+                allowlist_pages: vec![0, 7, 255],
+                fee_categories: vec![3],
+            },
+            CustomInstruction::WithdrawTreasury { amount: 1_000_000 },
+            CustomInstruction::SetReceiveOnly { receive_only: true },
+            CustomInstruction::SetReceiveOnly {
+                receive_only: false,
+            },
+            CustomInstruction::VerifyExtraAccountMetaList { repair: true },
+            CustomInstruction::VerifyExtraAccountMetaList { repair: false },
+            CustomInstruction::FinalizeSetup,
+            CustomInstruction::InitializeTransferRoot,
+            CustomInstruction::MigrateTransferAccount,
+        ];
+        for instruction in instructions {
+            let packed = instruction.pack();
+            assert_eq!(
+                CustomInstruction::unpack(&packed).unwrap(),
+                Some(instruction)
+            );
+        }
+    }
+
+    #[test]
+    fn unpack_other_instructions() {
+        assert_eq!(CustomInstruction::unpack(&[]).unwrap(), None);
+        // first byte of the `Execute` discriminator
+        assert_eq!(CustomInstruction::unpack(&[105, 37]).unwrap(), None);
+    }
+
+    #[test]
+    fn unpack_invalid_data() {
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::SET_LABEL, 1]).unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::ADD_EXEMPT_OWNER; 32])
+                .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::SET_INCOMING_POLICY, 2])
+                .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::INITIALIZE_FEE_LEDGER])
+                .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        assert_eq!(
+            CustomInstruction::unpack(&[
+                instruction_discriminator::SET_ALLOWANCE,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                2
+            ])
+            .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        assert_eq!(
+            CustomInstruction::unpack(&[
+                instruction_discriminator::SET_ALLOWANCE,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                1
+            ])
+            .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        // trailing data after the metas
+        assert_eq!(
+            CustomInstruction::unpack(&[
+                instruction_discriminator::REPLACE_EXTRA_ACCOUNT_META_LIST,
+                0,
+                0,
+                0,
+                0,
+                1
+            ])
+            .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        // one meta announced, none provided
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::SETUP_MINT, 1, 0, 0, 0])
+                .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        // two allowlist pages announced, one provided
+        assert_eq!(
+            CustomInstruction::unpack(&[instruction_discriminator::TEARDOWN_MINT, 2, 0])
+                .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+        // schedule missing its duration
+        assert_eq!(
+            CustomInstruction::unpack(
+                &[
+                    &[instruction_discriminator::SET_VESTING_SCHEDULE][..],
+                    &[0; 24]
+                ]
+                .concat()
+            )
+            .unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+    }
+}
@@ -1,8 +1,18 @@
 //! Error types
 
-use solana_program_error::{ProgramError, ToStr};
+use {
+    solana_program_error::{ProgramError, ToStr},
+    std::ops::Range,
+};
+
+/// Codes reserved for the errors of the interface. New variants take the
+/// next free code in the range, and existing codes never change, since
+/// clients match on them.
+pub const TRANSFER_HOOK_ERROR_CODES: Range<u32> = 2_110_272_652..2_110_272_652 + 256;
 
 /// Errors that may be returned by the interface.
+///
+/// Every variant has an explicit code within [`TRANSFER_HOOK_ERROR_CODES`].
 #[repr(u32)]
 #[derive(Clone, Debug, Eq, thiserror::Error, num_derive::FromPrimitive, PartialEq)]
 pub enum TransferHookError {
@@ -11,13 +21,54 @@ pub enum TransferHookError {
     IncorrectAccount = 2_110_272_652,
     /// Mint has no mint authority
     #[error("Mint has no mint authority")]
-    MintHasNoMintAuthority,
+    MintHasNoMintAuthority = 2_110_272_653,
     /// Incorrect mint authority has signed the instruction
     #[error("Incorrect mint authority has signed the instruction")]
-    IncorrectMintAuthority,
+    IncorrectMintAuthority = 2_110_272_654,
     /// Program called outside of a token transfer
     #[error("Program called outside of a token transfer")]
-    ProgramCalledOutsideOfTransfer,
+    ProgramCalledOutsideOfTransfer = 2_110_272_655,
+    /// Extra account meta list does not fit in a validation account
+    #[error("Extra account meta list does not fit in a validation account")]
+    ExtraAccountMetaListTooLarge = 2_110_272_656,
+    /// Extra account meta duplicates an earlier one
+    #[error("Extra account meta duplicates an earlier one")]
+    DuplicateExtraAccountMeta = 2_110_272_657,
+    /// Extra account meta cannot be resolved for `Execute`
+    #[error("Extra account meta cannot be resolved for `Execute`")]
+    UnresolvableExtraAccountMeta = 2_110_272_658,
+    /// Too many extra account metas for the validation account
+    #[error("Too many extra account metas for the validation account")]
+    TooManyExtraAccountMetas = 2_110_272_659,
+}
+
+const _: () = {
+    let codes = [
+        TransferHookError::IncorrectAccount as u32,
+        TransferHookError::MintHasNoMintAuthority as u32,
+        TransferHookError::IncorrectMintAuthority as u32,
+        TransferHookError::ProgramCalledOutsideOfTransfer as u32,
+        TransferHookError::ExtraAccountMetaListTooLarge as u32,
+        TransferHookError::DuplicateExtraAccountMeta as u32,
+        TransferHookError::UnresolvableExtraAccountMeta as u32,
+        TransferHookError::TooManyExtraAccountMetas as u32,
+    ];
+    let mut i = 0;
+    while i < codes.len() {
+        assert!(
+            codes[i] >= TRANSFER_HOOK_ERROR_CODES.start && codes[i] < TRANSFER_HOOK_ERROR_CODES.end
+        );
+        i += 1;
+    }
+};
+
+/// Decode the interface error of a `ProgramError::Custom` code, if the code
+/// is one of the interface's.
+///
+/// The codes start at [`TRANSFER_HOOK_ERROR_CODES`] rather than zero, so the
+/// code must be decoded as is, not as an offset into the range.
+pub fn decode_error(code: u32) -> Option<TransferHookError> {
+    num_traits::FromPrimitive::from_u32(code)
 }
 
 impl From<TransferHookError> for ProgramError {
@@ -37,6 +88,63 @@ impl ToStr for TransferHookError {
             TransferHookError::ProgramCalledOutsideOfTransfer => {
                 "Program called outside of a token transfer"
             }
+            TransferHookError::ExtraAccountMetaListTooLarge => {
+                "Extra account meta list does not fit in a validation account"
+            }
+            TransferHookError::DuplicateExtraAccountMeta => {
+                "Extra account meta duplicates an earlier one"
+            }
+            TransferHookError::UnresolvableExtraAccountMeta => {
+                "Extra account meta cannot be resolved for `Execute`"
+            }
+            TransferHookError::TooManyExtraAccountMetas => {
+                "Too many extra account metas for the validation account"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_are_stable() {
+        for (error, code) in [
+            (TransferHookError::IncorrectAccount, 2_110_272_652),
+            (TransferHookError::MintHasNoMintAuthority, 2_110_272_653),
+            (TransferHookError::IncorrectMintAuthority, 2_110_272_654),
+            (
+                TransferHookError::ProgramCalledOutsideOfTransfer,
+                2_110_272_655,
+            ),
+            (
+                TransferHookError::ExtraAccountMetaListTooLarge,
+                2_110_272_656,
+            ),
+            (TransferHookError::DuplicateExtraAccountMeta, 2_110_272_657),
+            (
+                TransferHookError::UnresolvableExtraAccountMeta,
+                2_110_272_658,
+            ),
+            (TransferHookError::TooManyExtraAccountMetas, 2_110_272_659),
+        ] {
+            assert_eq!(error.clone() as u32, code);
+            assert_eq!(decode_error(code), Some(error.clone()));
+            assert_eq!(ProgramError::from(error), ProgramError::Custom(code));
+        }
+    }
+
+    #[test]
+    fn unknown_codes_are_not_decoded() {
+        for code in [
+            0,
+            TRANSFER_HOOK_ERROR_CODES.start - 1,
+            TRANSFER_HOOK_ERROR_CODES.start + 8,
+            TRANSFER_HOOK_ERROR_CODES.end,
+            u32::MAX,
+        ] {
+            assert_eq!(decode_error(code), None);
         }
     }
 }
@@ -0,0 +1,202 @@
+//! Events logged by the transfer-tracking example program, and their
+//! client-side parser
+
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_pubkey::Pubkey,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+};
+
+/// Prefix of the log lines written by `sol_log_data`
+const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Transfer accepted by `Execute`, logged with `sol_log_data` as a single
+/// slice: the discriminator followed by the borsh-serialized event
+#[derive(
+    BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq, SplDiscriminate,
+)]
+#[discriminator_hash_input("spl-transfer-hook-interface:transfer-event")]
+pub struct TransferEvent {
+    /// Mint of the transferred tokens
+    pub mint: Pubkey,
+    /// Owner of the source token account
+    pub owner: Pubkey,
+    /// Destination token account
+    pub destination: Pubkey,
+    /// Amount of tokens transferred
+    pub amount: u64,
+}
+
+impl TransferEvent {
+    /// Data to log for the event
+    pub fn to_log_data(&self) -> Vec<u8> {
+        let mut data = Self::SPL_DISCRIMINATOR_SLICE.to_vec();
+        // serializing into a vector can't fail
+        self.serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Decode the event from logged data, `None` if the data is not a
+    /// transfer event
+    pub fn from_log_data(data: &[u8]) -> Option<Self> {
+        let (discriminator, event) = data.split_at_checked(ArrayDiscriminator::LENGTH)?;
+        if discriminator != Self::SPL_DISCRIMINATOR_SLICE {
+            return None;
+        }
+        Self::try_from_slice(event).ok()
+    }
+}
+
+/// Owner and amount of one of the transfers of a [`BatchTransferEvent`]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchTransferEntry {
+    /// Owner of the source token account
+    pub owner: Pubkey,
+    /// Amount of tokens transferred
+    pub amount: u64,
+}
+
+/// Transfers of a mint accepted by a single instruction, logged with
+/// `sol_log_data` as a single slice like [`TransferEvent`], rather than as
+/// one event per transfer
+#[derive(
+    BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq, SplDiscriminate,
+)]
+#[discriminator_hash_input("spl-transfer-hook-interface:batch-transfer-event")]
+pub struct BatchTransferEvent {
+    /// Mint of the transferred tokens
+    pub mint: Pubkey,
+    /// Transfers of the batch, in the order they were accepted
+    pub entries: Vec<BatchTransferEntry>,
+}
+
+impl BatchTransferEvent {
+    /// Data to log for the event
+    pub fn to_log_data(&self) -> Vec<u8> {
+        let mut data = Self::SPL_DISCRIMINATOR_SLICE.to_vec();
+        // serializing into a vector can't fail
+        self.serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Decode the event from logged data, `None` if the data is not a batch
+    /// transfer event
+    pub fn from_log_data(data: &[u8]) -> Option<Self> {
+        let (discriminator, event) = data.split_at_checked(ArrayDiscriminator::LENGTH)?;
+        if discriminator != Self::SPL_DISCRIMINATOR_SLICE {
+            return None;
+        }
+        Self::try_from_slice(event).ok()
+    }
+}
+
+/// The data of the program data lines of the logs, each logged as a single
+/// slice. Lines that are not program data, or not valid base64, are skipped.
+fn program_data(logs: &[String]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix(PROGRAM_DATA_LOG_PREFIX))
+        .filter_map(|data| BASE64_STANDARD.decode(data).ok())
+}
+
+/// Parse the transfer events from the logs of a transaction.
+///
+/// Lines that are not program data, or whose data is not a transfer event,
+/// are skipped. The logs don't tell which program wrote a data line, so any
+/// program logging data with the transfer event discriminator is picked up.
+pub fn parse_transfer_events(logs: &[String]) -> Vec<TransferEvent> {
+    program_data(logs)
+        .filter_map(|data| TransferEvent::from_log_data(&data))
+        .collect()
+}
+
+/// Parse the batch transfer events from the logs of a transaction, skipping
+/// other lines as [`parse_transfer_events`] does
+pub fn parse_batch_transfer_events(logs: &[String]) -> Vec<BatchTransferEvent> {
+    program_data(logs)
+        .filter_map(|data| BatchTransferEvent::from_log_data(&data))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_line(data: &[u8]) -> String {
+        format!(
+            "{}{}",
+            PROGRAM_DATA_LOG_PREFIX,
+            BASE64_STANDARD.encode(data)
+        )
+    }
+
+    #[test]
+    fn parse_events_among_noise() {
+        let events = [1, 2].map(|amount| TransferEvent {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            amount,
+        });
+        let truncated = events[0].to_log_data();
+        let mut other_discriminator = events[1].to_log_data();
+        other_discriminator[0] ^= 1;
+        let logs = [
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            log_line(&events[0].to_log_data()),
+            "Program log: Instruction: Execute".to_string(),
+            format!("{}not base64!", PROGRAM_DATA_LOG_PREFIX),
+            log_line(&truncated[..truncated.len() - 1]),
+            log_line(&other_discriminator),
+            log_line(&[]),
+            // two slices
+            format!("{} {}", log_line(&events[1].to_log_data()), "AQ=="),
+            log_line(&events[1].to_log_data()),
+        ];
+        assert_eq!(parse_transfer_events(&logs), events);
+        assert_eq!(parse_transfer_events(&[]), vec![]);
+    }
+
+    #[test]
+    fn parse_batch_events() {
+        let event = BatchTransferEvent {
+            mint: Pubkey::new_unique(),
+            entries: [1, 2, 3]
+                .map(|amount| BatchTransferEntry {
+                    owner: Pubkey::new_unique(),
+                    amount,
+                })
+                .to_vec(),
+        };
+        let data = event.to_log_data();
+        assert_eq!(data.len(), ArrayDiscriminator::LENGTH + 32 + 4 + 3 * 40);
+        assert_eq!(
+            BatchTransferEvent::from_log_data(&data),
+            Some(event.clone())
+        );
+        let empty = BatchTransferEvent {
+            mint: event.mint,
+            entries: vec![],
+        };
+
+        // batch and single transfer events don't decode as each other
+        let single = TransferEvent {
+            mint: event.mint,
+            amount: 4,
+            ..TransferEvent::default()
+        };
+        assert_eq!(TransferEvent::from_log_data(&data), None);
+        assert_eq!(
+            BatchTransferEvent::from_log_data(&single.to_log_data()),
+            None
+        );
+        let logs = [
+            log_line(&data),
+            log_line(&single.to_log_data()),
+            log_line(&data[..data.len() - 1]),
+            log_line(&empty.to_log_data()),
+        ];
+        assert_eq!(parse_batch_transfer_events(&logs), vec![event, empty]);
+        assert_eq!(parse_transfer_events(&logs), vec![single]);
+    }
+}
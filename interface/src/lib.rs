@@ -7,14 +7,32 @@
 #![deny(missing_docs)]
 #![cfg_attr(not(test), forbid(unsafe_code))]
 
+pub mod account;
+pub mod amount;
+pub mod custom_instruction;
 pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod merkle;
+pub mod mint;
 pub mod offchain;
 pub mod onchain;
+pub mod pda;
+pub mod state;
 
 // Export current sdk types for downstream users building with a different sdk
 // version
-use solana_pubkey::Pubkey;
+use {
+    error::TransferHookError,
+    solana_account_info::MAX_PERMITTED_DATA_INCREASE,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+    spl_tlv_account_resolution::{
+        account::ExtraAccountMeta, pubkey_data::PubkeyData, seeds::Seed,
+        state::ExtraAccountMetaList,
+    },
+};
 pub use {
     solana_account_info, solana_cpi, solana_instruction, solana_msg, solana_program_error,
     solana_pubkey,
@@ -54,3 +72,324 @@ pub fn collect_extra_account_metas_signer_seeds<'a>(
 ) -> [&'a [u8]; 3] {
     [EXTRA_ACCOUNT_METAS_SEED, mint.as_ref(), bump_seed]
 }
+
+/// Offset of the owner in a token account
+const TOKEN_ACCOUNT_OWNER_OFFSET: u8 = 32;
+
+/// Extra account metas expected by the transfer-tracking example program,
+/// to be passed to `InitializeExtraAccountMetaList`.
+///
+/// The first meta is the transfer account of the source owner, derived from
+/// the owner field of the source token account, and from the mint as well
+/// with `per_mint_tracking`, matching the program's feature of the same name.
+/// Any other metas needed by the mint can be appended to the list.
+pub fn transfer_tracking_extra_account_metas(
+    per_mint_tracking: bool,
+) -> Result<Vec<ExtraAccountMeta>, ProgramError> {
+    let mut seeds = vec![Seed::AccountData {
+        account_index: 0,
+        data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+        length: 32,
+    }];
+    if per_mint_tracking {
+        seeds.push(Seed::AccountKey { index: 1 });
+    }
+    Ok(vec![ExtraAccountMeta::new_with_seeds(&seeds, false, true)?])
+}
+
+/// Number of accounts of an `Execute` instruction before its extra accounts:
+/// the source, mint, destination, authority and validation accounts
+const EXECUTE_ACCOUNTS_LEN: usize = 5;
+
+/// Length of the data of an `Execute` instruction: the discriminator and the
+/// amount
+const EXECUTE_DATA_LEN: usize = 16;
+
+/// Validate a proposed list of extra account metas before sending it in
+/// `InitializeExtraAccountMetaList`, returning the size of the validation
+/// account holding it, as `ExtraAccountMetaList::size_of`.
+///
+/// On top of the size, which must not exceed what a validation account may be
+/// allocated with in a single instruction, the list must not hold two metas
+/// with the same address config, and every meta must resolve for `Execute`:
+/// its seeds and pubkey data may only refer to the `Execute` accounts and the
+/// extra accounts before it, and to the `Execute` instruction data.
+pub fn validate_extra_account_metas(
+    extra_account_metas: &[ExtraAccountMeta],
+) -> Result<usize, ProgramError> {
+    let account_size = ExtraAccountMetaList::size_of(extra_account_metas.len())?;
+    if account_size > MAX_PERMITTED_DATA_INCREASE {
+        return Err(TransferHookError::ExtraAccountMetaListTooLarge.into());
+    }
+    for (i, meta) in extra_account_metas.iter().enumerate() {
+        if extra_account_metas[..i].iter().any(|other| {
+            other.discriminator == meta.discriminator && other.address_config == meta.address_config
+        }) {
+            return Err(TransferHookError::DuplicateExtraAccountMeta.into());
+        }
+        if !extra_account_meta_resolves(meta, EXECUTE_ACCOUNTS_LEN + i) {
+            return Err(TransferHookError::UnresolvableExtraAccountMeta.into());
+        }
+    }
+    Ok(account_size)
+}
+
+/// Whether an extra account meta only refers to the first `accounts_len`
+/// accounts and to the data of an `Execute` instruction
+fn extra_account_meta_resolves(meta: &ExtraAccountMeta, accounts_len: usize) -> bool {
+    let account_fits = |index: u8| (index as usize) < accounts_len;
+    let data_fits = |index: u8, length: u8| index as usize + length as usize <= EXECUTE_DATA_LEN;
+    match meta.discriminator {
+        0 => true,
+        discriminator if discriminator == 1 || discriminator >= 1 << 7 => {
+            if discriminator != 1 && !account_fits(discriminator - (1 << 7)) {
+                return false;
+            }
+            let Ok(seeds) = Seed::unpack_address_config(&meta.address_config) else {
+                return false;
+            };
+            !seeds.is_empty()
+                && seeds.iter().all(|seed| match seed {
+                    Seed::Uninitialized | Seed::Literal { .. } => true,
+                    Seed::InstructionData { index, length } => data_fits(*index, *length),
+                    Seed::AccountKey { index } => account_fits(*index),
+                    Seed::AccountData { account_index, .. } => account_fits(*account_index),
+                })
+        }
+        2 => match PubkeyData::unpack(&meta.address_config) {
+            Ok(PubkeyData::InstructionData { index }) => data_fits(index, 32),
+            Ok(PubkeyData::AccountData { account_index, .. }) => account_fits(account_index),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Tag of the Token-2022 `TransferChecked` instruction
+const TRANSFER_CHECKED_TAG: u8 = 12;
+
+/// Creates a Token-2022 `TransferChecked` instruction for a mint hooked into
+/// the transfer-tracking example program, with the accounts required by the
+/// hook appended: the transfer account of `source_owner`, the hook program
+/// and the validation account.
+///
+/// The transfer account is derived as described in
+/// `transfer_tracking_extra_account_metas`, so nothing needs to be fetched.
+/// Mints whose validation account holds more metas than the base list should
+/// use `offchain::add_extra_account_metas_for_execute` instead.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked_with_tracking(
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    per_mint_tracking: bool,
+) -> Instruction {
+    let mut data = vec![TRANSFER_CHECKED_TAG];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+
+    let (transfer_account, _) = pda::find_transfer_account_address(
+        source_owner,
+        mint_pubkey,
+        per_mint_tracking,
+        program_id,
+    );
+
+    let accounts = vec![
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, true),
+        AccountMeta::new(transfer_account, false),
+        AccountMeta::new_readonly(*program_id, false),
+        AccountMeta::new_readonly(
+            get_extra_account_metas_address(mint_pubkey, program_id),
+            false,
+        ),
+    ];
+    Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_tracking_meta_resolves_to_source_owner_pda() {
+        let program_id = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        // only the mint and owner fields of the token account matter
+        let mut source_data = vec![0; 165];
+        source_data[..32].copy_from_slice(mint.as_ref());
+        source_data[32..64].copy_from_slice(owner.as_ref());
+        let keys = [source, mint, Pubkey::new_unique(), authority];
+
+        for (per_mint_tracking, seeds) in [
+            (false, vec![owner.as_ref()]),
+            (true, vec![owner.as_ref(), mint.as_ref()]),
+        ] {
+            let metas = transfer_tracking_extra_account_metas(per_mint_tracking).unwrap();
+            assert_eq!(metas.len(), 1);
+            let resolved = metas[0]
+                .resolve(&[], &program_id, |index| {
+                    let data = (index == 0).then_some(source_data.as_slice());
+                    keys.get(index).map(|key| (key, data))
+                })
+                .unwrap();
+            let (expected, _) = Pubkey::find_program_address(&seeds, &program_id);
+            assert_eq!(resolved.pubkey, expected);
+            assert!(resolved.is_writable);
+            assert!(!resolved.is_signer);
+        }
+    }
+
+    #[test]
+    fn validate_extra_account_metas_checks_list() {
+        let mut metas = transfer_tracking_extra_account_metas(true).unwrap();
+        metas.push(ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap());
+        // an external PDA of the fixed program above, from the amount
+        metas.push(
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                6,
+                &[Seed::InstructionData {
+                    index: 8,
+                    length: 8,
+                }],
+                false,
+                false,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            validate_extra_account_metas(&metas),
+            Ok(ExtraAccountMetaList::size_of(3).unwrap())
+        );
+        assert_eq!(
+            validate_extra_account_metas(&[]),
+            Ok(ExtraAccountMetaList::size_of(0).unwrap())
+        );
+
+        // more metas than fit in a validation account
+        let oversized = (0..=MAX_PERMITTED_DATA_INCREASE / std::mem::size_of::<ExtraAccountMeta>())
+            .map(|_| {
+                ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            validate_extra_account_metas(&oversized),
+            Err(TransferHookError::ExtraAccountMetaListTooLarge.into())
+        );
+
+        // the same address with other flags is still a duplicate
+        let key = Pubkey::new_unique();
+        let duplicates = [
+            ExtraAccountMeta::new_with_pubkey(&key, false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&key, false, true).unwrap(),
+        ];
+        assert_eq!(
+            validate_extra_account_metas(&duplicates),
+            Err(TransferHookError::DuplicateExtraAccountMeta.into())
+        );
+
+        // a meta may only refer to the accounts before it
+        for unresolvable in [
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 5 }], false, false),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::AccountData {
+                    account_index: 7,
+                    data_index: 0,
+                },
+                false,
+                false,
+            ),
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::InstructionData {
+                    index: 8,
+                    length: 16,
+                }],
+                false,
+                false,
+            ),
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                5,
+                &[Seed::Literal {
+                    bytes: b"seed".to_vec(),
+                }],
+                false,
+                false,
+            ),
+        ] {
+            assert_eq!(
+                validate_extra_account_metas(&[unresolvable.unwrap()]),
+                Err(TransferHookError::UnresolvableExtraAccountMeta.into())
+            );
+        }
+    }
+
+    #[test]
+    fn transfer_checked_with_tracking_appends_hook_accounts() {
+        let token_program_id = spl_token_2022::id().to_bytes().into();
+        let program_id = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        for (per_mint_tracking, seeds) in [
+            (false, vec![owner.as_ref()]),
+            (true, vec![owner.as_ref(), mint.as_ref()]),
+        ] {
+            let instruction = transfer_checked_with_tracking(
+                &token_program_id,
+                &program_id,
+                &source,
+                &mint,
+                &destination,
+                &authority,
+                &owner,
+                42,
+                6,
+                per_mint_tracking,
+            );
+            assert_eq!(instruction.program_id, token_program_id);
+            assert_eq!(
+                spl_token_2022::instruction::TokenInstruction::unpack(&instruction.data).unwrap(),
+                spl_token_2022::instruction::TokenInstruction::TransferChecked {
+                    amount: 42,
+                    decimals: 6
+                }
+            );
+
+            let (transfer_account, _) = Pubkey::find_program_address(&seeds, &program_id);
+            assert_eq!(
+                instruction.accounts,
+                vec![
+                    AccountMeta::new(source, false),
+                    AccountMeta::new_readonly(mint, false),
+                    AccountMeta::new(destination, false),
+                    AccountMeta::new_readonly(authority, true),
+                    AccountMeta::new(transfer_account, false),
+                    AccountMeta::new_readonly(program_id, false),
+                    AccountMeta::new_readonly(
+                        get_extra_account_metas_address(&mint, &program_id),
+                        false
+                    ),
+                ]
+            );
+        }
+    }
+}
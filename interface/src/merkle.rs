@@ -0,0 +1,156 @@
+//! Merkle tree of the transfers of a mint, whose running root the
+//! transfer-tracking example program keeps in the transfer root PDA, and the
+//! client-side helpers proving a transfer was included
+
+use {solana_pubkey::Pubkey, solana_sha256_hasher::hashv};
+
+/// Depth of the tree, which holds up to `2^TRANSFER_ROOT_DEPTH - 1` transfers:
+/// the program's frontier can't represent the leaf filling the tree
+pub const TRANSFER_ROOT_DEPTH: usize = 32;
+
+/// Prefix of the hash of a leaf, telling leaves and inner nodes apart
+const LEAF_PREFIX: &[u8] = &[0];
+/// Prefix of the hash of an inner node
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf of a transfer of `amount` from `owner`, accepted at `slot`
+pub fn transfer_leaf(owner: &Pubkey, amount: u64, slot: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        owner.as_ref(),
+        &amount.to_le_bytes(),
+        &slot.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Inner node of the tree, above its two children
+pub fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Roots of the empty subtrees of each height, from the zeroed leaves up to
+/// the empty tree
+pub fn empty_subtree_roots() -> [[u8; 32]; TRANSFER_ROOT_DEPTH + 1] {
+    let mut roots = [[0; 32]; TRANSFER_ROOT_DEPTH + 1];
+    for height in 0..TRANSFER_ROOT_DEPTH {
+        roots[height + 1] = hash_nodes(&roots[height], &roots[height]);
+    }
+    roots
+}
+
+/// Root of the tree holding the leaves, in the order of the transfers
+pub fn transfer_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let empty_roots = empty_subtree_roots();
+    let mut level = leaves.to_vec();
+    for empty_root in &empty_roots[..TRANSFER_ROOT_DEPTH] {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(empty_root)))
+            .collect();
+    }
+    level
+        .first()
+        .copied()
+        .unwrap_or(empty_roots[TRANSFER_ROOT_DEPTH])
+}
+
+/// Sibling nodes from the leaf at `index` up to the root, proving its
+/// inclusion in the tree holding the leaves, `None` if there is no such leaf
+pub fn inclusion_proof(leaves: &[[u8; 32]], index: u64) -> Option<Vec<[u8; 32]>> {
+    let mut position = usize::try_from(index).ok()?;
+    if position >= leaves.len() {
+        return None;
+    }
+    let empty_roots = empty_subtree_roots();
+    let mut level = leaves.to_vec();
+    let mut proof = Vec::with_capacity(TRANSFER_ROOT_DEPTH);
+    for empty_root in &empty_roots[..TRANSFER_ROOT_DEPTH] {
+        proof.push(*level.get(position ^ 1).unwrap_or(empty_root));
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(empty_root)))
+            .collect();
+        position /= 2;
+    }
+    Some(proof)
+}
+
+/// Verify that the leaf is the transfer at `index` of the tree with the root,
+/// as read from the transfer root PDA, given the sibling nodes from the leaf
+/// up to the root
+pub fn verify_transfer_inclusion(
+    root: &[u8; 32],
+    leaf: &[u8; 32],
+    index: u64,
+    proof: &[[u8; 32]],
+) -> bool {
+    if proof.len() != TRANSFER_ROOT_DEPTH || index >> TRANSFER_ROOT_DEPTH != 0 {
+        return false;
+    }
+    let node = proof
+        .iter()
+        .enumerate()
+        .fold(*leaf, |node, (height, sibling)| {
+            if index >> height & 1 == 0 {
+                hash_nodes(&node, sibling)
+            } else {
+                hash_nodes(sibling, &node)
+            }
+        });
+    node == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_inclusion_proofs() {
+        let owner = Pubkey::new_unique();
+        let leaves = (0..5)
+            .map(|i| transfer_leaf(&owner, 100 + i, 10 + i))
+            .collect::<Vec<_>>();
+        let root = transfer_root(&leaves);
+        assert_ne!(root, transfer_root(&leaves[..4]));
+        assert_eq!(
+            transfer_root(&[]),
+            empty_subtree_roots()[TRANSFER_ROOT_DEPTH]
+        );
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = inclusion_proof(&leaves, index as u64).unwrap();
+            assert!(verify_transfer_inclusion(&root, leaf, index as u64, &proof));
+            // the proof holds for its leaf at its index only
+            assert!(!verify_transfer_inclusion(
+                &root,
+                &leaves[(index + 1) % leaves.len()],
+                index as u64,
+                &proof
+            ));
+            assert!(!verify_transfer_inclusion(
+                &root,
+                leaf,
+                index as u64 ^ 1,
+                &proof
+            ));
+            assert!(!verify_transfer_inclusion(
+                &root,
+                leaf,
+                index as u64,
+                &proof[1..]
+            ));
+        }
+        assert_eq!(inclusion_proof(&leaves, 5), None);
+
+        // a leaf differing in any field is not included
+        let proof = inclusion_proof(&leaves, 0).unwrap();
+        for leaf in [
+            transfer_leaf(&Pubkey::new_unique(), 100, 10),
+            transfer_leaf(&owner, 101, 10),
+            transfer_leaf(&owner, 100, 11),
+        ] {
+            assert!(!verify_transfer_inclusion(&root, &leaf, 0, &proof));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Readers for the Token-2022 mint extensions used by the transfer-tracking
+//! example program, without depending on the token program crate
+
+use solana_pubkey::Pubkey;
+
+/// Length of the base `Account` state, which mints with extensions are padded
+/// to before their account type
+const BASE_ACCOUNT_LENGTH: usize = 165;
+/// Account type of a mint, following the padded base state
+const ACCOUNT_TYPE_MINT: u8 = 1;
+/// Extension type of `TransferHook`
+const EXTENSION_TYPE_TRANSFER_HOOK: u16 = 14;
+/// Offset of `program_id` in the `TransferHook` extension, after `authority`
+const TRANSFER_HOOK_PROGRAM_ID_OFFSET: usize = 32;
+
+/// Reads the transfer hook program configured in the `TransferHook` extension
+/// of a mint.
+///
+/// Returns `None` for mints without the extension, with the extension but no
+/// program, or whose data isn't a mint with extensions.
+pub fn get_hook_program_id(mint_data: &[u8]) -> Option<Pubkey> {
+    if mint_data.get(BASE_ACCOUNT_LENGTH) != Some(&ACCOUNT_TYPE_MINT) {
+        return None;
+    }
+    let mut tlv_data = &mint_data[BASE_ACCOUNT_LENGTH + 1..];
+    while let Some((header, rest)) = tlv_data.split_first_chunk::<4>() {
+        let extension_type = u16::from_le_bytes([header[0], header[1]]);
+        let length = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let value = rest.get(..length)?;
+        if extension_type == EXTENSION_TYPE_TRANSFER_HOOK {
+            let program_id =
+                value.get(TRANSFER_HOOK_PROGRAM_ID_OFFSET..TRANSFER_HOOK_PROGRAM_ID_OFFSET + 32)?;
+            let program_id = Pubkey::try_from(program_id).ok()?;
+            return (program_id != Pubkey::default()).then_some(program_id);
+        }
+        tlv_data = &rest[length..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_program::{program_option::COption, program_pack::Pack},
+        spl_token_2022::{
+            extension::{
+                metadata_pointer::MetadataPointer, transfer_hook::TransferHook,
+                BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+            },
+            state::Mint,
+        },
+    };
+
+    fn mint_data(extension_types: &[ExtensionType], hook_program_id: Option<Pubkey>) -> Vec<u8> {
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(extension_types).unwrap();
+        let mut mint_data = vec![0; mint_size];
+        let mut state =
+            StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut mint_data).unwrap();
+        for extension_type in extension_types {
+            match extension_type {
+                ExtensionType::TransferHook => {
+                    let extension = state.init_extension::<TransferHook>(true).unwrap();
+                    extension.program_id = hook_program_id
+                        .map(|id| solana_program::pubkey::Pubkey::new_from_array(id.to_bytes()))
+                        .try_into()
+                        .unwrap();
+                }
+                ExtensionType::MetadataPointer => {
+                    state.init_extension::<MetadataPointer>(true).unwrap();
+                }
+                _ => unreachable!(),
+            }
+        }
+        state.base = Mint {
+            mint_authority: COption::None,
+            supply: 0,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        mint_data
+    }
+
+    #[test]
+    fn read_hook_program_id() {
+        let hook_program_id = Pubkey::new_unique();
+        assert_eq!(
+            get_hook_program_id(&mint_data(
+                &[ExtensionType::TransferHook],
+                Some(hook_program_id)
+            )),
+            Some(hook_program_id)
+        );
+        // after another extension
+        assert_eq!(
+            get_hook_program_id(&mint_data(
+                &[ExtensionType::MetadataPointer, ExtensionType::TransferHook],
+                Some(hook_program_id)
+            )),
+            Some(hook_program_id)
+        );
+        assert_eq!(
+            get_hook_program_id(&mint_data(&[ExtensionType::TransferHook], None)),
+            None
+        );
+    }
+
+    #[test]
+    fn read_hook_program_id_without_extension() {
+        assert_eq!(
+            get_hook_program_id(&mint_data(&[ExtensionType::MetadataPointer], None)),
+            None
+        );
+        let mut base_mint = vec![0; Mint::LEN];
+        Mint::default().pack_into_slice(&mut base_mint);
+        assert_eq!(get_hook_program_id(&base_mint), None);
+        assert_eq!(get_hook_program_id(&[]), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! Seeds and derivations of the PDAs of the transfer-tracking example
+//! program, which the program derives its accounts from as well
+
+use solana_pubkey::Pubkey;
+
+/// Seed for the config of a mint
+pub const SEED_CONFIG: &[u8] = b"mint-config";
+/// Seed for the blocklist of a mint
+pub const SEED_BLOCKLIST: &[u8] = b"blocklist";
+/// Seed for the exempt owners list of a mint
+pub const SEED_EXEMPT_OWNERS: &[u8] = b"exempt-owners";
+/// Seed for the incoming transfer policy of an owner
+pub const SEED_INCOMING_POLICY: &[u8] = b"incoming-policy";
+/// Seed for the pages of the allowlist of a mint
+pub const SEED_ALLOWLIST: &[u8] = b"allow";
+/// Seed for the burn ledger of an owner
+pub const SEED_BURN_LEDGER: &[u8] = b"burn-ledger";
+/// Seed for the fee ledger of a category
+pub const SEED_FEE_LEDGER: &[u8] = b"fee-ledger";
+/// Seed for the transfer statistics of a mint
+pub const SEED_STATS: &[u8] = b"mint-stats";
+/// Seed for the history of mint stats snapshots
+pub const SEED_STATS_HISTORY: &[u8] = b"stats-history";
+/// Seed for the recent transfer log of a mint
+pub const SEED_RECENT_TRANSFER_LOG: &[u8] = b"recent-log";
+/// Seed for the vesting schedule of an owner
+pub const SEED_VESTING_SCHEDULE: &[u8] = b"vesting";
+/// Seed for the count of transfer accounts created by a payer
+pub const SEED_CREATION_COUNT: &[u8] = b"creation-count";
+/// Seed for the treasury of a mint
+pub const SEED_TREASURY: &[u8] = b"treasury";
+/// Seed for the receive-only flag of an owner
+pub const SEED_RECEIVE_ONLY: &[u8] = b"receive-only";
+/// Seed for the running Merkle root of the transfers of a mint
+pub const SEED_TRANSFER_ROOT: &[u8] = b"transfer-root";
+
+/// Find the transfer account PDA tracking an owner's transfers, along with
+/// its bump seed.
+///
+/// The PDA is derived from the owner only, unless `per_mint_tracking`,
+/// matching the program's feature of the same name, adds the mint to the
+/// seeds.
+pub fn find_transfer_account_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    per_mint_tracking: bool,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    if per_mint_tracking {
+        Pubkey::find_program_address(&[owner.as_ref(), mint.as_ref()], program_id)
+    } else {
+        Pubkey::find_program_address(&[owner.as_ref()], program_id)
+    }
+}
+
+/// Find the config PDA of a mint, along with its bump seed
+pub fn find_mint_config_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_CONFIG, mint.as_ref()], program_id)
+}
+
+/// Find the blocklist PDA of a mint, along with its bump seed
+pub fn find_blocklist_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_BLOCKLIST, mint.as_ref()], program_id)
+}
+
+/// Find the exempt owners PDA of a mint, along with its bump seed
+pub fn find_exempt_owners_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_EXEMPT_OWNERS, mint.as_ref()], program_id)
+}
+
+/// Find the incoming policy PDA of an owner for a mint, along with its bump
+/// seed
+pub fn find_incoming_policy_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_INCOMING_POLICY, mint.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the receive-only flag PDA of an owner for a mint, along with its bump
+/// seed
+pub fn find_receive_only_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_RECEIVE_ONLY, mint.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the PDA of an allowlist page of a mint, along with its bump seed.
+/// An owner falls on the page of their first byte.
+pub fn find_allowlist_address(mint: &Pubkey, page: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_ALLOWLIST, mint.as_ref(), &[page]], program_id)
+}
+
+/// Find the burn ledger PDA of an owner for a mint, along with its bump seed
+pub fn find_burn_ledger_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_BURN_LEDGER, mint.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the vesting schedule PDA of an owner for a mint, along with its bump
+/// seed
+pub fn find_vesting_schedule_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_VESTING_SCHEDULE, mint.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the PDA counting the transfer accounts a payer created for a mint,
+/// along with its bump seed
+pub fn find_creation_count_address(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_CREATION_COUNT, mint.as_ref(), payer.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the fee ledger PDA of a category for a mint, along with its bump seed
+pub fn find_fee_ledger_address(mint: &Pubkey, category: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_FEE_LEDGER, mint.as_ref(), &[category]], program_id)
+}
+
+/// Find the treasury PDA of a mint, along with its bump seed
+pub fn find_treasury_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_TREASURY, mint.as_ref()], program_id)
+}
+
+/// Find the transfer statistics PDA of a mint, along with its bump seed
+pub fn find_mint_stats_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_STATS, mint.as_ref()], program_id)
+}
+
+/// Find the stats history PDA of a mint, along with its bump seed
+pub fn find_stats_history_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_STATS_HISTORY, mint.as_ref()], program_id)
+}
+
+/// Find the recent transfer log PDA of a mint, along with its bump seed
+pub fn find_recent_transfer_log_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_RECENT_TRANSFER_LOG, mint.as_ref()], program_id)
+}
+
+/// Find the transfer root PDA of a mint, along with its bump seed
+pub fn find_transfer_root_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_TRANSFER_ROOT, mint.as_ref()], program_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_distinct() {
+        let seeds = [
+            SEED_CONFIG,
+            SEED_BLOCKLIST,
+            SEED_EXEMPT_OWNERS,
+            SEED_INCOMING_POLICY,
+            SEED_ALLOWLIST,
+            SEED_BURN_LEDGER,
+            SEED_FEE_LEDGER,
+            SEED_STATS,
+            SEED_STATS_HISTORY,
+            SEED_RECENT_TRANSFER_LOG,
+            SEED_VESTING_SCHEDULE,
+            SEED_CREATION_COUNT,
+            SEED_TREASURY,
+            SEED_RECEIVE_ONLY,
+            SEED_TRANSFER_ROOT,
+        ];
+        for (i, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[..i].contains(seed));
+        }
+    }
+}
@@ -0,0 +1,640 @@
+//! Client-side decoders for accounts written by the transfer-tracking example
+//! program
+
+use {
+    crate::merkle::TRANSFER_ROOT_DEPTH,
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+};
+
+/// Transfer account of an owner, laid out as the program packs it, so it can
+/// be decoded with borsh
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferAccount {
+    /// Owner whose transfers are tracked
+    pub owner: Pubkey,
+    /// Total amount transferred
+    pub transfered: u64,
+    /// Unix timestamp of the last transfer, zero if unknown
+    pub last_transfer_timestamp: i64,
+    /// Label set by the owner for off-chain categorization
+    pub label: [u8; crate::custom_instruction::LABEL_LEN],
+    /// Slot at which the current rate limiting window started
+    pub window_start_slot: u64,
+    /// Amount transferred during the current rate limiting window
+    pub window_amount: u64,
+    /// Unix timestamp of the first tracked transfer, zero if unknown
+    pub first_transfer_timestamp: i64,
+    /// Unix timestamp of the first transfer received by the owner, zero if
+    /// unknown
+    pub first_received_timestamp: i64,
+    /// Amount the owner can still transfer, when the mint config enforces
+    /// allowances
+    pub allowance_remaining: u64,
+    /// Nonce of the last transfer ticket used by the owner
+    pub last_ticket_nonce: u64,
+    /// Unix timestamp of the start of the UTC day of the owner's last counted
+    /// transfer, zero if none
+    pub day_start_ts: i64,
+    /// Number of transfers made by the owner during that day
+    pub day_count: u32,
+    /// Total amount received from other owners, when the owner's transfer
+    /// account was passed along
+    pub received: u64,
+    /// Largest single tracked transfer of the owner
+    pub max_single_transfer: u64,
+    /// Slot of the first transfer received by the owner, zero if unknown
+    pub first_received_slot: u64,
+    /// Fingerprint of the transaction whose partial fills were last
+    /// aggregated, zero if none
+    pub fill_transaction: [u8; 32],
+    /// Amount of the partial fills aggregated for that transaction
+    pub fill_amount: u64,
+    /// Index of the instruction that made the last of those partial fills
+    pub fill_instruction_index: u16,
+}
+
+/// Share of the transfer fee accrued to the fee ledger of a category
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeSplit {
+    /// Category of the fee ledger
+    pub category: u8,
+    /// Share of the transferred amount accrued to the ledger, in basis points
+    pub bps: u16,
+}
+
+/// Mint config of the current layout version, laid out as the program packs
+/// it, so it can be decoded with borsh
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MintConfig {
+    /// Version of the layout
+    pub version: u8,
+    /// Authority allowed to update the config
+    pub authority: Pubkey,
+    /// Maximum share of the source holdings moved per transfer, in basis
+    /// points
+    pub max_pct_bps: u16,
+    /// Minimum number of seconds between two transfers of the same owner
+    pub cooldown_seconds: u32,
+    /// Length of the rate limiting window, in slots
+    pub window_slots: u64,
+    /// Maximum amount an owner can transfer per window
+    pub max_amount_per_window_slots: u64,
+    /// Handling of zero-amount transfers: 0 allows them, 1 rejects them, 2
+    /// skips their tracking
+    pub zero_amount_mode: u8,
+    /// Program notified of each transfer
+    pub notify_program: Pubkey,
+    /// Transfers of at most this amount skip the limits
+    pub limit_exempt_amount: u64,
+    /// Number of `signers` required to sign config changes
+    pub signer_threshold: u8,
+    /// Signers allowed to change the config
+    pub signers: [Pubkey; MintConfig::MAX_SIGNERS],
+    /// Fee accrued on each transfer, in basis points
+    pub fee_bps: u16,
+    /// Split of the fee across the fee ledgers
+    pub fee_splits: [FeeSplit; MintConfig::MAX_FEE_SPLITS],
+    /// Whether only the source token account must be transferring
+    pub source_transferring_only: bool,
+    /// Whether transfers between accounts of the same owner skip tracking
+    pub skip_same_owner_transfers: bool,
+    /// Whether the amount is checked against the token balances
+    pub check_amount_consistency: bool,
+    /// Minimum number of seconds between an owner's first received transfer
+    /// and their outbound transfers
+    pub holding_period_seconds: u32,
+    /// Whether tracked transfers are debited from the owner's allowance
+    pub enforce_allowances: bool,
+    /// Whether transfers are rejected while the mint is paused or once its
+    /// mint authority has been revoked
+    pub require_active_mint: bool,
+    /// Share of each transfer owed to burn, in basis points
+    pub burn_bps: u16,
+    /// List of owners consulted, as the program's `AccessMode`
+    pub access_mode: u8,
+    /// Whether transfers to token accounts owned by the mint's PDAs are
+    /// rejected
+    pub reject_program_destinations: bool,
+    /// Maximum total amount moved by the mint's transfers within an epoch
+    pub max_epoch_volume: u64,
+    /// Whether `Execute` leaves the transfer account out
+    pub skip_transfer_tracking: bool,
+    /// Signer of the tickets required for each transfer, the default pubkey
+    /// if none are
+    pub ticket_signer: Pubkey,
+    /// Maximum number of transfers an owner can make per UTC day
+    pub max_transfers_per_day: u32,
+    /// Whether transfers require the source owner's signature
+    pub require_owner_signer: bool,
+    /// Maximum total amount an owner can receive from other owners
+    pub max_received_amount: u64,
+    /// Whether transfers to destinations short of rent exemption are rejected
+    pub require_rent_exempt_destination: bool,
+    /// Bitmask of the extra account metas whose account must be owned by the
+    /// program of their external PDA
+    pub owner_checked_metas: u32,
+    /// Only owner allowed to send tokens until trading is enabled
+    pub distributor: Pubkey,
+    /// Whether transfers by a delegate of the source are rejected
+    pub reject_delegate_transfers: bool,
+    /// Slot from which transfers are paused, zero if no pause is scheduled
+    pub pause_effective_slot: u64,
+    /// Reason of the scheduled pause, padded with zero bytes
+    pub pause_reason: [u8; crate::custom_instruction::PAUSE_REASON_LEN],
+    /// Whether a transfer above the balance percentage reports the allowed
+    /// maximum through return data
+    pub report_allowed_maximum: bool,
+    /// Token-2022 extension type the destination token account must carry,
+    /// zero if none
+    pub required_destination_extension: u16,
+    /// Version of the extra account metas layout the validation account was
+    /// written for, zero if unchecked
+    pub extra_account_metas_version: u8,
+    /// Maximum number of transfer accounts a single payer may create, zero if
+    /// uncapped
+    pub max_transfer_accounts_per_payer: u32,
+    /// Rounding of the fee of a transfer: 0 floor, 1 ceil, 2 round
+    pub fee_rounding: u8,
+    /// Programs whose transfers skip the limits, unused entries left to the
+    /// default pubkey
+    pub trusted_callers: [Pubkey; MintConfig::MAX_TRUSTED_CALLERS],
+    /// Minimum number of slots between the first transfer received by an
+    /// owner and their outbound transfers, zero if unchecked
+    pub min_account_age_slots: u64,
+    /// Check that the mint has `expected_decimals`
+    pub enforce_decimals: bool,
+    /// Decimals the mint must have, when `enforce_decimals`
+    pub expected_decimals: u8,
+    /// Apply the per-transfer limits to the sum of the transfers from the
+    /// same source within a transaction
+    pub aggregate_partial_fills: bool,
+    /// Track the amounts transferred and received net of the Token-2022
+    /// transfer fee of the mint
+    pub track_net_of_transfer_fee: bool,
+    /// Reject transfers until the setup of the mint is finalized
+    pub require_setup: bool,
+    /// Whether the setup of the mint is finalized
+    pub setup_complete: bool,
+}
+
+impl MintConfig {
+    /// Number of config signer entries
+    pub const MAX_SIGNERS: usize = 5;
+    /// Number of fee split entries
+    pub const MAX_FEE_SPLITS: usize = 4;
+    /// Number of trusted caller entries
+    pub const MAX_TRUSTED_CALLERS: usize = 4;
+    /// Current version of the layout
+    pub const VERSION: u8 = 2;
+    /// Size of the mint config account data
+    pub const LEN: usize = 567;
+}
+
+/// Size of the data of each mint config layout version, starting with
+/// version 1
+const MINT_CONFIG_LAYOUT_LENS: [usize; MintConfig::VERSION as usize] = [96, MintConfig::LEN];
+/// Size of a transfer account
+const TRANSFER_ACCOUNT_LEN: usize = 190;
+/// Maximum number of owners in an owner list
+const OWNER_LIST_CAPACITY: usize = 64;
+/// Size of an owner list: the number of owners (u32), followed by the owners
+const OWNER_LIST_LEN: usize = 4 + OWNER_LIST_CAPACITY * 32;
+/// Maximum number of entries on an allowlist page
+const ALLOWLIST_PAGE_CAPACITY: usize = 64;
+/// Size of an allowlist page: the number of entries (u32), followed by the
+/// entries
+const ALLOWLIST_PAGE_LEN: usize = 4 + ALLOWLIST_PAGE_CAPACITY * ALLOWLIST_ENTRY_LEN;
+/// Size of the recent transfer log written by the program
+const RECENT_TRANSFER_LOG_LEN: usize = 8 + 16 * RECENT_TRANSFER_ENTRY_LEN;
+/// Size of the stats history written by the program
+const STATS_HISTORY_LEN: usize = 8 + 32 * STATS_SNAPSHOT_LEN;
+/// Size of the transfer root: the number of transfers (u64) and the root,
+/// followed by the frontier of the tree
+const TRANSFER_ROOT_LEN: usize = 8 + 32 + TRANSFER_ROOT_DEPTH * 32;
+
+/// Account owned by the program, as decoded by [`decode_program_account`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgramAccount {
+    /// Transfer account of an owner
+    TransferAccount(TransferAccount),
+    /// Mint config of the current layout version
+    MintConfig(Box<MintConfig>),
+    /// Mint config of an older layout version, to be migrated
+    OutdatedMintConfig {
+        /// Version of the layout
+        version: u8,
+    },
+    /// Owners of a blocklist or exempt owners list
+    OwnerList(Vec<Pubkey>),
+    /// Entries of an allowlist page
+    AllowlistPage(Vec<AllowlistEntry>),
+    /// Transfers of a recent transfer log, oldest first
+    RecentTransferLog(Vec<RecentTransfer>),
+    /// Snapshots of a stats history, oldest first
+    StatsHistory(Vec<StatsSnapshot>),
+    /// Running Merkle root of the transfers of a mint
+    TransferRoot(TransferRoot),
+    /// Data not matching any of the above
+    Unknown,
+}
+
+/// Decode the data of any account owned by the program, for inspection.
+///
+/// The accounts have no discriminator, so they are told apart by their size,
+/// and by their version for mint configs. Accounts of other sizes, and data
+/// that doesn't decode, are [`ProgramAccount::Unknown`].
+pub fn decode_program_account(data: &[u8]) -> ProgramAccount {
+    let outdated_version = data.first().copied().filter(|version| {
+        (1..MintConfig::VERSION).contains(version)
+            && MINT_CONFIG_LAYOUT_LENS[usize::from(*version) - 1] == data.len()
+    });
+    let decoded = match data.len() {
+        TRANSFER_ACCOUNT_LEN => TransferAccount::try_from_slice(data)
+            .ok()
+            .map(ProgramAccount::TransferAccount),
+        _ if outdated_version.is_some() => {
+            outdated_version.map(|version| ProgramAccount::OutdatedMintConfig { version })
+        }
+        MintConfig::LEN => MintConfig::try_from_slice(data)
+            .ok()
+            .filter(|config| config.version == MintConfig::VERSION)
+            .map(|config| ProgramAccount::MintConfig(Box::new(config))),
+        OWNER_LIST_LEN => unpack_owner_list(data).map(ProgramAccount::OwnerList),
+        ALLOWLIST_PAGE_LEN => unpack_allowlist_page(data).map(ProgramAccount::AllowlistPage),
+        RECENT_TRANSFER_LOG_LEN => unpack_recent_transfers(data)
+            .ok()
+            .map(ProgramAccount::RecentTransferLog),
+        STATS_HISTORY_LEN => unpack_stats_history(data)
+            .ok()
+            .map(ProgramAccount::StatsHistory),
+        TRANSFER_ROOT_LEN => TransferRoot::deserialize(&mut &data[..])
+            .ok()
+            .map(ProgramAccount::TransferRoot),
+        _ => None,
+    };
+    decoded.unwrap_or(ProgramAccount::Unknown)
+}
+
+/// Decodes the owners of an owner list, `None` if the count exceeds the
+/// capacity
+fn unpack_owner_list(data: &[u8]) -> Option<Vec<Pubkey>> {
+    let (count, owners) = data.split_first_chunk::<4>()?;
+    let count = u32::from_le_bytes(*count) as usize;
+    if count > OWNER_LIST_CAPACITY {
+        return None;
+    }
+    owners[..count * 32]
+        .chunks_exact(32)
+        .map(|owner| Pubkey::try_from(owner).ok())
+        .collect()
+}
+
+/// Size of a single allowlist entry: Pubkey (32) + i64 (8)
+const ALLOWLIST_ENTRY_LEN: usize = 32 + 8;
+
+/// Owner allowed by an allowlist page
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllowlistEntry {
+    /// Allowed owner
+    pub owner: Pubkey,
+    /// Unix timestamp the entry expires at, 0 if it never expires
+    pub expires_at: i64,
+}
+
+/// Decodes the entries of an allowlist page, `None` if the count exceeds the
+/// capacity
+fn unpack_allowlist_page(data: &[u8]) -> Option<Vec<AllowlistEntry>> {
+    let (count, entries) = data.split_first_chunk::<4>()?;
+    let count = u32::from_le_bytes(*count) as usize;
+    if count > ALLOWLIST_PAGE_CAPACITY {
+        return None;
+    }
+    entries[..count * ALLOWLIST_ENTRY_LEN]
+        .chunks_exact(ALLOWLIST_ENTRY_LEN)
+        .map(|entry| AllowlistEntry::try_from_slice(entry).ok())
+        .collect()
+}
+
+/// Running Merkle root of the transfers of a mint, as stored at the start of
+/// the transfer root PDA, against which `merkle::verify_transfer_inclusion`
+/// checks a transfer
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferRoot {
+    /// Number of transfers in the tree, the index of the next one
+    pub count: u64,
+    /// Root of the tree
+    pub root: [u8; 32],
+}
+
+/// Size of a single recent transfer log entry: Pubkey (32) + u64 (8) + u64 (8)
+const RECENT_TRANSFER_ENTRY_LEN: usize = 32 + 8 + 8;
+
+/// A transfer recorded in the recent transfer log PDA
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecentTransfer {
+    /// Owner of the source token account
+    pub owner: Pubkey,
+    /// Amount of tokens transferred
+    pub amount: u64,
+    /// Slot at which the transfer was executed
+    pub slot: u64,
+}
+
+/// Decodes the data of a recent transfer log PDA, returning the stored
+/// transfers oldest first.
+///
+/// The log is a ring buffer: a little-endian `u64` count of all appended
+/// entries, followed by the fixed-capacity entry array. The capacity is
+/// derived from the data length.
+pub fn unpack_recent_transfers(data: &[u8]) -> Result<Vec<RecentTransfer>, ProgramError> {
+    let (total, entries) = data
+        .split_first_chunk::<8>()
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let total = u64::from_le_bytes(*total);
+    if entries.is_empty() || entries.len() % RECENT_TRANSFER_ENTRY_LEN != 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let capacity = (entries.len() / RECENT_TRANSFER_ENTRY_LEN) as u64;
+    let len = total.min(capacity);
+
+    (total - len..total)
+        .map(|position| {
+            let offset = (position % capacity) as usize * RECENT_TRANSFER_ENTRY_LEN;
+            let entry = &entries[offset..offset + RECENT_TRANSFER_ENTRY_LEN];
+            let owner =
+                Pubkey::try_from(&entry[..32]).map_err(|_| ProgramError::InvalidAccountData)?;
+            let amount = u64::from_le_bytes(
+                entry[32..40]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            );
+            let slot = u64::from_le_bytes(
+                entry[40..48]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            );
+            Ok(RecentTransfer {
+                owner,
+                amount,
+                slot,
+            })
+        })
+        .collect()
+}
+
+/// Size of a single stats history entry, laid out as [`StatsSnapshot`]
+const STATS_SNAPSHOT_LEN: usize = 8 + 8 + 8 + 8 + 8 + 8;
+
+/// Mint stats copied into the stats history PDA, laid out as the program
+/// packs them, so they can be decoded with borsh
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Number of transfers accepted at the time of the snapshot
+    pub accepted_transfers: u64,
+    /// Total amount of the transfers accepted at the time of the snapshot
+    pub accepted_amount: u64,
+    /// Epoch of the last transfer accepted before the snapshot
+    pub epoch: u64,
+    /// Total amount of the transfers accepted within `epoch`
+    pub epoch_volume: u64,
+    /// Slot at which the snapshot was taken
+    pub slot: u64,
+    /// Unix timestamp at which the snapshot was taken
+    pub unix_timestamp: i64,
+}
+
+/// Decodes the data of a stats history PDA, returning the stored snapshots
+/// oldest first.
+///
+/// The history is a ring buffer laid out like the recent transfer log, see
+/// [`unpack_recent_transfers`].
+pub fn unpack_stats_history(data: &[u8]) -> Result<Vec<StatsSnapshot>, ProgramError> {
+    let (total, entries) = data
+        .split_first_chunk::<8>()
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let total = u64::from_le_bytes(*total);
+    if entries.is_empty() || entries.len() % STATS_SNAPSHOT_LEN != 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let capacity = (entries.len() / STATS_SNAPSHOT_LEN) as u64;
+    let len = total.min(capacity);
+
+    (total - len..total)
+        .map(|position| {
+            let offset = (position % capacity) as usize * STATS_SNAPSHOT_LEN;
+            StatsSnapshot::try_from_slice(&entries[offset..offset + STATS_SNAPSHOT_LEN])
+                .map_err(|_| ProgramError::InvalidAccountData)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack_log(capacity: usize, total: u64, entries: &[(usize, RecentTransfer)]) -> Vec<u8> {
+        let mut data = vec![0; 8 + capacity * RECENT_TRANSFER_ENTRY_LEN];
+        data[..8].copy_from_slice(&total.to_le_bytes());
+        for (index, entry) in entries {
+            let offset = 8 + index * RECENT_TRANSFER_ENTRY_LEN;
+            data[offset..offset + 32].copy_from_slice(entry.owner.as_ref());
+            data[offset + 32..offset + 40].copy_from_slice(&entry.amount.to_le_bytes());
+            data[offset + 40..offset + 48].copy_from_slice(&entry.slot.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn transfer_account_layout() {
+        let transfer_account = TransferAccount {
+            owner: Pubkey::new_unique(),
+            transfered: 1,
+            last_transfer_timestamp: 2,
+            label: [3; crate::custom_instruction::LABEL_LEN],
+            window_start_slot: 4,
+            window_amount: 5,
+            first_transfer_timestamp: 6,
+            first_received_timestamp: 7,
+            allowance_remaining: 8,
+            last_ticket_nonce: 9,
+            day_start_ts: 10,
+            day_count: 11,
+            received: 12,
+            max_single_transfer: 13,
+            first_received_slot: 14,
+            fill_transaction: [15; 32],
+            fill_amount: 16,
+            fill_instruction_index: 17,
+        };
+        let data = borsh::to_vec(&transfer_account).unwrap();
+        assert_eq!(data.len(), 190);
+        assert_eq!(&data[..32], transfer_account.owner.as_ref());
+        assert_eq!(data[32..40], 1u64.to_le_bytes());
+        assert_eq!(data[40..48], 2i64.to_le_bytes());
+        assert_eq!(data[48..64], [3; 16]);
+        assert_eq!(data[64..72], 4u64.to_le_bytes());
+        assert_eq!(data[72..80], 5u64.to_le_bytes());
+        assert_eq!(data[80..88], 6i64.to_le_bytes());
+        assert_eq!(data[88..96], 7i64.to_le_bytes());
+        assert_eq!(data[96..104], 8u64.to_le_bytes());
+        assert_eq!(data[104..112], 9u64.to_le_bytes());
+        assert_eq!(data[112..120], 10i64.to_le_bytes());
+        assert_eq!(data[120..124], 11u32.to_le_bytes());
+        assert_eq!(data[124..132], 12u64.to_le_bytes());
+        assert_eq!(data[132..140], 13u64.to_le_bytes());
+        assert_eq!(data[140..148], 14u64.to_le_bytes());
+        assert_eq!(data[148..180], [15; 32]);
+        assert_eq!(data[180..188], 16u64.to_le_bytes());
+        assert_eq!(data[188..190], 17u16.to_le_bytes());
+        assert_eq!(
+            TransferAccount::try_from_slice(&data).unwrap(),
+            transfer_account
+        );
+    }
+
+    #[test]
+    fn decode_each_program_account() {
+        let transfer_account = TransferAccount {
+            owner: Pubkey::new_unique(),
+            transfered: 1,
+            ..TransferAccount::default()
+        };
+        assert_eq!(
+            decode_program_account(&borsh::to_vec(&transfer_account).unwrap()),
+            ProgramAccount::TransferAccount(transfer_account)
+        );
+
+        let mint_config = MintConfig {
+            version: MintConfig::VERSION,
+            authority: Pubkey::new_unique(),
+            fee_bps: 10,
+            holding_period_seconds: 60,
+            ..MintConfig::default()
+        };
+        let mut data = borsh::to_vec(&mint_config).unwrap();
+        assert_eq!(data.len(), MintConfig::LEN);
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::MintConfig(Box::new(mint_config))
+        );
+        data[0] = MintConfig::VERSION + 1;
+        assert_eq!(decode_program_account(&data), ProgramAccount::Unknown);
+        data[0] = 1;
+        data.truncate(MINT_CONFIG_LAYOUT_LENS[0]);
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::OutdatedMintConfig { version: 1 }
+        );
+
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data = vec![0; OWNER_LIST_LEN];
+        data[..4].copy_from_slice(&2u32.to_le_bytes());
+        data[4..36].copy_from_slice(owners[0].as_ref());
+        data[36..68].copy_from_slice(owners[1].as_ref());
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::OwnerList(owners.to_vec())
+        );
+        data[..4].copy_from_slice(&(OWNER_LIST_CAPACITY as u32 + 1).to_le_bytes());
+        assert_eq!(decode_program_account(&data), ProgramAccount::Unknown);
+
+        let entry = AllowlistEntry {
+            owner: Pubkey::new_unique(),
+            expires_at: 100,
+        };
+        let mut data = vec![0; ALLOWLIST_PAGE_LEN];
+        data[..4].copy_from_slice(&1u32.to_le_bytes());
+        data[4..4 + ALLOWLIST_ENTRY_LEN].copy_from_slice(&borsh::to_vec(&entry).unwrap());
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::AllowlistPage(vec![entry])
+        );
+
+        let transfer = RecentTransfer {
+            owner: Pubkey::new_unique(),
+            amount: 10,
+            slot: 1,
+        };
+        assert_eq!(
+            decode_program_account(&pack_log(16, 1, &[(0, transfer)])),
+            ProgramAccount::RecentTransferLog(vec![transfer])
+        );
+
+        let snapshot = StatsSnapshot {
+            accepted_transfers: 2,
+            accepted_amount: 20,
+            epoch: 1,
+            epoch_volume: 10,
+            slot: 3,
+            unix_timestamp: 4,
+        };
+        let mut data = vec![0; STATS_HISTORY_LEN];
+        data[..8].copy_from_slice(&1u64.to_le_bytes());
+        data[8..8 + STATS_SNAPSHOT_LEN].copy_from_slice(&borsh::to_vec(&snapshot).unwrap());
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::StatsHistory(vec![snapshot])
+        );
+
+        let transfer_root = TransferRoot {
+            count: 3,
+            root: [7; 32],
+        };
+        let mut data = vec![0; TRANSFER_ROOT_LEN];
+        data[..40].copy_from_slice(&borsh::to_vec(&transfer_root).unwrap());
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::TransferRoot(transfer_root)
+        );
+
+        assert_eq!(decode_program_account(&[]), ProgramAccount::Unknown);
+        assert_eq!(decode_program_account(&[1; 42]), ProgramAccount::Unknown);
+    }
+
+    #[test]
+    fn unpack_partially_filled_log() {
+        let first = RecentTransfer {
+            owner: Pubkey::new_unique(),
+            amount: 10,
+            slot: 1,
+        };
+        let second = RecentTransfer {
+            owner: Pubkey::new_unique(),
+            amount: 20,
+            slot: 2,
+        };
+        let data = pack_log(4, 2, &[(0, first), (1, second)]);
+        assert_eq!(unpack_recent_transfers(&data).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn unpack_wrapped_log() {
+        let owner = Pubkey::new_unique();
+        let entry = |i: u64| RecentTransfer {
+            owner,
+            amount: i,
+            slot: i,
+        };
+        // 5 entries written into a log of capacity 3: positions 3 and 4 have
+        // overwritten indexes 0 and 1
+        let data = pack_log(3, 5, &[(0, entry(3)), (1, entry(4)), (2, entry(2))]);
+        assert_eq!(
+            unpack_recent_transfers(&data).unwrap(),
+            vec![entry(2), entry(3), entry(4)]
+        );
+    }
+
+    #[test]
+    fn unpack_invalid_log() {
+        assert_eq!(
+            unpack_recent_transfers(&[0; 7]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        assert_eq!(
+            unpack_recent_transfers(&[0; 8 + RECENT_TRANSFER_ENTRY_LEN + 1]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+}
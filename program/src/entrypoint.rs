@@ -1,9 +1,14 @@
 //! Program entrypoint
 
+#![allow(deprecated)]
+
 use {
-    crate::processor,
+    crate::{error::ExampleError, processor},
+    num_traits::FromPrimitive,
     solana_program::{
-        account_info::AccountInfo, entrypoint::ProgramResult, program_error::PrintProgramError,
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        program_error::{PrintProgramError, ProgramError},
         pubkey::Pubkey,
     },
     spl_transfer_hook_interface::error::TransferHookError,
@@ -17,7 +22,12 @@ fn process_instruction(
 ) -> ProgramResult {
     if let Err(error) = processor::process(program_id, accounts, instruction_data) {
         // catch the error so we can print it
-        error.print::<TransferHookError>();
+        match error {
+            ProgramError::Custom(code) if ExampleError::from_u32(code).is_some() => {
+                error.print::<ExampleError>()
+            }
+            _ => error.print::<TransferHookError>(),
+        }
         return Err(error);
     }
     Ok(())
@@ -0,0 +1,364 @@
+//! Error types
+
+#![allow(deprecated)]
+
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+
+/// Errors that may be returned by the example program, on top of the ones
+/// defined by the transfer hook interface.
+#[repr(u32)]
+#[derive(Clone, Debug, Eq, thiserror::Error, num_derive::FromPrimitive, PartialEq)]
+pub enum ExampleError {
+    /// Transfer exceeds the allowed percentage of the source balance
+    #[error("Transfer exceeds the allowed percentage of the source balance")]
+    TransferExceedsBalancePercentage = 56_668_618,
+    /// Incorrect mint config authority has signed the instruction
+    #[error("Incorrect mint config authority has signed the instruction")]
+    IncorrectConfigAuthority,
+    /// Owner is on the blocklist of the mint
+    #[error("Owner is on the blocklist of the mint")]
+    OwnerBlocklisted,
+    /// Owner list cannot hold any more owners
+    #[error("Owner list cannot hold any more owners")]
+    OwnerListFull,
+    /// Mint has no freeze authority
+    #[error("Mint has no freeze authority")]
+    MintHasNoFreezeAuthority,
+    /// Incorrect freeze authority has signed the instruction
+    #[error("Incorrect freeze authority has signed the instruction")]
+    IncorrectFreezeAuthority,
+    /// Transfer cooldown is still active for the owner
+    #[error("Transfer cooldown is still active for the owner")]
+    TransferCooldownActive,
+    /// Tracked transfer amount would go below zero
+    #[error("Tracked transfer amount would go below zero")]
+    TrackingUnderflow,
+    /// Transfer exceeds the amount allowed per slot window
+    #[error("Transfer exceeds the amount allowed per slot window")]
+    WindowLimitExceeded,
+    /// Zero-amount transfers are rejected for the mint
+    #[error("Zero-amount transfers are rejected for the mint")]
+    ZeroAmountTransfer,
+    /// Tracked transfer amount would overflow
+    #[error("Tracked transfer amount would overflow")]
+    TrackingOverflow,
+    /// Extra account metas resolve the same account more than once
+    #[error("Extra account metas resolve the same account more than once")]
+    DuplicateExtraAccountMeta,
+    /// Destination owner rejects incoming transfers
+    #[error("Destination owner rejects incoming transfers")]
+    IncomingTransfersRejected,
+    /// Validation account would exceed its maximum size
+    #[error("Validation account would exceed its maximum size")]
+    ValidationAccountTooLarge,
+    /// Not enough config signers have signed the instruction
+    #[error("Not enough config signers have signed the instruction")]
+    NotEnoughConfigSigners,
+    /// Mint's transfer hook is not this program
+    #[error("Mint's transfer hook is not this program")]
+    TransferHookProgramMismatch,
+    /// Created account is undersized or not rent exempt
+    #[error("Created account is undersized or not rent exempt")]
+    InvalidCreatedAccount,
+    /// Transfer amount is inconsistent with the token balances
+    #[error("Transfer amount is inconsistent with the token balances")]
+    InconsistentTransferAmount,
+    /// Holding period is still active for the owner
+    #[error("Holding period is still active for the owner")]
+    HoldingPeriodActive,
+    /// Transfer exceeds the remaining allowance of the owner
+    #[error("Transfer exceeds the remaining allowance of the owner")]
+    AllowanceExceeded,
+    /// Mint is paused or its mint authority has been revoked
+    #[error("Mint is paused or its mint authority has been revoked")]
+    MintHalted,
+    /// Mint account of the transfer is not a mint
+    #[error("Mint account of the transfer is not a mint")]
+    InvalidMintAccount,
+    /// Source is not a token account of the mint
+    #[error("Source is not a token account of the mint")]
+    SourceAccountMismatch,
+    /// Destination is not a token account of the mint
+    #[error("Destination is not a token account of the mint")]
+    DestinationAccountMismatch,
+    /// Authority is not allowed to transfer from the source
+    #[error("Authority is not allowed to transfer from the source")]
+    UnexpectedTransferAuthority,
+    /// Owner is not on the allowlist of the mint
+    #[error("Owner is not on the allowlist of the mint")]
+    OwnerNotAllowlisted,
+    /// Destination is owned by a PDA of the program
+    #[error("Destination is owned by a PDA of the program")]
+    DestinationOwnedByProgram,
+    /// Transfer exceeds the volume allowed for the mint per epoch
+    #[error("Transfer exceeds the volume allowed for the mint per epoch")]
+    EpochVolumeExceeded,
+    /// Transfer has no valid ticket from the ticket signer
+    #[error("Transfer has no valid ticket from the ticket signer")]
+    MissingTransferTicket,
+    /// Transfer exceeds the number of transfers allowed per day
+    #[error("Transfer exceeds the number of transfers allowed per day")]
+    DailyTransferLimitExceeded,
+    /// Owner of the source account has not signed the transfer
+    #[error("Owner of the source account has not signed the transfer")]
+    SourceOwnerNotSigner,
+    /// Transfer exceeds the total amount the destination owner may receive
+    #[error("Transfer exceeds the total amount the destination owner may receive")]
+    ReceiveCapExceeded,
+    /// Destination account is not rent exempt
+    #[error("Destination account is not rent exempt")]
+    DestinationNotRentExempt,
+    /// Account of an external PDA meta is not owned by its program
+    #[error("Account of an external PDA meta is not owned by its program")]
+    ExternalAccountOwnerMismatch,
+    /// Only the distributor may transfer until trading is enabled
+    #[error("Only the distributor may transfer until trading is enabled")]
+    TradingNotEnabled,
+    /// Transfers by a delegate of the source are rejected for the mint
+    #[error("Transfers by a delegate of the source are rejected for the mint")]
+    DelegateTransferRejected,
+    /// Transfer exceeds the allowed maximum reported through return data
+    #[error("Transfer exceeds the allowed maximum reported through return data")]
+    TransferExceedsAllowedMaximum,
+    /// Destination account lacks the extension required for the mint
+    #[error("Destination account lacks the extension required for the mint")]
+    DestinationExtensionMissing,
+    /// Transfer exceeds the amount vested for the owner
+    #[error("Transfer exceeds the amount vested for the owner")]
+    TransferExceedsVestedAmount,
+    /// Validation account was written for another version of the program
+    #[error("Validation account was written for another version of the program")]
+    ExtraAccountMetasVersionMismatch,
+    /// Payer has created the most transfer accounts allowed for the mint
+    #[error("Payer has created the most transfer accounts allowed for the mint")]
+    CreationCapExceeded,
+    /// Transfer account tracks another owner than the source owner
+    #[error("Transfer account tracks another owner than the source owner")]
+    TransferAccountOwnerMismatch,
+    /// Owner first received tokens fewer slots ago than the minimum account
+    /// age
+    #[error("Owner first received tokens fewer slots ago than the minimum account age")]
+    AccountTooYoung,
+    /// Mint decimals differ from the decimals expected by the config
+    #[error("Mint decimals differ from the decimals expected by the config")]
+    MintDecimalsMismatch,
+    /// Source owner can only receive transfers
+    #[error("Source owner can only receive transfers")]
+    OwnerReceiveOnly,
+    /// Validation account is too small to hold an extra account meta list
+    #[error("Validation account is too small to hold an extra account meta list")]
+    MetaListNotInitialized,
+    /// Validation account is not owned by the program
+    #[error("Validation account is not owned by the program")]
+    MetaListWrongOwner,
+    /// Transfers are rejected until the setup of the mint is finalized
+    #[error("Transfers are rejected until the setup of the mint is finalized")]
+    SetupNotFinalized,
+    /// Transfer root holds the most transfers allowed
+    #[error("Transfer root holds the most transfers allowed")]
+    TransferRootFull,
+    /// Transfer account is of an older layout, to be migrated
+    #[error("Transfer account is of an older layout, to be migrated")]
+    OutdatedTransferAccount,
+}
+
+impl ExampleError {
+    /// The `Execute` check that failed with this error, if any
+    pub fn failed_check(&self) -> Option<FailedCheck> {
+        match self {
+            ExampleError::TransferExceedsBalancePercentage => Some(FailedCheck::BalancePercentage),
+            ExampleError::OwnerBlocklisted => Some(FailedCheck::Blocklist),
+            ExampleError::TransferCooldownActive => Some(FailedCheck::Cooldown),
+            ExampleError::WindowLimitExceeded => Some(FailedCheck::WindowLimit),
+            ExampleError::ZeroAmountTransfer => Some(FailedCheck::ZeroAmount),
+            ExampleError::IncomingTransfersRejected => Some(FailedCheck::IncomingPolicy),
+            ExampleError::InconsistentTransferAmount => Some(FailedCheck::AmountConsistency),
+            ExampleError::HoldingPeriodActive => Some(FailedCheck::HoldingPeriod),
+            ExampleError::AllowanceExceeded => Some(FailedCheck::Allowance),
+            ExampleError::MintHalted => Some(FailedCheck::MintState),
+            ExampleError::OwnerNotAllowlisted => Some(FailedCheck::Allowlist),
+            ExampleError::DestinationOwnedByProgram => Some(FailedCheck::ProgramDestination),
+            ExampleError::EpochVolumeExceeded => Some(FailedCheck::EpochVolume),
+            ExampleError::MissingTransferTicket => Some(FailedCheck::TransferTicket),
+            ExampleError::DailyTransferLimitExceeded => Some(FailedCheck::DailyTransfers),
+            ExampleError::SourceOwnerNotSigner => Some(FailedCheck::OwnerSigner),
+            ExampleError::ReceiveCapExceeded => Some(FailedCheck::ReceiveCap),
+            ExampleError::DestinationNotRentExempt => Some(FailedCheck::DestinationRent),
+            ExampleError::ExternalAccountOwnerMismatch => Some(FailedCheck::ExternalOwner),
+            ExampleError::TradingNotEnabled => Some(FailedCheck::Trading),
+            ExampleError::DelegateTransferRejected => Some(FailedCheck::DelegateAuthority),
+            ExampleError::DestinationExtensionMissing => Some(FailedCheck::DestinationExtension),
+            ExampleError::TransferExceedsVestedAmount => Some(FailedCheck::Vesting),
+            ExampleError::AccountTooYoung => Some(FailedCheck::AccountAge),
+            ExampleError::MintDecimalsMismatch => Some(FailedCheck::MintDecimals),
+            ExampleError::OwnerReceiveOnly => Some(FailedCheck::ReceiveOnly),
+            ExampleError::SetupNotFinalized => Some(FailedCheck::Setup),
+            _ => None,
+        }
+    }
+
+    /// Decode the allowed maximum from the return data of an `Execute` that
+    /// failed with `TransferExceedsAllowedMaximum`
+    pub fn allowed_maximum_from_return_data(data: &[u8]) -> Option<u64> {
+        data.try_into().ok().map(u64::from_le_bytes)
+    }
+}
+
+/// Check that rejected an `Execute`, reported as a single byte of return
+/// data before the error is returned.
+///
+/// The return data is only visible when simulating the transaction: a failed
+/// transaction is rolled back, and the token program calling the hook aborts
+/// as soon as the hook errors. The error variant remains the reliable way to
+/// tell the failures apart.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, num_derive::FromPrimitive, PartialEq)]
+pub enum FailedCheck {
+    /// Transfer exceeds the allowed percentage of the source balance
+    BalancePercentage = 1,
+    /// Source or destination owner is blocklisted
+    Blocklist,
+    /// Transfer cooldown is still active
+    Cooldown,
+    /// Transfer exceeds the amount allowed per slot window
+    WindowLimit,
+    /// Zero-amount transfers are rejected
+    ZeroAmount,
+    /// Destination owner rejects incoming transfers
+    IncomingPolicy,
+    /// Transfer amount is inconsistent with the token balances
+    AmountConsistency,
+    /// Holding period is still active
+    HoldingPeriod,
+    /// Transfer exceeds the remaining allowance
+    Allowance,
+    /// Mint is paused or its mint authority has been revoked
+    MintState,
+    /// Source or destination owner is not allowlisted
+    Allowlist,
+    /// Destination is owned by a PDA of the program
+    ProgramDestination,
+    /// Transfer exceeds the volume allowed per epoch
+    EpochVolume,
+    /// Transfer has no valid ticket
+    TransferTicket,
+    /// Transfer exceeds the number of transfers allowed per day
+    DailyTransfers,
+    /// Source owner has not signed the transfer
+    OwnerSigner,
+    /// Transfer exceeds the total amount the destination owner may receive
+    ReceiveCap,
+    /// Destination account is not rent exempt
+    DestinationRent,
+    /// Account of an external PDA meta is not owned by its program
+    ExternalOwner,
+    /// Only the distributor may transfer until trading is enabled
+    Trading,
+    /// Transfer authority is a delegate of the source
+    DelegateAuthority,
+    /// Destination account lacks the required extension
+    DestinationExtension,
+    /// Transfer exceeds the amount vested
+    Vesting,
+    /// Minimum account age is not reached yet
+    AccountAge,
+    /// Mint decimals differ from the expected decimals
+    MintDecimals,
+    /// Source owner can only receive transfers
+    ReceiveOnly,
+    /// Setup of the mint is not finalized yet
+    Setup,
+}
+
+impl FailedCheck {
+    /// Decode the check from the return data of a failed `Execute`
+    pub fn from_return_data(data: &[u8]) -> Option<Self> {
+        match data {
+            [check] => num_traits::FromPrimitive::from_u8(*check),
+            _ => None,
+        }
+    }
+}
+
+impl From<ExampleError> for ProgramError {
+    fn from(e: ExampleError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for ExampleError {
+    fn type_of() -> &'static str {
+        "ExampleError"
+    }
+}
+
+impl PrintProgramError for ExampleError {
+    fn print<E>(&self)
+    where
+        E: 'static
+            + std::error::Error
+            + DecodeError<E>
+            + PrintProgramError
+            + num_traits::FromPrimitive,
+    {
+        msg!(&self.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_checks_are_distinct() {
+        let checks = [
+            ExampleError::TransferExceedsBalancePercentage,
+            ExampleError::OwnerBlocklisted,
+            ExampleError::TransferCooldownActive,
+            ExampleError::WindowLimitExceeded,
+            ExampleError::ZeroAmountTransfer,
+            ExampleError::IncomingTransfersRejected,
+            ExampleError::InconsistentTransferAmount,
+            ExampleError::HoldingPeriodActive,
+            ExampleError::AllowanceExceeded,
+            ExampleError::MintHalted,
+            ExampleError::OwnerNotAllowlisted,
+            ExampleError::DestinationOwnedByProgram,
+            ExampleError::EpochVolumeExceeded,
+            ExampleError::MissingTransferTicket,
+            ExampleError::DailyTransferLimitExceeded,
+            ExampleError::SourceOwnerNotSigner,
+            ExampleError::ReceiveCapExceeded,
+            ExampleError::DestinationNotRentExempt,
+            ExampleError::ExternalAccountOwnerMismatch,
+            ExampleError::TradingNotEnabled,
+            ExampleError::DelegateTransferRejected,
+            ExampleError::DestinationExtensionMissing,
+            ExampleError::TransferExceedsVestedAmount,
+            ExampleError::AccountTooYoung,
+            ExampleError::MintDecimalsMismatch,
+            ExampleError::OwnerReceiveOnly,
+            ExampleError::SetupNotFinalized,
+        ]
+        .map(|error| error.failed_check().unwrap() as u8);
+        for (i, check) in checks.iter().enumerate() {
+            assert!(!checks[..i].contains(check));
+            assert_eq!(
+                FailedCheck::from_return_data(&[*check]).map(|check| check as u8),
+                Some(*check)
+            );
+        }
+        assert_eq!(ExampleError::OwnerListFull.failed_check(), None);
+        // its return data is the allowed maximum instead
+        assert_eq!(
+            ExampleError::TransferExceedsAllowedMaximum.failed_check(),
+            None
+        );
+        assert_eq!(FailedCheck::from_return_data(&[]), None);
+        assert_eq!(FailedCheck::from_return_data(&[0]), None);
+    }
+}
@@ -0,0 +1,1071 @@
+//! Custom instructions supported by the example program, on top of the
+//! transfer-hook interface instructions
+
+use {
+    crate::{
+        get_allowlist_address, get_allowlist_page, get_blocklist_address, get_burn_ledger_address,
+        get_creation_count_address, get_exempt_owners_address, get_fee_ledger_address,
+        get_incoming_policy_address, get_mint_config_address, get_mint_stats_address,
+        get_receive_only_address, get_stats_history_address, get_transfer_account_address,
+        get_transfer_root_address, get_treasury_address, get_vesting_schedule_address,
+        processor::TransferAccount,
+        state::{MintConfig, VestingSchedule},
+    },
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    solana_system_interface::program as system_program,
+    spl_tlv_account_resolution::account::ExtraAccountMeta,
+    spl_transfer_tracking_interface::{
+        custom_instruction::CustomInstruction, solana_pubkey::Pubkey as InterfacePubkey,
+    },
+};
+
+pub use spl_transfer_tracking_interface::custom_instruction::instruction_discriminator;
+
+/// Status flags returned by `HealthCheck` as a single byte of return data
+pub mod health_status {
+    /// The validation account exists and is owned by the program
+    pub const VALIDATION_ACCOUNT_INITIALIZED: u8 = 1 << 0;
+    /// The validation account holds a valid extra account meta list
+    pub const VALIDATION_ACCOUNT_VALID: u8 = 1 << 1;
+    /// The mint config exists and is owned by the program
+    pub const MINT_CONFIG_INITIALIZED: u8 = 1 << 2;
+    /// The mint config holds a valid config
+    pub const MINT_CONFIG_VALID: u8 = 1 << 3;
+}
+
+/// Status flags returned by `VerifyExtraAccountMetaList` as a single byte of
+/// return data, describing the list as it was found
+pub mod meta_list_status {
+    /// The list holds the meta resolving the source owner's transfer account
+    pub const TRANSFER_ACCOUNT_META_PRESENT: u8 = 1 << 0;
+    /// The transfer account meta is the first of the list
+    pub const TRANSFER_ACCOUNT_META_FIRST: u8 = 1 << 1;
+    /// The transfer account meta is writable and doesn't sign
+    pub const TRANSFER_ACCOUNT_META_FLAGS: u8 = 1 << 2;
+    /// The list was rewritten to match the template
+    pub const REPAIRED: u8 = 1 << 3;
+    /// Flags of a list matching the template
+    pub const MATCHING: u8 =
+        TRANSFER_ACCOUNT_META_PRESENT | TRANSFER_ACCOUNT_META_FIRST | TRANSFER_ACCOUNT_META_FLAGS;
+}
+
+/// Creates an `InitializeTransferAccount` instruction.
+///
+/// The mint is always provided, even though it's only part of the transfer
+/// account derivation with the `per-mint-tracking` feature.
+pub fn initialize_transfer_account(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    let transfer_account_pubkey =
+        get_transfer_account_address(owner_pubkey, mint_pubkey, program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner_pubkey, true),
+            AccountMeta::new(transfer_account_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+        ],
+        data: CustomInstruction::InitializeTransferAccount.pack(),
+    }
+}
+
+/// Creates an `InitializeTransferAccount` instruction whose rent is funded by
+/// a payer other than the owner, e.g. a service onboarding owners. The owner
+/// still signs, and the account is counted against the payer when the mint
+/// caps the transfer accounts created per payer.
+pub fn initialize_transfer_account_with_payer(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    let mut instruction = initialize_transfer_account(program_id, owner_pubkey, mint_pubkey);
+    instruction.accounts[0].is_writable = false;
+    instruction.accounts.extend([
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+        AccountMeta::new(
+            get_creation_count_address(payer_pubkey, mint_pubkey, program_id),
+            false,
+        ),
+    ]);
+    instruction
+}
+
+/// Creates an `InitializeDestinationAccount` instruction, creating the
+/// transfer account of the owner of the destination token account.
+pub fn initialize_destination_account(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    destination_owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_transfer_account_address(destination_owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*destination_pubkey, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new(
+                get_creation_count_address(payer_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+        ],
+        data: CustomInstruction::InitializeDestinationAccount.pack(),
+    }
+}
+
+/// Creates a `ReinitializeTransferAccount` instruction, resetting the owner's
+/// corrupted transfer account to the current layout.
+pub fn reinitialize_transfer_account(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner_pubkey, true),
+            AccountMeta::new(
+                get_transfer_account_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+        ],
+        data: CustomInstruction::ReinitializeTransferAccount.pack(),
+    }
+}
+
+/// Creates a `MigrateTransferAccount` instruction, rewriting the owner's
+/// transfer account of an older layout with the current one.
+pub fn migrate_transfer_account(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_transfer_account_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*owner_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+        ],
+        data: CustomInstruction::MigrateTransferAccount.pack(),
+    }
+}
+
+/// Creates a `SetLabel` instruction, overwriting the label of the owner's
+/// transfer account.
+pub fn set_label(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    label: &[u8; TransferAccount::LABEL_LEN],
+) -> Instruction {
+    let transfer_account_pubkey =
+        get_transfer_account_address(owner_pubkey, mint_pubkey, program_id);
+    let data = CustomInstruction::SetLabel { label: *label }.pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*owner_pubkey, true),
+            AccountMeta::new(transfer_account_pubkey, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `SetIncomingPolicy` instruction, setting whether transfers to
+/// the owner are rejected.
+pub fn set_incoming_policy(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    reject_incoming: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner_pubkey, true),
+            AccountMeta::new(
+                get_incoming_policy_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::SetIncomingPolicy { reject_incoming }.pack(),
+    }
+}
+
+/// Creates a `SetReceiveOnly` instruction, setting whether the owner can only
+/// receive transfers. The payer funds the flag on first use.
+pub fn set_receive_only(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    receive_only: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_receive_only_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*owner_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::SetReceiveOnly { receive_only }.pack(),
+    }
+}
+
+/// Creates a `ReassignTransferAccount` instruction, which both owners must
+/// sign.
+pub fn reassign_transfer_account(
+    program_id: &Pubkey,
+    old_owner_pubkey: &Pubkey,
+    new_owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*old_owner_pubkey, true),
+            AccountMeta::new(
+                get_transfer_account_address(old_owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new(*new_owner_pubkey, true),
+            AccountMeta::new(
+                get_transfer_account_address(new_owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+        ],
+        data: CustomInstruction::ReassignTransferAccount.pack(),
+    }
+}
+
+/// Creates a `HealthCheck` instruction, whose return data is a bitmask of
+/// [`health_status`] flags.
+pub fn health_check(program_id: &Pubkey, mint_pubkey: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(
+                spl_transfer_hook_interface::get_extra_account_metas_address(
+                    mint_pubkey,
+                    program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+        ],
+        data: CustomInstruction::HealthCheck.pack(),
+    }
+}
+
+/// Creates a `VerifyExtraAccountMetaList` instruction, whose return data is a
+/// bitmask of [`meta_list_status`] flags.
+pub fn verify_extra_account_meta_list(program_id: &Pubkey, mint_pubkey: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(
+                spl_transfer_hook_interface::get_extra_account_metas_address(
+                    mint_pubkey,
+                    program_id,
+                ),
+                false,
+            ),
+        ],
+        data: CustomInstruction::VerifyExtraAccountMetaList { repair: false }.pack(),
+    }
+}
+
+/// Creates a `VerifyExtraAccountMetaList` instruction in repair mode, which
+/// rewrites the list to match the template. The payer funds the validation
+/// account when the transfer account meta has to be added.
+pub fn repair_extra_account_meta_list(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new(
+                spl_transfer_hook_interface::get_extra_account_metas_address(
+                    mint_pubkey,
+                    program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::VerifyExtraAccountMetaList { repair: true }.pack(),
+    }
+}
+
+/// Creates a `ReadTotals` instruction, whose return data is the `transfered`
+/// total of each transfer account, as concatenated little-endian `u64`s.
+pub fn read_totals(program_id: &Pubkey, transfer_account_pubkeys: &[Pubkey]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: transfer_account_pubkeys
+            .iter()
+            .map(|pubkey| AccountMeta::new_readonly(*pubkey, false))
+            .collect(),
+        data: CustomInstruction::ReadTotals.pack(),
+    }
+}
+
+/// Creates a `GetMintConfig` instruction, whose return data is the config of
+/// the mint, packed by `MintConfig::pack`.
+pub fn get_mint_config(program_id: &Pubkey, mint_pubkey: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+        ],
+        data: CustomInstruction::GetMintConfig.pack(),
+    }
+}
+
+/// Creates the instruction sent by `Execute` to the notify program of the
+/// mint, if configured.
+///
+/// The instruction has no accounts, and its data is the owner of the source
+/// account (32 bytes) followed by the transferred amount (little-endian u64).
+pub fn notify_transfer(notify_program_id: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: *notify_program_id,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Creates an `InitializeRecentTransferLog` instruction.
+pub fn initialize_recent_transfer_log(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    recent_transfer_log_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(*recent_transfer_log_pubkey, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeRecentTransferLog.pack(),
+    }
+}
+
+/// Creates an `InitializeMintStats` instruction.
+pub fn initialize_mint_stats(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_mint_stats_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeMintStats.pack(),
+    }
+}
+
+/// Creates an `InitializeTransferRoot` instruction.
+pub fn initialize_transfer_root(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_transfer_root_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeTransferRoot.pack(),
+    }
+}
+
+/// Creates an `InitializeFeeLedger` instruction.
+pub fn initialize_fee_ledger(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    category: u8,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_fee_ledger_address(mint_pubkey, category, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeFeeLedger { category }.pack(),
+    }
+}
+
+/// Creates an `InitializeBurnLedger` instruction, for the burn owed by
+/// `owner_pubkey`.
+pub fn initialize_burn_ledger(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_burn_ledger_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeBurnLedger.pack(),
+    }
+}
+
+/// Creates a `SettleBurn` instruction, burning the amount owed by the owner
+/// from their token account.
+pub fn settle_burn(
+    program_id: &Pubkey,
+    token_account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(
+                get_burn_ledger_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new(*token_account_pubkey, false),
+            AccountMeta::new(*mint_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, true),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        data: CustomInstruction::SettleBurn.pack(),
+    }
+}
+
+/// Creates an `InitializeMintConfig` instruction.
+///
+/// The config authority is set to the mint authority, with every check
+/// disabled.
+pub fn initialize_mint_config(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*mint_authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::InitializeMintConfig.pack(),
+    }
+}
+
+/// Creates a `SetupMint` instruction, initializing the validation account
+/// with the extra account metas and the mint config with the provided config
+/// in a single instruction.
+pub fn setup_mint(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    extra_account_metas: &[ExtraAccountMeta],
+    config: &MintConfig,
+) -> Instruction {
+    let mut packed_config = vec![0; MintConfig::LEN];
+    config.pack(&mut packed_config);
+    let data = CustomInstruction::SetupMint {
+        extra_account_metas: extra_account_metas
+            .iter()
+            .map(|meta| bytemuck::cast(*meta))
+            .collect(),
+        config: packed_config,
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                spl_transfer_hook_interface::get_extra_account_metas_address(
+                    mint_pubkey,
+                    program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*mint_authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `ReplaceExtraAccountMetaList` instruction, re-initializing the
+/// validation account of the mint with the given metas.
+pub fn replace_extra_account_meta_list(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    mint_authority_pubkey: &Pubkey,
+    extra_account_metas: &[ExtraAccountMeta],
+) -> Instruction {
+    let data = CustomInstruction::ReplaceExtraAccountMetaList {
+        extra_account_metas: extra_account_metas
+            .iter()
+            .map(|meta| bytemuck::cast(*meta))
+            .collect(),
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                spl_transfer_hook_interface::get_extra_account_metas_address(
+                    mint_pubkey,
+                    program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*mint_authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates an `UpdateMintConfig` instruction, overwriting the whole config.
+pub fn update_mint_config(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    config: &MintConfig,
+) -> Instruction {
+    let mut packed_config = vec![0; MintConfig::LEN];
+    config.pack(&mut packed_config);
+    let data = CustomInstruction::UpdateMintConfig {
+        config: packed_config,
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data,
+    }
+}
+
+/// Creates a `MigrateMintConfig` instruction, rewriting a config of an older
+/// layout version with the current one.
+pub fn migrate_mint_config(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::MigrateMintConfig.pack(),
+    }
+}
+
+/// Creates a `SnapshotStats` instruction, appending the current mint stats
+/// to the stats history of the mint.
+pub fn snapshot_stats(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_stats_history_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_stats_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::SnapshotStats.pack(),
+    }
+}
+
+/// Creates an `EnableTrading` instruction, letting anyone transfer, not only
+/// the distributor of the mint.
+pub fn enable_trading(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::EnableTrading.pack(),
+    }
+}
+
+/// Creates a `FinalizeSetup` instruction, marking the setup of the mint as
+/// complete so that its transfers are accepted.
+pub fn finalize_setup(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::FinalizeSetup.pack(),
+    }
+}
+
+/// Creates a `SchedulePause` instruction, pausing the transfers of the mint
+/// from `slot` for the reason, padded with zero bytes.
+pub fn schedule_pause(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    slot: u64,
+    reason: &[u8; MintConfig::PAUSE_REASON_LEN],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::SchedulePause {
+            slot,
+            reason: *reason,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CancelPause` instruction, cancelling the scheduled pause of the
+/// mint.
+pub fn cancel_pause(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::CancelPause.pack(),
+    }
+}
+
+/// Appends the config signers to an instruction changing the config of a
+/// mint, `UpdateMintConfig`, `EnableTrading`, `FinalizeSetup`,
+/// `SchedulePause`, `CancelPause`,
+/// `MigrateMintConfig`, `SetAllowance`, `SetVestingSchedule`,
+/// `ResetAccountsBatch`, `SnapshotStats`, `AddExemptOwner`,
+/// `RemoveExemptOwner`, `AddAllowlistedOwner`, `RemoveAllowlistedOwner` or
+/// `TeardownMint`, for configs with a signer threshold.
+pub fn with_config_signers(
+    mut instruction: Instruction,
+    signer_pubkeys: &[&Pubkey],
+) -> Instruction {
+    instruction.accounts.extend(
+        signer_pubkeys
+            .iter()
+            .map(|signer_pubkey| AccountMeta::new_readonly(**signer_pubkey, true)),
+    );
+    instruction
+}
+
+/// Creates a `FreezeOwner` instruction, freezing the token account through
+/// Token-2022 and adding its owner to the mint's blocklist.
+pub fn freeze_owner(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    token_account_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_blocklist_address(mint_pubkey, program_id), false),
+            AccountMeta::new(*token_account_pubkey, false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*freeze_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::FreezeOwner.pack(),
+    }
+}
+
+/// Creates a `SetAllowance` instruction, setting the allowance of the owner
+/// to `amount`, or adding `amount` to it when `top_up` is set.
+pub fn set_allowance(
+    program_id: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    amount: u64,
+    top_up: bool,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(
+                get_transfer_account_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::SetAllowance { amount, top_up }.pack(),
+    }
+}
+
+/// Creates a `SetVestingSchedule` instruction, setting the vesting schedule
+/// of the owner.
+pub fn set_vesting_schedule(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    schedule: &VestingSchedule,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_vesting_schedule_address(owner_pubkey, mint_pubkey, program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data: CustomInstruction::SetVestingSchedule {
+            total: schedule.total,
+            start_timestamp: schedule.start_timestamp,
+            cliff_timestamp: schedule.cliff_timestamp,
+            duration_seconds: schedule.duration_seconds,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `TeardownMint` instruction, closing every account of the mint,
+/// including the listed allowlist pages and fee ledgers, and draining their
+/// rent and the treasury to the recipient.
+pub fn teardown_mint(
+    program_id: &Pubkey,
+    recipient_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    allowlist_pages: &[u8],
+    fee_categories: &[u8],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*recipient_pubkey, false),
+        AccountMeta::new(get_mint_config_address(mint_pubkey, program_id), false),
+        AccountMeta::new(get_mint_stats_address(mint_pubkey, program_id), false),
+        AccountMeta::new(
+            spl_transfer_hook_interface::get_extra_account_metas_address(mint_pubkey, program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, true),
+        AccountMeta::new(get_blocklist_address(mint_pubkey, program_id), false),
+        AccountMeta::new(get_exempt_owners_address(mint_pubkey, program_id), false),
+        AccountMeta::new(get_stats_history_address(mint_pubkey, program_id), false),
+        AccountMeta::new(get_transfer_root_address(mint_pubkey, program_id), false),
+        AccountMeta::new(get_treasury_address(mint_pubkey, program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(allowlist_pages.iter().map(|page| {
+        AccountMeta::new(get_allowlist_address(mint_pubkey, *page, program_id), false)
+    }));
+    accounts.extend(fee_categories.iter().map(|category| {
+        AccountMeta::new(
+            get_fee_ledger_address(mint_pubkey, *category, program_id),
+            false,
+        )
+    }));
+    #[cfg(feature = "recent-log")]
+    accounts.push(AccountMeta::new(
+        crate::get_recent_transfer_log_address(mint_pubkey, program_id),
+        false,
+    ));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: CustomInstruction::TeardownMint {
+            allowlist_pages: allowlist_pages.to_vec(),
+            fee_categories: fee_categories.to_vec(),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `WithdrawTreasury` instruction, moving `amount` lamports from
+/// the treasury of the mint to the recipient.
+pub fn withdraw_treasury(
+    program_id: &Pubkey,
+    recipient_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_treasury_address(mint_pubkey, program_id), false),
+            AccountMeta::new(*recipient_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CustomInstruction::WithdrawTreasury { amount }.pack(),
+    }
+}
+
+/// Creates a `ResetAccountsBatch` instruction, zeroing the totals of the
+/// transfer accounts of the owners.
+pub fn reset_accounts_batch(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkeys: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*mint_pubkey, false),
+        AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+        AccountMeta::new_readonly(*authority_pubkey, true),
+    ];
+    accounts.extend(owner_pubkeys.iter().map(|owner_pubkey| {
+        AccountMeta::new(
+            get_transfer_account_address(owner_pubkey, mint_pubkey, program_id),
+            false,
+        )
+    }));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: CustomInstruction::ResetAccountsBatch {
+            count: owner_pubkeys.len() as u8,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `AddExemptOwner` instruction, whose transfers then skip
+/// tracking.
+pub fn add_exempt_owner(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let data = CustomInstruction::AddExemptOwner {
+        owner: InterfacePubkey::new_from_array(owner.to_bytes()),
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(get_exempt_owners_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `RemoveExemptOwner` instruction.
+pub fn remove_exempt_owner(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let data = CustomInstruction::RemoveExemptOwner {
+        owner: InterfacePubkey::new_from_array(owner.to_bytes()),
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(get_exempt_owners_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data,
+    }
+}
+
+/// Creates an `AddAllowlistedOwner` instruction, adding the owner to the
+/// allowlist page it falls on until the `expires_at` unix timestamp, or for
+/// good if 0.
+pub fn add_allowlisted_owner(
+    program_id: &Pubkey,
+    payer_pubkey: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner: &Pubkey,
+    expires_at: i64,
+) -> Instruction {
+    let data = CustomInstruction::AddAllowlistedOwner {
+        owner: InterfacePubkey::new_from_array(owner.to_bytes()),
+        expires_at,
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer_pubkey, true),
+            AccountMeta::new(
+                get_allowlist_address(mint_pubkey, get_allowlist_page(owner), program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Creates a `RemoveAllowlistedOwner` instruction.
+pub fn remove_allowlisted_owner(
+    program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let data = CustomInstruction::RemoveAllowlistedOwner {
+        owner: InterfacePubkey::new_from_array(owner.to_bytes()),
+    }
+    .pack();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(
+                get_allowlist_address(mint_pubkey, get_allowlist_page(owner), program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint_pubkey, false),
+            AccountMeta::new_readonly(get_mint_config_address(mint_pubkey, program_id), false),
+            AccountMeta::new_readonly(*authority_pubkey, true),
+        ],
+        data,
+    }
+}
@@ -7,6 +7,8 @@
 #![deny(missing_docs)]
 #![cfg_attr(not(test), forbid(unsafe_code))]
 
+pub mod error;
+pub mod instruction;
 pub mod processor;
 pub mod state;
 
@@ -17,6 +19,348 @@ mod entrypoint;
 // version
 pub use solana_program;
 
+use {
+    solana_program::pubkey::Pubkey,
+    spl_transfer_tracking_interface::{
+        pda::{
+            self, SEED_ALLOWLIST, SEED_BLOCKLIST, SEED_BURN_LEDGER, SEED_CONFIG,
+            SEED_CREATION_COUNT, SEED_EXEMPT_OWNERS, SEED_FEE_LEDGER, SEED_INCOMING_POLICY,
+            SEED_RECEIVE_ONLY, SEED_RECENT_TRANSFER_LOG, SEED_STATS, SEED_STATS_HISTORY,
+            SEED_TRANSFER_ROOT, SEED_TREASURY, SEED_VESTING_SCHEDULE,
+        },
+        solana_pubkey::Pubkey as InterfacePubkey,
+    },
+};
+
+/// Convert a pubkey to the sdk version of the interface crate, whose `pda`
+/// module derives every address of the program
+pub(crate) fn interface_pubkey(pubkey: &Pubkey) -> InterfacePubkey {
+    InterfacePubkey::new_from_array(pubkey.to_bytes())
+}
+
+/// Convert an address derived by the interface `pda` module, along with its
+/// bump seed, back to the sdk version of the program
+pub(crate) fn program_address((address, bump_seed): (InterfacePubkey, u8)) -> (Pubkey, u8) {
+    (Pubkey::new_from_array(address.to_bytes()), bump_seed)
+}
+
+/// Get the transfer account PDA tracking an owner's transfers
+///
+/// By default the PDA is derived from the owner only, so an owner shares a
+/// single tracking account across every mint using this program. With the
+/// `per-mint-tracking` feature, the mint is also part of the seeds.
+pub fn get_transfer_account_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_transfer_account_address(
+        &interface_pubkey(owner),
+        &interface_pubkey(mint),
+        cfg!(feature = "per-mint-tracking"),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the seeds for the transfer account PDA
+pub fn collect_transfer_account_seeds<'a>(owner: &'a Pubkey, mint: &'a Pubkey) -> Vec<&'a [u8]> {
+    if cfg!(feature = "per-mint-tracking") {
+        vec![owner.as_ref(), mint.as_ref()]
+    } else {
+        vec![owner.as_ref()]
+    }
+}
+
+/// Get the signer seeds for the transfer account PDA
+pub fn collect_transfer_account_signer_seeds<'a>(
+    owner: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> Vec<&'a [u8]> {
+    let mut seeds = collect_transfer_account_seeds(owner, mint);
+    seeds.push(bump_seed);
+    seeds
+}
+
+/// Get the mint config PDA
+pub fn get_mint_config_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_mint_config_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the mint config PDA
+pub fn collect_mint_config_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [SEED_CONFIG, mint.as_ref(), bump_seed]
+}
+
+/// Get the blocklist PDA of a mint
+pub fn get_blocklist_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_blocklist_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the blocklist PDA
+pub fn collect_blocklist_signer_seeds<'a>(mint: &'a Pubkey, bump_seed: &'a [u8]) -> [&'a [u8]; 3] {
+    [SEED_BLOCKLIST, mint.as_ref(), bump_seed]
+}
+
+/// Get the exempt owners PDA of a mint
+pub fn get_exempt_owners_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_exempt_owners_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the exempt owners PDA
+pub fn collect_exempt_owners_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [SEED_EXEMPT_OWNERS, mint.as_ref(), bump_seed]
+}
+
+/// Get the incoming policy PDA of an owner for a mint
+pub fn get_incoming_policy_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_incoming_policy_address(
+        &interface_pubkey(owner),
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the incoming policy PDA
+pub fn collect_incoming_policy_signer_seeds<'a>(
+    owner: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_INCOMING_POLICY,
+        mint.as_ref(),
+        owner.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// Get the receive-only flag PDA of an owner for a mint
+pub fn get_receive_only_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_receive_only_address(
+        &interface_pubkey(owner),
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the receive-only flag PDA
+pub fn collect_receive_only_signer_seeds<'a>(
+    owner: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [SEED_RECEIVE_ONLY, mint.as_ref(), owner.as_ref(), bump_seed]
+}
+
+/// Get the allowlist page an owner falls on: the first byte of the owner,
+/// which the extra account metas of `Execute` can resolve from the token
+/// account data
+pub fn get_allowlist_page(owner: &Pubkey) -> u8 {
+    owner.as_ref()[0]
+}
+
+/// Get the PDA of an allowlist page of a mint
+pub fn get_allowlist_address(mint: &Pubkey, page: u8, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_allowlist_address(
+        &interface_pubkey(mint),
+        page,
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the PDA of an allowlist page
+pub fn collect_allowlist_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    page: &'a [u8],
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [SEED_ALLOWLIST, mint.as_ref(), page, bump_seed]
+}
+
+/// Get the burn ledger PDA of an owner for a mint
+pub fn get_burn_ledger_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_burn_ledger_address(
+        &interface_pubkey(owner),
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the burn ledger PDA
+pub fn collect_burn_ledger_signer_seeds<'a>(
+    owner: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [SEED_BURN_LEDGER, mint.as_ref(), owner.as_ref(), bump_seed]
+}
+
+/// Get the vesting schedule PDA of an owner for a mint
+pub fn get_vesting_schedule_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_vesting_schedule_address(
+        &interface_pubkey(owner),
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the vesting schedule PDA
+pub fn collect_vesting_schedule_signer_seeds<'a>(
+    owner: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_VESTING_SCHEDULE,
+        mint.as_ref(),
+        owner.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// Get the PDA counting the transfer accounts a payer created for a mint
+pub fn get_creation_count_address(payer: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_creation_count_address(
+        &interface_pubkey(payer),
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the creation count PDA
+pub fn collect_creation_count_signer_seeds<'a>(
+    payer: &'a Pubkey,
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [
+        SEED_CREATION_COUNT,
+        mint.as_ref(),
+        payer.as_ref(),
+        bump_seed,
+    ]
+}
+
+/// Get the fee ledger PDA of a category for a mint
+pub fn get_fee_ledger_address(mint: &Pubkey, category: u8, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_fee_ledger_address(
+        &interface_pubkey(mint),
+        category,
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the fee ledger PDA
+pub fn collect_fee_ledger_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    category: &'a [u8],
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 4] {
+    [SEED_FEE_LEDGER, mint.as_ref(), category, bump_seed]
+}
+
+/// Get the treasury PDA of a mint
+pub fn get_treasury_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_treasury_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the treasury PDA
+pub fn collect_treasury_signer_seeds<'a>(mint: &'a Pubkey, bump_seed: &'a [u8]) -> [&'a [u8]; 3] {
+    [SEED_TREASURY, mint.as_ref(), bump_seed]
+}
+
+/// Get the transfer statistics PDA of a mint
+pub fn get_mint_stats_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_mint_stats_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the transfer statistics PDA
+pub fn collect_mint_stats_signer_seeds<'a>(mint: &'a Pubkey, bump_seed: &'a [u8]) -> [&'a [u8]; 3] {
+    [SEED_STATS, mint.as_ref(), bump_seed]
+}
+
+/// Get the transfer root PDA of a mint
+pub fn get_transfer_root_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_transfer_root_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the transfer root PDA
+pub fn collect_transfer_root_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [SEED_TRANSFER_ROOT, mint.as_ref(), bump_seed]
+}
+
+/// Get the stats history PDA of a mint
+pub fn get_stats_history_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_stats_history_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the stats history PDA
+pub fn collect_stats_history_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [SEED_STATS_HISTORY, mint.as_ref(), bump_seed]
+}
+
+/// Get the recent transfer log PDA for a mint
+pub fn get_recent_transfer_log_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    program_address(pda::find_recent_transfer_log_address(
+        &interface_pubkey(mint),
+        &interface_pubkey(program_id),
+    ))
+    .0
+}
+
+/// Get the signer seeds for the recent transfer log PDA
+pub fn collect_recent_transfer_log_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [SEED_RECENT_TRANSFER_LOG, mint.as_ref(), bump_seed]
+}
+
 /// Place the mint id that you want to target with your transfer hook program.
 /// Any other mint will fail to initialize, protecting the transfer hook program
 /// from rogue mints trying to get access to accounts.
@@ -1,20 +1,59 @@
 //! Program state processor
 
+#[cfg(feature = "recent-log")]
+use crate::{
+    collect_recent_transfer_log_signer_seeds, get_recent_transfer_log_address,
+    state::RecentTransferLog,
+};
 use {
+    crate::{
+        collect_allowlist_signer_seeds, collect_blocklist_signer_seeds,
+        collect_burn_ledger_signer_seeds, collect_creation_count_signer_seeds,
+        collect_exempt_owners_signer_seeds, collect_fee_ledger_signer_seeds,
+        collect_incoming_policy_signer_seeds, collect_mint_config_signer_seeds,
+        collect_mint_stats_signer_seeds, collect_receive_only_signer_seeds,
+        collect_stats_history_signer_seeds, collect_transfer_account_signer_seeds,
+        collect_transfer_root_signer_seeds, collect_treasury_signer_seeds,
+        collect_vesting_schedule_signer_seeds,
+        error::ExampleError,
+        get_allowlist_address, get_allowlist_page, get_blocklist_address, get_burn_ledger_address,
+        get_exempt_owners_address, get_fee_ledger_address, get_incoming_policy_address,
+        get_mint_config_address, get_mint_stats_address, get_receive_only_address,
+        get_stats_history_address, get_transfer_account_address, get_transfer_root_address,
+        get_vesting_schedule_address,
+        instruction::{health_status, meta_list_status, notify_transfer},
+        interface_pubkey, program_address,
+        state::{
+            AccessMode, AllowlistPage, BurnLedger, CreationCount, FeeLedger, IncomingPolicy,
+            MintConfig, MintStats, OwnerList, ReceiveOnly, StatsHistory, StatsSnapshot,
+            TransferRoot, TransferTicket, VestingSchedule, ZeroAmountMode,
+        },
+    },
+    num_traits::FromPrimitive,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
-        entrypoint::ProgramResult,
+        clock::Clock,
+        ed25519_program,
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+        hash::hashv,
+        log::sol_log_data,
         msg,
-        program::invoke_signed,
+        program::{invoke, invoke_signed, set_return_data, MAX_RETURN_DATA},
         program_error::ProgramError,
+        program_option::COption,
         pubkey::Pubkey,
         rent::Rent,
+        sysvar::{self, Sysvar},
     },
     solana_system_interface::instruction as system_instruction,
     spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList},
     spl_token_2022::{
         extension::{
-            transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+            pausable::PausableConfig,
+            permanent_delegate::PermanentDelegate,
+            transfer_fee::TransferFeeConfig,
+            transfer_hook::{TransferHook, TransferHookAccount},
+            BaseStateWithExtensions, StateWithExtensions,
         },
         state::{Account, Mint},
     },
@@ -24,12 +63,18 @@ use {
         get_extra_account_metas_address, get_extra_account_metas_address_and_bump_seed,
         instruction::{ExecuteInstruction, TransferHookInstruction},
     },
+    spl_transfer_tracking_interface::{
+        custom_instruction::CustomInstruction, error::TransferHookError as TrackingHookError,
+        event::TransferEvent, merkle::transfer_leaf, pda, solana_pubkey::Pubkey as InterfacePubkey,
+        transfer_tracking_extra_account_metas,
+    },
     spl_type_length_value::state::TlvStateBorrowed,
+    std::slice::{self, Iter},
 };
 
-fn check_token_account_is_transferring(account_info: &AccountInfo) -> Result<(), ProgramError> {
-    let account_data = account_info.try_borrow_data()?;
-    let token_account = StateWithExtensions::<Account>::unpack(&account_data)?;
+fn check_token_account_is_transferring(
+    token_account: &StateWithExtensions<Account>,
+) -> Result<(), ProgramError> {
     let extension = token_account.get_extension::<TransferHookAccount>()?;
     if bool::from(extension.transferring) {
         Ok(())
@@ -39,25 +84,179 @@ fn check_token_account_is_transferring(account_info: &AccountInfo) -> Result<(),
 }
 
 /// Transfer account state structure
-pub struct TransferAccount;
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferAccount {
+    /// Owner whose transfers are tracked
+    pub owner: Pubkey,
+    /// Total amount transferred
+    pub transfered: u64,
+    /// Unix timestamp of the last transfer, zero if unknown
+    pub last_transfer_timestamp: i64,
+    /// Label set by the owner for off-chain categorization
+    pub label: [u8; TransferAccount::LABEL_LEN],
+    /// Slot at which the current rate limiting window started
+    pub window_start_slot: u64,
+    /// Amount transferred during the current rate limiting window
+    pub window_amount: u64,
+    /// Unix timestamp of the first tracked transfer, zero if unknown
+    pub first_transfer_timestamp: i64,
+    /// Unix timestamp of the initialization of the account, or of the first
+    /// transfer received by an account reset without one, zero if unknown
+    pub first_received_timestamp: i64,
+    /// Amount the owner can still transfer, debited by each tracked transfer
+    /// when the mint config enforces allowances
+    pub allowance_remaining: u64,
+    /// Nonce of the last transfer ticket used by the owner, when the mint
+    /// config requires tickets
+    pub last_ticket_nonce: u64,
+    /// Unix timestamp of the start of the UTC day of the last counted
+    /// transfer, zero if none
+    pub day_start_ts: i64,
+    /// Number of transfers made during the day starting at `day_start_ts`,
+    /// when the mint config caps them
+    pub day_count: u32,
+    /// Total amount received from other owners, recorded when the transfer
+    /// account is passed to `Execute` along with the other accounts
+    pub received: u64,
+    /// Largest single tracked transfer of the owner
+    pub max_single_transfer: u64,
+    /// Slot of the initialization of the account, or of the first transfer
+    /// received by an account reset without one, zero if unknown
+    pub first_received_slot: u64,
+    /// Fingerprint of the transaction whose partial fills were last
+    /// aggregated, zero if none
+    pub fill_transaction: [u8; 32],
+    /// Amount of the partial fills aggregated for that transaction, which the
+    /// per-transfer limits apply to when the mint config aggregates them
+    pub fill_amount: u64,
+    /// Index of the instruction of the transaction that made the last of
+    /// those partial fills
+    pub fill_instruction_index: u16,
+}
 
 impl TransferAccount {
+    /// Size of the owner-defined label
+    pub const LABEL_LEN: usize = spl_transfer_tracking_interface::custom_instruction::LABEL_LEN;
     /// Size of the transfer account data
-    pub const LEN: usize = 32 + 8; // Pubkey (32) + u64 (8)
+    pub const LEN: usize = Self::FILL_INSTRUCTION_INDEX_OFFSET + 2;
+
+    /// Size of the data of each older layout, which tells them apart as
+    /// fields were only ever appended
+    const LAYOUT_LENS: [usize; 12] = [
+        // the owner and the transferred amount
+        Self::LAST_TRANSFER_TIMESTAMP_OFFSET,
+        // before the label
+        Self::LABEL_OFFSET,
+        // before the rate limiting window
+        Self::WINDOW_START_SLOT_OFFSET,
+        // before the first transfer timestamp
+        Self::FIRST_TRANSFER_TIMESTAMP_OFFSET,
+        // before the first received timestamp
+        Self::FIRST_RECEIVED_TIMESTAMP_OFFSET,
+        // before the allowance
+        Self::ALLOWANCE_REMAINING_OFFSET,
+        // before the ticket nonce
+        Self::LAST_TICKET_NONCE_OFFSET,
+        // before the daily transfer count
+        Self::DAY_START_TS_OFFSET,
+        // before the received amount
+        Self::RECEIVED_OFFSET,
+        // before the largest single transfer
+        Self::MAX_SINGLE_TRANSFER_OFFSET,
+        // before the first received slot
+        Self::FIRST_RECEIVED_SLOT_OFFSET,
+        // before the partial fill aggregation
+        Self::FILL_TRANSACTION_OFFSET,
+    ];
 
     // Offsets
     const OWNER_OFFSET: usize = 0;
     const TRANSFERED_OFFSET: usize = 32;
+    const LAST_TRANSFER_TIMESTAMP_OFFSET: usize = 40;
+    const LABEL_OFFSET: usize = 48;
+    const WINDOW_START_SLOT_OFFSET: usize = Self::LABEL_OFFSET + Self::LABEL_LEN;
+    const WINDOW_AMOUNT_OFFSET: usize = Self::WINDOW_START_SLOT_OFFSET + 8;
+    const FIRST_TRANSFER_TIMESTAMP_OFFSET: usize = Self::WINDOW_AMOUNT_OFFSET + 8;
+    const FIRST_RECEIVED_TIMESTAMP_OFFSET: usize = Self::FIRST_TRANSFER_TIMESTAMP_OFFSET + 8;
+    const ALLOWANCE_REMAINING_OFFSET: usize = Self::FIRST_RECEIVED_TIMESTAMP_OFFSET + 8;
+    const LAST_TICKET_NONCE_OFFSET: usize = Self::ALLOWANCE_REMAINING_OFFSET + 8;
+    const DAY_START_TS_OFFSET: usize = Self::LAST_TICKET_NONCE_OFFSET + 8;
+    const DAY_COUNT_OFFSET: usize = Self::DAY_START_TS_OFFSET + 8;
+    const RECEIVED_OFFSET: usize = Self::DAY_COUNT_OFFSET + 4;
+    const MAX_SINGLE_TRANSFER_OFFSET: usize = Self::RECEIVED_OFFSET + 8;
+    const FIRST_RECEIVED_SLOT_OFFSET: usize = Self::MAX_SINGLE_TRANSFER_OFFSET + 8;
+    const FILL_TRANSACTION_OFFSET: usize = Self::FIRST_RECEIVED_SLOT_OFFSET + 8;
+    const FILL_AMOUNT_OFFSET: usize = Self::FILL_TRANSACTION_OFFSET + 32;
+    const FILL_INSTRUCTION_INDEX_OFFSET: usize = Self::FILL_AMOUNT_OFFSET + 8;
+
+    /// Blank transfer account of the owner, initialized at the current time,
+    /// which stands for the first receive of the tokens the owner got without
+    /// a transfer, such as minted ones
+    pub fn new_initialized(owner: Pubkey) -> Result<Self, ProgramError> {
+        let clock = Clock::get()?;
+        Ok(Self {
+            owner,
+            first_received_timestamp: clock.unix_timestamp,
+            first_received_slot: clock.slot,
+            ..Self::default()
+        })
+    }
 
     /// Pack transfer account data into bytes
-    pub fn pack(owner: &Pubkey, transfered: u64, dst: &mut [u8]) {
-        dst[Self::OWNER_OFFSET..Self::OWNER_OFFSET + 32].copy_from_slice(owner.as_ref());
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[Self::OWNER_OFFSET..Self::OWNER_OFFSET + 32].copy_from_slice(self.owner.as_ref());
         dst[Self::TRANSFERED_OFFSET..Self::TRANSFERED_OFFSET + 8]
-            .copy_from_slice(&transfered.to_le_bytes());
+            .copy_from_slice(&self.transfered.to_le_bytes());
+        dst[Self::LAST_TRANSFER_TIMESTAMP_OFFSET..Self::LAST_TRANSFER_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&self.last_transfer_timestamp.to_le_bytes());
+        dst[Self::LABEL_OFFSET..Self::LABEL_OFFSET + Self::LABEL_LEN].copy_from_slice(&self.label);
+        dst[Self::WINDOW_START_SLOT_OFFSET..Self::WINDOW_START_SLOT_OFFSET + 8]
+            .copy_from_slice(&self.window_start_slot.to_le_bytes());
+        dst[Self::WINDOW_AMOUNT_OFFSET..Self::WINDOW_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.window_amount.to_le_bytes());
+        dst[Self::FIRST_TRANSFER_TIMESTAMP_OFFSET..Self::FIRST_TRANSFER_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&self.first_transfer_timestamp.to_le_bytes());
+        dst[Self::FIRST_RECEIVED_TIMESTAMP_OFFSET..Self::FIRST_RECEIVED_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&self.first_received_timestamp.to_le_bytes());
+        dst[Self::ALLOWANCE_REMAINING_OFFSET..Self::ALLOWANCE_REMAINING_OFFSET + 8]
+            .copy_from_slice(&self.allowance_remaining.to_le_bytes());
+        dst[Self::LAST_TICKET_NONCE_OFFSET..Self::LAST_TICKET_NONCE_OFFSET + 8]
+            .copy_from_slice(&self.last_ticket_nonce.to_le_bytes());
+        dst[Self::DAY_START_TS_OFFSET..Self::DAY_START_TS_OFFSET + 8]
+            .copy_from_slice(&self.day_start_ts.to_le_bytes());
+        dst[Self::DAY_COUNT_OFFSET..Self::DAY_COUNT_OFFSET + 4]
+            .copy_from_slice(&self.day_count.to_le_bytes());
+        dst[Self::RECEIVED_OFFSET..Self::RECEIVED_OFFSET + 8]
+            .copy_from_slice(&self.received.to_le_bytes());
+        dst[Self::MAX_SINGLE_TRANSFER_OFFSET..Self::MAX_SINGLE_TRANSFER_OFFSET + 8]
+            .copy_from_slice(&self.max_single_transfer.to_le_bytes());
+        dst[Self::FIRST_RECEIVED_SLOT_OFFSET..Self::FIRST_RECEIVED_SLOT_OFFSET + 8]
+            .copy_from_slice(&self.first_received_slot.to_le_bytes());
+        dst[Self::FILL_TRANSACTION_OFFSET..Self::FILL_TRANSACTION_OFFSET + 32]
+            .copy_from_slice(&self.fill_transaction);
+        dst[Self::FILL_AMOUNT_OFFSET..Self::FILL_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.fill_amount.to_le_bytes());
+        dst[Self::FILL_INSTRUCTION_INDEX_OFFSET..Self::FILL_INSTRUCTION_INDEX_OFFSET + 2]
+            .copy_from_slice(&self.fill_instruction_index.to_le_bytes());
+    }
+
+    /// Unpack a transfer account written with an older layout, the fields
+    /// appended since keeping their zero defaults
+    pub fn unpack_outdated(src: &[u8]) -> Result<Self, ProgramError> {
+        if !Self::LAYOUT_LENS.contains(&src.len()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut data = [0; Self::LEN];
+        data[..src.len()].copy_from_slice(src);
+        Self::unpack(&data)
     }
 
-    /// Unpack transfer account data from bytes
-    pub fn unpack(src: &[u8]) -> Result<(Pubkey, u64), ProgramError> {
+    /// Unpack transfer account data from bytes, failing with
+    /// `OutdatedTransferAccount` for an account of an older layout
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if Self::LAYOUT_LENS.contains(&src.len()) {
+            return Err(ExampleError::OutdatedTransferAccount.into());
+        }
         if src.len() < Self::LEN {
             return Err(ProgramError::InvalidAccountData);
         }
@@ -71,7 +270,119 @@ impl TransferAccount {
                 .map_err(|_| ProgramError::InvalidAccountData)?,
         );
 
-        Ok((owner, transfered))
+        let last_transfer_timestamp = i64::from_le_bytes(
+            src[Self::LAST_TRANSFER_TIMESTAMP_OFFSET..Self::LAST_TRANSFER_TIMESTAMP_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let label = src[Self::LABEL_OFFSET..Self::LABEL_OFFSET + Self::LABEL_LEN]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        let window_start_slot = u64::from_le_bytes(
+            src[Self::WINDOW_START_SLOT_OFFSET..Self::WINDOW_START_SLOT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let window_amount = u64::from_le_bytes(
+            src[Self::WINDOW_AMOUNT_OFFSET..Self::WINDOW_AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let first_transfer_timestamp = i64::from_le_bytes(
+            src[Self::FIRST_TRANSFER_TIMESTAMP_OFFSET..Self::FIRST_TRANSFER_TIMESTAMP_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let first_received_timestamp = i64::from_le_bytes(
+            src[Self::FIRST_RECEIVED_TIMESTAMP_OFFSET..Self::FIRST_RECEIVED_TIMESTAMP_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let allowance_remaining = u64::from_le_bytes(
+            src[Self::ALLOWANCE_REMAINING_OFFSET..Self::ALLOWANCE_REMAINING_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let last_ticket_nonce = u64::from_le_bytes(
+            src[Self::LAST_TICKET_NONCE_OFFSET..Self::LAST_TICKET_NONCE_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let day_start_ts = i64::from_le_bytes(
+            src[Self::DAY_START_TS_OFFSET..Self::DAY_START_TS_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let day_count = u32::from_le_bytes(
+            src[Self::DAY_COUNT_OFFSET..Self::DAY_COUNT_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let received = u64::from_le_bytes(
+            src[Self::RECEIVED_OFFSET..Self::RECEIVED_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let max_single_transfer = u64::from_le_bytes(
+            src[Self::MAX_SINGLE_TRANSFER_OFFSET..Self::MAX_SINGLE_TRANSFER_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let first_received_slot = u64::from_le_bytes(
+            src[Self::FIRST_RECEIVED_SLOT_OFFSET..Self::FIRST_RECEIVED_SLOT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let fill_transaction = src
+            [Self::FILL_TRANSACTION_OFFSET..Self::FILL_TRANSACTION_OFFSET + 32]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        let fill_amount = u64::from_le_bytes(
+            src[Self::FILL_AMOUNT_OFFSET..Self::FILL_AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let fill_instruction_index = u16::from_le_bytes(
+            src[Self::FILL_INSTRUCTION_INDEX_OFFSET..Self::FILL_INSTRUCTION_INDEX_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self {
+            owner,
+            transfered,
+            last_transfer_timestamp,
+            label,
+            window_start_slot,
+            window_amount,
+            first_transfer_timestamp,
+            first_received_timestamp,
+            allowance_remaining,
+            last_ticket_nonce,
+            day_start_ts,
+            day_count,
+            received,
+            max_single_transfer,
+            first_received_slot,
+            fill_transaction,
+            fill_amount,
+            fill_instruction_index,
+        })
     }
 
     /// Update only the transferred amount
@@ -79,37 +390,79 @@ impl TransferAccount {
         data[Self::TRANSFERED_OFFSET..Self::TRANSFERED_OFFSET + 8]
             .copy_from_slice(&transfered.to_le_bytes());
     }
-}
 
-/// Custom instruction discriminators
-pub mod instruction_discriminator {
-    /// Initialize transfer account (custom instruction)
-    pub const INITIALIZE_TRANSFER_ACCOUNT: u8 = 255;
+    /// Decrement only the transferred amount, failing if it would go below
+    /// zero
+    pub fn decrement_transfered(data: &mut [u8], amount: u64) -> Result<(), ProgramError> {
+        let transfered = Self::unpack(data)?
+            .transfered
+            .checked_sub(amount)
+            .ok_or(ExampleError::TrackingUnderflow)?;
+        Self::update_transfered(data, transfered);
+        Ok(())
+    }
 }
 
 /// Process InitializeTransferAccount instruction
 /// Accounts:
-/// 0. Owner/payer (signer, writable)
-/// 1. Transfer account (writable, derived from owner - matches index 3 in Execute)
+/// 0. Owner (signer, writable when paying)
+/// 1. Transfer account (writable, derived from owner - the source token account
+///    owner in Execute)
 /// 2. System program
-pub fn process_initialize_transfer_account<'a>(
+/// 3. Mint (only required with the `per-mint-tracking` feature, or before a
+///    payer)
+/// 4. Payer (optional signer, writable), funding the rent instead of the owner
+/// 5. Mint config (required with a payer), so its cap on the transfer
+///    accounts created per payer can't be skipped
+/// 6. Creation count (writable, derived from the mint and the payer), only
+///    required when the config caps the transfer accounts created per payer
+///
+/// An owner funding its own transfer account creates at most one for the
+/// mint, which no cap rejects, so only the accounts funded by a payer are
+/// counted. Without `per-mint-tracking`, transfer accounts are shared by every
+/// mint, so with `forbid-additional-mints` they're capped by the config of the
+/// one mint, which can't be swapped for another mint without a cap.
+pub fn process_initialize_transfer_account(
     program_id: &Pubkey,
-    accounts: &[AccountInfo<'a>],
+    accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let owner_info = next_account_info(account_info_iter)?;
     let transfer_account_info = next_account_info(account_info_iter)?;
-    let system_program_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    // The mint is only part of the derivation with `per-mint-tracking`
+    let mint_info = account_info_iter.next();
+    let mint_key = &if cfg!(feature = "per-mint-tracking") {
+        *mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?.key
+    } else {
+        Pubkey::default()
+    };
+    let payer_info = account_info_iter.next();
 
-    // Verify owner is signer
-    if !owner_info.is_signer {
+    // check that shared transfer accounts are created through the one mint we
+    // want to target
+    #[cfg(all(
+        feature = "forbid-additional-mints",
+        not(feature = "per-mint-tracking")
+    ))]
+    if mint_info.is_some_and(|mint_info| *mint_info.key != crate::mint::id()) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Verify owner and payer are signers
+    if !owner_info.is_signer || payer_info.is_some_and(|payer_info| !payer_info.is_signer) {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Verify transfer account is derived from owner (matches index 3 in Execute)
-    let (expected_pda, bump_seed) =
-        Pubkey::find_program_address(&[owner_info.key.as_ref()], program_id);
+    // Verify transfer account is derived from owner (the source token account
+    // owner in Execute)
+    let (expected_pda, bump_seed) = program_address(pda::find_transfer_account_address(
+        &interface_pubkey(owner_info.key),
+        &interface_pubkey(mint_key),
+        cfg!(feature = "per-mint-tracking"),
+        &interface_pubkey(program_id),
+    ));
     msg!("Expected PDA: {}", expected_pda);
     msg!("Transfer account: {}", transfer_account_info.key);
 
@@ -128,216 +481,4286 @@ pub fn process_initialize_transfer_account<'a>(
         return Ok(());
     }
 
+    if let (Some(mint_info), Some(payer_info)) = (mint_info, payer_info) {
+        let mint_config_info = next_account_info(account_info_iter)?;
+        count_capped_creation(
+            program_id,
+            account_info_iter,
+            mint_config_info,
+            mint_info,
+            payer_info,
+        )?;
+    }
+    let payer_info = payer_info.unwrap_or(owner_info);
+
     // Calculate rent
-    let required_lamports = Rent::default().minimum_balance(TransferAccount::LEN);
+    let required_lamports = Rent::get()?.minimum_balance(TransferAccount::LEN);
 
     // Create account with seed
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_transfer_account_signer_seeds(owner_info.key, mint_key, &bump_seed);
     invoke_signed(
         &system_instruction::create_account(
-            owner_info.key,
+            payer_info.key,
             transfer_account_info.key,
             required_lamports,
             TransferAccount::LEN as u64,
             program_id,
         ),
-        &[owner_info.clone(), transfer_account_info.clone()],
-        &[&[&owner_info.key.to_bytes(), &[bump_seed]]],
+        &[payer_info.clone(), transfer_account_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    check_created_account(
+        transfer_account_info,
+        TransferAccount::LEN,
+        required_lamports,
     )?;
 
     // Initialize account data
     let mut data = transfer_account_info.try_borrow_mut_data()?;
-    TransferAccount::pack(owner_info.key, 0, &mut data);
+    TransferAccount::new_initialized(*owner_info.key)?.pack(&mut data);
 
     msg!("Transfer account initialized for owner: {}", owner_info.key);
     Ok(())
 }
 
-/// Processes an [Execute](enum.TransferHookInstruction.html) instruction.
-pub fn process_execute(
+/// Count a transfer account created by the payer, when the config of the mint
+/// caps the transfer accounts created per payer. The creation count is then
+/// the next account.
+fn count_capped_creation<'a>(
     program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amount: u64,
+    account_info_iter: &mut Iter<AccountInfo<'a>>,
+    mint_config_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-
-    let source_account_info = next_account_info(account_info_iter)?;
-    let mint_info = next_account_info(account_info_iter)?;
-    let destination_account_info = next_account_info(account_info_iter)?;
-    let _authority_info = next_account_info(account_info_iter)?;
-    let extra_account_metas_info = next_account_info(account_info_iter)?;
-
-    // Check that the accounts are properly in "transferring" mode
-    check_token_account_is_transferring(source_account_info)?;
-    check_token_account_is_transferring(destination_account_info)?;
-
-    // For the example program, we just check that the correct pda and validation
-    // pubkeys are provided
-    let expected_validation_address = get_extra_account_metas_address(mint_info.key, program_id);
-    if expected_validation_address != *extra_account_metas_info.key {
+    if *mint_config_info.key != get_mint_config_address(mint_info.key, program_id) {
         return Err(ProgramError::InvalidSeeds);
     }
-
-    let data = extra_account_metas_info.try_borrow_data()?;
-
-    ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
-        accounts,
-        &TransferHookInstruction::Execute { amount }.pack(),
+    let config = get_mint_config(program_id, slice::from_ref(mint_config_info), mint_info)?
+        .unwrap_or_default();
+    if config.max_transfer_accounts_per_payer == 0 {
+        return Ok(());
+    }
+    count_creation(
         program_id,
-        &data,
-    )?;
-
-    // Get the extra account metas from the account data
-    let data = extra_account_metas_info.try_borrow_data()?;
-    msg!("Data: {:?}", data);
-    let state = TlvStateBorrowed::unpack(&data).unwrap();
-    let _extra_account_metas =
-        ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&state)?;
-
-    // Get the transfer account (must already exist)
-    let transfer_account = next_account_info(account_info_iter)?;
+        next_account_info(account_info_iter)?,
+        mint_info,
+        payer_info,
+        config.max_transfer_accounts_per_payer,
+    )
+}
 
-    // Verify transfer account exists and is initialized
-    if transfer_account.lamports() == 0 {
-        msg!("Transfer account does not exist. Call InitializeTransferAccount first.");
-        msg!("Transfer account: {}", transfer_account.key);
-        return Err(ProgramError::UninitializedAccount);
+/// Count a transfer account created by the payer in its creation count, created
+/// on first use, failing once the payer has created `max_created` of them
+fn count_creation<'a>(
+    program_id: &Pubkey,
+    creation_count_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    max_created: u32,
+) -> ProgramResult {
+    let (expected_creation_count_address, bump_seed) =
+        program_address(pda::find_creation_count_address(
+            &interface_pubkey(payer_info.key),
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_creation_count_address != *creation_count_info.key {
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    // Verify transfer account is owned by this program
-    if transfer_account.owner != program_id {
-        msg!("Transfer account not owned by program");
+    if creation_count_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds =
+            collect_creation_count_signer_seeds(payer_info.key, mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                creation_count_info.key,
+                Rent::get()?.minimum_balance(CreationCount::LEN),
+                CreationCount::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), creation_count_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if creation_count_info.owner != program_id {
         return Err(ProgramError::IllegalOwner);
     }
 
-    // Update the transfer amount
-    let mut transfer_account_data = transfer_account.try_borrow_mut_data()?;
-    let (_, current_amount) = TransferAccount::unpack(&transfer_account_data)?;
-    TransferAccount::update_transfered(&mut transfer_account_data, current_amount + amount);
-
-    msg!(
-        "Transfer tracked: {} total for account {}",
-        current_amount + amount,
-        transfer_account.key
-    );
-
+    let mut data = creation_count_info.try_borrow_mut_data()?;
+    let mut creation_count = CreationCount::unpack(&data)?;
+    if creation_count.created >= max_created {
+        msg!(
+            "Payer {} created {} transfer accounts, at most {} allowed",
+            payer_info.key,
+            creation_count.created,
+            max_created
+        );
+        return Err(ExampleError::CreationCapExceeded.into());
+    }
+    creation_count.created += 1;
+    creation_count.pack(&mut data);
     Ok(())
 }
 
-/// Processes a
-/// [`InitializeExtraAccountMetaList`](enum.TransferHookInstruction.html)
-/// instruction.
-pub fn process_initialize_extra_account_meta_list(
+/// Process InitializeDestinationAccount instruction, creating the transfer
+/// account of the owner of a destination token account, so senders can make
+/// sure it exists before transferring to it. Does nothing if it already
+/// exists.
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Transfer account (writable, derived from the destination owner)
+/// 2. Destination token account
+/// 3. Mint
+/// 4. System program
+/// 5. Mint config, so its cap on the transfer accounts created per payer can't
+///    be skipped
+/// 6. Creation count (writable, derived from the mint and the payer), only
+///    required when the config caps the transfer accounts created per payer
+pub fn process_initialize_destination_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    extra_account_metas: &[ExtraAccountMeta],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
-    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let transfer_account_info = next_account_info(account_info_iter)?;
+    let destination_account_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
     let _system_program_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    #[cfg(feature = "per-mint-tracking")]
+    let mint_key = mint_info.key;
+    #[cfg(not(feature = "per-mint-tracking"))]
+    let mint_key = &Pubkey::default();
 
-    // check that the one mint we want to target is trying to create extra
-    // account metas
-    #[cfg(feature = "forbid-additional-mints")]
-    if *mint_info.key != crate::mint::id() {
-        return Err(ProgramError::InvalidArgument);
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // check that the mint authority is valid without fully deserializing
-    let mint_data = mint_info.try_borrow_data()?;
-    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
-    let mint_authority = mint
-        .base
-        .mint_authority
-        .ok_or(TransferHookError::MintHasNoMintAuthority)?;
+    let destination_owner = {
+        let destination_data = destination_account_info.try_borrow_data()?;
+        match StateWithExtensions::<Account>::unpack(&destination_data) {
+            Ok(destination) if destination.base.mint == *mint_info.key => destination.base.owner,
+            _ => {
+                msg!(
+                    "Account {} is not a token account of the mint",
+                    destination_account_info.key
+                );
+                return Err(ExampleError::DestinationAccountMismatch.into());
+            }
+        }
+    };
 
-    // Check signers
-    if !authority_info.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-    if *authority_info.key != mint_authority {
-        return Err(TransferHookError::IncorrectMintAuthority.into());
+    let (expected_pda, bump_seed) = program_address(pda::find_transfer_account_address(
+        &interface_pubkey(&destination_owner),
+        &interface_pubkey(mint_key),
+        cfg!(feature = "per-mint-tracking"),
+        &interface_pubkey(program_id),
+    ));
+    if *transfer_account_info.key != expected_pda {
+        msg!(
+            "Invalid transfer account derivation. Expected: {}, Got: {}",
+            expected_pda,
+            transfer_account_info.key
+        );
+        return Err(ProgramError::InvalidSeeds);
     }
 
-    // Check validation account
-    let (expected_validation_address, bump_seed) =
-        get_extra_account_metas_address_and_bump_seed(mint_info.key, program_id);
-    if expected_validation_address != *extra_account_metas_info.key {
-        return Err(ProgramError::InvalidSeeds);
+    if transfer_account_info.owner == program_id {
+        msg!("Transfer account already exists");
+        return Ok(());
     }
 
-    // Create the account
+    count_capped_creation(
+        program_id,
+        account_info_iter,
+        mint_config_info,
+        mint_info,
+        payer_info,
+    )?;
+
+    let required_lamports = Rent::get()?.minimum_balance(TransferAccount::LEN);
     let bump_seed = [bump_seed];
-    let signer_seeds = collect_extra_account_metas_signer_seeds(mint_info.key, &bump_seed);
-    let length = extra_account_metas.len();
-    let account_size = ExtraAccountMetaList::size_of(length)?;
+    let signer_seeds =
+        collect_transfer_account_signer_seeds(&destination_owner, mint_key, &bump_seed);
     invoke_signed(
-        &system_instruction::allocate(extra_account_metas_info.key, account_size as u64),
-        &[extra_account_metas_info.clone()],
+        &system_instruction::create_account(
+            payer_info.key,
+            transfer_account_info.key,
+            required_lamports,
+            TransferAccount::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), transfer_account_info.clone()],
         &[&signer_seeds],
     )?;
-    invoke_signed(
-        &system_instruction::assign(extra_account_metas_info.key, program_id),
-        &[extra_account_metas_info.clone()],
-        &[&signer_seeds],
+
+    check_created_account(
+        transfer_account_info,
+        TransferAccount::LEN,
+        required_lamports,
     )?;
 
-    // Write the data
-    let mut data = extra_account_metas_info.try_borrow_mut_data()?;
-    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, extra_account_metas)?;
+    let mut data = transfer_account_info.try_borrow_mut_data()?;
+    TransferAccount::new_initialized(destination_owner)?.pack(&mut data);
 
+    msg!(
+        "Transfer account initialized for destination owner: {}",
+        destination_owner
+    );
     Ok(())
 }
 
-/// Processes a
-/// [`UpdateExtraAccountMetaList`](enum.TransferHookInstruction.html)
-/// instruction.
-pub fn process_update_extra_account_meta_list(
+/// Check that an account just created by the system program holds `len` bytes
+/// and at least `required_lamports`, before its data is packed
+fn check_created_account(
+    account_info: &AccountInfo,
+    len: usize,
+    required_lamports: u64,
+) -> ProgramResult {
+    if account_info.data_len() != len || account_info.lamports() < required_lamports {
+        msg!(
+            "Created account {} has {} bytes and {} lamports, expected {} bytes and {} lamports",
+            account_info.key,
+            account_info.data_len(),
+            account_info.lamports(),
+            len,
+            required_lamports
+        );
+        return Err(ExampleError::InvalidCreatedAccount.into());
+    }
+    Ok(())
+}
+
+/// Process ReinitializeTransferAccount instruction, resetting a transfer
+/// account that doesn't unpack, or isn't of the size of the current layout, to
+/// a blank account of the current layout. A healthy account is rejected, so
+/// that its owner can't drop the limits tracked on it, as is an account of an
+/// older layout, which `MigrateTransferAccount` brings to the current one.
+///
+/// Everything tracked is dropped, and the owner field is written back with the
+/// owner the account is derived from, as on initialization, so that the
+/// account tracks its owner again.
+/// Accounts:
+/// 0. Owner (signer, writable), funding or refunded the rent of the new size
+/// 1. Transfer account (writable, derived from owner)
+/// 2. System program
+/// 3. Mint (only required with the `per-mint-tracking` feature)
+pub fn process_reinitialize_transfer_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    extra_account_metas: &[ExtraAccountMeta],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
-    let extra_account_metas_info = next_account_info(account_info_iter)?;
-    let mint_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-
-    // check that the mint authority is valid without fully deserializing
-    let mint_data = mint_info.try_borrow_data()?;
-    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
-    let mint_authority = mint
-        .base
-        .mint_authority
-        .ok_or(TransferHookError::MintHasNoMintAuthority)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let transfer_account_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    #[cfg(feature = "per-mint-tracking")]
+    let mint_key = next_account_info(account_info_iter)?.key;
+    #[cfg(not(feature = "per-mint-tracking"))]
+    let mint_key = &Pubkey::default();
 
-    // Check signers
-    if !authority_info.is_signer {
+    if !owner_info.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
-    if *authority_info.key != mint_authority {
-        return Err(TransferHookError::IncorrectMintAuthority.into());
-    }
-
-    // Check validation account
-    let expected_validation_address = get_extra_account_metas_address(mint_info.key, program_id);
-    if expected_validation_address != *extra_account_metas_info.key {
+    if get_transfer_account_address(owner_info.key, mint_key, program_id)
+        != *transfer_account_info.key
+    {
         return Err(ProgramError::InvalidSeeds);
     }
-
-    // Check if the extra metas have been initialized
+    if transfer_account_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if transfer_account_info.data_len() == TransferAccount::LEN
+        && TransferAccount::unpack(&transfer_account_info.try_borrow_data()?).is_ok()
+    {
+        msg!("Transfer account is not corrupted");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if TransferAccount::unpack_outdated(&transfer_account_info.try_borrow_data()?).is_ok() {
+        msg!("Transfer account is of an older layout. Call MigrateTransferAccount instead.");
+        return Err(ExampleError::OutdatedTransferAccount.into());
+    }
+
+    msg!(
+        "Reinitializing transfer account of {} bytes",
+        transfer_account_info.data_len()
+    );
+    transfer_account_info.resize(TransferAccount::LEN)?;
+    {
+        let mut data = transfer_account_info.try_borrow_mut_data()?;
+        data.fill(0);
+        TransferAccount::new_initialized(*owner_info.key)?.pack(&mut data);
+    }
+    settle_rent(transfer_account_info, owner_info, system_program_info)?;
+
+    msg!(
+        "Transfer account reinitialized for owner: {}",
+        owner_info.key
+    );
+    Ok(())
+}
+
+/// Process MigrateTransferAccount instruction, rewriting a transfer account of
+/// an older layout with the current one. Everything tracked is kept, and an
+/// account that predates the first receive fields starts its holding period
+/// and age at the migration, as on initialization.
+/// Accounts:
+/// 0. Payer (signer, writable), funding the larger account
+/// 1. Transfer account (writable, derived from owner)
+/// 2. Owner
+/// 3. System program
+/// 4. Mint (only required with the `per-mint-tracking` feature)
+pub fn process_migrate_transfer_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let transfer_account_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    #[cfg(feature = "per-mint-tracking")]
+    let mint_key = next_account_info(account_info_iter)?.key;
+    #[cfg(not(feature = "per-mint-tracking"))]
+    let mint_key = &Pubkey::default();
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if get_transfer_account_address(owner_info.key, mint_key, program_id)
+        != *transfer_account_info.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if transfer_account_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut tracked = TransferAccount::unpack_outdated(&transfer_account_info.try_borrow_data()?)
+        .inspect_err(|_| {
+        msg!("Transfer account is not of an older layout");
+    })?;
+    let clock = Clock::get()?;
+    if tracked.first_received_timestamp == 0 {
+        tracked.first_received_timestamp = clock.unix_timestamp;
+    }
+    if tracked.first_received_slot == 0 {
+        tracked.first_received_slot = clock.slot;
+    }
+
+    transfer_account_info.resize(TransferAccount::LEN)?;
+    tracked.pack(&mut transfer_account_info.try_borrow_mut_data()?);
+    settle_rent(transfer_account_info, payer_info, system_program_info)?;
+
+    msg!("Transfer account migrated for owner: {}", owner_info.key);
+    Ok(())
+}
+
+/// Process SetLabel instruction
+/// Accounts:
+/// 0. Owner (signer)
+/// 1. Transfer account (writable, derived from owner)
+/// 2. Mint (only required with the `per-mint-tracking` feature)
+pub fn process_set_label(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    label: [u8; TransferAccount::LABEL_LEN],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let owner_info = next_account_info(account_info_iter)?;
+    let transfer_account_info = next_account_info(account_info_iter)?;
+    #[cfg(feature = "per-mint-tracking")]
+    let mint_key = next_account_info(account_info_iter)?.key;
+    #[cfg(not(feature = "per-mint-tracking"))]
+    let mint_key = &Pubkey::default();
+
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if get_transfer_account_address(owner_info.key, mint_key, program_id)
+        != *transfer_account_info.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if transfer_account_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let mut data = transfer_account_info.try_borrow_mut_data()?;
+    let mut transfer_account = TransferAccount::unpack(&data)?;
+    transfer_account.label = label;
+    transfer_account.pack(&mut data);
+    Ok(())
+}
+
+/// Process SetIncomingPolicy instruction, creating the owner's policy on
+/// first use
+/// Accounts:
+/// 0. Owner (signer, writable, pays for the policy if needed)
+/// 1. Incoming policy (writable, derived from the mint and the owner)
+/// 2. Mint
+/// 3. System program
+pub fn process_set_incoming_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    reject_incoming: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let owner_info = next_account_info(account_info_iter)?;
+    let incoming_policy_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (expected_incoming_policy_address, bump_seed) =
+        program_address(pda::find_incoming_policy_address(
+            &interface_pubkey(owner_info.key),
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_incoming_policy_address != *incoming_policy_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the policy on first use
+    if incoming_policy_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds =
+            collect_incoming_policy_signer_seeds(owner_info.key, mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                incoming_policy_info.key,
+                Rent::get()?.minimum_balance(IncomingPolicy::LEN),
+                IncomingPolicy::LEN as u64,
+                program_id,
+            ),
+            &[owner_info.clone(), incoming_policy_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if incoming_policy_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    IncomingPolicy { reject_incoming }.pack(&mut incoming_policy_info.try_borrow_mut_data()?);
+
+    msg!(
+        "Incoming transfers to {} rejected: {}",
+        owner_info.key,
+        reject_incoming
+    );
+    Ok(())
+}
+
+/// Process SetReceiveOnly instruction, setting whether an owner can only
+/// receive transfers, and creating their flag on first use
+/// Accounts:
+/// 0. Payer (signer, writable), funding the flag if needed
+/// 1. Receive-only flag (writable, derived from the mint and the owner)
+/// 2. Owner
+/// 3. Mint config
+/// 4. Mint
+/// 5. Config authority (signer)
+/// 6. System program
+/// 7. ..7+M Config signers (signer), when the config has a signer threshold
+pub fn process_set_receive_only(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    receive_only: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let receive_only_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    let (expected_receive_only_address, bump_seed) =
+        program_address(pda::find_receive_only_address(
+            &interface_pubkey(owner_info.key),
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_receive_only_address != *receive_only_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the flag on first use
+    if receive_only_info.lamports() == 0 {
+        if !payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let bump_seed = [bump_seed];
+        let signer_seeds =
+            collect_receive_only_signer_seeds(owner_info.key, mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                receive_only_info.key,
+                Rent::get()?.minimum_balance(ReceiveOnly::LEN),
+                ReceiveOnly::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), receive_only_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if receive_only_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    ReceiveOnly { receive_only }.pack(&mut receive_only_info.try_borrow_mut_data()?);
+
+    msg!("Owner {} receive only: {}", owner_info.key, receive_only);
+    Ok(())
+}
+
+/// Maximum number of transfer accounts read by `ReadTotals`, bounded by the
+/// size of the return data
+pub const MAX_READ_TOTALS: usize = MAX_RETURN_DATA / 8;
+
+/// Process ReadTotals instruction, setting the `transfered` total of each
+/// transfer account as return data, as concatenated little-endian `u64`s
+/// Accounts:
+/// 0. ..MAX_READ_TOTALS Transfer accounts
+pub fn process_read_totals(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    if accounts.len() > MAX_READ_TOTALS {
+        msg!(
+            "{} transfer accounts, at most {} allowed",
+            accounts.len(),
+            MAX_READ_TOTALS
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut totals = Vec::with_capacity(accounts.len() * 8);
+    for transfer_account_info in accounts {
+        if transfer_account_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        let transfer_account = TransferAccount::unpack(&transfer_account_info.try_borrow_data()?)?;
+        totals.extend_from_slice(&transfer_account.transfered.to_le_bytes());
+    }
+    set_return_data(&totals);
+    Ok(())
+}
+
+/// Process GetMintConfig instruction, setting the config of the mint as
+/// return data, packed by `MintConfig::pack`.
+///
+/// A config at an older layout version is returned at the current one. The
+/// version comes first and fields are only ever appended, so clients built
+/// against an older layout can parse the prefix they know.
+/// Accounts:
+/// 0. Mint
+/// 1. Mint config
+pub fn process_get_mint_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+
+    if get_mint_config_address(mint_info.key, program_id) != *mint_config_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if mint_config_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let data = mint_config_info.try_borrow_data()?;
+    let config = MintConfig::unpack(&data).or_else(|_| MintConfig::unpack_outdated(&data))?;
+
+    let mut packed = [0; MintConfig::LEN];
+    config.pack(&mut packed);
+    set_return_data(&packed);
+    Ok(())
+}
+
+/// Process HealthCheck instruction, setting a bitmask of
+/// [`health_status`](crate::instruction::health_status) flags as return data
+/// Accounts:
+/// 0. Mint
+/// 1. Validation account
+/// 2. Mint config
+pub fn process_health_check(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+
+    if get_extra_account_metas_address(mint_info.key, program_id) != *extra_account_metas_info.key
+        || get_mint_config_address(mint_info.key, program_id) != *mint_config_info.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut status = 0;
+    if extra_account_metas_info.owner == program_id {
+        status |= health_status::VALIDATION_ACCOUNT_INITIALIZED;
+        let data = extra_account_metas_info.try_borrow_data()?;
+        if TlvStateBorrowed::unpack(&data)
+            .and_then(|state| {
+                ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&state)
+                    .map(|_| ())
+            })
+            .is_ok()
+        {
+            status |= health_status::VALIDATION_ACCOUNT_VALID;
+        }
+    }
+    if mint_config_info.owner == program_id {
+        status |= health_status::MINT_CONFIG_INITIALIZED;
+        if MintConfig::unpack(&mint_config_info.try_borrow_data()?).is_ok() {
+            status |= health_status::MINT_CONFIG_VALID;
+        }
+    }
+
+    msg!("Health status: {:#06b}", status);
+    set_return_data(&[status]);
+    Ok(())
+}
+
+/// The earliest of two timestamps, zero standing for unknown
+fn earliest_timestamp(a: i64, b: i64) -> i64 {
+    match (a, b) {
+        (0, b) => b,
+        (a, 0) => a,
+        (a, b) => a.min(b),
+    }
+}
+
+/// The earliest of two slots, zero standing for unknown
+fn earliest_slot(a: u64, b: u64) -> u64 {
+    match (a, b) {
+        (0, b) => b,
+        (a, 0) => a,
+        (a, b) => a.min(b),
+    }
+}
+
+/// Process ReassignTransferAccount instruction, moving the tracked stats of
+/// the old owner to the new owner.
+///
+/// The old transfer account is kept as a tombstone: the moved totals,
+/// received amount and allowance are zeroed, but its limit state stays, so
+/// the old owner can't start over from a blank account to escape a cooldown,
+/// window limit, daily cap, holding period or ticket nonce.
+///
+/// If the new owner already has a transfer account, the stats are merged:
+/// totals, received amounts, window amounts and daily transfer counts are
+/// added up, failing on overflow, and the most recent transfer timestamp,
+/// window start and day are kept, so the merge can't be used to escape a
+/// cooldown, window limit, daily cap or receive cap. The earliest known first
+/// transfer timestamp and the largest single transfer are kept, along with the
+/// new owner's label.
+/// Accounts:
+/// 0. Old owner (signer)
+/// 1. Old transfer account (writable)
+/// 2. New owner (signer, writable, pays for the new transfer account if needed)
+/// 3. New transfer account (writable)
+/// 4. System program
+/// 5. Mint (only required with the `per-mint-tracking` feature)
+pub fn process_reassign_transfer_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let old_owner_info = next_account_info(account_info_iter)?;
+    let old_transfer_account_info = next_account_info(account_info_iter)?;
+    let new_owner_info = next_account_info(account_info_iter)?;
+    let new_transfer_account_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    #[cfg(feature = "per-mint-tracking")]
+    let mint_key = next_account_info(account_info_iter)?.key;
+    #[cfg(not(feature = "per-mint-tracking"))]
+    let mint_key = &Pubkey::default();
+
+    // Both owners must agree
+    if !old_owner_info.is_signer || !new_owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if old_owner_info.key == new_owner_info.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if get_transfer_account_address(old_owner_info.key, mint_key, program_id)
+        != *old_transfer_account_info.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if old_transfer_account_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let (expected_new_transfer_account, bump_seed) =
+        program_address(pda::find_transfer_account_address(
+            &interface_pubkey(new_owner_info.key),
+            &interface_pubkey(mint_key),
+            cfg!(feature = "per-mint-tracking"),
+            &interface_pubkey(program_id),
+        ));
+    if expected_new_transfer_account != *new_transfer_account_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let old = TransferAccount::unpack(&old_transfer_account_info.try_borrow_data()?)?;
+
+    let new = if new_transfer_account_info.lamports() == 0 {
+        let required_lamports = Rent::get()?.minimum_balance(TransferAccount::LEN);
+        let bump_seed = [bump_seed];
+        let signer_seeds =
+            collect_transfer_account_signer_seeds(new_owner_info.key, mint_key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                new_owner_info.key,
+                new_transfer_account_info.key,
+                required_lamports,
+                TransferAccount::LEN as u64,
+                program_id,
+            ),
+            &[new_owner_info.clone(), new_transfer_account_info.clone()],
+            &[&signer_seeds],
+        )?;
+        check_created_account(
+            new_transfer_account_info,
+            TransferAccount::LEN,
+            required_lamports,
+        )?;
+        TransferAccount::new_initialized(*new_owner_info.key)?
+    } else {
+        if new_transfer_account_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        TransferAccount::unpack(&new_transfer_account_info.try_borrow_data()?)?
+    };
+
+    let merged = TransferAccount {
+        transfered: new
+            .transfered
+            .checked_add(old.transfered)
+            .ok_or(ExampleError::TrackingOverflow)?,
+        last_transfer_timestamp: new.last_transfer_timestamp.max(old.last_transfer_timestamp),
+        first_transfer_timestamp: earliest_timestamp(
+            new.first_transfer_timestamp,
+            old.first_transfer_timestamp,
+        ),
+        first_received_timestamp: earliest_timestamp(
+            new.first_received_timestamp,
+            old.first_received_timestamp,
+        ),
+        first_received_slot: earliest_slot(new.first_received_slot, old.first_received_slot),
+        window_start_slot: new.window_start_slot.max(old.window_start_slot),
+        window_amount: new
+            .window_amount
+            .checked_add(old.window_amount)
+            .ok_or(ExampleError::TrackingOverflow)?,
+        allowance_remaining: new
+            .allowance_remaining
+            .checked_add(old.allowance_remaining)
+            .ok_or(ExampleError::TrackingOverflow)?,
+        day_start_ts: new.day_start_ts.max(old.day_start_ts),
+        day_count: new
+            .day_count
+            .checked_add(old.day_count)
+            .ok_or(ExampleError::TrackingOverflow)?,
+        received: new
+            .received
+            .checked_add(old.received)
+            .ok_or(ExampleError::TrackingOverflow)?,
+        max_single_transfer: new.max_single_transfer.max(old.max_single_transfer),
+        ..new
+    };
+    merged.pack(&mut new_transfer_account_info.try_borrow_mut_data()?);
+
+    TransferAccount {
+        transfered: 0,
+        allowance_remaining: 0,
+        received: 0,
+        ..old
+    }
+    .pack(&mut old_transfer_account_info.try_borrow_mut_data()?);
+
+    msg!(
+        "Transfer account reassigned from {} to {}, {} total",
+        old_owner_info.key,
+        new_owner_info.key,
+        merged.transfered
+    );
+    Ok(())
+}
+
+/// Process InitializeMintStats instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Mint stats (writable, derived from the mint)
+/// 2. Mint
+/// 3. System program
+pub fn process_initialize_mint_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let mint_stats_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_mint_stats_address, bump_seed) = program_address(pda::find_mint_stats_address(
+        &interface_pubkey(mint_info.key),
+        &interface_pubkey(program_id),
+    ));
+    if expected_mint_stats_address != *mint_stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if mint_stats_info.lamports() > 0 {
+        msg!("Mint stats already exist");
+        return Ok(());
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_mint_stats_signer_seeds(mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            mint_stats_info.key,
+            Rent::get()?.minimum_balance(MintStats::LEN),
+            MintStats::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), mint_stats_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    msg!("Mint stats initialized for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Process InitializeTransferRoot instruction, creating the running Merkle
+/// root of the transfers of the mint, starting from the empty tree
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Transfer root (writable, derived from the mint)
+/// 2. Mint
+/// 3. System program
+pub fn process_initialize_transfer_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let transfer_root_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_transfer_root_address, bump_seed) =
+        program_address(pda::find_transfer_root_address(
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_transfer_root_address != *transfer_root_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if transfer_root_info.lamports() > 0 {
+        msg!("Transfer root already exists");
+        return Ok(());
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_transfer_root_signer_seeds(mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            transfer_root_info.key,
+            Rent::get()?.minimum_balance(TransferRoot::LEN),
+            TransferRoot::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), transfer_root_info.clone()],
+        &[&signer_seeds],
+    )?;
+    TransferRoot::init(&mut transfer_root_info.try_borrow_mut_data()?)?;
+
+    msg!("Transfer root initialized for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Fold the transfer into the running Merkle root of the mint, if the
+/// transfer root PDA was provided in the account list
+fn update_transfer_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    source_owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let transfer_root_address = get_transfer_root_address(mint_info.key, program_id);
+    let Some(transfer_root_info) =
+        find_program_account(program_id, accounts, &transfer_root_address)?
+    else {
+        return Ok(());
+    };
+    let leaf = transfer_leaf(
+        &InterfacePubkey::new_from_array(source_owner.to_bytes()),
+        amount,
+        Clock::get()?.slot,
+    );
+    TransferRoot::append(&mut transfer_root_info.try_borrow_mut_data()?, &leaf)?;
+    Ok(())
+}
+
+/// Process SnapshotStats instruction, appending the current mint stats to
+/// the stats history of the mint
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Stats history (writable, derived from the mint, created if needed)
+/// 2. Mint
+/// 3. Mint stats
+/// 4. Mint config
+/// 5. Config authority (signer)
+/// 6. System program
+/// 7. ..7+M Config signers (signer), when the config has a signer threshold
+pub fn process_snapshot_stats(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let stats_history_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_stats_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if get_mint_stats_address(mint_info.key, program_id) != *mint_stats_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if mint_stats_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let stats = MintStats::unpack(&mint_stats_info.try_borrow_data()?)?;
+
+    let (expected_stats_history_address, bump_seed) =
+        program_address(pda::find_stats_history_address(
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_stats_history_address != *stats_history_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the history on first use
+    if stats_history_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds = collect_stats_history_signer_seeds(mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                stats_history_info.key,
+                Rent::get()?.minimum_balance(StatsHistory::LEN),
+                StatsHistory::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), stats_history_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if stats_history_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let clock = Clock::get()?;
+    StatsHistory::append(
+        &mut stats_history_info.try_borrow_mut_data()?,
+        &StatsSnapshot {
+            stats,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        },
+    )?;
+
+    msg!(
+        "Stats snapshot taken at slot {} for mint: {}",
+        clock.slot,
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Process InitializeFeeLedger instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Fee ledger (writable, derived from the mint and the category)
+/// 2. Mint
+/// 3. System program
+pub fn process_initialize_fee_ledger(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    category: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let fee_ledger_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_fee_ledger_address, bump_seed) = program_address(pda::find_fee_ledger_address(
+        &interface_pubkey(mint_info.key),
+        category,
+        &interface_pubkey(program_id),
+    ));
+    if expected_fee_ledger_address != *fee_ledger_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if fee_ledger_info.lamports() > 0 {
+        msg!("Fee ledger already exists");
+        return Ok(());
+    }
+
+    let category = [category];
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_fee_ledger_signer_seeds(mint_info.key, &category, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            fee_ledger_info.key,
+            Rent::get()?.minimum_balance(FeeLedger::LEN),
+            FeeLedger::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), fee_ledger_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    msg!(
+        "Fee ledger {} initialized for mint: {}",
+        category[0],
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Accrue the fee of the transfer to the fee ledgers of the config. The
+/// ledger of each split must be part of the account list.
+fn accrue_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    config: &MintConfig,
+    amount: u64,
+) -> ProgramResult {
+    for (category, share) in config.fee_shares(amount) {
+        let fee_ledger_address = get_fee_ledger_address(mint_info.key, category, program_id);
+        let Some(fee_ledger_info) =
+            find_program_account(program_id, accounts, &fee_ledger_address)?
+        else {
+            msg!("Fee ledger {} not provided", category);
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        let mut data = fee_ledger_info.try_borrow_mut_data()?;
+        let mut ledger = FeeLedger::unpack(&data)?;
+        ledger.accrued = ledger
+            .accrued
+            .checked_add(share)
+            .ok_or(ExampleError::TrackingOverflow)?;
+        ledger.pack(&mut data);
+    }
+    Ok(())
+}
+
+/// Process InitializeBurnLedger instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Burn ledger (writable, derived from the mint and the owner)
+/// 2. Mint
+/// 3. Owner
+/// 4. System program
+pub fn process_initialize_burn_ledger(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let burn_ledger_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_burn_ledger_address, bump_seed) = program_address(pda::find_burn_ledger_address(
+        &interface_pubkey(owner_info.key),
+        &interface_pubkey(mint_info.key),
+        &interface_pubkey(program_id),
+    ));
+    if expected_burn_ledger_address != *burn_ledger_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if burn_ledger_info.lamports() > 0 {
+        msg!("Burn ledger already exists");
+        return Ok(());
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_burn_ledger_signer_seeds(owner_info.key, mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            burn_ledger_info.key,
+            Rent::get()?.minimum_balance(BurnLedger::LEN),
+            BurnLedger::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), burn_ledger_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    msg!(
+        "Burn ledger of owner {} initialized for mint: {}",
+        owner_info.key,
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Record the burn owed for the transfer in the burn ledger of the source
+/// owner, which must be part of the account list when the config has a burn
+fn record_owed_burn(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    config: &MintConfig,
+    owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let burn = config.burn_amount(amount);
+    if burn == 0 {
+        return Ok(());
+    }
+    let burn_ledger_address = get_burn_ledger_address(owner, mint_info.key, program_id);
+    let Some(burn_ledger_info) = find_program_account(program_id, accounts, &burn_ledger_address)?
+    else {
+        msg!("Burn ledger of owner {} not provided", owner);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let mut data = burn_ledger_info.try_borrow_mut_data()?;
+    let mut ledger = BurnLedger::unpack(&data)?;
+    ledger.owed = ledger
+        .owed
+        .checked_add(burn)
+        .ok_or(ExampleError::TrackingOverflow)?;
+    ledger.pack(&mut data);
+    Ok(())
+}
+
+/// Process SettleBurn instruction, burning the amount owed by the owner from
+/// one of their token accounts through the token program
+/// Accounts:
+/// 0. Burn ledger (writable)
+/// 1. Token account of the owner (writable)
+/// 2. Mint (writable)
+/// 3. Owner (signer)
+/// 4. Token program
+pub fn process_settle_burn(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let burn_ledger_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if token_program_info.key != mint_info.owner {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if get_burn_ledger_address(owner_info.key, mint_info.key, program_id) != *burn_ledger_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if burn_ledger_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // the owner settles from their own account, not one delegated to them
+    {
+        let token_account_data = token_account_info.try_borrow_data()?;
+        let token_account = StateWithExtensions::<Account>::unpack(&token_account_data)?;
+        if token_account.base.mint != *mint_info.key || token_account.base.owner != *owner_info.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    let mut data = burn_ledger_info.try_borrow_mut_data()?;
+    let mut ledger = BurnLedger::unpack(&data)?;
+    if ledger.owed == 0 {
+        msg!("Nothing to burn for owner {}", owner_info.key);
+        return Ok(());
+    }
+
+    invoke(
+        &spl_token_2022::instruction::burn(
+            token_program_info.key,
+            token_account_info.key,
+            mint_info.key,
+            owner_info.key,
+            &[],
+            ledger.owed,
+        )?,
+        &[
+            token_account_info.clone(),
+            mint_info.clone(),
+            owner_info.clone(),
+        ],
+    )?;
+
+    msg!("Burned {} owed by owner {}", ledger.owed, owner_info.key);
+    ledger.owed = 0;
+    ledger.pack(&mut data);
+    Ok(())
+}
+
+/// Count the accepted transfer in the mint stats, if the stats PDA was
+/// provided in the account list, and check that the volume of the current
+/// epoch stays within the cap of the config.
+///
+/// The stats PDA is required while the cap is set, since it tracks the
+/// volume.
+fn record_accepted_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    config: &MintConfig,
+    amount: u64,
+) -> ProgramResult {
+    let mint_stats_address = get_mint_stats_address(mint_info.key, program_id);
+    let Some(mint_stats_info) = find_program_account(program_id, accounts, &mint_stats_address)?
+    else {
+        if config.max_epoch_volume != 0 {
+            msg!("Mint stats account required by the epoch volume cap");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        return Ok(());
+    };
+    let mut data = mint_stats_info.try_borrow_mut_data()?;
+    let mut stats = MintStats::unpack(&data)?;
+    let epoch = Clock::get()?.epoch;
+    if config.max_epoch_volume != 0 {
+        let volume = stats.volume_in_epoch(epoch);
+        if volume
+            .checked_add(amount)
+            .is_none_or(|volume| volume > config.max_epoch_volume)
+        {
+            msg!(
+                "Transfer of {} exceeds the epoch volume cap of {}, {} already moved in epoch {}",
+                amount,
+                config.max_epoch_volume,
+                volume,
+                epoch
+            );
+            return Err(ExampleError::EpochVolumeExceeded.into());
+        }
+    }
+    stats.record(amount, epoch);
+    stats.pack(&mut data);
+    Ok(())
+}
+
+/// Process InitializeRecentTransferLog instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Recent transfer log (writable, derived from the mint)
+/// 2. Mint
+/// 3. System program
+#[cfg(feature = "recent-log")]
+pub fn process_initialize_recent_transfer_log(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let recent_log_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_recent_log_address, bump_seed) =
+        program_address(pda::find_recent_transfer_log_address(
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_recent_log_address != *recent_log_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if recent_log_info.lamports() > 0 {
+        msg!("Recent transfer log already exists");
+        return Ok(());
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_recent_transfer_log_signer_seeds(mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            recent_log_info.key,
+            Rent::get()?.minimum_balance(RecentTransferLog::LEN),
+            RecentTransferLog::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), recent_log_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    msg!(
+        "Recent transfer log initialized for mint: {}",
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Append the transfer of the source owner to the mint's recent transfer log,
+/// if the log PDA was provided in the account list and initialized
+#[cfg(feature = "recent-log")]
+fn append_to_recent_transfer_log(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    source_owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let recent_log_address = get_recent_transfer_log_address(mint_info.key, program_id);
+    let Some(recent_log_info) = find_program_account(program_id, accounts, &recent_log_address)?
+    else {
+        return Ok(());
+    };
+
+    let slot = Clock::get()?.slot;
+    let mut data = recent_log_info.try_borrow_mut_data()?;
+    RecentTransferLog::append(&mut data, source_owner, amount, slot)
+}
+
+/// Process InitializeMintConfig instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Mint config (writable, derived from the mint)
+/// 2. Mint
+/// 3. Mint authority (signer)
+/// 4. System program
+pub fn process_initialize_mint_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    // check that the one mint we want to target is trying to create a config
+    #[cfg(feature = "forbid-additional-mints")]
+    if *mint_info.key != crate::mint::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mint_authority = check_mint_authority(mint_info, authority_info)?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    create_mint_config(
+        program_id,
+        payer_info,
+        mint_config_info,
+        mint_info,
+        &MintConfig {
+            authority: mint_authority,
+            ..MintConfig::default()
+        },
+    )
+}
+
+/// Check that the mint authority has signed, without fully deserializing the
+/// mint, returning the mint authority
+fn check_mint_authority(
+    mint_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> Result<Pubkey, ProgramError> {
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let mint_authority = mint
+        .base
+        .mint_authority
+        .ok_or(TransferHookError::MintHasNoMintAuthority)?;
+
+    // Check signers
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *authority_info.key != mint_authority {
+        return Err(TransferHookError::IncorrectMintAuthority.into());
+    }
+    Ok(mint_authority)
+}
+
+/// Create the config of the mint, unless it already exists
+fn create_mint_config<'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    mint_config_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo,
+    config: &MintConfig,
+) -> ProgramResult {
+    let (expected_mint_config_address, bump_seed) = program_address(pda::find_mint_config_address(
+        &interface_pubkey(mint_info.key),
+        &interface_pubkey(program_id),
+    ));
+    if expected_mint_config_address != *mint_config_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if account already exists
+    if mint_config_info.lamports() > 0 {
+        msg!("Mint config already exists");
+        return Ok(());
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_mint_config_signer_seeds(mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            mint_config_info.key,
+            Rent::get()?.minimum_balance(MintConfig::LEN),
+            MintConfig::LEN as u64,
+            program_id,
+        ),
+        &[payer_info.clone(), mint_config_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+
+    msg!("Mint config initialized for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Process UpdateMintConfig instruction, overwriting the whole config but the
+/// setup and pause state
+/// Accounts:
+/// 0. Mint config (writable)
+/// 1. Mint
+/// 2. Config authority (signer)
+/// 3. ..3+M Config signers (signer), when the config has a signer threshold
+pub fn process_update_mint_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let new_config = MintConfig::unpack(input)?;
+    new_config.check()?;
+    check_config_decimals(mint_info, &new_config)?;
+    let config = check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+
+    // a distributor is only named by the initial config, so trading can't be
+    // disabled once enabled
+    if config.distributor == Pubkey::default() && new_config.distributor != Pubkey::default() {
+        msg!("Trading is already enabled");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // only `FinalizeSetup` completes the setup, and nothing undoes it, while
+    // the pause is only scheduled by `SchedulePause` and `CancelPause`
+    MintConfig {
+        setup_complete: config.setup_complete,
+        pause_effective_slot: config.pause_effective_slot,
+        pause_reason: config.pause_reason,
+        ..new_config
+    }
+    .pack(&mut mint_config_info.try_borrow_mut_data()?);
+    Ok(())
+}
+
+/// Process EnableTrading instruction, clearing the distributor of the mint so
+/// that anyone can transfer
+/// Accounts:
+/// 0. Mint config (writable)
+/// 1. Mint
+/// 2. Config authority (signer)
+/// 3. ..3+M Config signers (signer), when the config has a signer threshold
+pub fn process_enable_trading(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut config = check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if config.distributor == Pubkey::default() {
+        msg!("Trading is already enabled");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    config.distributor = Pubkey::default();
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+
+    msg!("Trading enabled for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Process FinalizeSetup instruction, marking the setup of the mint as
+/// complete so that its transfers are accepted when the config requires it
+/// Accounts:
+/// 0. Mint config (writable)
+/// 1. Mint
+/// 2. Config authority (signer)
+/// 3. ..3+M Config signers (signer), when the config has a signer threshold
+pub fn process_finalize_setup(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut config = check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if config.setup_complete {
+        msg!("Setup is already finalized");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    config.setup_complete = true;
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+
+    msg!("Setup finalized for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Process SchedulePause instruction, pausing the transfers of the mint from
+/// a future slot, for the given reason
+/// Accounts:
+/// 0. Mint config (writable)
+/// 1. Mint
+/// 2. Config authority (signer)
+/// 3. ..3+M Config signers (signer), when the config has a signer threshold
+pub fn process_schedule_pause(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    slot: u64,
+    reason: [u8; MintConfig::PAUSE_REASON_LEN],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut config = check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    let current_slot = Clock::get()?.slot;
+    if slot <= current_slot {
+        msg!(
+            "Pause slot {} is not after the current slot {}",
+            slot,
+            current_slot
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    config.pause_effective_slot = slot;
+    config.pause_reason = reason;
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+
+    msg!(
+        "Pause scheduled at slot {} for mint: {}",
+        slot,
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Process CancelPause instruction, cancelling the scheduled pause of the
+/// mint, or lifting it once effective
+/// Accounts:
+/// 0. Mint config (writable)
+/// 1. Mint
+/// 2. Config authority (signer)
+/// 3. ..3+M Config signers (signer), when the config has a signer threshold
+pub fn process_cancel_pause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut config = check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if config.pause_effective_slot == 0 {
+        msg!("No pause is scheduled");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    config.pause_effective_slot = 0;
+    config.pause_reason = [0; MintConfig::PAUSE_REASON_LEN];
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+
+    msg!("Pause cancelled for mint: {}", mint_info.key);
+    Ok(())
+}
+
+/// Check that the config authority of the mint has signed, returning the
+/// current config.
+///
+/// When the config has a signer threshold, at least that many of its signers
+/// must have signed instead, among the authority account and the
+/// `signer_infos` following the instruction's accounts.
+fn check_config_authority(
+    program_id: &Pubkey,
+    mint_config_info: &AccountInfo,
+    mint_info: &AccountInfo,
+    authority_info: &AccountInfo,
+    signer_infos: &[AccountInfo],
+) -> Result<MintConfig, ProgramError> {
+    if get_mint_config_address(mint_info.key, program_id) != *mint_config_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if mint_config_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let config = MintConfig::unpack(&mint_config_info.try_borrow_data()?)?;
+    check_config_signers(&config, authority_info, signer_infos)?;
+    Ok(config)
+}
+
+/// Check that the authority of `config`, or enough of its signers when it
+/// has a signer threshold, have signed
+fn check_config_signers(
+    config: &MintConfig,
+    authority_info: &AccountInfo,
+    signer_infos: &[AccountInfo],
+) -> ProgramResult {
+    if config.signer_threshold != 0 {
+        let signed = config
+            .configured_signers()
+            .filter(|signer| {
+                let signed = |info: &AccountInfo| info.key == *signer && info.is_signer;
+                signed(authority_info) || signer_infos.iter().any(signed)
+            })
+            .count();
+        if signed < config.signer_threshold as usize {
+            msg!(
+                "{} of the {} required config signers have signed",
+                signed,
+                config.signer_threshold
+            );
+            return Err(ExampleError::NotEnoughConfigSigners.into());
+        }
+        return Ok(());
+    }
+
+    // Check signers
+    if !authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *authority_info.key != config.authority {
+        return Err(ExampleError::IncorrectConfigAuthority.into());
+    }
+    Ok(())
+}
+
+/// Process MigrateMintConfig instruction, rewriting a config of an older
+/// layout version with the current one
+/// Accounts:
+/// 0. Payer (signer, writable), funding the larger config
+/// 1. Mint config (writable)
+/// 2. Mint
+/// 3. Config authority (signer)
+/// 4. System program
+/// 5. ..5+M Config signers (signer), when the config has a signer threshold
+pub fn process_migrate_mint_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if get_mint_config_address(mint_info.key, program_id) != *mint_config_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if mint_config_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let config =
+        MintConfig::unpack_outdated(&mint_config_info.try_borrow_data()?).inspect_err(|_| {
+            msg!("Mint config is not at an older version");
+        })?;
+    check_config_signers(&config, authority_info, account_info_iter.as_slice())?;
+
+    mint_config_info.resize(MintConfig::LEN)?;
+    config.pack(&mut mint_config_info.try_borrow_mut_data()?);
+    settle_rent(mint_config_info, payer_info, system_program_info)?;
+
+    msg!(
+        "Mint config migrated to version {} for mint: {}",
+        MintConfig::VERSION,
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Process SetAllowance instruction, setting or topping up the allowance of
+/// the owner of a transfer account.
+///
+/// Without the `per-mint-tracking` feature, the transfer account and its
+/// allowance are shared by the mints the owner transfers.
+/// Accounts:
+/// 0. Transfer account (writable)
+/// 1. Mint
+/// 2. Mint config
+/// 3. Config authority (signer)
+/// 4. ..4+M Config signers (signer), when the config has a signer threshold
+pub fn process_set_allowance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    top_up: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let transfer_account_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+
+    if transfer_account_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut data = transfer_account_info.try_borrow_mut_data()?;
+    let mut tracked = TransferAccount::unpack(&data)?;
+    if get_transfer_account_address(&tracked.owner, mint_info.key, program_id)
+        != *transfer_account_info.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    tracked.allowance_remaining = if top_up {
+        tracked
+            .allowance_remaining
+            .checked_add(amount)
+            .ok_or(ExampleError::TrackingOverflow)?
+    } else {
+        amount
+    };
+    tracked.pack(&mut data);
+
+    msg!(
+        "Allowance of owner {} set to {}",
+        tracked.owner,
+        tracked.allowance_remaining
+    );
+    Ok(())
+}
+
+/// Process SetVestingSchedule instruction, setting the vesting schedule of an
+/// owner and creating it on first use. `Execute` keeps the amount not vested
+/// yet in the owner's source accounts, when the schedule PDA is part of the
+/// extra account metas.
+/// Accounts:
+/// 0. Payer (signer, writable, pays for the schedule if needed)
+/// 1. Vesting schedule (writable, derived from the mint and the owner)
+/// 2. Mint
+/// 3. Owner
+/// 4. System program
+/// 5. Mint config
+/// 6. Config authority (signer)
+/// 7. ..7+M Config signers (signer), when the config has a signer threshold
+pub fn process_set_vesting_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    schedule: VestingSchedule,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let vesting_schedule_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (expected_vesting_schedule_address, bump_seed) =
+        program_address(pda::find_vesting_schedule_address(
+            &interface_pubkey(owner_info.key),
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_vesting_schedule_address != *vesting_schedule_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the schedule on first use
+    if vesting_schedule_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds =
+            collect_vesting_schedule_signer_seeds(owner_info.key, mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                vesting_schedule_info.key,
+                Rent::get()?.minimum_balance(VestingSchedule::LEN),
+                VestingSchedule::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), vesting_schedule_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if vesting_schedule_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    schedule.pack(&mut vesting_schedule_info.try_borrow_mut_data()?);
+
+    msg!(
+        "Vesting schedule of owner {} set to {} from {} over {}s",
+        owner_info.key,
+        schedule.total,
+        schedule.start_timestamp,
+        schedule.duration_seconds
+    );
+    Ok(())
+}
+
+/// Process TeardownMint instruction, closing every account of a mint when
+/// retiring its hook, and draining their rent, along with the treasury, to the
+/// recipient. Accounts that don't exist are skipped.
+///
+/// Allowlist pages and fee ledgers can't be enumerated by the program, so the
+/// ones to close are listed in the instruction. Any left open would apply
+/// again once the mint is set up again.
+///
+/// The mint authority must sign, and so must the config authority, or enough
+/// of the config signers, while the config exists.
+///
+/// The hook of the mint is left as is: transfers fail without the validation
+/// account until the hook is removed or the mint set up again.
+/// Accounts:
+/// 0. Recipient (writable)
+/// 1. Mint config (writable, derived from the mint)
+/// 2. Mint stats (writable, derived from the mint)
+/// 3. Validation account (writable, derived from the mint)
+/// 4. Mint
+/// 5. Mint authority (signer)
+/// 6. Blocklist (writable, derived from the mint)
+/// 7. Exempt owners (writable, derived from the mint)
+/// 8. Stats history (writable, derived from the mint)
+/// 9. Transfer root (writable, derived from the mint)
+/// 10. Treasury (writable, derived from the mint)
+/// 11. System program
+/// 12. ..12+P Allowlist pages (writable), one per listed page, followed by
+///     the fee ledgers (writable), one per listed category, the recent
+///     transfer log (writable) with the `recent-log` feature, and the config
+///     signers (signer) when the config has a signer threshold
+pub fn process_teardown_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    allowlist_pages: &[u8],
+    fee_categories: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let recipient_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_stats_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let mut closed_accounts = vec![
+        (
+            mint_config_info,
+            get_mint_config_address(mint_info.key, program_id),
+        ),
+        (
+            mint_stats_info,
+            get_mint_stats_address(mint_info.key, program_id),
+        ),
+        (
+            extra_account_metas_info,
+            get_extra_account_metas_address(mint_info.key, program_id),
+        ),
+        (
+            next_account_info(account_info_iter)?,
+            get_blocklist_address(mint_info.key, program_id),
+        ),
+        (
+            next_account_info(account_info_iter)?,
+            get_exempt_owners_address(mint_info.key, program_id),
+        ),
+        (
+            next_account_info(account_info_iter)?,
+            get_stats_history_address(mint_info.key, program_id),
+        ),
+        (
+            next_account_info(account_info_iter)?,
+            get_transfer_root_address(mint_info.key, program_id),
+        ),
+    ];
+    let treasury_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    for page in allowlist_pages {
+        closed_accounts.push((
+            next_account_info(account_info_iter)?,
+            get_allowlist_address(mint_info.key, *page, program_id),
+        ));
+    }
+    for category in fee_categories {
+        closed_accounts.push((
+            next_account_info(account_info_iter)?,
+            get_fee_ledger_address(mint_info.key, *category, program_id),
+        ));
+    }
+    #[cfg(feature = "recent-log")]
+    closed_accounts.push((
+        next_account_info(account_info_iter)?,
+        crate::get_recent_transfer_log_address(mint_info.key, program_id),
+    ));
+
+    check_mint_authority(mint_info, authority_info)?;
+    if *mint_config_info.key == get_mint_config_address(mint_info.key, program_id)
+        && mint_config_info.owner == program_id
+    {
+        let data = mint_config_info.try_borrow_data()?;
+        let config = MintConfig::unpack(&data).or_else(|_| MintConfig::unpack_outdated(&data))?;
+        check_config_signers(&config, authority_info, account_info_iter.as_slice())?;
+    }
+
+    let (expected_treasury_address, treasury_bump_seed) =
+        program_address(pda::find_treasury_address(
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    for (account_info, expected_address) in closed_accounts
+        .iter()
+        .chain([&(treasury_info, expected_treasury_address)])
+    {
+        if account_info.key != expected_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if account_info.key == recipient_info.key {
+            msg!("Recipient {} is closed by the teardown", recipient_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    // The treasury is a system account, emptied through the system program
+    // before the lamports of the other accounts are moved by hand
+    if treasury_info.lamports() > 0 {
+        let bump_seed = [treasury_bump_seed];
+        let signer_seeds = collect_treasury_signer_seeds(mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::transfer(
+                treasury_info.key,
+                recipient_info.key,
+                treasury_info.lamports(),
+            ),
+            &[treasury_info.clone(), recipient_info.clone()],
+            &[&signer_seeds],
+        )?;
+    }
+
+    for (account_info, _) in closed_accounts {
+        if account_info.owner != program_id {
+            msg!("Account {} does not exist, skipped", account_info.key);
+            continue;
+        }
+        close_program_account(account_info, recipient_info)?;
+    }
+
+    msg!("Mint {} torn down", mint_info.key);
+    Ok(())
+}
+
+/// Close an account owned by the program, draining its lamports to the
+/// recipient and handing it back to the system program
+fn close_program_account(
+    account_info: &AccountInfo,
+    recipient_info: &AccountInfo,
+) -> ProgramResult {
+    let lamports = account_info.lamports();
+    **account_info.try_borrow_mut_lamports()? = 0;
+    let recipient_lamports = recipient_info.lamports();
+    **recipient_info.try_borrow_mut_lamports()? = recipient_lamports
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    account_info.resize(0)?;
+    account_info.assign(&solana_system_interface::program::id());
+    Ok(())
+}
+
+/// Process WithdrawTreasury instruction, moving lamports accrued by the
+/// treasury of a mint to a recipient.
+///
+/// The treasury is a system account at a PDA of the program, funded by plain
+/// lamport transfers and signed for by the program on withdrawal. It keeps
+/// its rent-exempt minimum, so only the lamports above it can be withdrawn.
+/// Accounts:
+/// 0. Treasury (writable, derived from the mint)
+/// 1. Recipient (writable)
+/// 2. Mint config
+/// 3. Mint
+/// 4. Config authority (signer)
+/// 5. System program
+/// 6. ..6+M Config signers (signer), when the config has a signer threshold
+pub fn process_withdraw_treasury(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let treasury_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    let (expected_treasury_address, bump_seed) = program_address(pda::find_treasury_address(
+        &interface_pubkey(mint_info.key),
+        &interface_pubkey(program_id),
+    ));
+    if expected_treasury_address != *treasury_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let withdrawable = treasury_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(treasury_info.data_len()));
+    if amount > withdrawable {
+        msg!(
+            "Treasury holds {} withdrawable lamports, {} requested",
+            withdrawable,
+            amount
+        );
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_treasury_signer_seeds(mint_info.key, &bump_seed);
+    invoke_signed(
+        &system_instruction::transfer(treasury_info.key, recipient_info.key, amount),
+        &[treasury_info.clone(), recipient_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    msg!(
+        "Withdrew {} lamports from the treasury of mint {}",
+        amount,
+        mint_info.key
+    );
+    Ok(())
+}
+
+/// Maximum number of transfer accounts reset by `ResetAccountsBatch`
+pub const MAX_RESET_ACCOUNTS: usize = 16;
+
+/// Process ResetAccountsBatch instruction, zeroing the transferred and
+/// received totals of `count` transfer accounts, e.g. for a new season.
+///
+/// The owners, labels and the state of the limits are left as they are.
+/// Accounts:
+/// 0. Mint
+/// 1. Mint config
+/// 2. Config authority (signer)
+/// 3. ..3+N Transfer accounts (writable), up to `MAX_RESET_ACCOUNTS`, followed
+///    by the config signers (signer) when the config has a signer threshold
+pub fn process_reset_accounts_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    count: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let count = usize::from(count);
+    if count > MAX_RESET_ACCOUNTS {
+        msg!(
+            "{} transfer accounts, at most {} allowed",
+            count,
+            MAX_RESET_ACCOUNTS
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (transfer_account_infos, signer_infos) = account_info_iter
+        .as_slice()
+        .split_at_checked(count)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        signer_infos,
+    )?;
+
+    for transfer_account_info in transfer_account_infos {
+        if transfer_account_info.owner != program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        let mut data = transfer_account_info.try_borrow_mut_data()?;
+        let mut tracked = TransferAccount::unpack(&data)?;
+        if get_transfer_account_address(&tracked.owner, mint_info.key, program_id)
+            != *transfer_account_info.key
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        tracked.transfered = 0;
+        tracked.received = 0;
+        tracked.pack(&mut data);
+    }
+
+    msg!("{} transfer accounts reset", count);
+    Ok(())
+}
+
+/// Process AddExemptOwner instruction
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Exempt owners (writable, derived from the mint, created if needed)
+/// 2. Mint
+/// 3. Mint config
+/// 4. Config authority (signer)
+/// 5. System program
+/// 6. ..6+M Config signers (signer), when the config has a signer threshold
+pub fn process_add_exempt_owner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    owner: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let exempt_owners_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (expected_exempt_owners_address, bump_seed) =
+        program_address(pda::find_exempt_owners_address(
+            &interface_pubkey(mint_info.key),
+            &interface_pubkey(program_id),
+        ));
+    if expected_exempt_owners_address != *exempt_owners_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the list on first use
+    if exempt_owners_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds = collect_exempt_owners_signer_seeds(mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                exempt_owners_info.key,
+                Rent::get()?.minimum_balance(OwnerList::LEN),
+                OwnerList::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), exempt_owners_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if exempt_owners_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    OwnerList::add(&mut exempt_owners_info.try_borrow_mut_data()?, &owner)?;
+
+    msg!("Owner exempt from tracking: {}", owner);
+    Ok(())
+}
+
+/// Process AddAllowlistedOwner instruction, adding the owner to the
+/// allowlist page it falls on, or updating the expiry of its entry
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Allowlist page (writable, derived from the mint and the owner's page,
+///    created if needed)
+/// 2. Mint
+/// 3. Mint config
+/// 4. Config authority (signer)
+/// 5. System program
+/// 6. ..6+M Config signers (signer), when the config has a signer threshold
+pub fn process_add_allowlisted_owner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    owner: Pubkey,
+    expires_at: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let allowlist_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let page = get_allowlist_page(&owner);
+    let (expected_allowlist_address, bump_seed) = program_address(pda::find_allowlist_address(
+        &interface_pubkey(mint_info.key),
+        page,
+        &interface_pubkey(program_id),
+    ));
+    if expected_allowlist_address != *allowlist_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the page on first use
+    if allowlist_info.lamports() == 0 {
+        let page = [page];
+        let bump_seed = [bump_seed];
+        let signer_seeds = collect_allowlist_signer_seeds(mint_info.key, &page, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                allowlist_info.key,
+                Rent::get()?.minimum_balance(AllowlistPage::LEN),
+                AllowlistPage::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), allowlist_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if allowlist_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    AllowlistPage::add(
+        &mut allowlist_info.try_borrow_mut_data()?,
+        &owner,
+        expires_at,
+    )?;
+
+    msg!(
+        "Owner allowlisted on page {} until {}: {}",
+        page,
+        expires_at,
+        owner
+    );
+    Ok(())
+}
+
+/// Process RemoveAllowlistedOwner instruction
+/// Accounts:
+/// 0. Allowlist page (writable)
+/// 1. Mint
+/// 2. Mint config
+/// 3. Config authority (signer)
+/// 4. ..4+M Config signers (signer), when the config has a signer threshold
+pub fn process_remove_allowlisted_owner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    owner: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let allowlist_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+
+    let page = get_allowlist_page(&owner);
+    if get_allowlist_address(mint_info.key, page, program_id) != *allowlist_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if allowlist_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    AllowlistPage::remove(&mut allowlist_info.try_borrow_mut_data()?, &owner)?;
+
+    msg!("Owner no longer allowlisted: {}", owner);
+    Ok(())
+}
+
+/// Process RemoveExemptOwner instruction
+/// Accounts:
+/// 0. Exempt owners (writable)
+/// 1. Mint
+/// 2. Mint config
+/// 3. Config authority (signer)
+/// 4. ..4+M Config signers (signer), when the config has a signer threshold
+pub fn process_remove_exempt_owner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    owner: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let exempt_owners_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    check_config_authority(
+        program_id,
+        mint_config_info,
+        mint_info,
+        authority_info,
+        account_info_iter.as_slice(),
+    )?;
+
+    if get_exempt_owners_address(mint_info.key, program_id) != *exempt_owners_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if exempt_owners_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    OwnerList::remove(&mut exempt_owners_info.try_borrow_mut_data()?, &owner)?;
+
+    msg!("Owner no longer exempt from tracking: {}", owner);
+    Ok(())
+}
+
+/// Find an initialized program account at the given address in the account
+/// list, for the optional accounts of `Execute`
+fn find_program_account<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+    address: &Pubkey,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+    let Some(account_info) = accounts.iter().find(|info| info.key == address) else {
+        return Ok(None);
+    };
+    if account_info.data_is_empty() {
+        return Ok(None);
+    }
+    if account_info.owner != program_id {
+        msg!("Account {} not owned by program", address);
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(Some(account_info))
+}
+
+/// Process FreezeOwner instruction, freezing the token account through the
+/// token program and adding its owner to the mint's blocklist
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Blocklist (writable, derived from the mint, created if needed)
+/// 2. Token account to freeze (writable)
+/// 3. Mint
+/// 4. Mint freeze authority (signer)
+/// 5. Token program
+/// 6. System program
+pub fn process_freeze_owner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let blocklist_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let freeze_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    // check that the freeze authority is valid without fully deserializing
+    let freeze_authority = {
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        Option::<Pubkey>::from(mint.base.freeze_authority)
+            .ok_or(ExampleError::MintHasNoFreezeAuthority)?
+    };
+
+    // Check signers
+    if !payer_info.is_signer || !freeze_authority_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *freeze_authority_info.key != freeze_authority {
+        return Err(ExampleError::IncorrectFreezeAuthority.into());
+    }
+    if token_program_info.key != mint_info.owner {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (expected_blocklist_address, bump_seed) = program_address(pda::find_blocklist_address(
+        &interface_pubkey(mint_info.key),
+        &interface_pubkey(program_id),
+    ));
+    if expected_blocklist_address != *blocklist_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let owner = {
+        let token_account_data = token_account_info.try_borrow_data()?;
+        let token_account = StateWithExtensions::<Account>::unpack(&token_account_data)?;
+        if token_account.base.mint != *mint_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        token_account.base.owner
+    };
+
+    invoke(
+        &spl_token_2022::instruction::freeze_account(
+            token_program_info.key,
+            token_account_info.key,
+            mint_info.key,
+            freeze_authority_info.key,
+            &[],
+        )?,
+        &[
+            token_account_info.clone(),
+            mint_info.clone(),
+            freeze_authority_info.clone(),
+        ],
+    )?;
+
+    // Create the blocklist on first use
+    if blocklist_info.lamports() == 0 {
+        let bump_seed = [bump_seed];
+        let signer_seeds = collect_blocklist_signer_seeds(mint_info.key, &bump_seed);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                blocklist_info.key,
+                Rent::get()?.minimum_balance(OwnerList::LEN),
+                OwnerList::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), blocklist_info.clone()],
+            &[&signer_seeds],
+        )?;
+    } else if blocklist_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    OwnerList::add(&mut blocklist_info.try_borrow_mut_data()?, &owner)?;
+
+    msg!("Owner frozen and blocklisted: {}", owner);
+    Ok(())
+}
+
+/// Reject the transfer if the source or destination owner is on the mint's
+/// blocklist, when the blocklist PDA was provided in the account list
+fn check_blocklist<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    mint_info: &AccountInfo<'a>,
+    source: &StateWithExtensions<Account>,
+    destination: &StateWithExtensions<Account>,
+) -> ProgramResult {
+    let blocklist_address = get_blocklist_address(mint_info.key, program_id);
+    let Some(blocklist_info) = find_program_account(program_id, accounts, &blocklist_address)?
+    else {
+        return Ok(());
+    };
+
+    let blocklist_data = blocklist_info.try_borrow_data()?;
+    for owner in [source.base.owner, destination.base.owner] {
+        if OwnerList::contains(&blocklist_data, &owner)? {
+            msg!("Owner {} is blocklisted", owner);
+            return Err(ExampleError::OwnerBlocklisted.into());
+        }
+    }
+    Ok(())
+}
+
+/// Check the source and destination owners against the list selected by the
+/// access mode of the mint
+fn check_access<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    mint_info: &AccountInfo<'a>,
+    config: &MintConfig,
+    source: &StateWithExtensions<Account>,
+    destination: &StateWithExtensions<Account>,
+) -> ProgramResult {
+    match config.access_mode {
+        AccessMode::Blocklist => {
+            check_blocklist(program_id, accounts, mint_info, source, destination)
+        }
+        AccessMode::Allowlist => {
+            check_allowlist(program_id, accounts, mint_info, source, destination)
+        }
+        AccessMode::None => Ok(()),
+    }
+}
+
+/// Check that the source and destination owners are on the allowlist. The
+/// page of each owner must be part of the account list, a page that doesn't
+/// exist yet holding no owners, along with the clock sysvar, entries which
+/// expired being treated as absent.
+fn check_allowlist<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    mint_info: &AccountInfo<'a>,
+    source: &StateWithExtensions<Account>,
+    destination: &StateWithExtensions<Account>,
+) -> ProgramResult {
+    let Some(clock) = get_clock(accounts)? else {
+        msg!("Clock sysvar not provided");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    for owner in [source.base.owner, destination.base.owner] {
+        let page = get_allowlist_page(&owner);
+        let allowlist_address = get_allowlist_address(mint_info.key, page, program_id);
+        if !accounts.iter().any(|info| *info.key == allowlist_address) {
+            msg!("Allowlist page {} not provided", page);
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let allowlisted = match find_program_account(program_id, accounts, &allowlist_address)? {
+            Some(allowlist_info) => AllowlistPage::is_allowed(
+                &allowlist_info.try_borrow_data()?,
+                &owner,
+                clock.unix_timestamp,
+            )?,
+            None => false,
+        };
+        if !allowlisted {
+            msg!("Owner {} is not allowlisted", owner);
+            return Err(ExampleError::OwnerNotAllowlisted.into());
+        }
+    }
+    Ok(())
+}
+
+/// Check that the destination is not owned by one of the mint's PDAs, which
+/// can't move the tokens. Only the PDAs derived from the mint alone are
+/// checked, not the ones derived from an owner.
+fn check_destination_not_program_owned(
+    program_id: &Pubkey,
+    mint_info: &AccountInfo,
+    destination: &StateWithExtensions<Account>,
+) -> ProgramResult {
+    let destination_owner = destination.base.owner;
+    let program_addresses = [
+        get_extra_account_metas_address(mint_info.key, program_id),
+        get_mint_config_address(mint_info.key, program_id),
+        get_mint_stats_address(mint_info.key, program_id),
+        get_stats_history_address(mint_info.key, program_id),
+    ];
+    if program_addresses.contains(&destination_owner) {
+        msg!(
+            "Destination owner {} is a PDA of the program",
+            destination_owner
+        );
+        return Err(ExampleError::DestinationOwnedByProgram.into());
+    }
+    Ok(())
+}
+
+/// Check that the destination token account holds enough lamports to be rent
+/// exempt
+fn check_destination_rent_exempt(destination_account_info: &AccountInfo) -> ProgramResult {
+    let lamports = destination_account_info.lamports();
+    let required_lamports = Rent::get()?.minimum_balance(destination_account_info.data_len());
+    if lamports < required_lamports {
+        msg!(
+            "Destination {} holds {} lamports, {} required for rent exemption",
+            destination_account_info.key,
+            lamports,
+            required_lamports
+        );
+        return Err(ExampleError::DestinationNotRentExempt.into());
+    }
+    Ok(())
+}
+
+/// Check that the destination token account carries the extension of the
+/// given type
+fn check_destination_extension(
+    destination_account_info: &AccountInfo,
+    destination: &StateWithExtensions<Account>,
+    extension_type: u16,
+) -> ProgramResult {
+    if !destination
+        .get_extension_types()?
+        .into_iter()
+        .any(|destination_extension| u16::from(destination_extension) == extension_type)
+    {
+        msg!(
+            "Destination {} lacks extension type {}",
+            destination_account_info.key,
+            extension_type
+        );
+        return Err(ExampleError::DestinationExtensionMissing.into());
+    }
+    Ok(())
+}
+
+/// Check that the destination owner accepts incoming transfers, when its
+/// policy PDA was provided in the account list. The mint authority can always
+/// transfer.
+fn check_incoming_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_key: &Pubkey,
+    mint: &StateWithExtensions<Mint>,
+    destination: &StateWithExtensions<Account>,
+    authority_info: &AccountInfo,
+) -> ProgramResult {
+    let destination_owner = destination.base.owner;
+    let incoming_policy_address =
+        get_incoming_policy_address(&destination_owner, mint_key, program_id);
+    let Some(incoming_policy_info) =
+        find_program_account(program_id, accounts, &incoming_policy_address)?
+    else {
+        return Ok(());
+    };
+    if !IncomingPolicy::unpack(&incoming_policy_info.try_borrow_data()?)?.reject_incoming {
+        return Ok(());
+    }
+
+    if mint.base.mint_authority == COption::Some(*authority_info.key) {
+        msg!("Incoming policy bypassed by the mint authority");
+        return Ok(());
+    }
+    msg!("Owner {} rejects incoming transfers", destination_owner);
+    Err(ExampleError::IncomingTransfersRejected.into())
+}
+
+/// Check that the source owner isn't receive only, if their flag was provided
+/// in the account list
+fn check_receive_only(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    source_owner: &Pubkey,
+) -> ProgramResult {
+    let receive_only_address = get_receive_only_address(source_owner, mint_info.key, program_id);
+    let Some(receive_only_info) =
+        find_program_account(program_id, accounts, &receive_only_address)?
+    else {
+        return Ok(());
+    };
+    if ReceiveOnly::unpack(&receive_only_info.try_borrow_data()?)?.receive_only {
+        msg!("Owner {} can only receive transfers", source_owner);
+        return Err(ExampleError::OwnerReceiveOnly.into());
+    }
+    Ok(())
+}
+
+/// Load the mint config, if the config PDA was provided in the account list
+/// and has been initialized
+fn get_mint_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+) -> Result<Option<MintConfig>, ProgramError> {
+    let mint_config_address = get_mint_config_address(mint_info.key, program_id);
+    find_program_account(program_id, accounts, &mint_config_address)?
+        .map(|mint_config_info| MintConfig::unpack(&mint_config_info.try_borrow_data()?))
+        .transpose()
+}
+
+/// Check if the owner is on the mint's exempt owners list, when the list PDA
+/// was provided in the account list
+fn is_exempt_owner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    owner: &Pubkey,
+) -> Result<bool, ProgramError> {
+    let exempt_owners_address = get_exempt_owners_address(mint_info.key, program_id);
+    match find_program_account(program_id, accounts, &exempt_owners_address)? {
+        Some(exempt_owners_info) => {
+            OwnerList::contains(&exempt_owners_info.try_borrow_data()?, owner)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Get the clock from the clock sysvar account, if it was provided in the
+/// account list.
+///
+/// Callers that don't pass the sysvar skip the time-based checks, which are
+/// then treated as disabled.
+fn get_clock(accounts: &[AccountInfo]) -> Result<Option<Clock>, ProgramError> {
+    accounts
+        .iter()
+        .find(|info| sysvar::clock::check_id(info.key))
+        .map(Clock::from_account_info)
+        .transpose()
+}
+
+/// Check that the transfer leaves the source with at least the amount its
+/// owner's vesting schedule still locks, when the schedule PDA was provided in
+/// the account list. Vested tokens, and those held on top of the schedule,
+/// move freely.
+fn check_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    owner: &Pubkey,
+    balance: u64,
+) -> ProgramResult {
+    let vesting_schedule_address = get_vesting_schedule_address(owner, mint_info.key, program_id);
+    let Some(vesting_schedule_info) =
+        find_program_account(program_id, accounts, &vesting_schedule_address)?
+    else {
+        return Ok(());
+    };
+    let schedule = VestingSchedule::unpack(&vesting_schedule_info.try_borrow_data()?)?;
+    let locked = schedule.locked_amount(Clock::get()?.unix_timestamp);
+    if balance < locked {
+        msg!(
+            "Transfer leaves {} of the {} locked by the vesting schedule",
+            balance,
+            locked
+        );
+        return Err(ExampleError::TransferExceedsVestedAmount.into());
+    }
+    Ok(())
+}
+
+/// Check that at least `cooldown_seconds` have passed since the last
+/// transfer, if any
+fn check_cooldown(
+    cooldown_seconds: u32,
+    last_transfer_timestamp: i64,
+    unix_timestamp: i64,
+) -> ProgramResult {
+    if cooldown_seconds == 0 || last_transfer_timestamp == 0 {
+        return Ok(());
+    }
+    let elapsed = unix_timestamp.saturating_sub(last_transfer_timestamp);
+    if elapsed < i64::from(cooldown_seconds) {
+        msg!(
+            "Transfer cooldown active: {}s of {}s elapsed",
+            elapsed,
+            cooldown_seconds
+        );
+        return Err(ExampleError::TransferCooldownActive.into());
+    }
+    Ok(())
+}
+
+/// Check that at least `holding_period_seconds` have passed since the owner
+/// first received tokens, failing when that time is unknown
+fn check_holding_period(
+    holding_period_seconds: u32,
+    first_received_timestamp: i64,
+    unix_timestamp: i64,
+) -> ProgramResult {
+    if holding_period_seconds == 0 {
+        return Ok(());
+    }
+    if first_received_timestamp == 0 {
+        msg!("Holding period active: no receive recorded");
+        return Err(ExampleError::HoldingPeriodActive.into());
+    }
+    let elapsed = unix_timestamp.saturating_sub(first_received_timestamp);
+    if elapsed < i64::from(holding_period_seconds) {
+        msg!(
+            "Holding period active: {}s of {}s elapsed",
+            elapsed,
+            holding_period_seconds
+        );
+        return Err(ExampleError::HoldingPeriodActive.into());
+    }
+    Ok(())
+}
+
+/// Check that at least `min_account_age_slots` have passed since the owner
+/// first received tokens, failing when that slot is unknown
+fn check_account_age(
+    min_account_age_slots: u64,
+    first_received_slot: u64,
+    slot: u64,
+) -> ProgramResult {
+    if min_account_age_slots == 0 {
+        return Ok(());
+    }
+    if first_received_slot == 0 {
+        msg!("Account too young: no receive recorded");
+        return Err(ExampleError::AccountTooYoung.into());
+    }
+    let age = slot.saturating_sub(first_received_slot);
+    if age < min_account_age_slots {
+        msg!(
+            "Account too young: {} slots of {} elapsed",
+            age,
+            min_account_age_slots
+        );
+        return Err(ExampleError::AccountTooYoung.into());
+    }
+    Ok(())
+}
+
+/// Record the transfer received by the destination owner in their transfer
+/// account: its first timestamp and slot, for the holding period and minimum
+/// account age of their outbound transfers, and the total received, checked
+/// against the receive cap.
+///
+/// Skipped when the transfer account wasn't provided in the account list,
+/// unless the receive cap requires it, and the timestamp and slot are skipped
+/// without the clock sysvar. Moves between accounts of the same owner don't count as
+/// received.
+fn record_received_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_info: &AccountInfo,
+    config: &MintConfig,
+    destination: &StateWithExtensions<Account>,
+    source_owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let destination_owner = destination.base.owner;
+    if destination_owner == *source_owner {
+        return Ok(());
+    }
+    let transfer_account_address =
+        get_transfer_account_address(&destination_owner, mint_info.key, program_id);
+    let Some(transfer_account_info) =
+        find_program_account(program_id, accounts, &transfer_account_address)?
+    else {
+        if config.max_received_amount != 0 {
+            msg!(
+                "Receive cap requires the transfer account of {}",
+                destination_owner
+            );
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        return Ok(());
+    };
+    let mut data = transfer_account_info.try_borrow_mut_data()?;
+    let mut tracked = TransferAccount::unpack(&data)?;
+    if let Some(clock) = get_clock(accounts)? {
+        // set once, so that a later receive, however small, doesn't restart
+        // the holding period or the account age started at initialization
+        if tracked.first_received_timestamp == 0 {
+            tracked.first_received_timestamp = clock.unix_timestamp;
+        }
+        if tracked.first_received_slot == 0 {
+            tracked.first_received_slot = clock.slot;
+        }
+    }
+    let received = tracked
+        .received
+        .checked_add(amount)
+        .ok_or(ExampleError::TrackingOverflow)?;
+    if config.max_received_amount != 0 && received > config.max_received_amount {
+        msg!(
+            "Transfer of {} exceeds the receive cap of {}, {} already received",
+            amount,
+            config.max_received_amount,
+            tracked.received
+        );
+        return Err(ExampleError::ReceiveCapExceeded.into());
+    }
+    tracked.received = received;
+    tracked.pack(&mut data);
+    Ok(())
+}
+
+/// Check that an instruction of the ed25519 program in the transaction
+/// verifies a ticket of `ticket_signer` for the transfer of the mint, whose
+/// nonce is above the last one used by the owner, and record its nonce.
+///
+/// The ed25519 program fails the whole transaction on an invalid signature,
+/// so any ticket its instructions hold is signed. The lowest usable nonce is
+/// taken, leaving the others to later transfers of the transaction.
+fn check_transfer_ticket(
+    accounts: &[AccountInfo],
+    ticket_signer: &Pubkey,
+    transfer_account: &AccountInfo,
+    mint_key: &Pubkey,
+    source_owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let instructions_info = accounts
+        .iter()
+        .find(|info| sysvar::instructions::check_id(info.key))
+        .ok_or_else(|| {
+            msg!("Instructions sysvar required by the transfer ticket");
+            ProgramError::NotEnoughAccountKeys
+        })?;
+
+    let mut data = transfer_account.try_borrow_mut_data()?;
+    let mut tracked = TransferAccount::unpack(&data)?;
+    let nonce = (0..=usize::from(u16::MAX))
+        .map_while(|index| {
+            sysvar::instructions::load_instruction_at_checked(index, instructions_info).ok()
+        })
+        .filter(|instruction| instruction.program_id == ed25519_program::id())
+        .flat_map(|instruction| signed_tickets(&instruction.data))
+        .filter(|(signer, ticket)| {
+            signer == ticket_signer
+                && ticket.source_owner == *source_owner
+                && ticket.mint == *mint_key
+                && ticket.amount == amount
+                && ticket.nonce > tracked.last_ticket_nonce
+        })
+        .map(|(_, ticket)| ticket.nonce)
+        .min()
+        .ok_or_else(|| {
+            msg!(
+                "No ticket of {} for a transfer of {} of {} by {} above nonce {}",
+                ticket_signer,
+                amount,
+                mint_key,
+                source_owner,
+                tracked.last_ticket_nonce
+            );
+            ExampleError::MissingTransferTicket
+        })?;
+    tracked.last_ticket_nonce = nonce;
+    tracked.pack(&mut data);
+    Ok(())
+}
+
+/// Tickets whose signature is verified by an instruction of the ed25519
+/// program, along with their signer.
+///
+/// Signatures whose pubkey, signature or message are read from another
+/// instruction are skipped, as are messages that aren't tickets.
+fn signed_tickets(data: &[u8]) -> Vec<(Pubkey, TransferTicket)> {
+    // count of signatures and padding, followed by their offsets
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    let Some(&count) = data.first() else {
+        return Vec::new();
+    };
+    (0..usize::from(count))
+        .filter_map(|index| {
+            let offsets = data
+                .get(OFFSETS_START + index * OFFSETS_LEN..)?
+                .get(..OFFSETS_LEN)?;
+            let read = |field: usize| {
+                usize::from(u16::from_le_bytes([
+                    offsets[field * 2],
+                    offsets[field * 2 + 1],
+                ]))
+            };
+            // signature, pubkey and message instruction indexes
+            if [1, 3, 6]
+                .iter()
+                .any(|&field| read(field) != usize::from(u16::MAX))
+            {
+                return None;
+            }
+            let signer = Pubkey::try_from(data.get(read(2)..)?.get(..32)?).ok()?;
+            let message = data.get(read(4)..)?.get(..read(5))?;
+            Some((signer, TransferTicket::unpack(message).ok()?))
+        })
+        .collect()
+}
+
+/// Add the transfer to the current window of `window_slots` slots, starting a
+/// new window once the current one has elapsed, and check that the windowed
+/// amount stays within `max_amount`
+fn check_slot_window(
+    window_slots: u64,
+    max_amount: u64,
+    tracked: &mut TransferAccount,
+    slot: u64,
+    amount: u64,
+) -> ProgramResult {
+    if window_slots == 0 {
+        return Ok(());
+    }
+    if slot.saturating_sub(tracked.window_start_slot) >= window_slots {
+        tracked.window_start_slot = slot;
+        tracked.window_amount = 0;
+    }
+    let window_amount = tracked
+        .window_amount
+        .checked_add(amount)
+        .filter(|window_amount| *window_amount <= max_amount)
+        .ok_or_else(|| {
+            msg!(
+                "Transfer of {} exceeds the window limit of {} per {} slots, {} already transferred",
+                amount,
+                max_amount,
+                window_slots,
+                tracked.window_amount
+            );
+            ExampleError::WindowLimitExceeded
+        })?;
+    tracked.window_amount = window_amount;
+    Ok(())
+}
+
+/// Length of the days counting the transfers of each owner, in seconds
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Count the transfer in its UTC day, starting a new count once the day of the
+/// last counted transfer has passed, and check that the count stays within
+/// `max_transfers`
+fn check_daily_transfers(
+    max_transfers: u32,
+    tracked: &mut TransferAccount,
+    unix_timestamp: i64,
+) -> ProgramResult {
+    if max_transfers == 0 {
+        return Ok(());
+    }
+    let day_start_ts = unix_timestamp - unix_timestamp.rem_euclid(SECONDS_PER_DAY);
+    if day_start_ts > tracked.day_start_ts {
+        tracked.day_start_ts = day_start_ts;
+        tracked.day_count = 0;
+    }
+    if tracked.day_count >= max_transfers {
+        msg!(
+            "Transfer exceeds the cap of {} transfers per day",
+            max_transfers
+        );
+        return Err(ExampleError::DailyTransferLimitExceeded.into());
+    }
+    tracked.day_count += 1;
+    Ok(())
+}
+
+/// Check that a transfer doesn't move more than `max_pct_bps` of the source
+/// holdings.
+///
+/// The hook runs after the tokens have moved, so the holdings are the
+/// remaining source balance plus the transferred amount.
+fn check_max_balance_percentage(max_pct_bps: u16, balance: u64, amount: u64) -> ProgramResult {
+    if max_pct_bps == 0 {
+        return Ok(());
+    }
+    let holdings = u128::from(balance) + u128::from(amount);
+    if u128::from(amount) * 10_000 > holdings * u128::from(max_pct_bps) {
+        msg!(
+            "Transfer of {} exceeds {} bps of holdings {}",
+            amount,
+            max_pct_bps,
+            holdings
+        );
+        return Err(ExampleError::TransferExceedsBalancePercentage.into());
+    }
+    Ok(())
+}
+
+/// Largest amount a transfer may move under `max_pct_bps` of the source
+/// holdings, which the transfer doesn't change
+fn max_balance_percentage_amount(max_pct_bps: u16, balance: u64, amount: u64) -> u64 {
+    let holdings = u128::from(balance) + u128::from(amount);
+    u64::try_from(holdings * u128::from(max_pct_bps) / 10_000).unwrap_or(u64::MAX)
+}
+
+/// Add the transfer to the partial fills of its transaction, aggregated in
+/// the transfer account, returning the amount the transaction moved so far,
+/// the transfer included.
+///
+/// The transaction is told apart by the slot and the instructions it holds,
+/// read from the instructions sysvar. As transactions with the same
+/// instructions in the same slot can't be told apart that way, a transfer
+/// only adds to the fills of the transaction when its instruction comes after
+/// the one of the last fill: the first chunk of a repeated transaction starts
+/// new fills, and so do chunks moved by the same instruction, each limited as
+/// a transfer of its own.
+fn aggregate_partial_fill(
+    accounts: &[AccountInfo],
+    transfer_account: &AccountInfo,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let instructions_info = accounts
+        .iter()
+        .find(|info| sysvar::instructions::check_id(info.key))
+        .ok_or_else(|| {
+            msg!("Instructions sysvar required to aggregate partial fills");
+            ProgramError::NotEnoughAccountKeys
+        })?;
+    let fill_instruction_index =
+        sysvar::instructions::load_current_index_checked(instructions_info)?;
+    let fill_transaction = {
+        let data = instructions_info.try_borrow_data()?;
+        // the last two bytes hold the index of the current instruction
+        let instructions = &data[..data.len().saturating_sub(2)];
+        hashv(&[&Clock::get()?.slot.to_le_bytes(), instructions]).to_bytes()
+    };
+
+    let mut data = transfer_account.try_borrow_mut_data()?;
+    let mut tracked = TransferAccount::unpack(&data)?;
+    if tracked.fill_transaction != fill_transaction
+        || tracked.fill_instruction_index >= fill_instruction_index
+    {
+        tracked.fill_transaction = fill_transaction;
+        tracked.fill_amount = 0;
+    }
+    tracked.fill_instruction_index = fill_instruction_index;
+    tracked.fill_amount = tracked
+        .fill_amount
+        .checked_add(amount)
+        .ok_or(ExampleError::TrackingOverflow)?;
+    tracked.pack(&mut data);
+    Ok(tracked.fill_amount)
+}
+
+/// The amounts of a transfer checked by `Execute`
+#[derive(Clone, Copy)]
+struct TransferAmounts {
+    /// Amount `Execute` is called with
+    amount: u64,
+    /// Amount the per-transfer limits apply to: the transfer amount, or the
+    /// partial fills of the transaction so far, whose later chunks count as
+    /// the same transfer for the cooldown and the daily cap
+    limited: u64,
+    /// Amount added to the tracked totals: the transfer amount, or the amount
+    /// net of the transfer fee when the config tracks it
+    tracked: u64,
+}
+
+/// The amount of the transfer net of the Token-2022 transfer fee of the mint
+/// for the current epoch, which is withheld from the destination. Mints
+/// without the transfer fee extension take no fee.
+fn amount_net_of_transfer_fee(
+    mint: &StateWithExtensions<Mint>,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let fee = transfer_fee_config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    msg!("Transfer fee of {} withheld from {}", fee, amount);
+    Ok(amount.saturating_sub(fee))
+}
+
+/// Check the transfer against the limits based on the transfer account, and
+/// add it to the tracked total
+fn track_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transfer_account: &AccountInfo,
+    source_owner: &Pubkey,
+    config: &MintConfig,
+    amounts: TransferAmounts,
+) -> ProgramResult {
+    let TransferAmounts {
+        amount,
+        limited: limited_amount,
+        tracked: tracked_amount,
+    } = amounts;
+
+    // Verify transfer account exists and is initialized
+    if transfer_account.lamports() == 0 {
+        msg!("Transfer account does not exist. Call InitializeTransferAccount first.");
+        msg!("Transfer account: {}", transfer_account.key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Verify transfer account is owned by this program
+    if transfer_account.owner != program_id {
+        msg!("Transfer account not owned by program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let mut transfer_account_data = transfer_account.try_borrow_mut_data()?;
+    let mut tracked = TransferAccount::unpack(&transfer_account_data)?;
+
+    // The derivation alone doesn't rule out an account written for another
+    // owner, so the stored owner is checked before crediting it
+    if tracked.owner != *source_owner {
+        msg!(
+            "Transfer account tracks {}, not the source owner {}",
+            tracked.owner,
+            source_owner
+        );
+        return Err(ExampleError::TransferAccountOwnerMismatch.into());
+    }
+
+    // Time-based checks only run if the clock sysvar was provided
+    let later_fill = limited_amount > amount;
+    if let Some(clock) = get_clock(accounts)? {
+        if !later_fill {
+            check_cooldown(
+                config.cooldown_seconds,
+                tracked.last_transfer_timestamp,
+                clock.unix_timestamp,
+            )?;
+        }
+        check_holding_period(
+            config.holding_period_seconds,
+            tracked.first_received_timestamp,
+            clock.unix_timestamp,
+        )?;
+        check_account_age(
+            config.min_account_age_slots,
+            tracked.first_received_slot,
+            clock.slot,
+        )?;
+        if !later_fill {
+            check_daily_transfers(
+                config.max_transfers_per_day,
+                &mut tracked,
+                clock.unix_timestamp,
+            )?;
+        }
+        tracked.last_transfer_timestamp = clock.unix_timestamp;
+        if tracked.first_transfer_timestamp == 0 {
+            tracked.first_transfer_timestamp = clock.unix_timestamp;
+        }
+        if !config.is_limit_exempt(limited_amount) {
+            check_slot_window(
+                config.window_slots,
+                config.max_amount_per_window_slots,
+                &mut tracked,
+                clock.slot,
+                amount,
+            )?;
+        }
+    }
+
+    if config.enforce_allowances {
+        tracked.allowance_remaining =
+            tracked
+                .allowance_remaining
+                .checked_sub(amount)
+                .ok_or_else(|| {
+                    msg!(
+                        "Transfer of {} exceeds the remaining allowance of {}",
+                        amount,
+                        tracked.allowance_remaining
+                    );
+                    ExampleError::AllowanceExceeded
+                })?;
+    }
+
+    // Update the transfer amount
+    tracked.transfered = tracked
+        .transfered
+        .checked_add(tracked_amount)
+        .ok_or(ExampleError::TrackingOverflow)?;
+    tracked.max_single_transfer = tracked.max_single_transfer.max(tracked_amount);
+    tracked.pack(&mut transfer_account_data);
+
+    msg!(
+        "Transfer tracked: {} total for account {}",
+        tracked.transfered,
+        transfer_account.key
+    );
+    Ok(())
+}
+
+/// Log the compute units remaining at a step of `process_execute`
+#[cfg(feature = "debug-compute")]
+fn log_compute_units(step: &str) {
+    msg!(
+        "Compute units remaining {}: {}",
+        step,
+        solana_program::compute_units::sol_remaining_compute_units()
+    );
+}
+
+/// Processes an [Execute](enum.TransferHookInstruction.html) instruction.
+pub fn process_execute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let source_account_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+
+    #[cfg(feature = "debug-compute")]
+    log_compute_units("before checks");
+
+    // Unpacked once, like the config below, and passed to the checks needing
+    // them
+    let mint_data = mint_info.try_borrow_data()?;
+    let Ok(mint) = StateWithExtensions::<Mint>::unpack(&mint_data) else {
+        msg!("Account {} is not a mint", mint_info.key);
+        return Err(ExampleError::InvalidMintAccount.into());
+    };
+    let source_data = source_account_info.try_borrow_data()?;
+    let destination_data = destination_account_info.try_borrow_data()?;
+    let (source, destination) = check_execute_accounts(
+        mint_info.key,
+        &mint,
+        source_account_info,
+        &source_data,
+        destination_account_info,
+        &destination_data,
+        authority_info,
+    )?;
+
+    // Check that the accounts are properly in "transferring" mode
+    check_token_account_is_transferring(&source)?;
+    let config = get_mint_config(program_id, accounts, mint_info)?.unwrap_or_default();
+    if !config.source_transferring_only {
+        check_token_account_is_transferring(&destination)?;
+    }
+    check_mint_decimals(&mint, &config)?;
+
+    // For the example program, we just check that the correct pda and validation
+    // pubkeys are provided
+    let expected_validation_address = get_extra_account_metas_address(mint_info.key, program_id);
+    if expected_validation_address != *extra_account_metas_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if extra_account_metas_info.owner != program_id {
+        msg!("Validation account not owned by program");
+        return Err(TransferHookError::IncorrectAccount.into());
+    }
+    check_transfer_hook_program_id(program_id, &mint)?;
+    check_extra_account_metas_version(&config)?;
+
+    let data = extra_account_metas_info.try_borrow_data()?;
+
+    // Bound the metas resolved below, in case the validation account was
+    // written without the check
+    let state = TlvStateBorrowed::unpack(&data)?;
+    let extra_account_metas =
+        ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&state)?;
+    check_extra_account_metas_len(extra_account_metas.data().len())?;
+
+    ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+        accounts,
+        &TransferHookInstruction::Execute { amount }.pack(),
+        program_id,
+        &data,
+    )?;
+    if config.owner_checked_metas != 0 {
+        check_external_meta_owners(
+            config.owner_checked_metas,
+            extra_account_metas.data(),
+            accounts,
+        )?;
+    }
+
+    #[cfg(feature = "debug-compute")]
+    log_compute_units("after meta resolution");
+
+    // Get the transfer account (must already exist), unless the mint doesn't
+    // track transfers
+    let transfer_account = if config.skip_transfer_tracking {
+        None
+    } else {
+        Some(next_account_info(account_info_iter)?)
+    };
+
+    // Verify transfer account is derived from the source owner, so transfers by
+    // a delegate are tracked for the owner as well
+    let source_owner = source.base.owner;
+    if let Some(transfer_account) = transfer_account {
+        let expected_transfer_account =
+            get_transfer_account_address(&source_owner, mint_info.key, program_id);
+        if *transfer_account.key != expected_transfer_account {
+            msg!(
+                "Invalid transfer account derivation. Expected: {}, Got: {}",
+                expected_transfer_account,
+                transfer_account.key
+            );
+            return Err(ProgramError::InvalidSeeds);
+        }
+    }
+
+    check_access(
+        program_id,
+        accounts,
+        mint_info,
+        &config,
+        &source,
+        &destination,
+    )?;
+    if config.reject_program_destinations {
+        check_destination_not_program_owned(program_id, mint_info, &destination)?;
+    }
+    if config.require_rent_exempt_destination {
+        check_destination_rent_exempt(destination_account_info)?;
+    }
+    if config.required_destination_extension != 0 {
+        check_destination_extension(
+            destination_account_info,
+            &destination,
+            config.required_destination_extension,
+        )?;
+    }
+    check_incoming_policy(
+        program_id,
+        accounts,
+        mint_info.key,
+        &mint,
+        &destination,
+        authority_info,
+    )?;
+    check_receive_only(program_id, accounts, mint_info, &source_owner)?;
+
+    if config.require_active_mint {
+        check_mint_active(&mint)?;
+    }
+    check_pause_schedule(&config)?;
+    if config.require_owner_signer {
+        check_owner_signed(accounts, &source_owner)?;
+    }
+    if config.reject_delegate_transfers && *authority_info.key != source_owner {
+        msg!(
+            "Authority {} is a delegate of the source owner {}",
+            authority_info.key,
+            source_owner
+        );
+        return Err(ExampleError::DelegateTransferRejected.into());
+    }
+    if config.require_setup && !config.setup_complete {
+        msg!("Transfers are rejected until the setup is finalized");
+        return Err(ExampleError::SetupNotFinalized.into());
+    }
+    if config.distributor != Pubkey::default() && source_owner != config.distributor {
+        msg!(
+            "Only the distributor {} may transfer until trading is enabled",
+            config.distributor
+        );
+        return Err(ExampleError::TradingNotEnabled.into());
+    }
+    check_vesting(
+        program_id,
+        accounts,
+        mint_info,
+        &source_owner,
+        source.base.amount,
+    )?;
+    if config.check_amount_consistency {
+        check_amount_consistency(&mint, &source, &destination, amount)?;
+    }
+    if config.ticket_signer != Pubkey::default() {
+        // the config check keeps the transfer account along with tickets
+        let transfer_account = transfer_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        check_transfer_ticket(
+            accounts,
+            &config.ticket_signer,
+            transfer_account,
+            mint_info.key,
+            &source_owner,
+            amount,
+        )?;
+    }
+
+    // Zero-amount transfers whose tracking is disabled return early, without
+    // loading the transfer account, once every check that can halt them passed
+    if amount == 0 && config.zero_amount_mode == ZeroAmountMode::SkipTracking {
+        msg!("Zero-amount transfer not tracked");
+        return Ok(());
+    }
+
+    // Moves between accounts of the same owner may skip tracking and limits
+    let same_owner_skipped =
+        config.skip_same_owner_transfers && destination.base.owner == source_owner;
+    let trusted_caller = is_trusted_caller(accounts, &config)?;
+
+    // The chunks of a partial fill are limited as a whole
+    let limited_amount = match transfer_account {
+        Some(transfer_account)
+            if config.aggregate_partial_fills && transfer_account.owner == program_id =>
+        {
+            aggregate_partial_fill(accounts, transfer_account, amount)?
+        }
+        _ => amount,
+    };
+
+    if config.max_pct_bps != 0
+        && !config.is_limit_exempt(limited_amount)
+        && !same_owner_skipped
+        && !trusted_caller
+    {
+        let result =
+            check_max_balance_percentage(config.max_pct_bps, source.base.amount, limited_amount);
+        if result.is_err() && config.report_allowed_maximum {
+            let allowed_maximum = max_balance_percentage_amount(
+                config.max_pct_bps,
+                source.base.amount,
+                limited_amount,
+            );
+            msg!("Allowed maximum: {}", allowed_maximum);
+            set_return_data(&allowed_maximum.to_le_bytes());
+            return Err(ExampleError::TransferExceedsAllowedMaximum.into());
+        }
+        result?;
+    }
+
+    if amount == 0 && config.zero_amount_mode == ZeroAmountMode::Reject {
+        msg!("Zero-amount transfers are rejected");
+        return Err(ExampleError::ZeroAmountTransfer.into());
+    }
+
+    let tracked_amount = if config.track_net_of_transfer_fee {
+        amount_net_of_transfer_fee(&mint, amount)?
+    } else {
+        amount
+    };
+
+    // Exempt owners skip tracking, along with the checks based on it
+    match transfer_account {
+        None => msg!("Transfer tracking disabled for the mint"),
+        Some(_) if is_exempt_owner(program_id, accounts, mint_info, &source_owner)? => {
+            msg!("Owner {} is exempt from tracking", source_owner);
+        }
+        Some(_) if same_owner_skipped => {
+            msg!(
+                "Transfer between accounts of owner {} not tracked",
+                source_owner
+            );
+        }
+        Some(_) if trusted_caller => msg!("Trusted caller transfer not tracked"),
+        Some(transfer_account) => {
+            track_transfer(
+                program_id,
+                accounts,
+                transfer_account,
+                &source_owner,
+                &config,
+                TransferAmounts {
+                    amount,
+                    limited: limited_amount,
+                    tracked: tracked_amount,
+                },
+            )?;
+        }
+    }
+    record_received_transfer(
+        program_id,
+        accounts,
+        mint_info,
+        &config,
+        &destination,
+        &source_owner,
+        tracked_amount,
+    )?;
+
+    #[cfg(feature = "debug-compute")]
+    log_compute_units("after tracking update");
+
+    #[cfg(feature = "recent-log")]
+    append_to_recent_transfer_log(program_id, accounts, mint_info, &source_owner, amount)?;
+    update_transfer_root(program_id, accounts, mint_info, &source_owner, amount)?;
+
+    accrue_fees(program_id, accounts, mint_info, &config, amount)?;
+    record_owed_burn(
+        program_id,
+        accounts,
+        mint_info,
+        &config,
+        &source_owner,
+        amount,
+    )?;
+    record_accepted_transfer(program_id, accounts, mint_info, &config, amount)?;
+
+    let event = TransferEvent {
+        mint: InterfacePubkey::new_from_array(mint_info.key.to_bytes()),
+        owner: InterfacePubkey::new_from_array(source_owner.to_bytes()),
+        destination: InterfacePubkey::new_from_array(destination_account_info.key.to_bytes()),
+        amount,
+    };
+    sol_log_data(&[&event.to_log_data()]);
+
+    if config.notify_program != Pubkey::default() {
+        notify_program(accounts, &config.notify_program, &source_owner, amount)?;
+    }
+
+    Ok(())
+}
+
+/// Whether the program of the top-level instruction, calling into the token
+/// program, is one of the trusted callers of the config. Without the
+/// instructions sysvar among the accounts, no caller is trusted.
+fn is_trusted_caller(accounts: &[AccountInfo], config: &MintConfig) -> Result<bool, ProgramError> {
+    if config.configured_trusted_callers().next().is_none() {
+        return Ok(false);
+    }
+    let Some(instructions_info) = accounts
+        .iter()
+        .find(|info| sysvar::instructions::check_id(info.key))
+    else {
+        return Ok(false);
+    };
+    let caller = sysvar::instructions::get_instruction_relative(0, instructions_info)?.program_id;
+    let trusted = config
+        .configured_trusted_callers()
+        .any(|trusted_caller| *trusted_caller == caller);
+    if trusted {
+        msg!("Transfer called through trusted caller {}", caller);
+    }
+    Ok(trusted)
+}
+
+/// Notify the configured program of the transfer. The program must be part
+/// of the account list.
+fn notify_program(
+    accounts: &[AccountInfo],
+    notify_program_id: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let Some(notify_program_info) = accounts.iter().find(|info| info.key == notify_program_id)
+    else {
+        msg!("Notify program {} not provided", notify_program_id);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    invoke(
+        &notify_transfer(notify_program_id, owner, amount),
+        &[notify_program_info.clone()],
+    )
+}
+
+/// Report the failed `Execute` check through return data, see
+/// [`FailedCheck`](crate::error::FailedCheck)
+fn report_failed_check(error: &ProgramError) {
+    if let ProgramError::Custom(code) = error {
+        if let Some(check) = ExampleError::from_u32(*code).and_then(|e| e.failed_check()) {
+            set_return_data(&[check as u8]);
+        }
+    }
+}
+
+/// Maximum number of extra account metas in a validation account.
+///
+/// This bounds both the size of the validation account and the compute
+/// `Execute` spends resolving and checking the metas on every transfer.
+pub const MAX_EXTRA_ACCOUNT_METAS: usize = 24;
+
+/// Version of the extra account metas layout this program resolves, bumped
+/// whenever a program upgrade changes the metas `Execute` expects.
+///
+/// Mints record the version their validation account was written for in
+/// their config, so an upgraded program rejects the stale list instead of
+/// resolving it incorrectly.
+pub const EXTRA_ACCOUNT_METAS_VERSION: u8 = 1;
+
+/// Check that the validation account was written for the extra account metas
+/// layout of this program, if the config records its version
+fn check_extra_account_metas_version(config: &MintConfig) -> ProgramResult {
+    let version = config.extra_account_metas_version;
+    if version != 0 && version != EXTRA_ACCOUNT_METAS_VERSION {
+        msg!(
+            "Validation account written for extra account metas version {}, expected {}",
+            version,
+            EXTRA_ACCOUNT_METAS_VERSION
+        );
+        return Err(ExampleError::ExtraAccountMetasVersionMismatch.into());
+    }
+    Ok(())
+}
+
+/// Check that the `TransferHook` extension of the mint points to this program,
+/// so a mint is only tracked by the program it configured
+fn check_transfer_hook_program_id(
+    program_id: &Pubkey,
+    mint: &StateWithExtensions<Mint>,
+) -> ProgramResult {
+    let hook_program_id = mint
+        .get_extension::<TransferHook>()
+        .ok()
+        .and_then(|extension| Option::<Pubkey>::from(extension.program_id));
+    if hook_program_id != Some(*program_id) {
+        msg!("Mint transfer hook program: {:?}", hook_program_id);
+        return Err(ExampleError::TransferHookProgramMismatch.into());
+    }
+    Ok(())
+}
+
+/// Check that the accounts of `Execute` are in their expected positions: the
+/// source and destination are token accounts of the mint, and
+/// the authority can move the source tokens, as their owner, their delegate
+/// or the permanent delegate of the mint. Token-2022 clears the delegate of
+/// the source before invoking the hook once its approval is spent, so any
+/// authority is accepted while the source has no delegate. Returns the source
+/// and destination token accounts, unpacked from their data.
+fn check_execute_accounts<'data>(
+    mint_key: &Pubkey,
+    mint: &StateWithExtensions<Mint>,
+    source_account_info: &AccountInfo,
+    source_data: &'data [u8],
+    destination_account_info: &AccountInfo,
+    destination_data: &'data [u8],
+    authority_info: &AccountInfo,
+) -> Result<
+    (
+        StateWithExtensions<'data, Account>,
+        StateWithExtensions<'data, Account>,
+    ),
+    ProgramError,
+> {
+    let source = unpack_mint_token_account(
+        mint_key,
+        source_account_info,
+        source_data,
+        ExampleError::SourceAccountMismatch,
+    )?;
+    let destination = unpack_mint_token_account(
+        mint_key,
+        destination_account_info,
+        destination_data,
+        ExampleError::DestinationAccountMismatch,
+    )?;
+
+    let permanent_delegate = mint
+        .get_extension::<PermanentDelegate>()
+        .ok()
+        .and_then(|extension| Option::<Pubkey>::from(extension.delegate));
+    if *authority_info.key != source.base.owner
+        && source.base.delegate.is_some()
+        && source.base.delegate != COption::Some(*authority_info.key)
+        && permanent_delegate != Some(*authority_info.key)
+    {
+        msg!(
+            "Authority {} can't transfer from the source",
+            authority_info.key
+        );
+        return Err(ExampleError::UnexpectedTransferAuthority.into());
+    }
+    Ok((source, destination))
+}
+
+/// Unpack the data of a token account of the mint, failing with `error`
+/// otherwise
+fn unpack_mint_token_account<'data>(
+    mint_key: &Pubkey,
+    account_info: &AccountInfo,
+    data: &'data [u8],
+    error: ExampleError,
+) -> Result<StateWithExtensions<'data, Account>, ProgramError> {
+    match StateWithExtensions::<Account>::unpack(data) {
+        Ok(account) if account.base.mint == *mint_key => Ok(account),
+        _ => {
+            msg!(
+                "Account {} is not a token account of the mint",
+                account_info.key
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// Check that the mint account has the decimals expected by a config being
+/// written, if enforced
+fn check_config_decimals(mint_info: &AccountInfo, config: &MintConfig) -> ProgramResult {
+    if !config.enforce_decimals {
+        return Ok(());
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    check_mint_decimals(&mint, config)
+}
+
+/// Check that the mint has the decimals expected by the config, if enforced
+fn check_mint_decimals(mint: &StateWithExtensions<Mint>, config: &MintConfig) -> ProgramResult {
+    if config.enforce_decimals && mint.base.decimals != config.expected_decimals {
+        msg!(
+            "Mint has {} decimals, expected {}",
+            mint.base.decimals,
+            config.expected_decimals
+        );
+        return Err(ExampleError::MintDecimalsMismatch.into());
+    }
+    Ok(())
+}
+
+/// Check that the mint isn't paused and still has a mint authority
+fn check_mint_active(mint: &StateWithExtensions<Mint>) -> ProgramResult {
+    if mint.base.mint_authority.is_none() {
+        msg!("Mint authority has been revoked");
+        return Err(ExampleError::MintHalted.into());
+    }
+    if mint
+        .get_extension::<PausableConfig>()
+        .is_ok_and(|extension| bool::from(extension.paused))
+    {
+        msg!("Mint is paused");
+        return Err(ExampleError::MintHalted.into());
+    }
+    Ok(())
+}
+
+/// Check that the pause scheduled for the mint, if any, hasn't taken effect,
+/// logging its reason otherwise.
+///
+/// The slot is read from the clock syscall rather than the optional clock
+/// sysvar account, so the pause can't be avoided by leaving it out.
+fn check_pause_schedule(config: &MintConfig) -> ProgramResult {
+    if config.pause_effective_slot == 0 {
+        return Ok(());
+    }
+    if Clock::get()?.slot >= config.pause_effective_slot {
+        let reason = config.pause_reason.split(|byte| *byte == 0).next();
+        msg!(
+            "Mint paused since slot {}: {}",
+            config.pause_effective_slot,
+            String::from_utf8_lossy(reason.unwrap_or_default())
+        );
+        return Err(ExampleError::MintHalted.into());
+    }
+    Ok(())
+}
+
+/// Check that the source owner signed the transfer, as the authority or as
+/// any other account passed to `Execute`
+fn check_owner_signed(accounts: &[AccountInfo], source_owner: &Pubkey) -> ProgramResult {
+    if accounts
+        .iter()
+        .any(|account_info| account_info.is_signer && account_info.key == source_owner)
+    {
+        return Ok(());
+    }
+    msg!("Source owner {} has not signed the transfer", source_owner);
+    Err(ExampleError::SourceOwnerNotSigner.into())
+}
+
+/// Check the amount passed to `Execute` against the token balances.
+///
+/// The hook runs once the tokens have moved: the source balance before the
+/// transfer is its current balance plus the amount, which can't be checked
+/// beyond not overflowing, while the destination must now hold at least the
+/// amount it was credited, net of the transfer fee of the mint.
+fn check_amount_consistency(
+    mint: &StateWithExtensions<Mint>,
+    source: &StateWithExtensions<Account>,
+    destination: &StateWithExtensions<Account>,
+    amount: u64,
+) -> ProgramResult {
+    let source_balance = source.base.amount;
+    let destination_balance = destination.base.amount;
+    let credited = amount_net_of_transfer_fee(mint, amount)?;
+    if source_balance.checked_add(amount).is_none() || destination_balance < credited {
+        msg!(
+            "Amount {} with source balance {} and destination balance {}",
+            amount,
+            source_balance,
+            destination_balance
+        );
+        return Err(ExampleError::InconsistentTransferAmount.into());
+    }
+    Ok(())
+}
+
+/// Number of accounts of `Execute` preceding the extra accounts
+const EXECUTE_BASE_ACCOUNTS: usize = 5;
+
+/// Discriminator of the first external PDA meta, whose program is the account
+/// at the index added to it
+const EXTERNAL_PDA_DISCRIMINATOR: u8 = 1 << 7;
+
+/// Check that the accounts resolved from the metas selected by
+/// `owner_checked_metas`, by index in the list, are owned by the program their
+/// external PDA is derived from.
+///
+/// The accounts have already been checked against the metas.
+fn check_external_meta_owners(
+    owner_checked_metas: u32,
+    extra_account_metas: &[ExtraAccountMeta],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    for index in (0..u32::BITS).filter(|index| owner_checked_metas & (1 << index) != 0) {
+        let index = index as usize;
+        let program_index = extra_account_metas
+            .get(index)
+            .and_then(|meta| meta.discriminator.checked_sub(EXTERNAL_PDA_DISCRIMINATOR))
+            .ok_or_else(|| {
+                msg!("Extra account meta {} is not an external PDA", index);
+                ProgramError::InvalidArgument
+            })?;
+        let program_info = accounts
+            .get(usize::from(program_index))
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let account_info = accounts
+            .get(EXECUTE_BASE_ACCOUNTS + index)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if account_info.owner != program_info.key {
+            msg!(
+                "Account {} of extra account meta {} is owned by {}, not {}",
+                account_info.key,
+                index,
+                account_info.owner,
+                program_info.key
+            );
+            return Err(ExampleError::ExternalAccountOwnerMismatch.into());
+        }
+    }
+    Ok(())
+}
+
+/// Check the number of extra account metas of a validation account
+fn check_extra_account_metas_len(length: usize) -> ProgramResult {
+    if length > MAX_EXTRA_ACCOUNT_METAS {
+        msg!(
+            "{} extra account metas, at most {} allowed",
+            length,
+            MAX_EXTRA_ACCOUNT_METAS
+        );
+        return Err(ProgramError::Custom(
+            TrackingHookError::TooManyExtraAccountMetas as u32,
+        ));
+    }
+    Ok(())
+}
+
+/// Hard maximum size of a validation account, regardless of the number of
+/// extra account metas it holds: the most an account may grow by in a single
+/// instruction.
+pub const MAX_VALIDATION_ACCOUNT_SIZE: usize = MAX_PERMITTED_DATA_INCREASE;
+
+/// Check the size of a validation account before resizing it
+fn check_validation_account_size(account_size: usize) -> ProgramResult {
+    if account_size > MAX_VALIDATION_ACCOUNT_SIZE {
+        msg!(
+            "Validation account of {} bytes, at most {} allowed",
+            account_size,
+            MAX_VALIDATION_ACCOUNT_SIZE
+        );
+        return Err(ExampleError::ValidationAccountTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Check that no two extra account metas share the same address config, i.e.
+/// the same fixed address or the same seeds, regardless of their signer and
+/// writable flags
+fn check_duplicate_extra_account_metas(extra_account_metas: &[ExtraAccountMeta]) -> ProgramResult {
+    for (i, meta) in extra_account_metas.iter().enumerate() {
+        if extra_account_metas[..i].iter().any(|other| {
+            other.discriminator == meta.discriminator && other.address_config == meta.address_config
+        }) {
+            msg!("Extra account meta {} is a duplicate", i);
+            return Err(ExampleError::DuplicateExtraAccountMeta.into());
+        }
+    }
+    Ok(())
+}
+
+/// Processes a
+/// [`InitializeExtraAccountMetaList`](enum.TransferHookInstruction.html)
+/// instruction.
+pub fn process_initialize_extra_account_meta_list(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    extra_account_metas: &[ExtraAccountMeta],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    // check that the one mint we want to target is trying to create extra
+    // account metas
+    #[cfg(feature = "forbid-additional-mints")]
+    if *mint_info.key != crate::mint::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_mint_authority(mint_info, authority_info)?;
+    create_extra_account_meta_list(
+        program_id,
+        extra_account_metas_info,
+        mint_info,
+        extra_account_metas,
+    )
+}
+
+/// Allocate and write the validation account of the mint, which must hold
+/// enough lamports to be rent-exempt
+fn create_extra_account_meta_list(
+    program_id: &Pubkey,
+    extra_account_metas_info: &AccountInfo,
+    mint_info: &AccountInfo,
+    extra_account_metas: &[ExtraAccountMeta],
+) -> ProgramResult {
+    // Check validation account
+    let (expected_validation_address, bump_seed) =
+        get_extra_account_metas_address_and_bump_seed(mint_info.key, program_id);
+    if expected_validation_address != *extra_account_metas_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create the account
+    let bump_seed = [bump_seed];
+    let signer_seeds = collect_extra_account_metas_signer_seeds(mint_info.key, &bump_seed);
+    let length = extra_account_metas.len();
+    check_extra_account_metas_len(length)?;
+    check_duplicate_extra_account_metas(extra_account_metas)?;
+    let account_size = ExtraAccountMetaList::size_of(length)?;
+    invoke_signed(
+        &system_instruction::allocate(extra_account_metas_info.key, account_size as u64),
+        &[extra_account_metas_info.clone()],
+        &[&signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(extra_account_metas_info.key, program_id),
+        &[extra_account_metas_info.clone()],
+        &[&signer_seeds],
+    )?;
+
+    // Write the data
+    let mut data = extra_account_metas_info.try_borrow_mut_data()?;
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, extra_account_metas)?;
+
+    Ok(())
+}
+
+/// Process SetupMint instruction, creating the validation account and the
+/// mint config in a single instruction. The validation account is funded by
+/// the payer if needed, and the provided config is written as is.
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Validation account (writable, derived from the mint)
+/// 2. Mint config (writable, derived from the mint)
+/// 3. Mint
+/// 4. Mint authority (signer)
+/// 5. System program
+pub fn process_setup_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    extra_account_metas: &[ExtraAccountMeta],
+    config: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+
+    #[cfg(feature = "forbid-additional-mints")]
+    if *mint_info.key != crate::mint::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let config = MintConfig::unpack(config)?;
+    config.check()?;
+    check_config_decimals(mint_info, &config)?;
+    check_mint_authority(mint_info, authority_info)?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Fund the validation account up to rent exemption
+    check_extra_account_metas_len(extra_account_metas.len())?;
+    let required_lamports = Rent::get()?
+        .minimum_balance(ExtraAccountMetaList::size_of(extra_account_metas.len())?)
+        .saturating_sub(extra_account_metas_info.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(
+                payer_info.key,
+                extra_account_metas_info.key,
+                required_lamports,
+            ),
+            &[payer_info.clone(), extra_account_metas_info.clone()],
+        )?;
+    }
+    create_extra_account_meta_list(
+        program_id,
+        extra_account_metas_info,
+        mint_info,
+        extra_account_metas,
+    )?;
+
+    create_mint_config(program_id, payer_info, mint_config_info, mint_info, &config)
+}
+
+/// Processes a
+/// [`UpdateExtraAccountMetaList`](enum.TransferHookInstruction.html)
+/// instruction.
+pub fn process_update_extra_account_meta_list(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    extra_account_metas: &[ExtraAccountMeta],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    check_mint_authority(mint_info, authority_info)?;
+
+    // Check validation account
+    let expected_validation_address = get_extra_account_metas_address(mint_info.key, program_id);
+    if expected_validation_address != *extra_account_metas_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if the extra metas have been initialized
     let min_account_size = ExtraAccountMetaList::size_of(0)?;
     let original_account_size = extra_account_metas_info.data_len();
-    if program_id != extra_account_metas_info.owner || original_account_size < min_account_size {
-        return Err(ProgramError::UninitializedAccount);
+    if original_account_size < min_account_size {
+        msg!(
+            "Validation account of {} bytes, at least {} expected",
+            original_account_size,
+            min_account_size
+        );
+        return Err(ExampleError::MetaListNotInitialized.into());
+    }
+    if program_id != extra_account_metas_info.owner {
+        msg!(
+            "Validation account owned by {}",
+            extra_account_metas_info.owner
+        );
+        return Err(ExampleError::MetaListWrongOwner.into());
     }
 
     // If the new extra_account_metas length is different, resize the account and
     // update
     let length = extra_account_metas.len();
     let account_size = ExtraAccountMetaList::size_of(length)?;
+    check_validation_account_size(account_size)?;
+    check_extra_account_metas_len(length)?;
+    check_duplicate_extra_account_metas(extra_account_metas)?;
     if account_size >= original_account_size {
         extra_account_metas_info.resize(account_size)?;
         let mut data = extra_account_metas_info.try_borrow_mut_data()?;
@@ -353,12 +4776,410 @@ pub fn process_update_extra_account_meta_list(
     Ok(())
 }
 
+/// Process ReplaceExtraAccountMetaList instruction, re-initializing the
+/// validation account with the new metas, whatever its previous layout. The
+/// account keeps its address and is brought back to rent exemption for its
+/// new size: the payer funds a larger account and is refunded by a smaller
+/// one.
+/// Accounts:
+/// 0. Payer (signer, writable)
+/// 1. Validation account (writable, derived from the mint)
+/// 2. Mint
+/// 3. Mint authority (signer)
+/// 4. System program
+pub fn process_replace_extra_account_meta_list(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    extra_account_metas: &[ExtraAccountMeta],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    check_mint_authority(mint_info, authority_info)?;
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check validation account
+    if get_extra_account_metas_address(mint_info.key, program_id) != *extra_account_metas_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if extra_account_metas_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let length = extra_account_metas.len();
+    let account_size = ExtraAccountMetaList::size_of(length)?;
+    check_validation_account_size(account_size)?;
+    check_extra_account_metas_len(length)?;
+    check_duplicate_extra_account_metas(extra_account_metas)?;
+
+    // Wipe the previous layout and write the new one from scratch
+    extra_account_metas_info.resize(account_size)?;
+    {
+        let mut data = extra_account_metas_info.try_borrow_mut_data()?;
+        data.fill(0);
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, extra_account_metas)?;
+    }
+
+    settle_rent(extra_account_metas_info, payer_info, system_program_info)
+}
+
+/// The extra account meta resolving the transfer account of the source owner,
+/// which `Execute` expects first in the list of every mint
+pub fn transfer_account_extra_meta() -> Result<ExtraAccountMeta, ProgramError> {
+    let metas = transfer_tracking_extra_account_metas(cfg!(feature = "per-mint-tracking"))
+        .map_err(|error| ProgramError::from(u64::from(error)))?;
+    Ok(bytemuck::cast(metas[0]))
+}
+
+/// Process VerifyExtraAccountMetaList instruction, comparing the extra account
+/// metas of the mint against the template the program expects, and setting a
+/// bitmask of [`meta_list_status`](crate::instruction::meta_list_status)
+/// flags as return data. In repair mode, the list is rewritten with the
+/// transfer account meta first, the other metas keeping their order.
+/// Accounts:
+/// 0. Mint
+/// 1. Validation account (writable when repairing)
+/// 2. Mint authority (signer), when repairing
+/// 3. Payer (signer, writable), when repairing
+/// 4. System program, when repairing
+pub fn process_verify_extra_account_meta_list(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    repair: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+    let extra_account_metas_info = next_account_info(account_info_iter)?;
+
+    if get_extra_account_metas_address(mint_info.key, program_id) != *extra_account_metas_info.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if extra_account_metas_info.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let expected_meta = transfer_account_extra_meta()?;
+    let mut extra_account_metas = {
+        let data = extra_account_metas_info.try_borrow_data()?;
+        let state = TlvStateBorrowed::unpack(&data)?;
+        ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&state)?
+            .data()
+            .to_vec()
+    };
+    let position = extra_account_metas.iter().position(|meta| {
+        meta.discriminator == expected_meta.discriminator
+            && meta.address_config == expected_meta.address_config
+    });
+    let mut status = 0;
+    if let Some(index) = position {
+        status |= meta_list_status::TRANSFER_ACCOUNT_META_PRESENT;
+        if index == 0 {
+            status |= meta_list_status::TRANSFER_ACCOUNT_META_FIRST;
+        }
+        if extra_account_metas[index] == expected_meta {
+            status |= meta_list_status::TRANSFER_ACCOUNT_META_FLAGS;
+        }
+    }
+
+    if repair {
+        let authority_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        check_mint_authority(mint_info, authority_info)?;
+        if !payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        if status != meta_list_status::MATCHING {
+            if let Some(index) = position {
+                extra_account_metas.remove(index);
+            }
+            extra_account_metas.insert(0, expected_meta);
+
+            let length = extra_account_metas.len();
+            let account_size = ExtraAccountMetaList::size_of(length)?;
+            check_validation_account_size(account_size)?;
+            check_extra_account_metas_len(length)?;
+            check_duplicate_extra_account_metas(&extra_account_metas)?;
+
+            extra_account_metas_info.resize(account_size)?;
+            {
+                let mut data = extra_account_metas_info.try_borrow_mut_data()?;
+                data.fill(0);
+                ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas)?;
+            }
+            settle_rent(extra_account_metas_info, payer_info, system_program_info)?;
+            status |= meta_list_status::REPAIRED;
+        }
+    }
+
+    msg!("Extra account meta list status: {:#06b}", status);
+    set_return_data(&[status]);
+    Ok(())
+}
+
+/// Bring the balance of a resized program account to the rent exempt minimum
+/// for its size, funded by the payer, or refunding the payer the lamports
+/// above it
+fn settle_rent<'a>(
+    account_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let required_lamports = Rent::get()?.minimum_balance(account_info.data_len());
+    let lamports = account_info.lamports();
+    if lamports < required_lamports {
+        invoke(
+            &system_instruction::transfer(
+                payer_info.key,
+                account_info.key,
+                required_lamports - lamports,
+            ),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    } else {
+        let refund = lamports - required_lamports;
+        **account_info.try_borrow_mut_lamports()? = required_lamports;
+        let payer_lamports = payer_info.lamports();
+        **payer_info.try_borrow_mut_lamports()? = payer_lamports
+            .checked_add(refund)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    Ok(())
+}
+
+/// Processes a [`CustomInstruction`].
+fn process_custom(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction: CustomInstruction,
+) -> ProgramResult {
+    match instruction {
+        CustomInstruction::InitializeTransferAccount => {
+            msg!("Instruction: InitializeTransferAccount");
+            process_initialize_transfer_account(program_id, accounts)
+        }
+        CustomInstruction::InitializeDestinationAccount => {
+            msg!("Instruction: InitializeDestinationAccount");
+            process_initialize_destination_account(program_id, accounts)
+        }
+        CustomInstruction::SetVestingSchedule {
+            total,
+            start_timestamp,
+            cliff_timestamp,
+            duration_seconds,
+        } => {
+            msg!("Instruction: SetVestingSchedule");
+            process_set_vesting_schedule(
+                program_id,
+                accounts,
+                VestingSchedule {
+                    total,
+                    start_timestamp,
+                    cliff_timestamp,
+                    duration_seconds,
+                },
+            )
+        }
+        CustomInstruction::TeardownMint {
+            allowlist_pages,
+            fee_categories,
+        } => {
+            msg!("Instruction: TeardownMint");
+            process_teardown_mint(program_id, accounts, &allowlist_pages, &fee_categories)
+        }
+        CustomInstruction::WithdrawTreasury { amount } => {
+            msg!("Instruction: WithdrawTreasury");
+            process_withdraw_treasury(program_id, accounts, amount)
+        }
+        CustomInstruction::VerifyExtraAccountMetaList { repair } => {
+            msg!("Instruction: VerifyExtraAccountMetaList");
+            process_verify_extra_account_meta_list(program_id, accounts, repair)
+        }
+        CustomInstruction::SetReceiveOnly { receive_only } => {
+            msg!("Instruction: SetReceiveOnly");
+            process_set_receive_only(program_id, accounts, receive_only)
+        }
+        #[cfg(feature = "recent-log")]
+        CustomInstruction::InitializeRecentTransferLog => {
+            msg!("Instruction: InitializeRecentTransferLog");
+            process_initialize_recent_transfer_log(program_id, accounts)
+        }
+        #[cfg(not(feature = "recent-log"))]
+        CustomInstruction::InitializeRecentTransferLog => Err(ProgramError::InvalidInstructionData),
+        CustomInstruction::InitializeMintConfig => {
+            msg!("Instruction: InitializeMintConfig");
+            process_initialize_mint_config(program_id, accounts)
+        }
+        CustomInstruction::UpdateMintConfig { config } => {
+            msg!("Instruction: UpdateMintConfig");
+            process_update_mint_config(program_id, accounts, &config)
+        }
+        CustomInstruction::MigrateMintConfig => {
+            msg!("Instruction: MigrateMintConfig");
+            process_migrate_mint_config(program_id, accounts)
+        }
+        CustomInstruction::SetAllowance { amount, top_up } => {
+            msg!("Instruction: SetAllowance");
+            process_set_allowance(program_id, accounts, amount, top_up)
+        }
+        CustomInstruction::SnapshotStats => {
+            msg!("Instruction: SnapshotStats");
+            process_snapshot_stats(program_id, accounts)
+        }
+        CustomInstruction::InitializeBurnLedger => {
+            msg!("Instruction: InitializeBurnLedger");
+            process_initialize_burn_ledger(program_id, accounts)
+        }
+        CustomInstruction::SettleBurn => {
+            msg!("Instruction: SettleBurn");
+            process_settle_burn(program_id, accounts)
+        }
+        CustomInstruction::AddAllowlistedOwner { owner, expires_at } => {
+            msg!("Instruction: AddAllowlistedOwner");
+            process_add_allowlisted_owner(
+                program_id,
+                accounts,
+                Pubkey::new_from_array(owner.to_bytes()),
+                expires_at,
+            )
+        }
+        CustomInstruction::RemoveAllowlistedOwner { owner } => {
+            msg!("Instruction: RemoveAllowlistedOwner");
+            process_remove_allowlisted_owner(
+                program_id,
+                accounts,
+                Pubkey::new_from_array(owner.to_bytes()),
+            )
+        }
+        CustomInstruction::FreezeOwner => {
+            msg!("Instruction: FreezeOwner");
+            process_freeze_owner(program_id, accounts)
+        }
+        CustomInstruction::ReinitializeTransferAccount => {
+            msg!("Instruction: ReinitializeTransferAccount");
+            process_reinitialize_transfer_account(program_id, accounts)
+        }
+        CustomInstruction::ResetAccountsBatch { count } => {
+            msg!("Instruction: ResetAccountsBatch");
+            process_reset_accounts_batch(program_id, accounts, count)
+        }
+        CustomInstruction::GetMintConfig => {
+            msg!("Instruction: GetMintConfig");
+            process_get_mint_config(program_id, accounts)
+        }
+        CustomInstruction::EnableTrading => {
+            msg!("Instruction: EnableTrading");
+            process_enable_trading(program_id, accounts)
+        }
+        CustomInstruction::FinalizeSetup => {
+            msg!("Instruction: FinalizeSetup");
+            process_finalize_setup(program_id, accounts)
+        }
+        CustomInstruction::MigrateTransferAccount => {
+            msg!("Instruction: MigrateTransferAccount");
+            process_migrate_transfer_account(program_id, accounts)
+        }
+        CustomInstruction::InitializeTransferRoot => {
+            msg!("Instruction: InitializeTransferRoot");
+            process_initialize_transfer_root(program_id, accounts)
+        }
+        CustomInstruction::SchedulePause { slot, reason } => {
+            msg!("Instruction: SchedulePause");
+            process_schedule_pause(program_id, accounts, slot, reason)
+        }
+        CustomInstruction::CancelPause => {
+            msg!("Instruction: CancelPause");
+            process_cancel_pause(program_id, accounts)
+        }
+        CustomInstruction::SetLabel { label } => {
+            msg!("Instruction: SetLabel");
+            process_set_label(program_id, accounts, label)
+        }
+        CustomInstruction::ReassignTransferAccount => {
+            msg!("Instruction: ReassignTransferAccount");
+            process_reassign_transfer_account(program_id, accounts)
+        }
+        CustomInstruction::HealthCheck => {
+            msg!("Instruction: HealthCheck");
+            process_health_check(program_id, accounts)
+        }
+        CustomInstruction::AddExemptOwner { owner } => {
+            msg!("Instruction: AddExemptOwner");
+            process_add_exempt_owner(
+                program_id,
+                accounts,
+                Pubkey::new_from_array(owner.to_bytes()),
+            )
+        }
+        CustomInstruction::SetupMint {
+            extra_account_metas,
+            config,
+        } => {
+            msg!("Instruction: SetupMint");
+            let extra_account_metas = extra_account_metas
+                .into_iter()
+                .map(bytemuck::cast)
+                .collect::<Vec<ExtraAccountMeta>>();
+            process_setup_mint(program_id, accounts, &extra_account_metas, &config)
+        }
+        CustomInstruction::SetIncomingPolicy { reject_incoming } => {
+            msg!("Instruction: SetIncomingPolicy");
+            process_set_incoming_policy(program_id, accounts, reject_incoming)
+        }
+        CustomInstruction::InitializeFeeLedger { category } => {
+            msg!("Instruction: InitializeFeeLedger");
+            process_initialize_fee_ledger(program_id, accounts, category)
+        }
+        CustomInstruction::ReplaceExtraAccountMetaList {
+            extra_account_metas,
+        } => {
+            msg!("Instruction: ReplaceExtraAccountMetaList");
+            let extra_account_metas = extra_account_metas
+                .into_iter()
+                .map(bytemuck::cast)
+                .collect::<Vec<ExtraAccountMeta>>();
+            process_replace_extra_account_meta_list(program_id, accounts, &extra_account_metas)
+        }
+        CustomInstruction::ReadTotals => {
+            msg!("Instruction: ReadTotals");
+            process_read_totals(program_id, accounts)
+        }
+        CustomInstruction::InitializeMintStats => {
+            msg!("Instruction: InitializeMintStats");
+            process_initialize_mint_stats(program_id, accounts)
+        }
+        CustomInstruction::RemoveExemptOwner { owner } => {
+            msg!("Instruction: RemoveExemptOwner");
+            process_remove_exempt_owner(
+                program_id,
+                accounts,
+                Pubkey::new_from_array(owner.to_bytes()),
+            )
+        }
+    }
+}
+
 /// Processes an [Instruction](enum.Instruction.html).
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-    // Check if this is a custom instruction (discriminator 255)
-    if !input.is_empty() && input[0] == instruction_discriminator::INITIALIZE_TRANSFER_ACCOUNT {
-        msg!("Instruction: InitializeTransferAccount");
-        return process_initialize_transfer_account(program_id, accounts);
+    // Check if this is a custom instruction
+    if let Some(instruction) =
+        CustomInstruction::unpack(input).map_err(|_| ProgramError::InvalidInstructionData)?
+    {
+        return process_custom(program_id, accounts, instruction);
     }
 
     // Otherwise, parse as standard TransferHookInstruction
@@ -367,7 +5188,7 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> P
     match instruction {
         TransferHookInstruction::Execute { amount } => {
             msg!("Instruction: Execute");
-            process_execute(program_id, accounts, amount)
+            process_execute(program_id, accounts, amount).inspect_err(report_failed_check)
         }
         TransferHookInstruction::InitializeExtraAccountMetaList {
             extra_account_metas,
@@ -376,11 +5197,502 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> P
             process_initialize_extra_account_meta_list(program_id, accounts, &extra_account_metas)
         }
         TransferHookInstruction::UpdateExtraAccountMetaList {
-            extra_account_metas: _,
+            extra_account_metas,
         } => {
             msg!("Instruction: UpdateExtraAccountMetaList");
-            return Ok(());
-            // process_update_extra_account_meta_list(program_id, accounts, &extra_account_metas)
+            process_update_extra_account_meta_list(program_id, accounts, &extra_account_metas)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_program::program_pack::Pack};
+
+    #[test]
+    fn duplicate_extra_account_metas() {
+        let address = Pubkey::new_unique();
+        let pda = ExtraAccountMeta::new_with_seeds(
+            &[spl_tlv_account_resolution::seeds::Seed::AccountKey { index: 0 }],
+            false,
+            true,
+        )
+        .unwrap();
+        let unique = [
+            ExtraAccountMeta::new_with_pubkey(&address, false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap(),
+            pda,
+        ];
+        assert_eq!(check_duplicate_extra_account_metas(&unique), Ok(()));
+        assert_eq!(check_duplicate_extra_account_metas(&[]), Ok(()));
+
+        for duplicate in [
+            ExtraAccountMeta::new_with_pubkey(&address, false, false).unwrap(),
+            // the flags don't matter
+            ExtraAccountMeta::new_with_pubkey(&address, true, true).unwrap(),
+            pda,
+        ] {
+            let metas = [&unique[..], &[duplicate]].concat();
+            assert_eq!(
+                check_duplicate_extra_account_metas(&metas),
+                Err(ExampleError::DuplicateExtraAccountMeta.into())
+            );
+        }
+    }
+
+    /// Accounts of an `UpdateExtraAccountMetaList` instruction, for a mint
+    /// whose authority signs and a validation account of the given size and
+    /// owner
+    struct UpdateAccounts {
+        program_id: Pubkey,
+        token_program_id: Pubkey,
+        validation_owner: Pubkey,
+        validation_address: Pubkey,
+        mint_address: Pubkey,
+        authority: Pubkey,
+        validation_data: Vec<u8>,
+        mint_data: Vec<u8>,
+        lamports: [u64; 3],
+    }
+
+    impl UpdateAccounts {
+        fn new(program_id: Pubkey, validation_len: usize, validation_owner: Pubkey) -> Self {
+            let mint_address = Pubkey::new_unique();
+            let authority = Pubkey::new_unique();
+            let mut mint_data = vec![0; Mint::LEN];
+            Mint {
+                mint_authority: COption::Some(authority),
+                is_initialized: true,
+                ..Mint::default()
+            }
+            .pack_into_slice(&mut mint_data);
+            Self {
+                program_id,
+                token_program_id: spl_token_2022::id(),
+                validation_owner,
+                validation_address: get_extra_account_metas_address(&mint_address, &program_id),
+                mint_address,
+                authority,
+                validation_data: vec![0; validation_len],
+                mint_data,
+                lamports: [0; 3],
+            }
+        }
+
+        /// Process the instruction updating the list to the metas, through
+        /// the program entrypoint
+        fn update(&mut self, extra_account_metas: &[ExtraAccountMeta]) -> ProgramResult {
+            let [validation_lamports, mint_lamports, authority_lamports] = &mut self.lamports;
+            let accounts = [
+                AccountInfo::new(
+                    &self.validation_address,
+                    false,
+                    true,
+                    validation_lamports,
+                    &mut self.validation_data,
+                    &self.validation_owner,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &self.mint_address,
+                    false,
+                    false,
+                    mint_lamports,
+                    &mut self.mint_data,
+                    &self.token_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &self.authority,
+                    true,
+                    false,
+                    authority_lamports,
+                    &mut [],
+                    &self.program_id,
+                    false,
+                    0,
+                ),
+            ];
+            let input = TransferHookInstruction::UpdateExtraAccountMetaList {
+                extra_account_metas: extra_account_metas.to_vec(),
+            }
+            .pack();
+            process(&self.program_id, &accounts, &input)
+        }
+    }
+
+    #[test]
+    fn fail_update_oversized_validation_account() {
+        let program_id = Pubkey::new_unique();
+        let mut update_accounts = UpdateAccounts::new(
+            program_id,
+            ExtraAccountMetaList::size_of(1).unwrap(),
+            program_id,
+        );
+
+        // more metas than fit in the largest allowed validation account
+        let metas = (0..=MAX_VALIDATION_ACCOUNT_SIZE / std::mem::size_of::<ExtraAccountMeta>())
+            .map(|_| {
+                ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(ExtraAccountMetaList::size_of(metas.len()).unwrap() > MAX_VALIDATION_ACCOUNT_SIZE);
+        assert_eq!(
+            update_accounts.update(&metas),
+            Err(ExampleError::ValidationAccountTooLarge.into())
+        );
+        assert_eq!(
+            update_accounts.validation_data.len(),
+            ExtraAccountMetaList::size_of(1).unwrap()
+        );
+
+        // the cap on the number of metas stays well within the size bound
+        assert_eq!(
+            check_validation_account_size(
+                ExtraAccountMetaList::size_of(MAX_EXTRA_ACCOUNT_METAS).unwrap()
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn fail_update_uninitialized_validation_account() {
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+
+        for (validation_len, validation_owner, expected_error) in [
+            (0, program_id, ExampleError::MetaListNotInitialized),
+            (
+                ExtraAccountMetaList::size_of(0).unwrap() - 1,
+                program_id,
+                ExampleError::MetaListNotInitialized,
+            ),
+            (
+                ExtraAccountMetaList::size_of(1).unwrap(),
+                other_program_id,
+                ExampleError::MetaListWrongOwner,
+            ),
+        ] {
+            let mut update_accounts =
+                UpdateAccounts::new(program_id, validation_len, validation_owner);
+            let metas =
+                [ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap()];
+            assert_eq!(update_accounts.update(&metas), Err(expected_error.into()));
+        }
+    }
+
+    #[test]
+    fn created_account_invariant() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let required_lamports = Rent::default().minimum_balance(TransferAccount::LEN);
+        for (len, lamports, result) in [
+            (TransferAccount::LEN, required_lamports, Ok(())),
+            (TransferAccount::LEN, required_lamports + 1, Ok(())),
+            (
+                TransferAccount::LEN - 1,
+                required_lamports,
+                Err(ExampleError::InvalidCreatedAccount.into()),
+            ),
+            (
+                TransferAccount::LEN,
+                required_lamports - 1,
+                Err(ExampleError::InvalidCreatedAccount.into()),
+            ),
+        ] {
+            let mut lamports = lamports;
+            let mut data = vec![0; len];
+            let account_info = AccountInfo::new(
+                &key,
+                false,
+                true,
+                &mut lamports,
+                &mut data,
+                &owner,
+                false,
+                0,
+            );
+            assert_eq!(
+                check_created_account(&account_info, TransferAccount::LEN, required_lamports),
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn extra_account_metas_len() {
+        assert_eq!(check_extra_account_metas_len(0), Ok(()));
+        assert_eq!(check_extra_account_metas_len(5), Ok(()));
+        assert_eq!(
+            check_extra_account_metas_len(MAX_EXTRA_ACCOUNT_METAS),
+            Ok(())
+        );
+        assert_eq!(
+            check_extra_account_metas_len(MAX_EXTRA_ACCOUNT_METAS + 1),
+            Err(ProgramError::Custom(
+                TrackingHookError::TooManyExtraAccountMetas as u32
+            ))
+        );
+        assert_eq!(
+            check_extra_account_metas_len(usize::MAX),
+            Err(ProgramError::Custom(
+                TrackingHookError::TooManyExtraAccountMetas as u32
+            ))
+        );
+    }
+
+    #[test]
+    fn transfer_account_round_trip() {
+        let transfer_account = TransferAccount {
+            owner: Pubkey::new_unique(),
+            transfered: 42,
+            last_transfer_timestamp: 1_700_000_000,
+            label: *b"savings\0\0\0\0\0\0\0\0\0",
+            window_start_slot: 7,
+            window_amount: 8,
+            first_transfer_timestamp: 1_600_000_000,
+            first_received_timestamp: 1_500_000_000,
+            allowance_remaining: 9,
+            last_ticket_nonce: 10,
+            day_start_ts: 11,
+            day_count: 12,
+            received: 13,
+            max_single_transfer: 14,
+            first_received_slot: 15,
+            fill_transaction: [16; 32],
+            fill_amount: 17,
+            fill_instruction_index: 18,
+        };
+        let mut data = vec![0; TransferAccount::LEN];
+        transfer_account.pack(&mut data);
+        assert_eq!(TransferAccount::unpack(&data).unwrap(), transfer_account);
+    }
+
+    #[test]
+    fn transfer_account_unpack_outdated() {
+        let transfer_account = TransferAccount {
+            owner: Pubkey::new_unique(),
+            transfered: 42,
+            label: [1; TransferAccount::LABEL_LEN],
+            window_amount: 8,
+            received: 13,
+            fill_amount: 17,
+            ..TransferAccount::default()
+        };
+        let mut data = vec![0; TransferAccount::LEN];
+        transfer_account.pack(&mut data);
+
+        assert!(TransferAccount::LAYOUT_LENS
+            .windows(2)
+            .all(|lens| lens[0] < lens[1]));
+        for len in TransferAccount::LAYOUT_LENS {
+            assert_eq!(
+                TransferAccount::unpack(&data[..len]),
+                Err(ExampleError::OutdatedTransferAccount.into())
+            );
+            // the fields of the layout are preserved, the appended ones left
+            // to defaults
+            let mut expected = data.clone();
+            expected[len..].fill(0);
+            assert_eq!(
+                TransferAccount::unpack_outdated(&data[..len]),
+                TransferAccount::unpack(&expected)
+            );
+        }
+        assert_eq!(
+            TransferAccount::unpack_outdated(&data[..40]).unwrap(),
+            TransferAccount {
+                owner: transfer_account.owner,
+                transfered: 42,
+                ..TransferAccount::default()
+            }
+        );
+
+        for len in [0, 41, TransferAccount::LEN - 1, TransferAccount::LEN] {
+            assert_eq!(
+                TransferAccount::unpack_outdated(&data[..len]),
+                Err(ProgramError::InvalidAccountData)
+            );
+        }
+        assert_eq!(
+            TransferAccount::unpack(&data[..TransferAccount::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn transfer_account_matches_borsh_layout() {
+        use {
+            borsh::BorshDeserialize,
+            spl_transfer_tracking_interface::state::TransferAccount as BorshTransferAccount,
+        };
+
+        let transfer_account = TransferAccount {
+            owner: Pubkey::new_unique(),
+            transfered: u64::MAX,
+            last_transfer_timestamp: -1,
+            label: *b"payroll 2026\0\0\0\0",
+            window_start_slot: 7,
+            window_amount: 8,
+            first_transfer_timestamp: 1_600_000_000,
+            first_received_timestamp: 1_500_000_000,
+            allowance_remaining: 9,
+            last_ticket_nonce: 10,
+            day_start_ts: 11,
+            day_count: 12,
+            received: 13,
+            max_single_transfer: 14,
+            first_received_slot: 15,
+            fill_transaction: [16; 32],
+            fill_amount: 17,
+            fill_instruction_index: 18,
+        };
+        let mut data = vec![0; TransferAccount::LEN];
+        transfer_account.pack(&mut data);
+
+        let decoded = BorshTransferAccount::try_from_slice(&data).unwrap();
+        assert_eq!(
+            decoded,
+            BorshTransferAccount {
+                owner: InterfacePubkey::new_from_array(transfer_account.owner.to_bytes()),
+                transfered: transfer_account.transfered,
+                last_transfer_timestamp: transfer_account.last_transfer_timestamp,
+                label: transfer_account.label,
+                window_start_slot: transfer_account.window_start_slot,
+                window_amount: transfer_account.window_amount,
+                first_transfer_timestamp: transfer_account.first_transfer_timestamp,
+                first_received_timestamp: transfer_account.first_received_timestamp,
+                allowance_remaining: transfer_account.allowance_remaining,
+                last_ticket_nonce: transfer_account.last_ticket_nonce,
+                day_start_ts: transfer_account.day_start_ts,
+                day_count: transfer_account.day_count,
+                received: transfer_account.received,
+                max_single_transfer: transfer_account.max_single_transfer,
+                first_received_slot: transfer_account.first_received_slot,
+                fill_transaction: transfer_account.fill_transaction,
+                fill_amount: transfer_account.fill_amount,
+                fill_instruction_index: transfer_account.fill_instruction_index,
+            }
+        );
+        assert_eq!(borsh::to_vec(&decoded).unwrap(), data);
+    }
+
+    #[test]
+    fn decrement_transfered() {
+        let mut data = vec![0; TransferAccount::LEN];
+        TransferAccount {
+            transfered: 100,
+            ..TransferAccount::default()
         }
+        .pack(&mut data);
+
+        TransferAccount::decrement_transfered(&mut data, 40).unwrap();
+        assert_eq!(TransferAccount::unpack(&data).unwrap().transfered, 60);
+
+        assert_eq!(
+            TransferAccount::decrement_transfered(&mut data, 61),
+            Err(ExampleError::TrackingUnderflow.into())
+        );
+        assert_eq!(TransferAccount::unpack(&data).unwrap().transfered, 60);
+    }
+
+    #[test]
+    fn slot_window() {
+        let mut tracked = TransferAccount::default();
+        // disabled
+        assert_eq!(check_slot_window(0, 0, &mut tracked, 1, 100), Ok(()));
+        assert_eq!(tracked.window_amount, 0);
+
+        // window of 10 slots starting at slot 100, capped at 50
+        assert_eq!(check_slot_window(10, 50, &mut tracked, 100, 30), Ok(()));
+        assert_eq!(check_slot_window(10, 50, &mut tracked, 109, 20), Ok(()));
+        assert_eq!(
+            check_slot_window(10, 50, &mut tracked, 109, 1),
+            Err(ExampleError::WindowLimitExceeded.into())
+        );
+        assert_eq!(
+            (tracked.window_start_slot, tracked.window_amount),
+            (100, 50)
+        );
+
+        // a new window starts
+        assert_eq!(check_slot_window(10, 50, &mut tracked, 110, 50), Ok(()));
+        assert_eq!(
+            (tracked.window_start_slot, tracked.window_amount),
+            (110, 50)
+        );
+        assert_eq!(
+            check_slot_window(10, 50, &mut tracked, 110, u64::MAX),
+            Err(ExampleError::WindowLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn daily_transfers() {
+        let mut tracked = TransferAccount::default();
+        // disabled
+        assert_eq!(check_daily_transfers(0, &mut tracked, 1), Ok(()));
+        assert_eq!(tracked.day_count, 0);
+
+        // two transfers per day, from the day starting at 172_800
+        let day = 2 * SECONDS_PER_DAY;
+        assert_eq!(check_daily_transfers(2, &mut tracked, day + 5), Ok(()));
+        assert_eq!(check_daily_transfers(2, &mut tracked, day + 60), Ok(()));
+        assert_eq!(
+            check_daily_transfers(2, &mut tracked, day + SECONDS_PER_DAY - 1),
+            Err(ExampleError::DailyTransferLimitExceeded.into())
+        );
+        assert_eq!((tracked.day_start_ts, tracked.day_count), (day, 2));
+
+        // a new day starts
+        let next_day = day + SECONDS_PER_DAY;
+        assert_eq!(check_daily_transfers(2, &mut tracked, next_day), Ok(()));
+        assert_eq!((tracked.day_start_ts, tracked.day_count), (next_day, 1));
+    }
+
+    #[test]
+    fn max_balance_percentage() {
+        // disabled
+        assert_eq!(check_max_balance_percentage(0, 0, u64::MAX), Ok(()));
+        // 10% of 1_000 holdings
+        assert_eq!(check_max_balance_percentage(1_000, 900, 100), Ok(()));
+        assert_eq!(
+            check_max_balance_percentage(1_000, 900, 101),
+            Err(ExampleError::TransferExceedsBalancePercentage.into())
+        );
+        // no overflow in the multiply with huge balances
+        assert_eq!(
+            check_max_balance_percentage(10_000, u64::MAX, u64::MAX),
+            Ok(())
+        );
+        assert_eq!(
+            check_max_balance_percentage(9_999, 0, u64::MAX),
+            Err(ExampleError::TransferExceedsBalancePercentage.into())
+        );
+    }
+
+    #[test]
+    fn max_balance_percentage_amount_is_allowed() {
+        assert_eq!(max_balance_percentage_amount(1_000, 1_000, 200), 120);
+        assert_eq!(check_max_balance_percentage(1_000, 1_080, 120), Ok(()));
+        // rounded down, so the allowed maximum always passes
+        assert_eq!(max_balance_percentage_amount(3_333, 0, 10), 3);
+        // no overflow in the multiply with huge balances
+        assert_eq!(max_balance_percentage_amount(10_000, u64::MAX, 0), u64::MAX);
+    }
+
+    #[test]
+    fn cooldown() {
+        // disabled, or no previous transfer
+        assert_eq!(check_cooldown(0, 100, 100), Ok(()));
+        assert_eq!(check_cooldown(60, 0, 100), Ok(()));
+
+        assert_eq!(
+            check_cooldown(60, 100, 159),
+            Err(ExampleError::TransferCooldownActive.into())
+        );
+        assert_eq!(check_cooldown(60, 100, 160), Ok(()));
     }
 }
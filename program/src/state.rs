@@ -1,9 +1,12 @@
 //! State helpers for working with the example program
 
 use {
-    solana_program::program_error::ProgramError,
+    crate::error::ExampleError,
+    solana_program::{msg, program_error::ProgramError, pubkey::Pubkey},
     spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList},
+    spl_token_2022::extension::{AccountType, ExtensionType},
     spl_transfer_hook_interface::instruction::ExecuteInstruction,
+    spl_transfer_tracking_interface::merkle::{hash_nodes, TRANSFER_ROOT_DEPTH},
 };
 
 /// Generate example data to be used directly in an account for testing
@@ -13,3 +16,2408 @@ pub fn example_data(account_metas: &[ExtraAccountMeta]) -> Result<Vec<u8>, Progr
     ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, account_metas)?;
     Ok(data)
 }
+
+/// Ring buffer of the most recent transfers of a mint, stored in the
+/// recent-log PDA.
+///
+/// Layout: total number of appended entries (u64), followed by `CAPACITY`
+/// entries of `(owner, amount, slot)`. Once full, the oldest entry is
+/// overwritten.
+pub struct RecentTransferLog;
+
+impl RecentTransferLog {
+    /// Number of entries kept in the log
+    pub const CAPACITY: usize = 16;
+    /// Size of a single entry: Pubkey (32) + u64 (8) + u64 (8)
+    pub const ENTRY_LEN: usize = 32 + 8 + 8;
+    /// Size of the recent transfer log account data
+    pub const LEN: usize = 8 + Self::CAPACITY * Self::ENTRY_LEN;
+
+    // Offsets
+    const TOTAL_OFFSET: usize = 0;
+    const ENTRIES_OFFSET: usize = 8;
+
+    fn total(data: &[u8]) -> Result<u64, ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(u64::from_le_bytes(
+            data[Self::TOTAL_OFFSET..Self::TOTAL_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ))
+    }
+
+    fn entry_offset(position: u64) -> usize {
+        Self::ENTRIES_OFFSET + (position % Self::CAPACITY as u64) as usize * Self::ENTRY_LEN
+    }
+
+    /// Append an entry, overwriting the oldest one once the log is full
+    pub fn append(
+        data: &mut [u8],
+        owner: &Pubkey,
+        amount: u64,
+        slot: u64,
+    ) -> Result<(), ProgramError> {
+        let total = Self::total(data)?;
+        let offset = Self::entry_offset(total);
+        data[offset..offset + 32].copy_from_slice(owner.as_ref());
+        data[offset + 32..offset + 40].copy_from_slice(&amount.to_le_bytes());
+        data[offset + 40..offset + 48].copy_from_slice(&slot.to_le_bytes());
+        data[Self::TOTAL_OFFSET..Self::TOTAL_OFFSET + 8]
+            .copy_from_slice(&total.saturating_add(1).to_le_bytes());
+        Ok(())
+    }
+
+    /// Unpack the stored `(owner, amount, slot)` entries, oldest first
+    pub fn unpack(data: &[u8]) -> Result<Vec<(Pubkey, u64, u64)>, ProgramError> {
+        let total = Self::total(data)?;
+        let len = total.min(Self::CAPACITY as u64);
+        (total - len..total)
+            .map(|position| {
+                let offset = Self::entry_offset(position);
+                let owner = Pubkey::try_from(&data[offset..offset + 32])
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+                let amount = u64::from_le_bytes(
+                    data[offset + 32..offset + 40]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                );
+                let slot = u64::from_le_bytes(
+                    data[offset + 40..offset + 48]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                );
+                Ok((owner, amount, slot))
+            })
+            .collect()
+    }
+}
+
+/// Running Merkle root of the transfers of a mint, stored in the transfer
+/// root PDA, for light clients to prove a transfer was accepted.
+///
+/// Layout: number of transfers (u64) and root of the tree, followed by its
+/// frontier, the root of the last complete subtree of each height, from which
+/// each new leaf updates the root without the earlier leaves.
+pub struct TransferRoot;
+
+impl TransferRoot {
+    /// Depth of the tree, which holds up to `2^DEPTH - 1` transfers
+    pub const DEPTH: usize = TRANSFER_ROOT_DEPTH;
+    /// Size of the transfer root account data
+    pub const LEN: usize = Self::FRONTIER_OFFSET + Self::DEPTH * 32;
+
+    // Offsets
+    const COUNT_OFFSET: usize = 0;
+    const ROOT_OFFSET: usize = 8;
+    const FRONTIER_OFFSET: usize = 40;
+
+    /// Unpack the number of transfers and the root
+    pub fn unpack(data: &[u8]) -> Result<(u64, [u8; 32]), ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let count = u64::from_le_bytes(
+            data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let root = data[Self::ROOT_OFFSET..Self::ROOT_OFFSET + 32]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok((count, root))
+    }
+
+    /// Initialize the data to the root of the empty tree
+    pub fn init(data: &mut [u8]) -> Result<(), ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        data[..Self::LEN].fill(0);
+        let root = (0..Self::DEPTH).fold([0; 32], |node, _| hash_nodes(&node, &node));
+        data[Self::ROOT_OFFSET..Self::ROOT_OFFSET + 32].copy_from_slice(&root);
+        Ok(())
+    }
+
+    /// Append the leaf of a transfer, updating the frontier and the root,
+    /// returning the index of the leaf
+    pub fn append(data: &mut [u8], leaf: &[u8; 32]) -> Result<u64, ProgramError> {
+        let (count, _) = Self::unpack(data)?;
+        let size = count + 1;
+        if size >> Self::DEPTH != 0 {
+            msg!("Transfer root holds the most transfers allowed");
+            return Err(ExampleError::TransferRootFull.into());
+        }
+        let frontier = |data: &[u8], height: usize| -> [u8; 32] {
+            let offset = Self::FRONTIER_OFFSET + height * 32;
+            data[offset..offset + 32].try_into().unwrap()
+        };
+
+        // the leaf completes the subtrees of the trailing one bits of its
+        // index, and becomes the frontier of the height above them
+        let mut node = *leaf;
+        for height in 0..Self::DEPTH {
+            if size >> height & 1 == 1 {
+                let offset = Self::FRONTIER_OFFSET + height * 32;
+                data[offset..offset + 32].copy_from_slice(&node);
+                break;
+            }
+            node = hash_nodes(&frontier(data, height), &node);
+        }
+
+        // complete subtrees on the left, empty subtrees on the right
+        let mut root = [0; 32];
+        let mut empty_subtree = [0; 32];
+        for height in 0..Self::DEPTH {
+            root = if size >> height & 1 == 1 {
+                hash_nodes(&frontier(data, height), &root)
+            } else {
+                hash_nodes(&root, &empty_subtree)
+            };
+            empty_subtree = hash_nodes(&empty_subtree, &empty_subtree);
+        }
+
+        data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 8].copy_from_slice(&size.to_le_bytes());
+        data[Self::ROOT_OFFSET..Self::ROOT_OFFSET + 32].copy_from_slice(&root);
+        Ok(count)
+    }
+}
+
+/// Handling of zero-amount transfers by `Execute`
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ZeroAmountMode {
+    /// Track the transfer like any other
+    #[default]
+    Track,
+    /// Skip tracking, returning once every check of the transfer passes
+    SkipTracking,
+    /// Reject the transfer
+    Reject,
+}
+
+impl TryFrom<u8> for ZeroAmountMode {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Track),
+            1 => Ok(Self::SkipTracking),
+            2 => Ok(Self::Reject),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// List of owners consulted by `Execute`, only one of them applying to a mint
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccessMode {
+    /// Reject transfers from or to the owners on the blocklist, if the mint
+    /// has one
+    #[default]
+    Blocklist,
+    /// Reject transfers unless both owners are on the allowlist, whose page
+    /// of each owner must be passed to `Execute`
+    Allowlist,
+    /// Consult neither list
+    None,
+}
+
+impl TryFrom<u8> for AccessMode {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Blocklist),
+            1 => Ok(Self::Allowlist),
+            2 => Ok(Self::None),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Rounding of the fee of a transfer to a whole amount
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round down, in favor of the sender
+    #[default]
+    Floor,
+    /// Round up, in favor of the fee ledgers
+    Ceil,
+    /// Round to the nearest amount, halves up
+    Round,
+}
+
+impl RoundingMode {
+    /// Divide `numerator` by `denominator`, rounded with the mode
+    pub fn divide(self, numerator: u128, denominator: u128) -> u128 {
+        match self {
+            Self::Floor => numerator / denominator,
+            Self::Ceil => numerator.div_ceil(denominator),
+            Self::Round => (numerator + denominator / 2) / denominator,
+        }
+    }
+}
+
+impl TryFrom<u8> for RoundingMode {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Floor),
+            1 => Ok(Self::Ceil),
+            2 => Ok(Self::Round),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Share of the transfer fee accrued to the fee ledger of a category
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeSplit {
+    /// Category of the fee ledger, chosen by the issuer, e.g. treasury, burn
+    /// or staking
+    pub category: u8,
+    /// Share of the transferred amount accrued to the ledger, in basis
+    /// points. Zero leaves the split unused.
+    pub bps: u16,
+}
+
+/// Per-mint configuration of the checks performed during `Execute`, stored in
+/// the mint config PDA.
+///
+/// New fields are only ever appended to the layout, and a zero value always
+/// leaves the corresponding check disabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MintConfig {
+    /// Authority allowed to update the config
+    pub authority: Pubkey,
+    /// Maximum share of the source holdings that can be moved in a single
+    /// transfer, in basis points. Zero disables the check.
+    pub max_pct_bps: u16,
+    /// Minimum number of seconds between two transfers of the same owner.
+    /// Zero disables the check, which is also skipped when the clock sysvar
+    /// isn't provided to `Execute`.
+    pub cooldown_seconds: u32,
+    /// Length of the rate limiting window, in slots. Zero disables the check,
+    /// which is also skipped when the clock sysvar isn't provided to
+    /// `Execute`.
+    pub window_slots: u64,
+    /// Maximum amount an owner can transfer per window of `window_slots`
+    pub max_amount_per_window_slots: u64,
+    /// Handling of zero-amount transfers
+    pub zero_amount_mode: ZeroAmountMode,
+    /// Program notified of each transfer by `Execute`. The default pubkey
+    /// disables notifications.
+    pub notify_program: Pubkey,
+    /// Transfers of at most this amount skip the balance percentage and slot
+    /// window limits, other checks still apply. Zero disables the exemption.
+    pub limit_exempt_amount: u64,
+    /// Number of `signers` required to sign config changes. Zero leaves
+    /// `authority` alone in charge of the config.
+    pub signer_threshold: u8,
+    /// Signers allowed to change the config when `signer_threshold` is set,
+    /// unused entries left to the default pubkey
+    pub signers: [Pubkey; MintConfig::MAX_SIGNERS],
+    /// Fee accrued on each transfer, in basis points of the transferred
+    /// amount. Zero disables the fee.
+    pub fee_bps: u16,
+    /// Split of the fee across the fee ledgers, whose `bps` sum to `fee_bps`
+    pub fee_splits: [FeeSplit; MintConfig::MAX_FEE_SPLITS],
+    /// Only require the source token account to be transferring, for
+    /// integrations whose CPI ordering leaves the destination unflagged.
+    /// False keeps both checks.
+    pub source_transferring_only: bool,
+    /// Skip tracking and the limits for transfers between two accounts of the
+    /// same owner. False counts them like any other transfer.
+    pub skip_same_owner_transfers: bool,
+    /// Check that the amount passed to `Execute` is consistent with the token
+    /// balances. False trusts the amount.
+    pub check_amount_consistency: bool,
+    /// Minimum number of seconds between the initialization of the transfer
+    /// account of an owner, or the first transfer it received when reset
+    /// without one, and their outbound transfers. Later receives don't
+    /// restart it. Zero disables the check, which is also skipped when the
+    /// clock sysvar isn't provided to `Execute`, and fails for owners without
+    /// either time.
+    pub holding_period_seconds: u32,
+    /// Debit each tracked transfer from the owner's allowance, set by the
+    /// config authority, rejecting transfers exceeding it. False leaves
+    /// transfers unbounded by allowances.
+    pub enforce_allowances: bool,
+    /// Reject transfers while the mint is paused or once its mint authority
+    /// has been revoked, for mints whose transfers halt with the sale. False
+    /// ignores the mint state.
+    pub require_active_mint: bool,
+    /// Share of each transfer the source owner owes to burn, in basis points,
+    /// recorded in the owner's burn ledger and burned by `SettleBurn`. Zero
+    /// disables the burn.
+    pub burn_bps: u16,
+    /// List of owners consulted by `Execute`, the blocklist or the allowlist,
+    /// so the two can't contradict each other. Written by older layouts as
+    /// whether the allowlist was required, which the blocklist and allowlist
+    /// modes match.
+    pub access_mode: AccessMode,
+    /// Reject transfers to token accounts owned by one of the mint's PDAs:
+    /// its validation account, config, stats or stats history, which can't
+    /// move the tokens. False accepts any destination.
+    pub reject_program_destinations: bool,
+    /// Maximum total amount moved by the mint's transfers within an epoch,
+    /// tracked in the mint stats, which must then be passed to `Execute`.
+    /// Zero disables the cap.
+    pub max_epoch_volume: u64,
+    /// Leave the transfer account out of `Execute`, which then neither
+    /// requires nor updates it, skipping the cooldown, window limit, holding
+    /// period and allowance checks based on it. False tracks the transfers of
+    /// each owner.
+    pub skip_transfer_tracking: bool,
+    /// Signer whose ed25519 [`TransferTicket`] for each transfer must be
+    /// verified by the ed25519 program in the same transaction, through the
+    /// instructions sysvar passed to `Execute`. The ticket's nonce must exceed
+    /// the last one used by the owner, recorded in their transfer account.
+    /// The default pubkey disables tickets.
+    pub ticket_signer: Pubkey,
+    /// Maximum number of transfers an owner can make per UTC day, counted in
+    /// their transfer account. Zero disables the check, which is also skipped
+    /// when the clock sysvar isn't provided to `Execute`.
+    pub max_transfers_per_day: u32,
+    /// Reject transfers unless the owner of the source token account signed
+    /// the transaction and appears as a signer in the accounts of `Execute`,
+    /// for custodial flows where a delegate must not move the tokens alone.
+    /// False accepts transfers signed by the delegate only.
+    pub require_owner_signer: bool,
+    /// Maximum total amount an owner can receive from other owners, tracked
+    /// in their transfer account, which must be initialized and passed to
+    /// `Execute` with the other accounts. Zero disables the cap.
+    pub max_received_amount: u64,
+    /// Reject transfers to destination token accounts holding fewer lamports
+    /// than their rent exemption, which may be about to be closed. False
+    /// accepts any destination balance.
+    pub require_rent_exempt_destination: bool,
+    /// Bitmask of the extra account metas, by index in the validation
+    /// account's list, whose account must be owned by the program its
+    /// external PDA is derived from, e.g. a PDA of a governance program.
+    /// Bits of other metas fail `Execute`. Zero checks no owners.
+    pub owner_checked_metas: u32,
+    /// Only owner allowed to send tokens until trading is enabled, e.g. for a
+    /// fair launch. `EnableTrading` clears it, and the default pubkey lets
+    /// anyone transfer. Only named by the config of `SetupMint`, as
+    /// `UpdateMintConfig` can't set it once cleared.
+    pub distributor: Pubkey,
+    /// Reject transfers whose authority isn't the owner of the source token
+    /// account, i.e. transfers by its delegate or by the permanent delegate
+    /// of the mint. False accepts any authority able to move the tokens.
+    pub reject_delegate_transfers: bool,
+    /// Slot from which transfers are paused, set by `SchedulePause` ahead of
+    /// time to warn holders and cleared by `CancelPause`, which
+    /// `UpdateMintConfig` never changes. Zero schedules no pause.
+    pub pause_effective_slot: u64,
+    /// Reason of the scheduled pause, set along with it and logged by the
+    /// transfers it rejects. The reason ends at its first zero byte, if any.
+    pub pause_reason: [u8; MintConfig::PAUSE_REASON_LEN],
+    /// Whether a transfer above `max_pct_bps` of the source holdings fails
+    /// with `TransferExceedsAllowedMaximum`, reporting the largest amount it
+    /// may move through return data so the client can retry with it. False
+    /// fails with `TransferExceedsBalancePercentage` instead.
+    pub report_allowed_maximum: bool,
+    /// Token-2022 extension type the destination token account must carry,
+    /// e.g. `MemoTransfer` for the accounts of KYC'd owners. Zero, the
+    /// `Uninitialized` type, requires no extension.
+    pub required_destination_extension: u16,
+    /// Version of the extra account metas layout the validation account was
+    /// written for, which `Execute` requires to be
+    /// [`EXTRA_ACCOUNT_METAS_VERSION`](crate::processor::EXTRA_ACCOUNT_METAS_VERSION).
+    /// Zero skips the check, for validation accounts written before it.
+    pub extra_account_metas_version: u8,
+    /// Maximum number of transfer accounts a single payer may create for the
+    /// mint, counted in its creation count PDA. Zero sets no cap.
+    pub max_transfer_accounts_per_payer: u32,
+    /// Rounding of the fee of a transfer, `Floor` truncating it as before
+    /// the field was added. The shares of the splits are still rounded down.
+    pub fee_rounding: RoundingMode,
+    /// Programs, e.g. DEX routers, whose transfers skip the balance
+    /// percentage cap and the tracking along with the limits based on it,
+    /// like those of exempt owners. The calling program is read from the
+    /// instructions sysvar, which `Execute` must be given. Unused entries are
+    /// left to the default pubkey.
+    pub trusted_callers: [Pubkey; MintConfig::MAX_TRUSTED_CALLERS],
+    /// Minimum number of slots between the initialization of the transfer
+    /// account of an owner and their outbound transfers, against bots sending
+    /// from fresh accounts. Zero disables the check, which like the holding
+    /// period is skipped without the clock sysvar and starts at the first
+    /// receive of an account reset without a slot.
+    pub min_account_age_slots: u64,
+    /// Check that the mint has `expected_decimals`, when the config is
+    /// written and on every transfer, guarding integrations against a hook
+    /// pointed at the wrong mint. False leaves the decimals unchecked.
+    pub enforce_decimals: bool,
+    /// Decimals the mint must have, when `enforce_decimals`
+    pub expected_decimals: u8,
+    /// Apply the per-transfer limits to the sum of the transfers from the
+    /// same source within a transaction, as made by AMMs filling an order in
+    /// chunks, instead of to each chunk. Requires the instructions sysvar and
+    /// the transfer account; false checks every transfer on its own.
+    pub aggregate_partial_fills: bool,
+    /// Track the amounts transferred and received net of the Token-2022
+    /// transfer fee of the mint, withheld from the destination, instead of
+    /// the amount `Execute` is called with. Mints without the transfer fee
+    /// extension are tracked alike either way.
+    pub track_net_of_transfer_fee: bool,
+    /// Reject transfers until `FinalizeSetup` marks the setup of the mint as
+    /// complete, giving the issuer a grace period to finish the config after
+    /// the validation account is initialized. False accepts transfers right
+    /// away.
+    pub require_setup: bool,
+    /// Set once by `FinalizeSetup`, which `UpdateMintConfig` never clears
+    pub setup_complete: bool,
+}
+
+impl MintConfig {
+    /// Maximum number of config signers
+    pub const MAX_SIGNERS: usize = 5;
+    /// Maximum number of fee splits
+    pub const MAX_FEE_SPLITS: usize = 4;
+    /// Maximum number of trusted caller programs
+    pub const MAX_TRUSTED_CALLERS: usize = 4;
+    /// Size of the reason of a scheduled pause
+    pub const PAUSE_REASON_LEN: usize =
+        spl_transfer_tracking_interface::custom_instruction::PAUSE_REASON_LEN;
+    /// Current version of the layout
+    pub const VERSION: u8 = 2;
+    /// Size of the mint config account data
+    pub const LEN: usize = Self::SETUP_COMPLETE_OFFSET + 1;
+
+    /// Size of the data of each version of the layout, starting with version 1
+    const LAYOUT_LENS: [usize; Self::VERSION as usize] = [
+        // ending with `limit_exempt_amount`, before the config signers, the
+        // fees, the transfer flags and every later field were appended
+        Self::SIGNER_THRESHOLD_OFFSET,
+        Self::LEN,
+    ];
+
+    // Offsets
+    const VERSION_OFFSET: usize = 0;
+    const AUTHORITY_OFFSET: usize = Self::VERSION_OFFSET + 1;
+    const MAX_PCT_BPS_OFFSET: usize = Self::AUTHORITY_OFFSET + 32;
+    const COOLDOWN_SECONDS_OFFSET: usize = Self::MAX_PCT_BPS_OFFSET + 2;
+    const WINDOW_SLOTS_OFFSET: usize = Self::COOLDOWN_SECONDS_OFFSET + 4;
+    const MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET: usize = Self::WINDOW_SLOTS_OFFSET + 8;
+    const ZERO_AMOUNT_MODE_OFFSET: usize = Self::MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET + 8;
+    const NOTIFY_PROGRAM_OFFSET: usize = Self::ZERO_AMOUNT_MODE_OFFSET + 1;
+    const LIMIT_EXEMPT_AMOUNT_OFFSET: usize = Self::NOTIFY_PROGRAM_OFFSET + 32;
+    const SIGNER_THRESHOLD_OFFSET: usize = Self::LIMIT_EXEMPT_AMOUNT_OFFSET + 8;
+    const SIGNERS_OFFSET: usize = Self::SIGNER_THRESHOLD_OFFSET + 1;
+    const FEE_BPS_OFFSET: usize = Self::SIGNERS_OFFSET + 32 * Self::MAX_SIGNERS;
+    const FEE_SPLITS_OFFSET: usize = Self::FEE_BPS_OFFSET + 2;
+    const SOURCE_TRANSFERRING_ONLY_OFFSET: usize =
+        Self::FEE_SPLITS_OFFSET + 3 * Self::MAX_FEE_SPLITS;
+    const SKIP_SAME_OWNER_TRANSFERS_OFFSET: usize = Self::SOURCE_TRANSFERRING_ONLY_OFFSET + 1;
+    const CHECK_AMOUNT_CONSISTENCY_OFFSET: usize = Self::SKIP_SAME_OWNER_TRANSFERS_OFFSET + 1;
+    const HOLDING_PERIOD_SECONDS_OFFSET: usize = Self::CHECK_AMOUNT_CONSISTENCY_OFFSET + 1;
+    const ENFORCE_ALLOWANCES_OFFSET: usize = Self::HOLDING_PERIOD_SECONDS_OFFSET + 4;
+    const REQUIRE_ACTIVE_MINT_OFFSET: usize = Self::ENFORCE_ALLOWANCES_OFFSET + 1;
+    const BURN_BPS_OFFSET: usize = Self::REQUIRE_ACTIVE_MINT_OFFSET + 1;
+    const ACCESS_MODE_OFFSET: usize = Self::BURN_BPS_OFFSET + 2;
+    const REJECT_PROGRAM_DESTINATIONS_OFFSET: usize = Self::ACCESS_MODE_OFFSET + 1;
+    const MAX_EPOCH_VOLUME_OFFSET: usize = Self::REJECT_PROGRAM_DESTINATIONS_OFFSET + 1;
+    const SKIP_TRANSFER_TRACKING_OFFSET: usize = Self::MAX_EPOCH_VOLUME_OFFSET + 8;
+    const TICKET_SIGNER_OFFSET: usize = Self::SKIP_TRANSFER_TRACKING_OFFSET + 1;
+    const MAX_TRANSFERS_PER_DAY_OFFSET: usize = Self::TICKET_SIGNER_OFFSET + 32;
+    const REQUIRE_OWNER_SIGNER_OFFSET: usize = Self::MAX_TRANSFERS_PER_DAY_OFFSET + 4;
+    const MAX_RECEIVED_AMOUNT_OFFSET: usize = Self::REQUIRE_OWNER_SIGNER_OFFSET + 1;
+    const REQUIRE_RENT_EXEMPT_DESTINATION_OFFSET: usize = Self::MAX_RECEIVED_AMOUNT_OFFSET + 8;
+    const OWNER_CHECKED_METAS_OFFSET: usize = Self::REQUIRE_RENT_EXEMPT_DESTINATION_OFFSET + 1;
+    const DISTRIBUTOR_OFFSET: usize = Self::OWNER_CHECKED_METAS_OFFSET + 4;
+    const REJECT_DELEGATE_TRANSFERS_OFFSET: usize = Self::DISTRIBUTOR_OFFSET + 32;
+    const PAUSE_EFFECTIVE_SLOT_OFFSET: usize = Self::REJECT_DELEGATE_TRANSFERS_OFFSET + 1;
+    const PAUSE_REASON_OFFSET: usize = Self::PAUSE_EFFECTIVE_SLOT_OFFSET + 8;
+    const REPORT_ALLOWED_MAXIMUM_OFFSET: usize = Self::PAUSE_REASON_OFFSET + Self::PAUSE_REASON_LEN;
+    const REQUIRED_DESTINATION_EXTENSION_OFFSET: usize = Self::REPORT_ALLOWED_MAXIMUM_OFFSET + 1;
+    const EXTRA_ACCOUNT_METAS_VERSION_OFFSET: usize =
+        Self::REQUIRED_DESTINATION_EXTENSION_OFFSET + 2;
+    const MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET: usize =
+        Self::EXTRA_ACCOUNT_METAS_VERSION_OFFSET + 1;
+    const FEE_ROUNDING_OFFSET: usize = Self::MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET + 4;
+    const TRUSTED_CALLERS_OFFSET: usize = Self::FEE_ROUNDING_OFFSET + 1;
+    const MIN_ACCOUNT_AGE_SLOTS_OFFSET: usize =
+        Self::TRUSTED_CALLERS_OFFSET + 32 * Self::MAX_TRUSTED_CALLERS;
+    const ENFORCE_DECIMALS_OFFSET: usize = Self::MIN_ACCOUNT_AGE_SLOTS_OFFSET + 8;
+    const EXPECTED_DECIMALS_OFFSET: usize = Self::ENFORCE_DECIMALS_OFFSET + 1;
+    const AGGREGATE_PARTIAL_FILLS_OFFSET: usize = Self::EXPECTED_DECIMALS_OFFSET + 1;
+    const TRACK_NET_OF_TRANSFER_FEE_OFFSET: usize = Self::AGGREGATE_PARTIAL_FILLS_OFFSET + 1;
+    const REQUIRE_SETUP_OFFSET: usize = Self::TRACK_NET_OF_TRANSFER_FEE_OFFSET + 1;
+    const SETUP_COMPLETE_OFFSET: usize = Self::REQUIRE_SETUP_OFFSET + 1;
+
+    /// Size of the data of a version of the layout, `None` for unknown
+    /// versions
+    pub fn layout_len(version: u8) -> Option<usize> {
+        let index = usize::from(version).checked_sub(1)?;
+        Self::LAYOUT_LENS.get(index).copied()
+    }
+
+    /// Whether a transfer of `amount` is small enough to skip the limits
+    pub fn is_limit_exempt(&self, amount: u64) -> bool {
+        self.limit_exempt_amount != 0 && amount <= self.limit_exempt_amount
+    }
+
+    /// The configured signers, skipping unused entries
+    pub fn configured_signers(&self) -> impl Iterator<Item = &Pubkey> {
+        self.signers
+            .iter()
+            .filter(|signer| **signer != Pubkey::default())
+    }
+
+    /// The configured trusted callers, skipping unused entries
+    pub fn configured_trusted_callers(&self) -> impl Iterator<Item = &Pubkey> {
+        self.trusted_callers
+            .iter()
+            .filter(|caller| **caller != Pubkey::default())
+    }
+
+    /// Check the config can be written: see [`Self::check_signers`] and
+    /// [`Self::check_fee_splits`], and the burn must not exceed the transfer
+    pub fn check(&self) -> Result<(), ProgramError> {
+        self.check_signers()?;
+        self.check_fee_splits()?;
+        if self.burn_bps > 10_000 {
+            msg!("Burn of {} bps exceeds the transfer", self.burn_bps);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.ticket_signer != Pubkey::default() && self.skip_transfer_tracking {
+            msg!("Transfer tickets need the transfer account to record their nonce");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.required_destination_extension != 0
+            && ExtensionType::try_from(self.required_destination_extension)
+                .map(|extension_type| extension_type.get_account_type())
+                != Ok(AccountType::Account)
+        {
+            msg!(
+                "Extension type {} is not a token account extension",
+                self.required_destination_extension
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Check that the fee splits sum to the fee, each category appearing
+    /// once
+    pub fn check_fee_splits(&self) -> Result<(), ProgramError> {
+        let splits: Vec<_> = self.active_fee_splits().collect();
+        if splits
+            .iter()
+            .enumerate()
+            .any(|(i, split)| splits[..i].iter().any(|s| s.category == split.category))
+        {
+            msg!("Fee split categories must be distinct");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let total: u32 = splits.iter().map(|split| u32::from(split.bps)).sum();
+        if self.fee_bps > 10_000 || total != u32::from(self.fee_bps) {
+            msg!(
+                "Fee splits sum to {} bps, for a fee of {} bps",
+                total,
+                self.fee_bps
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// The fee splits in use
+    pub fn active_fee_splits(&self) -> impl Iterator<Item = &FeeSplit> {
+        self.fee_splits.iter().filter(|split| split.bps != 0)
+    }
+
+    /// The fee accrued to each category for a transfer of `amount`.
+    ///
+    /// The fee is rounded with `fee_rounding` and each share down, and the
+    /// remainder of the shares goes to the first split, so the shares always
+    /// sum to the fee.
+    pub fn fee_shares(&self, amount: u64) -> Vec<(u8, u64)> {
+        if self.fee_bps == 0 {
+            return vec![];
+        }
+        let fee = self
+            .fee_rounding
+            .divide(u128::from(amount) * u128::from(self.fee_bps), 10_000);
+        let mut shares: Vec<(u8, u64)> = self
+            .active_fee_splits()
+            .map(|split| {
+                let share = fee * u128::from(split.bps) / u128::from(self.fee_bps);
+                (split.category, share as u64)
+            })
+            .collect();
+        let distributed: u128 = shares.iter().map(|(_, share)| u128::from(*share)).sum();
+        if let Some((_, first)) = shares.first_mut() {
+            *first += (fee - distributed) as u64;
+        }
+        shares
+    }
+
+    /// The amount owed to burn for a transfer of `amount`, rounded down
+    pub fn burn_amount(&self, amount: u64) -> u64 {
+        (u128::from(amount) * u128::from(self.burn_bps) / 10_000) as u64
+    }
+
+    /// Check that the signer set can be satisfied: the threshold doesn't
+    /// exceed the number of signers, which are all distinct
+    pub fn check_signers(&self) -> Result<(), ProgramError> {
+        let signers: Vec<_> = self.configured_signers().collect();
+        if signers
+            .iter()
+            .enumerate()
+            .any(|(i, signer)| signers[..i].contains(signer))
+        {
+            msg!("Config signers must be distinct");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.signer_threshold as usize > signers.len() {
+            msg!(
+                "Signer threshold of {} with {} signers",
+                self.signer_threshold,
+                signers.len()
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Pack the config into bytes, with the current version
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[Self::VERSION_OFFSET] = Self::VERSION;
+        dst[Self::AUTHORITY_OFFSET..Self::AUTHORITY_OFFSET + 32]
+            .copy_from_slice(self.authority.as_ref());
+        dst[Self::MAX_PCT_BPS_OFFSET..Self::MAX_PCT_BPS_OFFSET + 2]
+            .copy_from_slice(&self.max_pct_bps.to_le_bytes());
+        dst[Self::COOLDOWN_SECONDS_OFFSET..Self::COOLDOWN_SECONDS_OFFSET + 4]
+            .copy_from_slice(&self.cooldown_seconds.to_le_bytes());
+        dst[Self::WINDOW_SLOTS_OFFSET..Self::WINDOW_SLOTS_OFFSET + 8]
+            .copy_from_slice(&self.window_slots.to_le_bytes());
+        dst[Self::MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET..Self::MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET + 8]
+            .copy_from_slice(&self.max_amount_per_window_slots.to_le_bytes());
+        dst[Self::ZERO_AMOUNT_MODE_OFFSET] = self.zero_amount_mode as u8;
+        dst[Self::NOTIFY_PROGRAM_OFFSET..Self::NOTIFY_PROGRAM_OFFSET + 32]
+            .copy_from_slice(self.notify_program.as_ref());
+        dst[Self::LIMIT_EXEMPT_AMOUNT_OFFSET..Self::LIMIT_EXEMPT_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.limit_exempt_amount.to_le_bytes());
+        dst[Self::SIGNER_THRESHOLD_OFFSET] = self.signer_threshold;
+        for (i, signer) in self.signers.iter().enumerate() {
+            let offset = Self::SIGNERS_OFFSET + 32 * i;
+            dst[offset..offset + 32].copy_from_slice(signer.as_ref());
+        }
+        dst[Self::FEE_BPS_OFFSET..Self::FEE_BPS_OFFSET + 2]
+            .copy_from_slice(&self.fee_bps.to_le_bytes());
+        for (i, split) in self.fee_splits.iter().enumerate() {
+            let offset = Self::FEE_SPLITS_OFFSET + 3 * i;
+            dst[offset] = split.category;
+            dst[offset + 1..offset + 3].copy_from_slice(&split.bps.to_le_bytes());
+        }
+        dst[Self::SOURCE_TRANSFERRING_ONLY_OFFSET] = self.source_transferring_only.into();
+        dst[Self::SKIP_SAME_OWNER_TRANSFERS_OFFSET] = self.skip_same_owner_transfers.into();
+        dst[Self::CHECK_AMOUNT_CONSISTENCY_OFFSET] = self.check_amount_consistency.into();
+        dst[Self::HOLDING_PERIOD_SECONDS_OFFSET..Self::HOLDING_PERIOD_SECONDS_OFFSET + 4]
+            .copy_from_slice(&self.holding_period_seconds.to_le_bytes());
+        dst[Self::ENFORCE_ALLOWANCES_OFFSET] = self.enforce_allowances.into();
+        dst[Self::REQUIRE_ACTIVE_MINT_OFFSET] = self.require_active_mint.into();
+        dst[Self::BURN_BPS_OFFSET..Self::BURN_BPS_OFFSET + 2]
+            .copy_from_slice(&self.burn_bps.to_le_bytes());
+        dst[Self::ACCESS_MODE_OFFSET] = self.access_mode as u8;
+        dst[Self::REJECT_PROGRAM_DESTINATIONS_OFFSET] = self.reject_program_destinations.into();
+        dst[Self::MAX_EPOCH_VOLUME_OFFSET..Self::MAX_EPOCH_VOLUME_OFFSET + 8]
+            .copy_from_slice(&self.max_epoch_volume.to_le_bytes());
+        dst[Self::SKIP_TRANSFER_TRACKING_OFFSET] = self.skip_transfer_tracking.into();
+        dst[Self::TICKET_SIGNER_OFFSET..Self::TICKET_SIGNER_OFFSET + 32]
+            .copy_from_slice(self.ticket_signer.as_ref());
+        dst[Self::MAX_TRANSFERS_PER_DAY_OFFSET..Self::MAX_TRANSFERS_PER_DAY_OFFSET + 4]
+            .copy_from_slice(&self.max_transfers_per_day.to_le_bytes());
+        dst[Self::REQUIRE_OWNER_SIGNER_OFFSET] = self.require_owner_signer.into();
+        dst[Self::MAX_RECEIVED_AMOUNT_OFFSET..Self::MAX_RECEIVED_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.max_received_amount.to_le_bytes());
+        dst[Self::REQUIRE_RENT_EXEMPT_DESTINATION_OFFSET] =
+            self.require_rent_exempt_destination.into();
+        dst[Self::OWNER_CHECKED_METAS_OFFSET..Self::OWNER_CHECKED_METAS_OFFSET + 4]
+            .copy_from_slice(&self.owner_checked_metas.to_le_bytes());
+        dst[Self::DISTRIBUTOR_OFFSET..Self::DISTRIBUTOR_OFFSET + 32]
+            .copy_from_slice(self.distributor.as_ref());
+        dst[Self::REJECT_DELEGATE_TRANSFERS_OFFSET] = self.reject_delegate_transfers.into();
+        dst[Self::PAUSE_EFFECTIVE_SLOT_OFFSET..Self::PAUSE_EFFECTIVE_SLOT_OFFSET + 8]
+            .copy_from_slice(&self.pause_effective_slot.to_le_bytes());
+        dst[Self::PAUSE_REASON_OFFSET..Self::PAUSE_REASON_OFFSET + Self::PAUSE_REASON_LEN]
+            .copy_from_slice(&self.pause_reason);
+        dst[Self::REPORT_ALLOWED_MAXIMUM_OFFSET] = self.report_allowed_maximum.into();
+        dst[Self::REQUIRED_DESTINATION_EXTENSION_OFFSET
+            ..Self::REQUIRED_DESTINATION_EXTENSION_OFFSET + 2]
+            .copy_from_slice(&self.required_destination_extension.to_le_bytes());
+        dst[Self::EXTRA_ACCOUNT_METAS_VERSION_OFFSET] = self.extra_account_metas_version;
+        dst[Self::MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET
+            ..Self::MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET + 4]
+            .copy_from_slice(&self.max_transfer_accounts_per_payer.to_le_bytes());
+        dst[Self::FEE_ROUNDING_OFFSET] = self.fee_rounding as u8;
+        for (i, caller) in self.trusted_callers.iter().enumerate() {
+            let offset = Self::TRUSTED_CALLERS_OFFSET + 32 * i;
+            dst[offset..offset + 32].copy_from_slice(caller.as_ref());
+        }
+        dst[Self::MIN_ACCOUNT_AGE_SLOTS_OFFSET..Self::MIN_ACCOUNT_AGE_SLOTS_OFFSET + 8]
+            .copy_from_slice(&self.min_account_age_slots.to_le_bytes());
+        dst[Self::ENFORCE_DECIMALS_OFFSET] = self.enforce_decimals.into();
+        dst[Self::EXPECTED_DECIMALS_OFFSET] = self.expected_decimals;
+        dst[Self::AGGREGATE_PARTIAL_FILLS_OFFSET] = self.aggregate_partial_fills.into();
+        dst[Self::TRACK_NET_OF_TRANSFER_FEE_OFFSET] = self.track_net_of_transfer_fee.into();
+        dst[Self::REQUIRE_SETUP_OFFSET] = self.require_setup.into();
+        dst[Self::SETUP_COMPLETE_OFFSET] = self.setup_complete.into();
+    }
+
+    /// Unpack a config written with an older version of the layout, the
+    /// fields appended since keeping their zero defaults
+    pub fn unpack_outdated(src: &[u8]) -> Result<Self, ProgramError> {
+        let version = *src
+            .get(Self::VERSION_OFFSET)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let len = match Self::layout_len(version) {
+            Some(len) if version < Self::VERSION && src.len() >= len => len,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let mut data = [0; Self::LEN];
+        data[..len].copy_from_slice(&src[..len]);
+        data[Self::VERSION_OFFSET] = Self::VERSION;
+        Self::unpack(&data)
+    }
+
+    /// Unpack the config from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < Self::LEN || src[Self::VERSION_OFFSET] != Self::VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let authority = Pubkey::try_from(&src[Self::AUTHORITY_OFFSET..Self::AUTHORITY_OFFSET + 32])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let max_pct_bps = u16::from_le_bytes(
+            src[Self::MAX_PCT_BPS_OFFSET..Self::MAX_PCT_BPS_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let cooldown_seconds = u32::from_le_bytes(
+            src[Self::COOLDOWN_SECONDS_OFFSET..Self::COOLDOWN_SECONDS_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let window_slots = u64::from_le_bytes(
+            src[Self::WINDOW_SLOTS_OFFSET..Self::WINDOW_SLOTS_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let max_amount_per_window_slots = u64::from_le_bytes(
+            src[Self::MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET
+                ..Self::MAX_AMOUNT_PER_WINDOW_SLOTS_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let zero_amount_mode = ZeroAmountMode::try_from(src[Self::ZERO_AMOUNT_MODE_OFFSET])?;
+        let notify_program =
+            Pubkey::try_from(&src[Self::NOTIFY_PROGRAM_OFFSET..Self::NOTIFY_PROGRAM_OFFSET + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        let limit_exempt_amount = u64::from_le_bytes(
+            src[Self::LIMIT_EXEMPT_AMOUNT_OFFSET..Self::LIMIT_EXEMPT_AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let signer_threshold = src[Self::SIGNER_THRESHOLD_OFFSET];
+        let mut signers = [Pubkey::default(); Self::MAX_SIGNERS];
+        for (i, signer) in signers.iter_mut().enumerate() {
+            let offset = Self::SIGNERS_OFFSET + 32 * i;
+            *signer = Pubkey::try_from(&src[offset..offset + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        }
+        let fee_bps = u16::from_le_bytes(
+            src[Self::FEE_BPS_OFFSET..Self::FEE_BPS_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let mut fee_splits = [FeeSplit::default(); Self::MAX_FEE_SPLITS];
+        for (i, split) in fee_splits.iter_mut().enumerate() {
+            let offset = Self::FEE_SPLITS_OFFSET + 3 * i;
+            *split = FeeSplit {
+                category: src[offset],
+                bps: u16::from_le_bytes(
+                    src[offset + 1..offset + 3]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                ),
+            };
+        }
+        let source_transferring_only = unpack_bool(src[Self::SOURCE_TRANSFERRING_ONLY_OFFSET])?;
+        let skip_same_owner_transfers = unpack_bool(src[Self::SKIP_SAME_OWNER_TRANSFERS_OFFSET])?;
+        let check_amount_consistency = unpack_bool(src[Self::CHECK_AMOUNT_CONSISTENCY_OFFSET])?;
+        let holding_period_seconds = u32::from_le_bytes(
+            src[Self::HOLDING_PERIOD_SECONDS_OFFSET..Self::HOLDING_PERIOD_SECONDS_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let enforce_allowances = unpack_bool(src[Self::ENFORCE_ALLOWANCES_OFFSET])?;
+        let require_active_mint = unpack_bool(src[Self::REQUIRE_ACTIVE_MINT_OFFSET])?;
+        let burn_bps = u16::from_le_bytes(
+            src[Self::BURN_BPS_OFFSET..Self::BURN_BPS_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let access_mode = AccessMode::try_from(src[Self::ACCESS_MODE_OFFSET])?;
+        let reject_program_destinations =
+            unpack_bool(src[Self::REJECT_PROGRAM_DESTINATIONS_OFFSET])?;
+        let max_epoch_volume = u64::from_le_bytes(
+            src[Self::MAX_EPOCH_VOLUME_OFFSET..Self::MAX_EPOCH_VOLUME_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let skip_transfer_tracking = unpack_bool(src[Self::SKIP_TRANSFER_TRACKING_OFFSET])?;
+        let ticket_signer =
+            Pubkey::try_from(&src[Self::TICKET_SIGNER_OFFSET..Self::TICKET_SIGNER_OFFSET + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        let max_transfers_per_day = u32::from_le_bytes(
+            src[Self::MAX_TRANSFERS_PER_DAY_OFFSET..Self::MAX_TRANSFERS_PER_DAY_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let require_owner_signer = unpack_bool(src[Self::REQUIRE_OWNER_SIGNER_OFFSET])?;
+        let max_received_amount = u64::from_le_bytes(
+            src[Self::MAX_RECEIVED_AMOUNT_OFFSET..Self::MAX_RECEIVED_AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let require_rent_exempt_destination =
+            unpack_bool(src[Self::REQUIRE_RENT_EXEMPT_DESTINATION_OFFSET])?;
+        let owner_checked_metas = u32::from_le_bytes(
+            src[Self::OWNER_CHECKED_METAS_OFFSET..Self::OWNER_CHECKED_METAS_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let distributor =
+            Pubkey::try_from(&src[Self::DISTRIBUTOR_OFFSET..Self::DISTRIBUTOR_OFFSET + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        let reject_delegate_transfers = unpack_bool(src[Self::REJECT_DELEGATE_TRANSFERS_OFFSET])?;
+        let pause_effective_slot = u64::from_le_bytes(
+            src[Self::PAUSE_EFFECTIVE_SLOT_OFFSET..Self::PAUSE_EFFECTIVE_SLOT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let pause_reason = src
+            [Self::PAUSE_REASON_OFFSET..Self::PAUSE_REASON_OFFSET + Self::PAUSE_REASON_LEN]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let report_allowed_maximum = unpack_bool(src[Self::REPORT_ALLOWED_MAXIMUM_OFFSET])?;
+        let required_destination_extension = u16::from_le_bytes(
+            src[Self::REQUIRED_DESTINATION_EXTENSION_OFFSET
+                ..Self::REQUIRED_DESTINATION_EXTENSION_OFFSET + 2]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let extra_account_metas_version = src[Self::EXTRA_ACCOUNT_METAS_VERSION_OFFSET];
+        let max_transfer_accounts_per_payer = u32::from_le_bytes(
+            src[Self::MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET
+                ..Self::MAX_TRANSFER_ACCOUNTS_PER_PAYER_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let fee_rounding = RoundingMode::try_from(src[Self::FEE_ROUNDING_OFFSET])?;
+        let mut trusted_callers = [Pubkey::default(); Self::MAX_TRUSTED_CALLERS];
+        for (i, caller) in trusted_callers.iter_mut().enumerate() {
+            let offset = Self::TRUSTED_CALLERS_OFFSET + 32 * i;
+            *caller = Pubkey::try_from(&src[offset..offset + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        }
+        let min_account_age_slots = u64::from_le_bytes(
+            src[Self::MIN_ACCOUNT_AGE_SLOTS_OFFSET..Self::MIN_ACCOUNT_AGE_SLOTS_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let enforce_decimals = unpack_bool(src[Self::ENFORCE_DECIMALS_OFFSET])?;
+        let expected_decimals = src[Self::EXPECTED_DECIMALS_OFFSET];
+        let aggregate_partial_fills = unpack_bool(src[Self::AGGREGATE_PARTIAL_FILLS_OFFSET])?;
+        let track_net_of_transfer_fee = unpack_bool(src[Self::TRACK_NET_OF_TRANSFER_FEE_OFFSET])?;
+        let require_setup = unpack_bool(src[Self::REQUIRE_SETUP_OFFSET])?;
+        let setup_complete = unpack_bool(src[Self::SETUP_COMPLETE_OFFSET])?;
+
+        Ok(Self {
+            authority,
+            max_pct_bps,
+            cooldown_seconds,
+            window_slots,
+            max_amount_per_window_slots,
+            zero_amount_mode,
+            notify_program,
+            limit_exempt_amount,
+            signer_threshold,
+            signers,
+            fee_bps,
+            fee_splits,
+            source_transferring_only,
+            skip_same_owner_transfers,
+            check_amount_consistency,
+            holding_period_seconds,
+            enforce_allowances,
+            require_active_mint,
+            burn_bps,
+            access_mode,
+            reject_program_destinations,
+            max_epoch_volume,
+            skip_transfer_tracking,
+            ticket_signer,
+            max_transfers_per_day,
+            require_owner_signer,
+            max_received_amount,
+            require_rent_exempt_destination,
+            owner_checked_metas,
+            distributor,
+            reject_delegate_transfers,
+            pause_effective_slot,
+            pause_reason,
+            report_allowed_maximum,
+            required_destination_extension,
+            extra_account_metas_version,
+            max_transfer_accounts_per_payer,
+            fee_rounding,
+            trusted_callers,
+            min_account_age_slots,
+            enforce_decimals,
+            expected_decimals,
+            aggregate_partial_fills,
+            track_net_of_transfer_fee,
+            require_setup,
+            setup_complete,
+        })
+    }
+}
+
+fn unpack_bool(src: u8) -> Result<bool, ProgramError> {
+    match src {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Counters of the transfers accepted by `Execute` for a mint, stored in the
+/// mint-stats PDA.
+///
+/// Rejected transfers can't be counted: a failed `Execute` rolls back every
+/// account it wrote. The reason of a rejection is reported through return
+/// data instead, see [`FailedCheck`](crate::error::FailedCheck).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MintStats {
+    /// Number of accepted transfers
+    pub accepted_transfers: u64,
+    /// Total amount of the accepted transfers, saturating at `u64::MAX`
+    pub accepted_amount: u64,
+    /// Epoch of the last accepted transfer
+    pub epoch: u64,
+    /// Total amount of the transfers accepted within `epoch`, saturating at
+    /// `u64::MAX`
+    pub epoch_volume: u64,
+}
+
+impl MintStats {
+    /// Size of the mint stats account data
+    pub const LEN: usize = Self::EPOCH_VOLUME_OFFSET + 8;
+
+    // Offsets
+    const ACCEPTED_TRANSFERS_OFFSET: usize = 0;
+    const ACCEPTED_AMOUNT_OFFSET: usize = Self::ACCEPTED_TRANSFERS_OFFSET + 8;
+    const EPOCH_OFFSET: usize = Self::ACCEPTED_AMOUNT_OFFSET + 8;
+    const EPOCH_VOLUME_OFFSET: usize = Self::EPOCH_OFFSET + 8;
+
+    /// Pack the stats into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[Self::ACCEPTED_TRANSFERS_OFFSET..Self::ACCEPTED_TRANSFERS_OFFSET + 8]
+            .copy_from_slice(&self.accepted_transfers.to_le_bytes());
+        dst[Self::ACCEPTED_AMOUNT_OFFSET..Self::ACCEPTED_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.accepted_amount.to_le_bytes());
+        dst[Self::EPOCH_OFFSET..Self::EPOCH_OFFSET + 8].copy_from_slice(&self.epoch.to_le_bytes());
+        dst[Self::EPOCH_VOLUME_OFFSET..Self::EPOCH_VOLUME_OFFSET + 8]
+            .copy_from_slice(&self.epoch_volume.to_le_bytes());
+    }
+
+    /// Unpack the stats from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let accepted_transfers = u64::from_le_bytes(
+            src[Self::ACCEPTED_TRANSFERS_OFFSET..Self::ACCEPTED_TRANSFERS_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let accepted_amount = u64::from_le_bytes(
+            src[Self::ACCEPTED_AMOUNT_OFFSET..Self::ACCEPTED_AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let epoch = u64::from_le_bytes(
+            src[Self::EPOCH_OFFSET..Self::EPOCH_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let epoch_volume = u64::from_le_bytes(
+            src[Self::EPOCH_VOLUME_OFFSET..Self::EPOCH_VOLUME_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        Ok(Self {
+            accepted_transfers,
+            accepted_amount,
+            epoch,
+            epoch_volume,
+        })
+    }
+
+    /// Amount already moved within `epoch`
+    pub fn volume_in_epoch(&self, epoch: u64) -> u64 {
+        if self.epoch == epoch {
+            self.epoch_volume
+        } else {
+            0
+        }
+    }
+
+    /// Count a transfer accepted within `epoch`, restarting the epoch volume
+    /// once the epoch has changed
+    pub fn record(&mut self, amount: u64, epoch: u64) {
+        self.accepted_transfers = self.accepted_transfers.saturating_add(1);
+        self.accepted_amount = self.accepted_amount.saturating_add(amount);
+        self.epoch_volume = self.volume_in_epoch(epoch).saturating_add(amount);
+        self.epoch = epoch;
+    }
+}
+
+/// Mint stats copied into the stats history, along with the time of the copy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// Mint stats at the time of the snapshot
+    pub stats: MintStats,
+    /// Slot at which the snapshot was taken
+    pub slot: u64,
+    /// Unix timestamp at which the snapshot was taken
+    pub unix_timestamp: i64,
+}
+
+/// Ring buffer of the snapshots of a mint's stats, stored in the
+/// stats-history PDA.
+///
+/// Layout: total number of appended snapshots (u64), followed by `CAPACITY`
+/// entries of `(mint stats, slot, unix timestamp)`. Once full, the oldest
+/// snapshot is overwritten.
+pub struct StatsHistory;
+
+impl StatsHistory {
+    /// Number of snapshots kept in the history
+    pub const CAPACITY: usize = 32;
+    /// Size of a single entry: MintStats (32) + u64 (8) + i64 (8)
+    pub const ENTRY_LEN: usize = MintStats::LEN + 8 + 8;
+    /// Size of the stats history account data
+    pub const LEN: usize = 8 + Self::CAPACITY * Self::ENTRY_LEN;
+
+    // Offsets
+    const TOTAL_OFFSET: usize = 0;
+    const ENTRIES_OFFSET: usize = 8;
+    const SLOT_OFFSET: usize = MintStats::LEN;
+    const UNIX_TIMESTAMP_OFFSET: usize = Self::SLOT_OFFSET + 8;
+
+    fn total(data: &[u8]) -> Result<u64, ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(u64::from_le_bytes(
+            data[Self::TOTAL_OFFSET..Self::TOTAL_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ))
+    }
+
+    fn entry_offset(position: u64) -> usize {
+        Self::ENTRIES_OFFSET + (position % Self::CAPACITY as u64) as usize * Self::ENTRY_LEN
+    }
+
+    /// Append a snapshot, overwriting the oldest one once the history is full
+    pub fn append(data: &mut [u8], snapshot: &StatsSnapshot) -> Result<(), ProgramError> {
+        let total = Self::total(data)?;
+        let entry = &mut data[Self::entry_offset(total)..][..Self::ENTRY_LEN];
+        snapshot.stats.pack(entry);
+        entry[Self::SLOT_OFFSET..Self::SLOT_OFFSET + 8]
+            .copy_from_slice(&snapshot.slot.to_le_bytes());
+        entry[Self::UNIX_TIMESTAMP_OFFSET..Self::UNIX_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&snapshot.unix_timestamp.to_le_bytes());
+        data[Self::TOTAL_OFFSET..Self::TOTAL_OFFSET + 8]
+            .copy_from_slice(&total.saturating_add(1).to_le_bytes());
+        Ok(())
+    }
+
+    /// Unpack the stored snapshots, oldest first
+    pub fn unpack(data: &[u8]) -> Result<Vec<StatsSnapshot>, ProgramError> {
+        let total = Self::total(data)?;
+        let len = total.min(Self::CAPACITY as u64);
+        (total - len..total)
+            .map(|position| {
+                let entry = &data[Self::entry_offset(position)..][..Self::ENTRY_LEN];
+                let stats = MintStats::unpack(entry)?;
+                let slot = u64::from_le_bytes(
+                    entry[Self::SLOT_OFFSET..Self::SLOT_OFFSET + 8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                );
+                let unix_timestamp = i64::from_le_bytes(
+                    entry[Self::UNIX_TIMESTAMP_OFFSET..Self::UNIX_TIMESTAMP_OFFSET + 8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                );
+                Ok(StatsSnapshot {
+                    stats,
+                    slot,
+                    unix_timestamp,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Authorization of a transfer by the ticket signer of the mint config,
+/// signed off-chain and verified by the ed25519 program.
+///
+/// The signed message is the packed ticket: source owner and mint (32 bytes
+/// each), amount and nonce (little-endian u64s). The mint keeps a ticket from
+/// being replayed on another mint, whose transfer accounts track their own
+/// nonces with `per-mint-tracking`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferTicket {
+    /// Owner of the source token account
+    pub source_owner: Pubkey,
+    /// Mint of the transfer
+    pub mint: Pubkey,
+    /// Amount of the transfer
+    pub amount: u64,
+    /// Nonce above the last one used by the owner
+    pub nonce: u64,
+}
+
+impl TransferTicket {
+    /// Size of the signed message
+    pub const LEN: usize = Self::NONCE_OFFSET + 8;
+
+    // Offsets
+    const SOURCE_OWNER_OFFSET: usize = 0;
+    const MINT_OFFSET: usize = 32;
+    const AMOUNT_OFFSET: usize = Self::MINT_OFFSET + 32;
+    const NONCE_OFFSET: usize = Self::AMOUNT_OFFSET + 8;
+
+    /// Pack the ticket into the message to sign
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut dst = [0; Self::LEN];
+        dst[Self::SOURCE_OWNER_OFFSET..Self::SOURCE_OWNER_OFFSET + 32]
+            .copy_from_slice(self.source_owner.as_ref());
+        dst[Self::MINT_OFFSET..Self::MINT_OFFSET + 32].copy_from_slice(self.mint.as_ref());
+        dst[Self::AMOUNT_OFFSET..Self::AMOUNT_OFFSET + 8]
+            .copy_from_slice(&self.amount.to_le_bytes());
+        dst[Self::NONCE_OFFSET..Self::NONCE_OFFSET + 8].copy_from_slice(&self.nonce.to_le_bytes());
+        dst
+    }
+
+    /// Unpack the ticket from a signed message, which must be exactly
+    /// `LEN` bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let source_owner =
+            Pubkey::try_from(&src[Self::SOURCE_OWNER_OFFSET..Self::SOURCE_OWNER_OFFSET + 32])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        let mint = Pubkey::try_from(&src[Self::MINT_OFFSET..Self::MINT_OFFSET + 32])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let amount = u64::from_le_bytes(
+            src[Self::AMOUNT_OFFSET..Self::AMOUNT_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        let nonce = u64::from_le_bytes(
+            src[Self::NONCE_OFFSET..Self::NONCE_OFFSET + 8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        Ok(Self {
+            source_owner,
+            mint,
+            amount,
+            nonce,
+        })
+    }
+}
+
+/// Fees accrued to a category, stored in a PDA derived from the mint and the
+/// category. The hook can't move tokens, so the ledger only records what the
+/// issuer is owed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeLedger {
+    /// Total fees accrued
+    pub accrued: u64,
+}
+
+impl FeeLedger {
+    /// Size of the fee ledger account data
+    pub const LEN: usize = 8;
+
+    /// Pack the ledger into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[..8].copy_from_slice(&self.accrued.to_le_bytes());
+    }
+
+    /// Unpack the ledger from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let accrued = src
+            .get(..8)
+            .and_then(|accrued| accrued.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Self { accrued })
+    }
+}
+
+/// Burn owed by an owner, stored in a PDA derived from the mint and the owner.
+/// The hook can't burn the transferred tokens, so `Execute` records the burn
+/// and `SettleBurn` executes it on behalf of the owner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BurnLedger {
+    /// Amount owed to burn
+    pub owed: u64,
+}
+
+impl BurnLedger {
+    /// Size of the burn ledger account data
+    pub const LEN: usize = 8;
+
+    /// Pack the ledger into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[..8].copy_from_slice(&self.owed.to_le_bytes());
+    }
+
+    /// Unpack the ledger from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let owed = src
+            .get(..8)
+            .and_then(|owed| owed.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Self { owed })
+    }
+}
+
+/// Number of transfer accounts a payer created, stored in a PDA derived from
+/// the mint and the payer and capped by the mint config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CreationCount {
+    /// Transfer accounts created
+    pub created: u32,
+}
+
+impl CreationCount {
+    /// Size of the creation count account data
+    pub const LEN: usize = 4;
+
+    /// Pack the count into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[..4].copy_from_slice(&self.created.to_le_bytes());
+    }
+
+    /// Unpack the count from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let created = src
+            .get(..4)
+            .and_then(|created| created.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(Self { created })
+    }
+}
+
+/// Vesting schedule of an owner, stored in a PDA derived from the mint and the
+/// owner and set by the config authority. The amount vests linearly from the
+/// start over the duration, nothing being vested before the cliff.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VestingSchedule {
+    /// Amount vested over the whole schedule
+    pub total: u64,
+    /// Unix timestamp from which the amount vests
+    pub start_timestamp: i64,
+    /// Unix timestamp before which nothing is vested
+    pub cliff_timestamp: i64,
+    /// Seconds over which the amount vests from the start
+    pub duration_seconds: u64,
+}
+
+impl VestingSchedule {
+    /// Size of the vesting schedule account data
+    pub const LEN: usize = 32;
+
+    /// Pack the schedule into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[..8].copy_from_slice(&self.total.to_le_bytes());
+        dst[8..16].copy_from_slice(&self.start_timestamp.to_le_bytes());
+        dst[16..24].copy_from_slice(&self.cliff_timestamp.to_le_bytes());
+        dst[24..32].copy_from_slice(&self.duration_seconds.to_le_bytes());
+    }
+
+    /// Unpack the schedule from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let word = |offset: usize| -> Result<[u8; 8], ProgramError> {
+            src.get(offset..offset + 8)
+                .and_then(|word| word.try_into().ok())
+                .ok_or(ProgramError::InvalidAccountData)
+        };
+        Ok(Self {
+            total: u64::from_le_bytes(word(0)?),
+            start_timestamp: i64::from_le_bytes(word(8)?),
+            cliff_timestamp: i64::from_le_bytes(word(16)?),
+            duration_seconds: u64::from_le_bytes(word(24)?),
+        })
+    }
+
+    /// The amount vested at the given time, all of it once the duration has
+    /// elapsed
+    pub fn vested_amount(&self, unix_timestamp: i64) -> u64 {
+        if unix_timestamp < self.cliff_timestamp {
+            return 0;
+        }
+        let elapsed =
+            u64::try_from(unix_timestamp.saturating_sub(self.start_timestamp)).unwrap_or_default();
+        if elapsed >= self.duration_seconds {
+            return self.total;
+        }
+        // below the total, as elapsed is below the duration
+        (u128::from(self.total) * u128::from(elapsed) / u128::from(self.duration_seconds)) as u64
+    }
+
+    /// The amount still locked at the given time
+    pub fn locked_amount(&self, unix_timestamp: i64) -> u64 {
+        self.total - self.vested_amount(unix_timestamp)
+    }
+}
+
+/// Incoming transfer policy of an owner, stored in a PDA derived from the mint
+/// and the owner
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IncomingPolicy {
+    /// Whether transfers to the owner are rejected, unless the transfer
+    /// authority is the mint authority
+    pub reject_incoming: bool,
+}
+
+impl IncomingPolicy {
+    /// Size of the incoming policy account data
+    pub const LEN: usize = 1;
+
+    /// Pack the policy into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[0] = self.reject_incoming.into();
+    }
+
+    /// Unpack the policy from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        match src.first() {
+            Some(0) => Ok(Self {
+                reject_incoming: false,
+            }),
+            Some(1) => Ok(Self {
+                reject_incoming: true,
+            }),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Receive-only flag of an owner, stored in a PDA derived from the mint and
+/// the owner. Set by the config authority, it turns the owner's accounts
+/// into lockboxes that can receive but not send.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReceiveOnly {
+    /// Whether transfers from the owner are rejected
+    pub receive_only: bool,
+}
+
+impl ReceiveOnly {
+    /// Size of the receive-only flag account data
+    pub const LEN: usize = 1;
+
+    /// Pack the flag into bytes
+    pub fn pack(&self, dst: &mut [u8]) {
+        dst[0] = self.receive_only.into();
+    }
+
+    /// Unpack the flag from bytes
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        match src.first() {
+            Some(byte) => Ok(Self {
+                receive_only: unpack_bool(*byte)?,
+            }),
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// List of owners stored in a mint-level PDA, used for the blocklist, whose
+/// owners have their transfers rejected, and for the exempt owners, whose
+/// transfers aren't tracked.
+///
+/// Layout: number of owners (u32), followed by up to `CAPACITY` owners.
+pub struct OwnerList;
+
+impl OwnerList {
+    /// Maximum number of owners in the list
+    pub const CAPACITY: usize = 64;
+    /// Size of the owner list account data
+    pub const LEN: usize = 4 + Self::CAPACITY * 32;
+
+    // Offsets
+    const COUNT_OFFSET: usize = 0;
+    const OWNERS_OFFSET: usize = 4;
+
+    fn count(data: &[u8]) -> Result<usize, ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let count = u32::from_le_bytes(
+            data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ) as usize;
+        if count > Self::CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(count)
+    }
+
+    /// Unpack the owners in the list
+    pub fn unpack(data: &[u8]) -> Result<Vec<Pubkey>, ProgramError> {
+        let count = Self::count(data)?;
+        data[Self::OWNERS_OFFSET..Self::OWNERS_OFFSET + count * 32]
+            .chunks_exact(32)
+            .map(|owner| Pubkey::try_from(owner).map_err(|_| ProgramError::InvalidAccountData))
+            .collect()
+    }
+
+    /// Check if the owner is in the list
+    pub fn contains(data: &[u8], owner: &Pubkey) -> Result<bool, ProgramError> {
+        let count = Self::count(data)?;
+        Ok(data[Self::OWNERS_OFFSET..Self::OWNERS_OFFSET + count * 32]
+            .chunks_exact(32)
+            .any(|entry| entry == owner.as_ref()))
+    }
+
+    /// Add an owner to the list, doing nothing if it's already there
+    pub fn add(data: &mut [u8], owner: &Pubkey) -> Result<(), ProgramError> {
+        if Self::contains(data, owner)? {
+            return Ok(());
+        }
+        let count = Self::count(data)?;
+        if count == Self::CAPACITY {
+            return Err(ExampleError::OwnerListFull.into());
+        }
+        let offset = Self::OWNERS_OFFSET + count * 32;
+        data[offset..offset + 32].copy_from_slice(owner.as_ref());
+        data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+            .copy_from_slice(&(count as u32 + 1).to_le_bytes());
+        Ok(())
+    }
+
+    /// Remove an owner from the list, doing nothing if it isn't there. The
+    /// last owner takes the place of the removed one.
+    pub fn remove(data: &mut [u8], owner: &Pubkey) -> Result<(), ProgramError> {
+        let count = Self::count(data)?;
+        let Some(index) = data[Self::OWNERS_OFFSET..Self::OWNERS_OFFSET + count * 32]
+            .chunks_exact(32)
+            .position(|entry| entry == owner.as_ref())
+        else {
+            return Ok(());
+        };
+        let last = count - 1;
+        let offset = Self::OWNERS_OFFSET + index * 32;
+        let last_offset = Self::OWNERS_OFFSET + last * 32;
+        data.copy_within(last_offset..last_offset + 32, offset);
+        data[last_offset..last_offset + 32].fill(0);
+        data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+            .copy_from_slice(&(last as u32).to_le_bytes());
+        Ok(())
+    }
+}
+
+/// Page of the allowlist of a mint, holding the allowed owners falling on it,
+/// each with the unix timestamp its entry expires at, or 0 if it never
+/// expires.
+///
+/// Layout: number of entries (u32), followed by up to `CAPACITY` entries of
+/// an owner (32 bytes) and an expiry (i64).
+pub struct AllowlistPage;
+
+impl AllowlistPage {
+    /// Maximum number of entries on the page
+    pub const CAPACITY: usize = 64;
+    /// Size of a single entry: Pubkey (32) + i64 (8)
+    const ENTRY_LEN: usize = 32 + 8;
+    /// Size of the allowlist page account data
+    pub const LEN: usize = 4 + Self::CAPACITY * Self::ENTRY_LEN;
+
+    // Offsets
+    const COUNT_OFFSET: usize = 0;
+    const ENTRIES_OFFSET: usize = 4;
+
+    fn entries(data: &[u8]) -> Result<&[u8], ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let count = u32::from_le_bytes(
+            data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        ) as usize;
+        if count > Self::CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(&data[Self::ENTRIES_OFFSET..Self::ENTRIES_OFFSET + count * Self::ENTRY_LEN])
+    }
+
+    fn position(data: &[u8], owner: &Pubkey) -> Result<Option<usize>, ProgramError> {
+        Ok(Self::entries(data)?
+            .chunks_exact(Self::ENTRY_LEN)
+            .position(|entry| &entry[..32] == owner.as_ref()))
+    }
+
+    /// Unpack the entries on the page, as owners along with their expiry
+    pub fn unpack(data: &[u8]) -> Result<Vec<(Pubkey, i64)>, ProgramError> {
+        Self::entries(data)?
+            .chunks_exact(Self::ENTRY_LEN)
+            .map(|entry| {
+                let owner =
+                    Pubkey::try_from(&entry[..32]).map_err(|_| ProgramError::InvalidAccountData)?;
+                let expires_at = i64::from_le_bytes(
+                    entry[32..]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?,
+                );
+                Ok((owner, expires_at))
+            })
+            .collect()
+    }
+
+    /// Check if the owner has an entry on the page which hasn't expired at
+    /// `unix_timestamp`. Expired entries are treated as absent.
+    pub fn is_allowed(
+        data: &[u8],
+        owner: &Pubkey,
+        unix_timestamp: i64,
+    ) -> Result<bool, ProgramError> {
+        Ok(Self::unpack(data)?.iter().any(|(entry, expires_at)| {
+            entry == owner && (*expires_at == 0 || unix_timestamp < *expires_at)
+        }))
+    }
+
+    /// Add an owner to the page, expiring at `expires_at`, or update the
+    /// expiry if the owner is already there
+    pub fn add(data: &mut [u8], owner: &Pubkey, expires_at: i64) -> Result<(), ProgramError> {
+        let count = Self::entries(data)?.len() / Self::ENTRY_LEN;
+        let index = match Self::position(data, owner)? {
+            Some(index) => index,
+            None if count == Self::CAPACITY => return Err(ExampleError::OwnerListFull.into()),
+            None => {
+                data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+                    .copy_from_slice(&(count as u32 + 1).to_le_bytes());
+                count
+            }
+        };
+        let offset = Self::ENTRIES_OFFSET + index * Self::ENTRY_LEN;
+        data[offset..offset + 32].copy_from_slice(owner.as_ref());
+        data[offset + 32..offset + Self::ENTRY_LEN].copy_from_slice(&expires_at.to_le_bytes());
+        Ok(())
+    }
+
+    /// Remove an owner from the page, doing nothing if it isn't there. The
+    /// last entry takes the place of the removed one.
+    pub fn remove(data: &mut [u8], owner: &Pubkey) -> Result<(), ProgramError> {
+        let count = Self::entries(data)?.len() / Self::ENTRY_LEN;
+        let Some(index) = Self::position(data, owner)? else {
+            return Ok(());
+        };
+        let last = count - 1;
+        let offset = Self::ENTRIES_OFFSET + index * Self::ENTRY_LEN;
+        let last_offset = Self::ENTRIES_OFFSET + last * Self::ENTRY_LEN;
+        data.copy_within(last_offset..last_offset + Self::ENTRY_LEN, offset);
+        data[last_offset..last_offset + Self::ENTRY_LEN].fill(0);
+        data[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 4]
+            .copy_from_slice(&(last as u32).to_le_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_transfer_log_wraps_around() {
+        let mut data = vec![0; RecentTransferLog::LEN];
+        assert_eq!(RecentTransferLog::unpack(&data).unwrap(), vec![]);
+
+        let owner = Pubkey::new_unique();
+        let count = RecentTransferLog::CAPACITY as u64 + 3;
+        for i in 0..count {
+            RecentTransferLog::append(&mut data, &owner, i * 10, i).unwrap();
+        }
+
+        let entries = RecentTransferLog::unpack(&data).unwrap();
+        assert_eq!(entries.len(), RecentTransferLog::CAPACITY);
+        let expected = (3..count).map(|i| (owner, i * 10, i)).collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn recent_transfer_log_rejects_short_data() {
+        let mut data = vec![0; RecentTransferLog::LEN - 1];
+        assert_eq!(
+            RecentTransferLog::append(&mut data, &Pubkey::new_unique(), 1, 1),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn transfer_root_matches_tree_of_leaves() {
+        use spl_transfer_tracking_interface::{merkle, solana_pubkey::Pubkey as InterfacePubkey};
+
+        let mut data = vec![0; TransferRoot::LEN];
+        TransferRoot::init(&mut data).unwrap();
+        assert_eq!(
+            TransferRoot::unpack(&data).unwrap(),
+            (0, merkle::transfer_root(&[]))
+        );
+
+        let owner = InterfacePubkey::new_unique();
+        let mut leaves = vec![];
+        for i in 0..9 {
+            let leaf = merkle::transfer_leaf(&owner, i * 10, i);
+            leaves.push(leaf);
+            assert_eq!(TransferRoot::append(&mut data, &leaf).unwrap(), i);
+            assert_eq!(
+                TransferRoot::unpack(&data).unwrap(),
+                (i + 1, merkle::transfer_root(&leaves))
+            );
+        }
+
+        // the frontier can't hold the leaf filling the tree
+        data[..8].copy_from_slice(&((1u64 << TransferRoot::DEPTH) - 1).to_le_bytes());
+        assert_eq!(
+            TransferRoot::append(&mut data, &leaves[0]),
+            Err(ExampleError::TransferRootFull.into())
+        );
+    }
+
+    #[test]
+    fn mint_stats_record() {
+        let mut data = vec![0; MintStats::LEN];
+        let mut stats = MintStats::unpack(&data).unwrap();
+        assert_eq!(stats, MintStats::default());
+
+        stats.record(5, 1);
+        stats.record(u64::MAX, 1);
+        stats.pack(&mut data);
+        assert_eq!(
+            MintStats::unpack(&data).unwrap(),
+            MintStats {
+                accepted_transfers: 2,
+                accepted_amount: u64::MAX,
+                epoch: 1,
+                epoch_volume: u64::MAX,
+            }
+        );
+
+        // the epoch volume restarts with the epoch
+        stats.record(3, 2);
+        assert_eq!(stats.epoch, 2);
+        assert_eq!(stats.epoch_volume, 3);
+        assert_eq!(stats.volume_in_epoch(2), 3);
+        assert_eq!(stats.volume_in_epoch(3), 0);
+        assert_eq!(
+            MintStats::unpack(&data[..MintStats::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn stats_history_wraps_around() {
+        let mut data = vec![0; StatsHistory::LEN];
+        assert_eq!(StatsHistory::unpack(&data).unwrap(), vec![]);
+
+        let snapshot = |i: u64| StatsSnapshot {
+            stats: MintStats {
+                accepted_transfers: i,
+                accepted_amount: i * 10,
+                epoch: i / 4,
+                epoch_volume: i,
+            },
+            slot: i + 1,
+            unix_timestamp: -(i as i64),
+        };
+        let count = StatsHistory::CAPACITY as u64 + 3;
+        for i in 0..count {
+            StatsHistory::append(&mut data, &snapshot(i)).unwrap();
+        }
+
+        let expected = (3..count).map(snapshot).collect::<Vec<_>>();
+        assert_eq!(StatsHistory::unpack(&data).unwrap(), expected);
+        assert_eq!(
+            StatsHistory::append(&mut data[..StatsHistory::LEN - 1], &snapshot(0)),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn incoming_policy_round_trip() {
+        for reject_incoming in [false, true] {
+            let policy = IncomingPolicy { reject_incoming };
+            let mut data = vec![0; IncomingPolicy::LEN];
+            policy.pack(&mut data);
+            assert_eq!(IncomingPolicy::unpack(&data).unwrap(), policy);
+        }
+        assert_eq!(
+            IncomingPolicy::unpack(&[2]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            IncomingPolicy::unpack(&[]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn vesting_schedule_vests_linearly() {
+        let schedule = VestingSchedule {
+            total: 1_000,
+            start_timestamp: 1_000,
+            cliff_timestamp: 1_250,
+            duration_seconds: 1_000,
+        };
+        let mut data = vec![0; VestingSchedule::LEN];
+        schedule.pack(&mut data);
+        assert_eq!(VestingSchedule::unpack(&data).unwrap(), schedule);
+        assert_eq!(
+            VestingSchedule::unpack(&data[..VestingSchedule::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        for (unix_timestamp, vested) in [
+            (0, 0),
+            (1_249, 0),
+            (1_250, 250),
+            (1_333, 333),
+            (1_999, 999),
+            (2_000, 1_000),
+            (i64::MAX, 1_000),
+        ] {
+            assert_eq!(schedule.vested_amount(unix_timestamp), vested);
+            assert_eq!(schedule.locked_amount(unix_timestamp), 1_000 - vested);
+        }
+        // no overflow in the multiply with huge amounts
+        let schedule = VestingSchedule {
+            total: u64::MAX,
+            duration_seconds: u64::MAX,
+            ..VestingSchedule::default()
+        };
+        assert_eq!(schedule.vested_amount(i64::MAX), u64::MAX / 2);
+    }
+
+    #[test]
+    fn mint_config_round_trip() {
+        let config = MintConfig {
+            authority: Pubkey::new_unique(),
+            max_pct_bps: 1_000,
+            cooldown_seconds: 60,
+            window_slots: 150,
+            max_amount_per_window_slots: 1_000_000,
+            zero_amount_mode: ZeroAmountMode::Reject,
+            notify_program: Pubkey::new_unique(),
+            limit_exempt_amount: 10,
+            signer_threshold: 2,
+            signers: [
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::default(),
+                Pubkey::default(),
+            ],
+            fee_bps: 30,
+            fee_splits: [
+                FeeSplit {
+                    category: 7,
+                    bps: 10,
+                },
+                FeeSplit {
+                    category: 1,
+                    bps: 20,
+                },
+                FeeSplit::default(),
+                FeeSplit::default(),
+            ],
+            source_transferring_only: true,
+            skip_same_owner_transfers: true,
+            check_amount_consistency: true,
+            holding_period_seconds: 3_600,
+            enforce_allowances: true,
+            require_active_mint: true,
+            burn_bps: 50,
+            access_mode: AccessMode::Allowlist,
+            reject_program_destinations: true,
+            max_epoch_volume: 1_000_000,
+            skip_transfer_tracking: true,
+            ticket_signer: Pubkey::new_unique(),
+            max_transfers_per_day: 20,
+            require_owner_signer: true,
+            max_received_amount: 5_000,
+            require_rent_exempt_destination: true,
+            owner_checked_metas: 0b101,
+            distributor: Pubkey::new_unique(),
+            reject_delegate_transfers: true,
+            pause_effective_slot: 1_000,
+            pause_reason: [b'x'; MintConfig::PAUSE_REASON_LEN],
+            report_allowed_maximum: true,
+            required_destination_extension: ExtensionType::MemoTransfer.into(),
+            extra_account_metas_version: 1,
+            max_transfer_accounts_per_payer: 10,
+            fee_rounding: RoundingMode::Round,
+            trusted_callers: [
+                Pubkey::new_unique(),
+                Pubkey::default(),
+                Pubkey::new_unique(),
+                Pubkey::default(),
+            ],
+            min_account_age_slots: 150,
+            enforce_decimals: true,
+            expected_decimals: 9,
+            aggregate_partial_fills: true,
+            track_net_of_transfer_fee: true,
+            require_setup: true,
+            setup_complete: true,
+        };
+        let mut data = vec![0; MintConfig::LEN];
+        config.pack(&mut data);
+        assert_eq!(MintConfig::unpack(&data).unwrap(), config);
+
+        data[MintConfig::ZERO_AMOUNT_MODE_OFFSET] = 3;
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        config.pack(&mut data);
+        data[MintConfig::ACCESS_MODE_OFFSET] = 3;
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        config.pack(&mut data);
+        data[MintConfig::FEE_ROUNDING_OFFSET] = 3;
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        config.pack(&mut data);
+        data[MintConfig::SOURCE_TRANSFERRING_ONLY_OFFSET] = 2;
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        config.pack(&mut data);
+        data[0] = MintConfig::VERSION + 1;
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn mint_config_unpack_outdated() {
+        let v1_config = MintConfig {
+            authority: Pubkey::new_unique(),
+            max_pct_bps: 1_000,
+            cooldown_seconds: 60,
+            window_slots: 150,
+            max_amount_per_window_slots: 1_000_000,
+            zero_amount_mode: ZeroAmountMode::Reject,
+            notify_program: Pubkey::new_unique(),
+            limit_exempt_amount: 10,
+            ..MintConfig::default()
+        };
+        let mut data = vec![0; MintConfig::LEN];
+        MintConfig {
+            signer_threshold: 1,
+            fee_bps: 30,
+            source_transferring_only: true,
+            holding_period_seconds: 3_600,
+            access_mode: AccessMode::Allowlist,
+            trusted_callers: [Pubkey::new_unique(); MintConfig::MAX_TRUSTED_CALLERS],
+            require_setup: true,
+            setup_complete: true,
+            ..v1_config
+        }
+        .pack(&mut data);
+        assert_eq!(
+            MintConfig::unpack_outdated(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // the fields appended since version 1 are dropped from its layout
+        let len = MintConfig::layout_len(1).unwrap();
+        data[MintConfig::VERSION_OFFSET] = 1;
+        data.truncate(len);
+        assert_eq!(MintConfig::unpack_outdated(&data).unwrap(), v1_config);
+        assert_eq!(
+            MintConfig::unpack(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            MintConfig::unpack_outdated(&data[..len - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        assert_eq!(MintConfig::layout_len(0), None);
+        assert_eq!(
+            MintConfig::layout_len(MintConfig::VERSION),
+            Some(MintConfig::LEN)
+        );
+        assert_eq!(MintConfig::layout_len(MintConfig::VERSION + 1), None);
+        assert_eq!(
+            MintConfig::unpack_outdated(&[]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn mint_config_matches_borsh_layout() {
+        use spl_transfer_tracking_interface::{
+            solana_pubkey::Pubkey as InterfacePubkey,
+            state::{
+                decode_program_account, AllowlistEntry, FeeSplit as BorshFeeSplit,
+                MintConfig as BorshMintConfig, ProgramAccount,
+                TransferRoot as InterfaceTransferRoot,
+            },
+        };
+
+        let interface_pubkey = |pubkey: &Pubkey| InterfacePubkey::new_from_array(pubkey.to_bytes());
+        let config = MintConfig {
+            authority: Pubkey::new_unique(),
+            max_pct_bps: 1_000,
+            zero_amount_mode: ZeroAmountMode::SkipTracking,
+            signer_threshold: 1,
+            signers: [Pubkey::new_unique(); MintConfig::MAX_SIGNERS],
+            fee_bps: 30,
+            fee_splits: [FeeSplit {
+                category: 7,
+                bps: 30,
+            }; MintConfig::MAX_FEE_SPLITS],
+            check_amount_consistency: true,
+            holding_period_seconds: 3_600,
+            enforce_allowances: true,
+            require_active_mint: true,
+            burn_bps: 50,
+            access_mode: AccessMode::Allowlist,
+            reject_program_destinations: true,
+            max_epoch_volume: 1_000_000,
+            skip_transfer_tracking: true,
+            ticket_signer: Pubkey::new_unique(),
+            max_transfers_per_day: 20,
+            require_owner_signer: true,
+            max_received_amount: 5_000,
+            require_rent_exempt_destination: true,
+            owner_checked_metas: 0b101,
+            distributor: Pubkey::new_unique(),
+            reject_delegate_transfers: true,
+            pause_effective_slot: 1_000,
+            pause_reason: [b'x'; MintConfig::PAUSE_REASON_LEN],
+            report_allowed_maximum: true,
+            required_destination_extension: ExtensionType::MemoTransfer.into(),
+            extra_account_metas_version: 1,
+            max_transfer_accounts_per_payer: 10,
+            fee_rounding: RoundingMode::Ceil,
+            trusted_callers: [Pubkey::new_unique(); MintConfig::MAX_TRUSTED_CALLERS],
+            min_account_age_slots: 150,
+            enforce_decimals: true,
+            expected_decimals: 9,
+            aggregate_partial_fills: true,
+            track_net_of_transfer_fee: true,
+            require_setup: true,
+            setup_complete: true,
+            ..MintConfig::default()
+        };
+        let mut data = vec![0; MintConfig::LEN];
+        config.pack(&mut data);
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::MintConfig(Box::new(BorshMintConfig {
+                version: MintConfig::VERSION,
+                authority: interface_pubkey(&config.authority),
+                max_pct_bps: config.max_pct_bps,
+                cooldown_seconds: config.cooldown_seconds,
+                window_slots: config.window_slots,
+                max_amount_per_window_slots: config.max_amount_per_window_slots,
+                zero_amount_mode: config.zero_amount_mode as u8,
+                notify_program: interface_pubkey(&config.notify_program),
+                limit_exempt_amount: config.limit_exempt_amount,
+                signer_threshold: config.signer_threshold,
+                signers: config.signers.map(|signer| interface_pubkey(&signer)),
+                fee_bps: config.fee_bps,
+                fee_splits: config.fee_splits.map(|split| BorshFeeSplit {
+                    category: split.category,
+                    bps: split.bps,
+                }),
+                source_transferring_only: config.source_transferring_only,
+                skip_same_owner_transfers: config.skip_same_owner_transfers,
+                check_amount_consistency: config.check_amount_consistency,
+                holding_period_seconds: config.holding_period_seconds,
+                enforce_allowances: config.enforce_allowances,
+                require_active_mint: config.require_active_mint,
+                burn_bps: config.burn_bps,
+                access_mode: config.access_mode as u8,
+                reject_program_destinations: config.reject_program_destinations,
+                max_epoch_volume: config.max_epoch_volume,
+                skip_transfer_tracking: config.skip_transfer_tracking,
+                ticket_signer: interface_pubkey(&config.ticket_signer),
+                max_transfers_per_day: config.max_transfers_per_day,
+                require_owner_signer: config.require_owner_signer,
+                max_received_amount: config.max_received_amount,
+                require_rent_exempt_destination: config.require_rent_exempt_destination,
+                owner_checked_metas: config.owner_checked_metas,
+                distributor: interface_pubkey(&config.distributor),
+                reject_delegate_transfers: config.reject_delegate_transfers,
+                pause_effective_slot: config.pause_effective_slot,
+                pause_reason: config.pause_reason,
+                report_allowed_maximum: config.report_allowed_maximum,
+                required_destination_extension: config.required_destination_extension,
+                extra_account_metas_version: config.extra_account_metas_version,
+                max_transfer_accounts_per_payer: config.max_transfer_accounts_per_payer,
+                fee_rounding: config.fee_rounding as u8,
+                trusted_callers: config
+                    .trusted_callers
+                    .map(|caller| interface_pubkey(&caller)),
+                min_account_age_slots: config.min_account_age_slots,
+                enforce_decimals: config.enforce_decimals,
+                expected_decimals: config.expected_decimals,
+                aggregate_partial_fills: config.aggregate_partial_fills,
+                track_net_of_transfer_fee: config.track_net_of_transfer_fee,
+                require_setup: config.require_setup,
+                setup_complete: config.setup_complete,
+            }))
+        );
+
+        data[0] = 1;
+        data.truncate(MintConfig::layout_len(1).unwrap());
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::OutdatedMintConfig { version: 1 }
+        );
+
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0; OwnerList::LEN];
+        OwnerList::add(&mut data, &owner).unwrap();
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::OwnerList(vec![interface_pubkey(&owner)])
+        );
+
+        let mut data = vec![0; AllowlistPage::LEN];
+        AllowlistPage::add(&mut data, &owner, 100).unwrap();
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::AllowlistPage(vec![AllowlistEntry {
+                owner: interface_pubkey(&owner),
+                expires_at: 100,
+            }])
+        );
+
+        let mut data = vec![0; TransferRoot::LEN];
+        TransferRoot::init(&mut data).unwrap();
+        TransferRoot::append(&mut data, &[1; 32]).unwrap();
+        let (count, root) = TransferRoot::unpack(&data).unwrap();
+        assert_eq!(
+            decode_program_account(&data),
+            ProgramAccount::TransferRoot(InterfaceTransferRoot { count, root })
+        );
+    }
+
+    #[test]
+    fn mint_config_check_signers() {
+        let signer = Pubkey::new_unique();
+        let mut config = MintConfig::default();
+        assert_eq!(config.check_signers(), Ok(()));
+
+        config.signer_threshold = 1;
+        assert_eq!(config.check_signers(), Err(ProgramError::InvalidArgument));
+
+        // unused entries may be anywhere
+        config.signers[3] = signer;
+        assert_eq!(config.check_signers(), Ok(()));
+        assert_eq!(config.configured_signers().collect::<Vec<_>>(), [&signer]);
+
+        config.signers[0] = signer;
+        assert_eq!(config.check_signers(), Err(ProgramError::InvalidArgument));
+    }
+
+    fn fee_config(fee_bps: u16, splits: &[(u8, u16)]) -> MintConfig {
+        let mut config = MintConfig {
+            fee_bps,
+            ..MintConfig::default()
+        };
+        for (split, (category, bps)) in config.fee_splits.iter_mut().zip(splits) {
+            *split = FeeSplit {
+                category: *category,
+                bps: *bps,
+            };
+        }
+        config
+    }
+
+    #[test]
+    fn burn_amount_rounds_down() {
+        let config = MintConfig {
+            burn_bps: 250,
+            ..MintConfig::default()
+        };
+        assert_eq!(config.check(), Ok(()));
+        assert_eq!(config.burn_amount(1_000), 25);
+        assert_eq!(config.burn_amount(39), 0);
+        assert_eq!(config.burn_amount(u64::MAX), u64::MAX / 40);
+        assert_eq!(MintConfig::default().burn_amount(u64::MAX), 0);
+
+        let config = MintConfig {
+            burn_bps: 10_001,
+            ..MintConfig::default()
+        };
+        assert_eq!(config.check(), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn transfer_ticket_round_trip() {
+        let ticket = TransferTicket {
+            source_owner: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount: 42,
+            nonce: 7,
+        };
+        let message = ticket.pack();
+        assert_eq!(TransferTicket::unpack(&message), Ok(ticket));
+        assert_eq!(
+            TransferTicket::unpack(&message[..TransferTicket::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            TransferTicket::unpack(&[&message[..], &[0]].concat()),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // tickets need the transfer account for their nonce
+        let config = MintConfig {
+            ticket_signer: Pubkey::new_unique(),
+            ..MintConfig::default()
+        };
+        assert_eq!(config.check(), Ok(()));
+        let config = MintConfig {
+            skip_transfer_tracking: true,
+            ..config
+        };
+        assert_eq!(config.check(), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn check_required_destination_extension() {
+        for extension_type in [
+            ExtensionType::Uninitialized,
+            ExtensionType::MemoTransfer,
+            ExtensionType::CpiGuard,
+        ] {
+            let config = MintConfig {
+                required_destination_extension: extension_type.into(),
+                ..MintConfig::default()
+            };
+            assert_eq!(config.check(), Ok(()));
+        }
+        // mint extensions, or no extension at all
+        for required_destination_extension in [ExtensionType::MetadataPointer.into(), u16::MAX] {
+            let config = MintConfig {
+                required_destination_extension,
+                ..MintConfig::default()
+            };
+            assert_eq!(config.check(), Err(ProgramError::InvalidArgument));
+        }
+    }
+
+    #[test]
+    fn two_way_fee_split() {
+        let config = fee_config(100, &[(0, 75), (1, 25)]);
+        assert_eq!(config.check(), Ok(()));
+        assert_eq!(config.fee_shares(10_000), [(0, 75), (1, 25)]);
+        // fee of 1, the remainder goes to the first split
+        assert_eq!(config.fee_shares(150), [(0, 1), (1, 0)]);
+        assert_eq!(config.fee_shares(99), [(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn three_way_fee_split() {
+        let config = fee_config(300, &[(2, 100), (0, 100), (1, 100)]);
+        assert_eq!(config.check(), Ok(()));
+        assert_eq!(config.fee_shares(1_000), [(2, 10), (0, 10), (1, 10)]);
+        // fee of 32, shares of 10 each and a remainder of 2
+        assert_eq!(config.fee_shares(1_067), [(2, 12), (0, 10), (1, 10)]);
+        let fee = u128::from(u64::MAX) * 300 / 10_000;
+        let shares = config.fee_shares(u64::MAX);
+        assert_eq!(
+            shares
+                .iter()
+                .map(|(_, share)| u128::from(*share))
+                .sum::<u128>(),
+            fee
+        );
+    }
+
+    #[test]
+    fn fee_rounding_modes() {
+        let mut config = fee_config(50, &[(0, 40), (1, 10)]);
+        // fees of 3, 3.15, 3.5 and 3.51
+        for (amount, floor, ceil, round) in [
+            (600, 3, 3, 3),
+            (630, 3, 4, 3),
+            (700, 3, 4, 4),
+            (702, 3, 4, 4),
+        ] {
+            for (fee_rounding, fee) in [
+                (RoundingMode::Floor, floor),
+                (RoundingMode::Ceil, ceil),
+                (RoundingMode::Round, round),
+            ] {
+                config.fee_rounding = fee_rounding;
+                let shares = config.fee_shares(amount);
+                assert_eq!(shares.iter().map(|(_, share)| share).sum::<u64>(), fee);
+            }
+        }
+        // a fee of 3.5 rounds half up, with the shares rounded down
+        config.fee_rounding = RoundingMode::Round;
+        assert_eq!(config.fee_shares(700), [(0, 4), (1, 0)]);
+
+        // the intermediate product doesn't overflow
+        let config = MintConfig {
+            fee_rounding: RoundingMode::Ceil,
+            ..fee_config(10_000, &[(0, 10_000)])
+        };
+        assert_eq!(config.fee_shares(u64::MAX), [(0, u64::MAX)]);
+        assert_eq!(RoundingMode::Ceil.divide(1, 10_000), 1);
+        assert_eq!(RoundingMode::Round.divide(4_999, 10_000), 0);
+        assert_eq!(RoundingMode::Round.divide(5_000, 10_000), 1);
+    }
+
+    #[test]
+    fn check_fee_splits() {
+        assert_eq!(MintConfig::default().check_fee_splits(), Ok(()));
+        assert!(MintConfig::default().fee_shares(1_000).is_empty());
+        for config in [
+            fee_config(100, &[(0, 75), (1, 20)]),
+            fee_config(100, &[]),
+            fee_config(0, &[(0, 10)]),
+            fee_config(100, &[(0, 50), (0, 50)]),
+            fee_config(10_001, &[(0, 10_001)]),
+        ] {
+            assert_eq!(config.check(), Err(ProgramError::InvalidArgument));
+        }
+    }
+
+    #[test]
+    fn owner_list_add_and_contains() {
+        let mut data = vec![0; OwnerList::LEN];
+        let owner = Pubkey::new_unique();
+        assert!(!OwnerList::contains(&data, &owner).unwrap());
+
+        OwnerList::add(&mut data, &owner).unwrap();
+        OwnerList::add(&mut data, &owner).unwrap();
+        assert!(OwnerList::contains(&data, &owner).unwrap());
+        assert_eq!(OwnerList::unpack(&data).unwrap(), vec![owner]);
+
+        for _ in 1..OwnerList::CAPACITY {
+            OwnerList::add(&mut data, &Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            OwnerList::add(&mut data, &Pubkey::new_unique()),
+            Err(ExampleError::OwnerListFull.into())
+        );
+    }
+
+    #[test]
+    fn owner_list_remove() {
+        let mut data = vec![0; OwnerList::LEN];
+        let owners = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        for owner in &owners {
+            OwnerList::add(&mut data, owner).unwrap();
+        }
+
+        OwnerList::remove(&mut data, &owners[0]).unwrap();
+        assert_eq!(
+            OwnerList::unpack(&data).unwrap(),
+            vec![owners[2], owners[1]]
+        );
+        OwnerList::remove(&mut data, &owners[0]).unwrap();
+        assert_eq!(
+            OwnerList::unpack(&data).unwrap(),
+            vec![owners[2], owners[1]]
+        );
+        OwnerList::remove(&mut data, &owners[1]).unwrap();
+        assert_eq!(OwnerList::unpack(&data).unwrap(), vec![owners[2]]);
+    }
+
+    #[test]
+    fn allowlist_page_expiry() {
+        let mut data = vec![0; AllowlistPage::LEN];
+        let owners = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        AllowlistPage::add(&mut data, &owners[0], 0).unwrap();
+        AllowlistPage::add(&mut data, &owners[1], 100).unwrap();
+        assert!(AllowlistPage::is_allowed(&data, &owners[0], i64::MAX).unwrap());
+        assert!(AllowlistPage::is_allowed(&data, &owners[1], 99).unwrap());
+        assert!(!AllowlistPage::is_allowed(&data, &owners[1], 100).unwrap());
+        assert!(!AllowlistPage::is_allowed(&data, &owners[2], 0).unwrap());
+
+        // adding again updates the expiry
+        AllowlistPage::add(&mut data, &owners[1], 200).unwrap();
+        assert!(AllowlistPage::is_allowed(&data, &owners[1], 100).unwrap());
+        AllowlistPage::add(&mut data, &owners[2], 0).unwrap();
+        assert_eq!(
+            AllowlistPage::unpack(&data).unwrap(),
+            vec![(owners[0], 0), (owners[1], 200), (owners[2], 0)]
+        );
+
+        AllowlistPage::remove(&mut data, &owners[0]).unwrap();
+        assert_eq!(
+            AllowlistPage::unpack(&data).unwrap(),
+            vec![(owners[2], 0), (owners[1], 200)]
+        );
+
+        for _ in 2..AllowlistPage::CAPACITY {
+            AllowlistPage::add(&mut data, &Pubkey::new_unique(), 0).unwrap();
+        }
+        assert_eq!(
+            AllowlistPage::add(&mut data, &Pubkey::new_unique(), 0),
+            Err(ExampleError::OwnerListFull.into())
+        );
+    }
+}
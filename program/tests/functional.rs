@@ -2,16 +2,25 @@
 // CPIing into the system program
 #![cfg(feature = "forbid-additional-mints")]
 
+#[cfg(feature = "recent-log")]
+use spl_transfer_hook_example::{
+    get_recent_transfer_log_address, instruction::initialize_recent_transfer_log,
+    state::RecentTransferLog,
+};
 use {
-    solana_program_test::{processor, tokio, ProgramTest},
+    solana_program_test::{processor, read_file, tokio, ProgramTest, ProgramTestContext},
     solana_sdk::{
         account::Account as SolanaAccount,
         account_info::AccountInfo,
+        bpf_loader,
+        clock::Clock,
         entrypoint::ProgramResult,
-        instruction::{AccountMeta, InstructionError},
+        instruction::{AccountMeta, Instruction, InstructionError},
         program_error::ProgramError,
         program_option::COption,
+        program_pack::Pack,
         pubkey::Pubkey,
+        rent::Rent,
         signature::Signer,
         signer::keypair::Keypair,
         sysvar,
@@ -24,23 +33,73 @@ use {
     },
     spl_token_2022::{
         extension::{
-            transfer_hook::TransferHookAccount, BaseStateWithExtensionsMut, ExtensionType,
-            StateWithExtensionsMut,
+            memo_transfer::MemoTransfer,
+            pausable::PausableConfig,
+            permanent_delegate::PermanentDelegate,
+            transfer_fee::{TransferFee, TransferFeeConfig},
+            transfer_hook::{TransferHook, TransferHookAccount},
+            BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions, StateWithExtensionsMut,
         },
+        instruction::{self as token_instruction, TokenInstruction},
         state::{Account, AccountState, Mint},
     },
+    spl_transfer_hook_example::{
+        error::{ExampleError, FailedCheck},
+        get_allowlist_address, get_allowlist_page, get_blocklist_address, get_burn_ledger_address,
+        get_creation_count_address, get_exempt_owners_address, get_fee_ledger_address,
+        get_incoming_policy_address, get_mint_config_address, get_mint_stats_address,
+        get_receive_only_address, get_stats_history_address, get_transfer_account_address,
+        get_transfer_root_address, get_treasury_address, get_vesting_schedule_address,
+        instruction::{
+            add_allowlisted_owner, add_exempt_owner, cancel_pause, enable_trading, finalize_setup,
+            freeze_owner, get_mint_config, health_check, health_status, initialize_burn_ledger,
+            initialize_destination_account, initialize_fee_ledger, initialize_mint_config,
+            initialize_mint_stats, initialize_transfer_account,
+            initialize_transfer_account_with_payer, initialize_transfer_root, meta_list_status,
+            migrate_mint_config, migrate_transfer_account, read_totals, reassign_transfer_account,
+            reinitialize_transfer_account, remove_allowlisted_owner, remove_exempt_owner,
+            repair_extra_account_meta_list, replace_extra_account_meta_list, reset_accounts_batch,
+            schedule_pause, set_allowance, set_incoming_policy, set_label, set_receive_only,
+            set_vesting_schedule, settle_burn, setup_mint, snapshot_stats, teardown_mint,
+            update_mint_config, verify_extra_account_meta_list, with_config_signers,
+            withdraw_treasury,
+        },
+        processor::TransferAccount,
+        state::{
+            example_data, AccessMode, AllowlistPage, BurnLedger, CreationCount, FeeLedger,
+            FeeSplit, MintConfig, MintStats, OwnerList, StatsHistory, StatsSnapshot,
+            TransferTicket, VestingSchedule, ZeroAmountMode,
+        },
+    },
     spl_transfer_hook_interface::{
         error::TransferHookError,
         get_extra_account_metas_address,
         instruction::{
             execute_with_extra_account_metas, initialize_extra_account_meta_list,
-            update_extra_account_meta_list,
+            update_extra_account_meta_list, ExecuteInstruction,
         },
         onchain,
     },
+    spl_transfer_tracking_interface::error::TransferHookError as TrackingHookError,
+    std::path::{Path, PathBuf},
 };
 
 fn setup(program_id: &Pubkey) -> ProgramTest {
+    let mut program_test = setup_with_sbf_token_program(program_id);
+
+    program_test.add_program(
+        "spl_token_2022",
+        spl_token_2022::id(),
+        processor!(spl_token_2022::processor::Processor::process),
+    );
+
+    program_test
+}
+
+/// Set up the program against the SBF build of Token-2022 bundled with
+/// `ProgramTest`, since the CPIs of its builtin processor are no-ops and never
+/// reach the hook
+fn setup_with_sbf_token_program(program_id: &Pubkey) -> ProgramTest {
     let mut program_test = ProgramTest::new(
         "spl_transfer_hook_example",
         *program_id,
@@ -49,12 +108,6 @@ fn setup(program_id: &Pubkey) -> ProgramTest {
 
     program_test.prefer_bpf(false); // simplicity in the build
 
-    program_test.add_program(
-        "spl_token_2022",
-        spl_token_2022::id(),
-        processor!(spl_token_2022::processor::Processor::process),
-    );
-
     program_test
 }
 
@@ -62,6 +115,7 @@ fn setup(program_id: &Pubkey) -> ProgramTest {
 fn setup_token_accounts(
     program_test: &mut ProgramTest,
     program_id: &Pubkey,
+    hook_program_id: &Pubkey,
     mint_address: &Pubkey,
     mint_authority: &Pubkey,
     source: &Pubkey,
@@ -72,18 +126,17 @@ fn setup_token_accounts(
 ) {
     // add mint, source, and destination accounts by hand to always force
     // the "transferring" flag to true
-    let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[]).unwrap();
-    let mut mint_data = vec![0; mint_size];
-    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut mint_data).unwrap();
     let token_amount = 1_000_000_000_000;
-    state.base = Mint {
-        mint_authority: COption::Some(*mint_authority),
-        supply: token_amount,
-        decimals,
-        is_initialized: true,
-        freeze_authority: COption::None,
-    };
-    state.pack_base();
+    let mint_data = mint_data(
+        Mint {
+            mint_authority: COption::Some(*mint_authority),
+            supply: token_amount,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        hook_program_id,
+    );
     program_test.add_account(
         *mint_address,
         SolanaAccount {
@@ -94,6 +147,62 @@ fn setup_token_accounts(
         },
     );
 
+    let account_data = token_account_data(mint_address, owner, token_amount, transferring);
+
+    program_test.add_account(
+        *source,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: account_data.clone(),
+            owner: *program_id,
+            ..SolanaAccount::default()
+        },
+    );
+    program_test.add_account(
+        *destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: account_data,
+            owner: *program_id,
+            ..SolanaAccount::default()
+        },
+    );
+}
+
+/// Data of a mint whose transfer hook program is `hook_program_id`
+fn mint_data(mint: Mint, hook_program_id: &Pubkey) -> Vec<u8> {
+    mint_data_with_extensions(mint, hook_program_id, &[], |_| {})
+}
+
+/// Data of a mint whose transfer hook program is `hook_program_id`, with the
+/// other `extensions` initialized by `init_extensions`
+fn mint_data_with_extensions(
+    mint: Mint,
+    hook_program_id: &Pubkey,
+    extensions: &[ExtensionType],
+    init_extensions: impl FnOnce(&mut StateWithExtensionsMut<Mint>),
+) -> Vec<u8> {
+    let mint_size = ExtensionType::try_calculate_account_len::<Mint>(
+        &[&[ExtensionType::TransferHook], extensions].concat(),
+    )
+    .unwrap();
+    let mut mint_data = vec![0; mint_size];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut mint_data).unwrap();
+    let extension = state.init_extension::<TransferHook>(true).unwrap();
+    extension.program_id = Some(*hook_program_id).try_into().unwrap();
+    init_extensions(&mut state);
+    state.base = mint;
+    state.pack_base();
+    state.init_account_type().unwrap();
+    mint_data
+}
+
+fn token_account_data(
+    mint_address: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    transferring: bool,
+) -> Vec<u8> {
     let account_size =
         ExtensionType::try_calculate_account_len::<Account>(&[ExtensionType::TransferHookAccount])
             .unwrap();
@@ -102,11 +211,10 @@ fn setup_token_accounts(
         StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut account_data).unwrap();
     let extension = state.init_extension::<TransferHookAccount>(true).unwrap();
     extension.transferring = transferring.into();
-    let token_amount = 1_000_000_000_000;
     state.base = Account {
         mint: *mint_address,
         owner: *owner,
-        amount: token_amount,
+        amount,
         delegate: COption::None,
         state: AccountState::Initialized,
         is_native: COption::None,
@@ -115,25 +223,721 @@ fn setup_token_accounts(
     };
     state.pack_base();
     state.init_account_type().unwrap();
+    account_data
+}
 
+fn add_funded_wallet(program_test: &mut ProgramTest, wallet: &Pubkey) {
     program_test.add_account(
-        *source,
+        *wallet,
         SolanaAccount {
             lamports: 1_000_000_000,
-            data: account_data.clone(),
-            owner: *program_id,
             ..SolanaAccount::default()
         },
     );
-    program_test.add_account(
-        *destination,
-        SolanaAccount {
-            lamports: 1_000_000_000,
-            data: account_data,
-            owner: *program_id,
-            ..SolanaAccount::default()
+}
+
+/// Extra account meta resolving the source owner's transfer account
+fn transfer_account_extra_meta() -> ExtraAccountMeta {
+    let mut seeds = vec![Seed::AccountData {
+        account_index: 0,
+        data_index: 32,
+        length: 32,
+    }];
+    if cfg!(feature = "per-mint-tracking") {
+        seeds.push(Seed::AccountKey { index: 1 });
+    }
+    ExtraAccountMeta::new_with_seeds(&seeds, false, true).unwrap()
+}
+
+/// Extra account meta resolving the destination owner's transfer account
+fn destination_transfer_account_extra_meta() -> ExtraAccountMeta {
+    let mut seeds = vec![Seed::AccountData {
+        account_index: 2,
+        data_index: 32,
+        length: 32,
+    }];
+    if cfg!(feature = "per-mint-tracking") {
+        seeds.push(Seed::AccountKey { index: 1 });
+    }
+    ExtraAccountMeta::new_with_seeds(&seeds, false, true).unwrap()
+}
+
+/// Extra account meta resolving the mint config
+fn mint_config_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"mint-config".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the blocklist
+fn blocklist_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"blocklist".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the mint's exempt owners
+fn exempt_owners_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"exempt-owners".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the destination owner's incoming policy
+fn incoming_policy_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"incoming-policy".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 2,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the mint stats
+fn mint_stats_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"mint-stats".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the transfer root of the mint
+fn transfer_root_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"transfer-root".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the fee ledger of a category
+fn fee_ledger_extra_meta(category: u8) -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"fee-ledger".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::Literal {
+                bytes: vec![category],
+            },
+        ],
+        false,
+        true,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the source owner's burn ledger
+fn burn_ledger_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"burn-ledger".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        true,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the vesting schedule of the source owner
+fn vesting_schedule_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"vesting".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the source owner's receive-only flag
+fn receive_only_extra_meta() -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"receive-only".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Extra account meta resolving the allowlist page of the owner of the token
+/// account at `account_index`, from the first byte of the owner
+fn allowlist_page_extra_meta(account_index: u8) -> ExtraAccountMeta {
+    ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: b"allow".to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index,
+                data_index: 32,
+                length: 1,
+            },
+        ],
+        false,
+        false,
+    )
+    .unwrap()
+}
+
+/// Seed of the PDA of the governance program in the extra account metas
+const GOVERNANCE_SEED: &[u8] = b"governance";
+
+/// Mint whose transfers are tracked by the example program, along with the
+/// wallet's token accounts
+struct TrackedMint {
+    program_id: Pubkey,
+    mint_address: Pubkey,
+    mint_authority: Keypair,
+    wallet: Keypair,
+    source: Pubkey,
+    destination: Pubkey,
+    /// Owner of the destination token account, the wallet by default
+    destination_owner: Pubkey,
+    /// Whether the clock sysvar is part of the extra account metas
+    with_clock_sysvar: bool,
+    /// Program to notify, appended to the extra account metas
+    notify_program: Option<Pubkey>,
+    /// Categories whose fee ledgers are appended to the extra account metas
+    fee_categories: Vec<u8>,
+    /// Whether the destination owner's transfer account is part of the extra
+    /// account metas
+    with_destination_transfer_account: bool,
+    /// Whether the wallet's burn ledger is part of the extra account metas
+    with_burn_ledger: bool,
+    /// Whether the wallet's vesting schedule is part of the extra account
+    /// metas
+    with_vesting_schedule: bool,
+    /// Whether the allowlist pages of the source and destination owners are
+    /// part of the extra account metas
+    with_allowlist_pages: bool,
+    /// Unused accounts, appended to the extra account metas to grow the list
+    filler_accounts: Vec<Pubkey>,
+    /// Whether the wallet's transfer account is left out of the extra account
+    /// metas
+    without_transfer_account: bool,
+    /// Whether the instructions sysvar is part of the extra account metas
+    with_instructions_sysvar: bool,
+    /// Program appended to the extra account metas, followed by its PDA
+    /// derived from the mint
+    governance_program: Option<Pubkey>,
+    /// Whether the wallet's receive-only flag is part of the extra account
+    /// metas
+    with_receive_only: bool,
+    /// Whether the transfer root of the mint is part of the extra account
+    /// metas
+    with_transfer_root: bool,
+}
+
+/// Set up the mint and the wallet's token accounts, with the source holding
+/// `source_amount` tokens
+fn setup_tracked_mint(source_amount: u64) -> (ProgramTest, TrackedMint) {
+    let program_id = Pubkey::new_unique();
+    setup_tracked_mint_in(setup(&program_id), program_id, source_amount)
+}
+
+/// Set up the mint and the wallet's token accounts in `program_test`, where
+/// the example program is `program_id`
+fn setup_tracked_mint_in(
+    mut program_test: ProgramTest,
+    program_id: Pubkey,
+    source_amount: u64,
+) -> (ProgramTest, TrackedMint) {
+    let wallet = Keypair::new();
+    let tracked = TrackedMint {
+        program_id,
+        mint_address: spl_transfer_hook_example::mint::id(),
+        mint_authority: Keypair::new(),
+        destination_owner: wallet.pubkey(),
+        wallet,
+        source: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        with_clock_sysvar: true,
+        notify_program: None,
+        fee_categories: vec![],
+        with_destination_transfer_account: false,
+        with_burn_ledger: false,
+        with_vesting_schedule: false,
+        with_allowlist_pages: false,
+        filler_accounts: vec![],
+        without_transfer_account: false,
+        with_instructions_sysvar: false,
+        governance_program: None,
+        with_receive_only: false,
+        with_transfer_root: false,
+    };
+
+    // the mint authority is also the freeze authority
+    let mint_data = mint_data(
+        Mint {
+            mint_authority: COption::Some(tracked.mint_authority.pubkey()),
+            supply: source_amount,
+            decimals: 2,
+            is_initialized: true,
+            freeze_authority: COption::Some(tracked.mint_authority.pubkey()),
         },
+        &program_id,
+    );
+
+    let wallet = tracked.wallet.pubkey();
+    for (address, data) in [
+        (tracked.mint_address, mint_data),
+        (
+            tracked.source,
+            token_account_data(&tracked.mint_address, &wallet, source_amount, true),
+        ),
+        (
+            tracked.destination,
+            token_account_data(&tracked.mint_address, &wallet, 0, true),
+        ),
+    ] {
+        program_test.add_account(
+            address,
+            SolanaAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner: spl_token_2022::id(),
+                ..SolanaAccount::default()
+            },
+        );
+    }
+    add_funded_wallet(&mut program_test, &tracked.wallet.pubkey());
+
+    (program_test, tracked)
+}
+
+impl TrackedMint {
+    fn init_extra_account_metas(&self) -> Vec<ExtraAccountMeta> {
+        let mut metas = vec![
+            transfer_account_extra_meta(),
+            mint_config_extra_meta(),
+            blocklist_extra_meta(),
+            exempt_owners_extra_meta(),
+            incoming_policy_extra_meta(),
+            mint_stats_extra_meta(),
+        ];
+        if self.without_transfer_account {
+            metas.remove(0);
+        }
+        if self.with_clock_sysvar {
+            metas.push(
+                ExtraAccountMeta::new_with_pubkey(&sysvar::clock::id(), false, false).unwrap(),
+            );
+        }
+        if let Some(notify_program) = &self.notify_program {
+            metas.push(ExtraAccountMeta::new_with_pubkey(notify_program, false, false).unwrap());
+        }
+        for category in &self.fee_categories {
+            metas.push(fee_ledger_extra_meta(*category));
+        }
+        if self.with_destination_transfer_account {
+            metas.push(destination_transfer_account_extra_meta());
+        }
+        if self.with_burn_ledger {
+            metas.push(burn_ledger_extra_meta());
+        }
+        if self.with_vesting_schedule {
+            metas.push(vesting_schedule_extra_meta());
+        }
+        if self.with_allowlist_pages {
+            metas.push(allowlist_page_extra_meta(0));
+            metas.push(allowlist_page_extra_meta(2));
+        }
+        if self.with_instructions_sysvar {
+            metas.push(
+                ExtraAccountMeta::new_with_pubkey(&sysvar::instructions::id(), false, false)
+                    .unwrap(),
+            );
+        }
+        if self.with_receive_only {
+            metas.push(receive_only_extra_meta());
+        }
+        if self.with_transfer_root {
+            metas.push(transfer_root_extra_meta());
+        }
+        if let Some(governance_program) = &self.governance_program {
+            // the program's index among the accounts of `Execute`, after the
+            // five base accounts
+            let program_index = (metas.len() as u8).wrapping_add(5);
+            metas
+                .push(ExtraAccountMeta::new_with_pubkey(governance_program, false, false).unwrap());
+            metas.push(
+                ExtraAccountMeta::new_external_pda_with_seeds(
+                    program_index,
+                    &[
+                        Seed::Literal {
+                            bytes: GOVERNANCE_SEED.to_vec(),
+                        },
+                        Seed::AccountKey { index: 1 },
+                    ],
+                    false,
+                    false,
+                )
+                .unwrap(),
+            );
+        }
+        for filler in &self.filler_accounts {
+            metas.push(ExtraAccountMeta::new_with_pubkey(filler, false, false).unwrap());
+        }
+        metas
+    }
+
+    fn extra_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(
+                get_transfer_account_address(
+                    &self.wallet.pubkey(),
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                get_mint_config_address(&self.mint_address, &self.program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                get_blocklist_address(&self.mint_address, &self.program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                get_exempt_owners_address(&self.mint_address, &self.program_id),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                get_incoming_policy_address(
+                    &self.destination_owner,
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ),
+            AccountMeta::new(
+                get_mint_stats_address(&self.mint_address, &self.program_id),
+                false,
+            ),
+        ];
+        if self.without_transfer_account {
+            metas.remove(0);
+        }
+        if self.with_clock_sysvar {
+            metas.push(AccountMeta::new_readonly(sysvar::clock::id(), false));
+        }
+        if let Some(notify_program) = &self.notify_program {
+            metas.push(AccountMeta::new_readonly(*notify_program, false));
+        }
+        for category in &self.fee_categories {
+            metas.push(AccountMeta::new(
+                get_fee_ledger_address(&self.mint_address, *category, &self.program_id),
+                false,
+            ));
+        }
+        if self.with_destination_transfer_account {
+            metas.push(AccountMeta::new(
+                get_transfer_account_address(
+                    &self.destination_owner,
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ));
+        }
+        if self.with_burn_ledger {
+            metas.push(AccountMeta::new(
+                get_burn_ledger_address(
+                    &self.wallet.pubkey(),
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ));
+        }
+        if self.with_vesting_schedule {
+            metas.push(AccountMeta::new_readonly(
+                get_vesting_schedule_address(
+                    &self.wallet.pubkey(),
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ));
+        }
+        if self.with_allowlist_pages {
+            for owner in [self.wallet.pubkey(), self.destination_owner] {
+                metas.push(AccountMeta::new_readonly(
+                    get_allowlist_address(
+                        &self.mint_address,
+                        get_allowlist_page(&owner),
+                        &self.program_id,
+                    ),
+                    false,
+                ));
+            }
+        }
+        if self.with_instructions_sysvar {
+            metas.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+        }
+        if self.with_receive_only {
+            metas.push(AccountMeta::new_readonly(
+                get_receive_only_address(
+                    &self.wallet.pubkey(),
+                    &self.mint_address,
+                    &self.program_id,
+                ),
+                false,
+            ));
+        }
+        if self.with_transfer_root {
+            metas.push(AccountMeta::new(
+                get_transfer_root_address(&self.mint_address, &self.program_id),
+                false,
+            ));
+        }
+        if let Some(governance_program) = &self.governance_program {
+            metas.push(AccountMeta::new_readonly(*governance_program, false));
+            metas.push(AccountMeta::new_readonly(
+                self.governance_address(governance_program),
+                false,
+            ));
+        }
+        for filler in &self.filler_accounts {
+            metas.push(AccountMeta::new_readonly(*filler, false));
+        }
+        metas
+    }
+
+    /// Initialize the validation account, the wallet's transfer account and
+    /// the mint config
+    async fn initialize(&self, context: &ProgramTestContext) {
+        let extra_account_metas_address =
+            get_extra_account_metas_address(&self.mint_address, &self.program_id);
+        let init_extra_account_metas = self.init_extra_account_metas();
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let rent_lamports = rent.minimum_balance(
+            ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap(),
+        );
+        process_transaction(
+            context,
+            &[
+                system_instruction::transfer(
+                    &context.payer.pubkey(),
+                    &extra_account_metas_address,
+                    rent_lamports,
+                ),
+                initialize_extra_account_meta_list(
+                    &self.program_id,
+                    &extra_account_metas_address,
+                    &self.mint_address,
+                    &self.mint_authority.pubkey(),
+                    &init_extra_account_metas,
+                ),
+                initialize_transfer_account(
+                    &self.program_id,
+                    &self.wallet.pubkey(),
+                    &self.mint_address,
+                ),
+                initialize_mint_config(
+                    &self.program_id,
+                    &context.payer.pubkey(),
+                    &self.mint_address,
+                    &self.mint_authority.pubkey(),
+                ),
+            ],
+            &[&self.mint_authority, &self.wallet],
+        )
+        .await
+        .unwrap();
+    }
+
+    fn update_mint_config(&self, config: &MintConfig) -> Instruction {
+        update_mint_config(
+            &self.program_id,
+            &self.mint_address,
+            &self.mint_authority.pubkey(),
+            config,
+        )
+    }
+
+    fn governance_address(&self, governance_program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[GOVERNANCE_SEED, self.mint_address.as_ref()],
+            governance_program,
+        )
+        .0
+    }
+
+    async fn transfer_account(&self, context: &ProgramTestContext) -> TransferAccount {
+        let account = context
+            .banks_client
+            .get_account(get_transfer_account_address(
+                &self.wallet.pubkey(),
+                &self.mint_address,
+                &self.program_id,
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        TransferAccount::unpack(&account.data).unwrap()
+    }
+
+    async fn owed_burn(&self, context: &ProgramTestContext) -> u64 {
+        let account = context
+            .banks_client
+            .get_account(get_burn_ledger_address(
+                &self.wallet.pubkey(),
+                &self.mint_address,
+                &self.program_id,
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        BurnLedger::unpack(&account.data).unwrap().owed
+    }
+
+    fn execute(&self, amount: u64) -> Instruction {
+        execute_with_extra_account_metas(
+            &self.program_id,
+            &self.source,
+            &self.mint_address,
+            &self.destination,
+            &self.wallet.pubkey(),
+            &get_extra_account_metas_address(&self.mint_address, &self.program_id),
+            &self.extra_account_metas(),
+            amount,
+        )
+    }
+}
+
+/// Process the instructions in a single transaction paid by the context
+/// payer. Identical transactions are deduplicated by the bank, so repeated
+/// calls must differ, e.g. in the transferred amount.
+async fn process_transaction(
+    context: &ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+/// Simulate the instructions, returning the return data
+async fn simulate_return_data(
+    context: &ProgramTestContext,
+    instructions: &[Instruction],
+) -> Option<Vec<u8>> {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
     );
+    context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap()
+        .simulation_details
+        .unwrap()
+        .return_data
+        .map(|return_data| return_data.data)
+}
+
+/// Simulate the instructions, returning the `Execute` check reported as failed
+/// through return data
+async fn simulate_failed_check(
+    context: &ProgramTestContext,
+    instructions: &[Instruction],
+) -> Option<FailedCheck> {
+    simulate_return_data(context, instructions)
+        .await
+        .and_then(|data| FailedCheck::from_return_data(&data))
 }
 
 #[tokio::test]
@@ -154,6 +958,7 @@ async fn success_execute() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -449,6 +1254,7 @@ async fn fail_incorrect_derivation() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -513,6 +1319,7 @@ async fn fail_incorrect_mint() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -605,6 +1412,7 @@ async fn success_on_chain_invoke() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &hook_program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -747,6 +1555,7 @@ async fn fail_without_transferring_flag() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -842,6 +1651,7 @@ async fn success_on_chain_invoke_with_updated_extra_account_metas() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &hook_program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -1045,6 +1855,7 @@ async fn success_execute_with_updated_extra_account_metas() {
     setup_token_accounts(
         &mut program_test,
         &token_program_id,
+        &program_id,
         &mint_address,
         &mint_authority_pubkey,
         &source,
@@ -1463,3 +2274,7230 @@ async fn success_execute_with_updated_extra_account_metas() {
             .unwrap();
     }
 }
+
+#[cfg(feature = "recent-log")]
+#[tokio::test]
+async fn success_execute_with_recent_transfer_log() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = setup(&program_id);
+
+    let token_program_id = spl_token_2022::id();
+    let wallet = Keypair::new();
+    let mint_address = spl_transfer_hook_example::mint::id();
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+    let source = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let decimals = 2;
+
+    setup_token_accounts(
+        &mut program_test,
+        &token_program_id,
+        &program_id,
+        &mint_address,
+        &mint_authority_pubkey,
+        &source,
+        &destination,
+        &wallet.pubkey(),
+        decimals,
+        true,
+    );
+    add_funded_wallet(&mut program_test, &wallet.pubkey());
+
+    let extra_account_metas_address = get_extra_account_metas_address(&mint_address, &program_id);
+    let transfer_account_address =
+        get_transfer_account_address(&wallet.pubkey(), &mint_address, &program_id);
+    let recent_log_address = get_recent_transfer_log_address(&mint_address, &program_id);
+
+    let init_extra_account_metas = [
+        transfer_account_extra_meta(),
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"recent-log".to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )
+        .unwrap(),
+    ];
+    let extra_account_metas = [
+        AccountMeta::new(transfer_account_address, false),
+        AccountMeta::new(recent_log_address, false),
+    ];
+
+    let context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let rent_lamports = rent
+        .minimum_balance(ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap());
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent_lamports,
+            ),
+            initialize_extra_account_meta_list(
+                &program_id,
+                &extra_account_metas_address,
+                &mint_address,
+                &mint_authority_pubkey,
+                &init_extra_account_metas,
+            ),
+            initialize_transfer_account(&program_id, &wallet.pubkey(), &mint_address),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &mint_authority, &wallet],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let execute = |amount| {
+        execute_with_extra_account_metas(
+            &program_id,
+            &source,
+            &mint_address,
+            &destination,
+            &wallet.pubkey(),
+            &extra_account_metas_address,
+            &extra_account_metas,
+            amount,
+        )
+    };
+
+    // transfers go through until the log is created
+    process_transaction(&context, &[execute(5)], &[])
+        .await
+        .unwrap();
+    process_transaction(
+        &context,
+        &[initialize_recent_transfer_log(
+            &program_id,
+            &context.payer.pubkey(),
+            &recent_log_address,
+            &mint_address,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let amounts = [10u64, 20, 30];
+    for amount in amounts {
+        process_transaction(&context, &[execute(amount)], &[])
+            .await
+            .unwrap();
+    }
+
+    let recent_log = context
+        .banks_client
+        .get_account(recent_log_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let entries = RecentTransferLog::unpack(&recent_log.data).unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|(owner, amount, _)| (*owner, *amount))
+            .collect::<Vec<_>>(),
+        amounts
+            .iter()
+            .map(|amount| (wallet.pubkey(), *amount))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "per-mint-tracking")]
+#[tokio::test]
+async fn success_execute_with_per_mint_tracking() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = setup(&program_id);
+
+    let token_program_id = spl_token_2022::id();
+    let wallet = Keypair::new();
+    let mint_authority = Keypair::new();
+    let mint_authority_pubkey = mint_authority.pubkey();
+    let decimals = 2;
+    add_funded_wallet(&mut program_test, &wallet.pubkey());
+
+    // Same owner, two different mints. The validation accounts are written by
+    // hand, since `InitializeExtraAccountMetaList` only accepts one mint.
+    let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let token_accounts = mints.map(|mint_address| {
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        setup_token_accounts(
+            &mut program_test,
+            &token_program_id,
+            &program_id,
+            &mint_address,
+            &mint_authority_pubkey,
+            &source,
+            &destination,
+            &wallet.pubkey(),
+            decimals,
+            true,
+        );
+        program_test.add_account(
+            get_extra_account_metas_address(&mint_address, &program_id),
+            SolanaAccount {
+                lamports: 1_000_000_000,
+                data: example_data(&[transfer_account_extra_meta()]).unwrap(),
+                owner: program_id,
+                ..SolanaAccount::default()
+            },
+        );
+        (source, destination)
+    });
+
+    let transfer_account_addresses = mints.map(|mint_address| {
+        get_transfer_account_address(&wallet.pubkey(), &mint_address, &program_id)
+    });
+    assert_ne!(transfer_account_addresses[0], transfer_account_addresses[1]);
+
+    let context = program_test.start_with_context().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            initialize_transfer_account(&program_id, &wallet.pubkey(), &mints[0]),
+            initialize_transfer_account(&program_id, &wallet.pubkey(), &mints[1]),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wallet],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let amounts = [100u64, 7];
+    for ((mint_address, (source, destination)), (transfer_account_address, amount)) in mints
+        .iter()
+        .zip(token_accounts.iter())
+        .zip(transfer_account_addresses.iter().zip(amounts))
+    {
+        let transaction = Transaction::new_signed_with_payer(
+            &[execute_with_extra_account_metas(
+                &program_id,
+                source,
+                mint_address,
+                destination,
+                &wallet.pubkey(),
+                &get_extra_account_metas_address(mint_address, &program_id),
+                &[AccountMeta::new(*transfer_account_address, false)],
+                amount,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+
+    // fail with the other mint's transfer account
+    {
+        let (source, destination) = token_accounts[0];
+        let transaction = Transaction::new_signed_with_payer(
+            &[execute_with_extra_account_metas(
+                &program_id,
+                &source,
+                &mints[0],
+                &destination,
+                &wallet.pubkey(),
+                &get_extra_account_metas_address(&mints[0], &program_id),
+                &[AccountMeta::new(transfer_account_addresses[1], false)],
+                1,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(AccountResolutionError::IncorrectAccount as u32),
+            )
+        );
+    }
+
+    for (transfer_account_address, amount) in transfer_account_addresses.iter().zip(amounts) {
+        let account = context
+            .banks_client
+            .get_account(*transfer_account_address)
+            .await
+            .unwrap()
+            .unwrap();
+        let account = TransferAccount::unpack(&account.data).unwrap();
+        assert_eq!(account.owner, wallet.pubkey());
+        assert_eq!(account.transfered, amount);
+    }
+}
+
+#[tokio::test]
+async fn execute_with_max_balance_percentage() {
+    for balance in [900u64, 9_000_000_000, u64::MAX / 2] {
+        // 10% of the holdings before the transfer
+        let cap = balance / 9;
+        let (program_test, tracked) = setup_tracked_mint(balance);
+        let context = program_test.start_with_context().await;
+        tracked.initialize(&context).await;
+
+        // no cap until configured
+        process_transaction(&context, &[tracked.execute(balance)], &[])
+            .await
+            .unwrap();
+
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                max_pct_bps: 1_000,
+                ..MintConfig::default()
+            })],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+
+        process_transaction(&context, &[tracked.execute(cap)], &[])
+            .await
+            .unwrap();
+        process_transaction(&context, &[tracked.execute(cap / 2)], &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            process_transaction(&context, &[tracked.execute(cap + 1)], &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn fail_update_mint_config_with_incorrect_authority() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[update_mint_config(
+                &tracked.program_id,
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+                &MintConfig {
+                    authority: wrong_authority.pubkey(),
+                    max_pct_bps: 1,
+                    ..MintConfig::default()
+                },
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn success_freeze_owner() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[freeze_owner(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.source,
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectFreezeAuthority as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[freeze_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.source,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the token program froze the account
+    let source = context
+        .banks_client
+        .get_account(tracked.source)
+        .await
+        .unwrap()
+        .unwrap();
+    let source = StateWithExtensions::<Account>::unpack(&source.data).unwrap();
+    assert_eq!(source.base.state, AccountState::Frozen);
+
+    // and the owner is blocklisted
+    let blocklist = context
+        .banks_client
+        .get_account(get_blocklist_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        OwnerList::unpack(&blocklist.data).unwrap(),
+        vec![tracked.wallet.pubkey()]
+    );
+
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerBlocklisted as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn report_failed_check_in_return_data() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_pct_bps: 1_000,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(100)]).await,
+        None
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(1_000)]).await,
+        Some(FailedCheck::BalancePercentage)
+    );
+
+    process_transaction(
+        &context,
+        &[freeze_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.source,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(1_000)]).await,
+        Some(FailedCheck::Blocklist)
+    );
+}
+
+#[tokio::test]
+async fn report_allowed_maximum_in_return_data() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_pct_bps: 1_000,
+            report_allowed_maximum: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // 10% of the holdings of 1_000 + 200
+    let allowed_maximum = simulate_return_data(&context, &[tracked.execute(200)])
+        .await
+        .and_then(|data| ExampleError::allowed_maximum_from_return_data(&data));
+    assert_eq!(allowed_maximum, Some(120));
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(200)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsAllowedMaximum as u32)
+        )
+    );
+
+    // retrying with the allowed maximum leaves the same holdings
+    context.set_account(
+        &tracked.source,
+        &SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 1_080, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+    process_transaction(&context, &[tracked.execute(120)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 120);
+}
+
+#[tokio::test]
+async fn execute_aggregates_partial_fills() {
+    // the same amount, in a single transfer or in chunks within a transaction
+    for chunks in [&[1_200][..], &[600, 600], &[400, 400, 400]] {
+        let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+        tracked.with_instructions_sysvar = true;
+        let other_payer = Keypair::new();
+        add_funded_wallet(&mut program_test, &other_payer.pubkey());
+        let context = program_test.start_with_context().await;
+        tracked.initialize(&context).await;
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                max_pct_bps: 5_000,
+                limit_exempt_amount: 500,
+                cooldown_seconds: 60,
+                max_transfers_per_day: 1,
+                aggregate_partial_fills: true,
+                ..MintConfig::default()
+            })],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+        let execute_chunks = |scale: u64| -> Vec<Instruction> {
+            chunks
+                .iter()
+                .map(|chunk| tracked.execute(chunk / scale))
+                .collect()
+        };
+
+        // the last chunk brings the fills over the limit
+        assert_eq!(
+            process_transaction(&context, &execute_chunks(1), &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                chunks.len() as u8 - 1,
+                InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+            )
+        );
+
+        // the chunks are a single transfer for the cooldown and the daily cap
+        process_transaction(&context, &execute_chunks(2), &[])
+            .await
+            .unwrap();
+        let transfer_account = tracked.transfer_account(&context).await;
+        assert_eq!(transfer_account.transfered, 600);
+        assert_eq!(transfer_account.day_count, 1);
+
+        // the same chunks, sent again in the same slot by another payer, are
+        // another transfer
+        let transaction = Transaction::new_signed_with_payer(
+            &execute_chunks(2),
+            Some(&other_payer.pubkey()),
+            &[&other_payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(ExampleError::TransferCooldownActive as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn success_execute_by_delegate_tracks_owner() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let delegate = Pubkey::new_unique();
+    let mut source_data =
+        token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 1_000, true);
+    let mut state = StateWithExtensionsMut::<Account>::unpack(&mut source_data).unwrap();
+    state.base.delegate = COption::Some(delegate);
+    state.base.delegated_amount = 1_000;
+    state.pack_base();
+    program_test.add_account(
+        tracked.source,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: source_data,
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[execute_with_extra_account_metas(
+            &tracked.program_id,
+            &tracked.source,
+            &tracked.mint_address,
+            &tracked.destination,
+            &delegate,
+            &get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+            &tracked.extra_account_metas(),
+            42,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let transfer_account = context
+        .banks_client
+        .get_account(get_transfer_account_address(
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let transfer_account = TransferAccount::unpack(&transfer_account.data).unwrap();
+    assert_eq!(transfer_account.owner, tracked.wallet.pubkey());
+    assert_eq!(transfer_account.transfered, 42);
+}
+
+#[tokio::test]
+async fn success_transfer_spending_delegated_amount() {
+    let program_id = Pubkey::new_unique();
+    let (program_test, tracked) =
+        setup_tracked_mint_in(setup_with_sbf_token_program(&program_id), program_id, 1_000);
+    let delegate = Keypair::new();
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[token_instruction::approve(
+            &spl_token_2022::id(),
+            &tracked.source,
+            &delegate.pubkey(),
+            &tracked.wallet.pubkey(),
+            &[],
+            100,
+        )
+        .unwrap()],
+        &[&tracked.wallet],
+    )
+    .await
+    .unwrap();
+
+    // Token-2022 clears the delegate of the source before invoking the hook
+    // once the approval is spent
+    let mut transfer = token_instruction::transfer_checked(
+        &spl_token_2022::id(),
+        &tracked.source,
+        &tracked.mint_address,
+        &tracked.destination,
+        &delegate.pubkey(),
+        &[],
+        100,
+        2,
+    )
+    .unwrap();
+    transfer.accounts.extend(tracked.extra_account_metas());
+    transfer.accounts.extend([
+        AccountMeta::new_readonly(tracked.program_id, false),
+        AccountMeta::new_readonly(
+            get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+            false,
+        ),
+    ]);
+    process_transaction(&context, &[transfer], &[&delegate])
+        .await
+        .unwrap();
+
+    let source = context
+        .banks_client
+        .get_account(tracked.source)
+        .await
+        .unwrap()
+        .unwrap();
+    let source = StateWithExtensions::<Account>::unpack(&source.data).unwrap();
+    assert_eq!(source.base.delegate, COption::None);
+    assert_eq!(source.base.amount, 900);
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 100);
+}
+
+#[tokio::test]
+async fn execute_with_transfer_account_of_another_owner() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let mut account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // the owner's PDA tracking someone else is rejected
+    let mut transfer_account = TransferAccount::unpack(&account.data).unwrap();
+    transfer_account.owner = Pubkey::new_unique();
+    transfer_account.pack(&mut account.data);
+    context.set_account(&transfer_account_address, &account.clone().into());
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(42)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferAccountOwnerMismatch as u32)
+        )
+    );
+
+    // and accepted once it tracks the source owner again
+    transfer_account.owner = tracked.wallet.pubkey();
+    transfer_account.pack(&mut account.data);
+    context.set_account(&transfer_account_address, &account.into());
+    process_transaction(&context, &[tracked.execute(43)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 43);
+}
+
+#[tokio::test]
+async fn execute_with_delegate_transfers_rejected() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let delegate = Pubkey::new_unique();
+    let mut source_data =
+        token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 1_000, true);
+    let mut state = StateWithExtensionsMut::<Account>::unpack(&mut source_data).unwrap();
+    state.base.delegate = COption::Some(delegate);
+    state.base.delegated_amount = 1_000;
+    state.pack_base();
+    program_test.add_account(
+        tracked.source,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: source_data,
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            reject_delegate_transfers: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the owner is the authority
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[execute_with_extra_account_metas(
+                &tracked.program_id,
+                &tracked.source,
+                &tracked.mint_address,
+                &tracked.destination,
+                &delegate,
+                &get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+                &tracked.extra_account_metas(),
+                2,
+            )],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::DelegateTransferRejected as u32)
+        )
+    );
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1);
+}
+
+#[tokio::test]
+async fn execute_with_cooldown() {
+    for with_clock_sysvar in [true, false] {
+        let (program_test, mut tracked) = setup_tracked_mint(1_000);
+        tracked.with_clock_sysvar = with_clock_sysvar;
+        let context = program_test.start_with_context().await;
+        tracked.initialize(&context).await;
+
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                cooldown_seconds: 3_600,
+                ..MintConfig::default()
+            })],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap();
+        let result = process_transaction(&context, &[tracked.execute(2)], &[]).await;
+        if with_clock_sysvar {
+            assert_eq!(
+                result.unwrap_err(),
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(ExampleError::TransferCooldownActive as u32)
+                )
+            );
+        } else {
+            // the cooldown is skipped without the clock
+            result.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn execute_records_first_transfer_timestamp() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    assert_eq!(
+        tracked
+            .transfer_account(&context)
+            .await
+            .first_transfer_timestamp,
+        0
+    );
+
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let first_transfer_timestamp = clock.unix_timestamp;
+    for (amount, unix_timestamp) in [
+        (1, first_transfer_timestamp),
+        (2, first_transfer_timestamp + 60),
+    ] {
+        clock.unix_timestamp = unix_timestamp;
+        context.set_sysvar(&clock);
+        process_transaction(&context, &[tracked.execute(amount)], &[])
+            .await
+            .unwrap();
+
+        let transfer_account = tracked.transfer_account(&context).await;
+        assert_eq!(
+            transfer_account.first_transfer_timestamp,
+            first_transfer_timestamp
+        );
+        assert_eq!(transfer_account.last_transfer_timestamp, unix_timestamp);
+    }
+}
+
+#[tokio::test]
+async fn success_set_label() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    for label in [*b"hot wallet\0\0\0\0\0\0", *b"payroll 2026\0\0\0\0"] {
+        process_transaction(
+            &context,
+            &[set_label(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+                &label,
+            )],
+            &[&tracked.wallet],
+        )
+        .await
+        .unwrap();
+
+        let transfer_account = context
+            .banks_client
+            .get_account(transfer_account_address)
+            .await
+            .unwrap()
+            .unwrap();
+        let transfer_account = TransferAccount::unpack(&transfer_account.data).unwrap();
+        assert_eq!(transfer_account.label, label);
+        assert_eq!(transfer_account.owner, tracked.wallet.pubkey());
+    }
+
+    // only the owner can set the label
+    let mut instruction = set_label(
+        &tracked.program_id,
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &[0; TransferAccount::LABEL_LEN],
+    );
+    instruction.accounts[0].is_signer = false;
+    assert_eq!(
+        process_transaction(&context, &[instruction], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn execute_with_exempt_owner() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[add_exempt_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &tracked.wallet.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(10)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 0);
+
+    process_transaction(
+        &context,
+        &[remove_exempt_owner(
+            &tracked.program_id,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &tracked.wallet.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(20)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 20);
+
+    // only the config authority manages the exempt owners
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[add_exempt_owner(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+                &tracked.wallet.pubkey(),
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_validation_account_not_owned_by_program() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    program_test.add_account(
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: example_data(&tracked.init_extra_account_metas()).unwrap(),
+            owner: Pubkey::new_unique(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferHookError::IncorrectAccount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn execute_with_slot_window_limit() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            window_slots: 50,
+            max_amount_per_window_slots: 100,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // within the window
+    process_transaction(&context, &[tracked.execute(60)], &[])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(40)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::WindowLimitExceeded as u32)
+        )
+    );
+
+    // across the window
+    let window_start_slot = tracked.transfer_account(&context).await.window_start_slot;
+    context.warp_to_slot(window_start_slot + 50).unwrap();
+    process_transaction(&context, &[tracked.execute(100)], &[])
+        .await
+        .unwrap();
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.window_start_slot, window_start_slot + 50);
+    assert_eq!(transfer_account.window_amount, 100);
+    assert_eq!(transfer_account.transfered, 200);
+}
+
+#[tokio::test]
+async fn execute_with_daily_transfer_cap() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_transfers_per_day: 2,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // an hour into the day
+    let day_start_ts = 20_000 * 86_400;
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = day_start_ts + 3_600;
+    context.set_sysvar(&clock);
+
+    let daily_limit_exceeded = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::DailyTransferLimitExceeded as u32),
+    );
+    process_transaction(&context, &[tracked.execute(1), tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(3)], &[])
+            .await
+            .unwrap_err(),
+        daily_limit_exceeded
+    );
+
+    // still the same day, a second before midnight
+    clock.unix_timestamp = day_start_ts + 86_399;
+    context.set_sysvar(&clock);
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(3)], &[])
+            .await
+            .unwrap_err(),
+        daily_limit_exceeded
+    );
+
+    // the count restarts with the next day
+    clock.unix_timestamp = day_start_ts + 86_400;
+    context.set_sysvar(&clock);
+    context.get_new_latest_blockhash().await.unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.day_start_ts, day_start_ts + 86_400);
+    assert_eq!(transfer_account.day_count, 1);
+    assert_eq!(transfer_account.transfered, 6);
+}
+
+#[tokio::test]
+async fn execute_with_owner_signer_requirement() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let signed_execute = |amount| {
+        let mut instruction = tracked.execute(amount);
+        // the wallet is both the authority and the source owner
+        instruction.accounts[3].is_signer = true;
+        instruction
+    };
+
+    // either way without the requirement
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    process_transaction(&context, &[signed_execute(2)], &[&tracked.wallet])
+        .await
+        .unwrap();
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            require_owner_signer: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(3)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::SourceOwnerNotSigner as u32)
+        )
+    );
+    process_transaction(&context, &[signed_execute(4)], &[&tracked.wallet])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 7);
+}
+
+#[tokio::test]
+async fn execute_tracks_max_single_transfer() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    for amount in [5, 20, 7] {
+        process_transaction(&context, &[tracked.execute(amount)], &[])
+            .await
+            .unwrap();
+    }
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.max_single_transfer, 20);
+    assert_eq!(transfer_account.transfered, 32);
+
+    process_transaction(&context, &[tracked.execute(21)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        tracked.transfer_account(&context).await.max_single_transfer,
+        21
+    );
+}
+
+#[cfg(feature = "debug-compute")]
+#[tokio::test]
+async fn execute_with_compute_unit_logging() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(&context, &[tracked.execute(5)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 5);
+}
+
+/// Compute units an `Execute` of a tracked mint may consume, well below the
+/// default limit of an instruction so that the transfer around it still fits
+const EXECUTE_COMPUTE_BUDGET: u64 = 100_000;
+
+/// Compute units consumed by an `Execute` of a tracked mint whose config runs
+/// the checks reading the token accounts, against the SBF build of the program
+/// at `program_file`, as the native processor is not metered
+async fn execute_compute_units(program_file: &Path) -> u64 {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    let data = read_file(program_file);
+    program_test.add_account(
+        program_id,
+        SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader::id(),
+            executable: true,
+            ..SolanaAccount::default()
+        },
+    );
+    let (program_test, tracked) = setup_tracked_mint_in(program_test, program_id, 1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_pct_bps: 5_000,
+            reject_program_destinations: true,
+            skip_same_owner_transfers: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[tracked.execute(5)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(simulation.result, Some(Ok(())));
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build of `make build-sbf-program`"]
+async fn execute_within_compute_budget() {
+    let sbf_out_dir = PathBuf::from(std::env::var_os("SBF_OUT_DIR").unwrap());
+    let units_consumed =
+        execute_compute_units(&sbf_out_dir.join("spl_transfer_hook_example.so")).await;
+    assert!(
+        units_consumed <= EXECUTE_COMPUTE_BUDGET,
+        "Execute consumed {units_consumed} compute units"
+    );
+}
+
+#[tokio::test]
+async fn execute_before_and_after_trading_enabled() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+
+    // the distributor sends the tokens out before trading is enabled
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        distributor: tracked.wallet.pubkey(),
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[
+            setup_mint(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &tracked.init_extra_account_metas(),
+                &config,
+            ),
+            initialize_transfer_account(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+            ),
+        ],
+        &[&tracked.mint_authority, &tracked.wallet],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    // anyone else is rejected
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            distributor: Pubkey::new_unique(),
+            ..config
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TradingNotEnabled as u32)
+        )
+    );
+
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[enable_trading(
+                &tracked.program_id,
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+    let enable = enable_trading(
+        &tracked.program_id,
+        &tracked.mint_address,
+        &tracked.mint_authority.pubkey(),
+    );
+    process_transaction(&context, &[enable.clone()], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+
+    // trading can only be enabled once, and a distributor can't be named
+    // again
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[enable], &[&tracked.mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&config)],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+    process_transaction(&context, &[tracked.execute(5)], &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn execute_before_and_after_setup_finalized() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // transfers are rejected during the grace period
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        require_setup: true,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::SetupNotFinalized as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(2)]).await,
+        Some(FailedCheck::Setup)
+    );
+
+    let finalize = finalize_setup(
+        &tracked.program_id,
+        &tracked.mint_address,
+        &tracked.mint_authority.pubkey(),
+    );
+    process_transaction(&context, &[finalize.clone()], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+
+    // updating the config keeps the setup finalized
+    context.get_new_latest_blockhash().await.unwrap();
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let mint_config = context
+        .banks_client
+        .get_account(get_mint_config_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        MintConfig::unpack(&mint_config.data)
+            .unwrap()
+            .setup_complete
+    );
+    process_transaction(&context, &[tracked.execute(4)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 7);
+
+    // the setup can only be finalized once
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[finalize], &[&tracked.mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn execute_with_scheduled_pause() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[schedule_pause(
+                &tracked.program_id,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                slot,
+                &[0; MintConfig::PAUSE_REASON_LEN],
+            )],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    let pause_slot = slot + 100;
+    let mut reason = [0; MintConfig::PAUSE_REASON_LEN];
+    reason[..17].copy_from_slice(b"Exchange delisted");
+    process_transaction(
+        &context,
+        &[schedule_pause(
+            &tracked.program_id,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            pause_slot,
+            &reason,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let return_data = simulate_return_data(
+        &context,
+        &[get_mint_config(&tracked.program_id, &tracked.mint_address)],
+    )
+    .await
+    .unwrap();
+    let config = MintConfig::unpack(&return_data).unwrap();
+    assert_eq!(config.pause_effective_slot, pause_slot);
+    assert_eq!(config.pause_reason, reason);
+
+    // updating the config leaves the schedule alone
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            pause_effective_slot: slot + 1,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let return_data = simulate_return_data(
+        &context,
+        &[get_mint_config(&tracked.program_id, &tracked.mint_address)],
+    )
+    .await
+    .unwrap();
+    let config = MintConfig::unpack(&return_data).unwrap();
+    assert_eq!(config.pause_effective_slot, pause_slot);
+    assert_eq!(config.pause_reason, reason);
+    // transfers go on until the pause slot
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    context.warp_to_slot(pause_slot).unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::MintHalted as u32)
+        )
+    );
+
+    let cancel = cancel_pause(
+        &tracked.program_id,
+        &tracked.mint_address,
+        &tracked.mint_authority.pubkey(),
+    );
+    process_transaction(&context, &[cancel.clone()], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+    let return_data = simulate_return_data(
+        &context,
+        &[get_mint_config(&tracked.program_id, &tracked.mint_address)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        MintConfig::unpack(&return_data).unwrap().pause_reason,
+        [0; MintConfig::PAUSE_REASON_LEN]
+    );
+
+    // nothing left to cancel
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[cancel], &[&tracked.mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn success_initialize_destination_account() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Pubkey::new_unique();
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient, 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    tracked.with_destination_transfer_account = true;
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient;
+
+    // the transfer account must be derived from the destination owner
+    let mut wrong_derivation = initialize_destination_account(
+        &tracked.program_id,
+        &context.payer.pubkey(),
+        &recipient_account,
+        &recipient,
+        &tracked.mint_address,
+    );
+    wrong_derivation.accounts[1].pubkey = get_transfer_account_address(
+        &Pubkey::new_unique(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    assert_eq!(
+        process_transaction(&context, &[wrong_derivation], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+    // and the destination must be a token account of the mint
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[initialize_destination_account(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &recipient_account,
+                &recipient,
+                &Pubkey::new_unique(),
+            )],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::DestinationAccountMismatch as u32)
+        )
+    );
+
+    // the sender pays for the recipient's transfer account
+    let initialize = initialize_destination_account(
+        &tracked.program_id,
+        &context.payer.pubkey(),
+        &recipient_account,
+        &recipient,
+        &tracked.mint_address,
+    );
+    process_transaction(&context, &[initialize.clone()], &[])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(25)], &[])
+        .await
+        .unwrap();
+    let recipient_transfer_account =
+        get_transfer_account_address(&recipient, &tracked.mint_address, &tracked.program_id);
+    let received = |context: &ProgramTestContext| {
+        let banks_client = context.banks_client.clone();
+        async move {
+            let account = banks_client
+                .get_account(recipient_transfer_account)
+                .await
+                .unwrap()
+                .unwrap();
+            let transfer_account = TransferAccount::unpack(&account.data).unwrap();
+            assert_eq!(transfer_account.owner, recipient);
+            transfer_account.received
+        }
+    };
+    assert_eq!(received(&context).await, 25);
+
+    // initializing again leaves the account as it is
+    context.get_new_latest_blockhash().await.unwrap();
+    process_transaction(&context, &[initialize], &[])
+        .await
+        .unwrap();
+    assert_eq!(received(&context).await, 25);
+}
+
+#[tokio::test]
+async fn execute_with_receive_cap() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Keypair::new();
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    add_funded_wallet(&mut program_test, &recipient.pubkey());
+    tracked.with_destination_transfer_account = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[
+            initialize_transfer_account(
+                &tracked.program_id,
+                &recipient.pubkey(),
+                &tracked.mint_address,
+            ),
+            tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                max_received_amount: 100,
+                ..MintConfig::default()
+            }),
+        ],
+        &[&recipient, &tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient.pubkey();
+
+    // below, then at the cap
+    process_transaction(&context, &[tracked.execute(60)], &[])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(40)], &[])
+        .await
+        .unwrap();
+    // above the cap
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::ReceiveCapExceeded as u32)
+        )
+    );
+
+    let account = context
+        .banks_client
+        .get_account(get_transfer_account_address(
+            &recipient.pubkey(),
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        TransferAccount::unpack(&account.data).unwrap().received,
+        100
+    );
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 100);
+}
+
+#[tokio::test]
+async fn execute_with_owner_checked_external_meta() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let governance_program = Pubkey::new_unique();
+    tracked.governance_program = Some(governance_program);
+    let governance_address = tracked.governance_address(&governance_program);
+    let governance_account = |owner| SolanaAccount {
+        lamports: 1_000_000_000,
+        data: vec![1; 8],
+        owner,
+        ..SolanaAccount::default()
+    };
+    program_test.add_account(governance_address, governance_account(governance_program));
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // the governance PDA follows the governance program in the metas
+    let governance_meta_index = tracked.init_extra_account_metas().len() - 1;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            owner_checked_metas: 1 << governance_meta_index,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    context.set_account(
+        &governance_address,
+        &governance_account(Pubkey::new_unique()).into(),
+    );
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::ExternalAccountOwnerMismatch as u32)
+        )
+    );
+
+    // only external PDA metas can be checked
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            owner_checked_metas: 1 << (governance_meta_index - 1),
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(3)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn execute_with_rent_exempt_destination_requirement() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let underfunded_destination = Pubkey::new_unique();
+    program_test.add_account(
+        underfunded_destination,
+        SolanaAccount {
+            lamports: 1_000,
+            data: token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let funded_destination = tracked.destination;
+
+    // any destination without the requirement
+    tracked.destination = underfunded_destination;
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            require_rent_exempt_destination: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::DestinationNotRentExempt as u32)
+        )
+    );
+    tracked.destination = funded_destination;
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+}
+
+#[tokio::test]
+async fn execute_with_expected_decimals() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // the mint has 2 decimals, so a config expecting 6 can't be written
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        enforce_decimals: true,
+        expected_decimals: 6,
+        ..MintConfig::default()
+    };
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&config)],
+            &[&tracked.mint_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::MintDecimalsMismatch as u32)
+        )
+    );
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            expected_decimals: 2,
+            ..config
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1);
+
+    // a config written against another mint rejects the transfers
+    let mint_config_address = get_mint_config_address(&tracked.mint_address, &tracked.program_id);
+    let mut account = context
+        .banks_client
+        .get_account(mint_config_address)
+        .await
+        .unwrap()
+        .unwrap();
+    config.pack(&mut account.data);
+    context.set_account(&mint_config_address, &account.into());
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::MintDecimalsMismatch as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(3)]).await,
+        Some(FailedCheck::MintDecimals)
+    );
+}
+
+#[tokio::test]
+async fn execute_with_required_destination_extension() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let account_size = ExtensionType::try_calculate_account_len::<Account>(&[
+        ExtensionType::TransferHookAccount,
+        ExtensionType::MemoTransfer,
+    ])
+    .unwrap();
+    let mut memo_data = vec![0; account_size];
+    let mut state =
+        StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut memo_data).unwrap();
+    state
+        .init_extension::<TransferHookAccount>(true)
+        .unwrap()
+        .transferring = true.into();
+    state
+        .init_extension::<MemoTransfer>(true)
+        .unwrap()
+        .require_incoming_transfer_memos = true.into();
+    state.base = Account {
+        mint: tracked.mint_address,
+        owner: tracked.wallet.pubkey(),
+        state: AccountState::Initialized,
+        ..Account::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    let memo_destination = Pubkey::new_unique();
+    program_test.add_account(
+        memo_destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: memo_data,
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            required_destination_extension: ExtensionType::MemoTransfer.into(),
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::DestinationExtensionMissing as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(2)]).await,
+        Some(FailedCheck::DestinationExtension)
+    );
+
+    tracked.destination = memo_destination;
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+
+    // only token account extensions can be required
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                required_destination_extension: ExtensionType::MetadataPointer.into(),
+                ..MintConfig::default()
+            })],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+/// Mock router program, passing its instruction on to the program of its
+/// first account
+fn process_routed_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let (program_info, account_infos) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let accounts = account_infos
+        .iter()
+        .map(|info| AccountMeta {
+            pubkey: *info.key,
+            is_signer: info.is_signer,
+            is_writable: info.is_writable,
+        })
+        .collect();
+    solana_program::program::invoke(
+        &Instruction {
+            program_id: *program_info.key,
+            accounts,
+            data: input.to_vec(),
+        },
+        account_infos,
+    )
+}
+
+#[tokio::test]
+async fn execute_through_trusted_caller() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_instructions_sysvar = true;
+    let router = Pubkey::new_unique();
+    let untrusted_router = Pubkey::new_unique();
+    for (name, program_id) in [("router", router), ("untrusted_router", untrusted_router)] {
+        program_test.add_program(name, program_id, processor!(process_routed_instruction));
+    }
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_pct_bps: 100,
+            trusted_callers: [
+                router,
+                Pubkey::default(),
+                Pubkey::default(),
+                Pubkey::default(),
+            ],
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    // the router calls `Execute`, standing in for a router calling the token
+    // program
+    let route = |router: &Pubkey, amount| {
+        let execute = tracked.execute(amount);
+        let mut accounts = vec![AccountMeta::new_readonly(tracked.program_id, false)];
+        accounts.extend(execute.accounts);
+        Instruction {
+            program_id: *router,
+            accounts,
+            data: execute.data,
+        }
+    };
+    let exceeds_balance_percentage = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32),
+    );
+
+    // the limits are enforced on direct transfers and untrusted callers
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(500)], &[])
+            .await
+            .unwrap_err(),
+        exceeds_balance_percentage
+    );
+    assert_eq!(
+        process_transaction(&context, &[route(&untrusted_router, 501)], &[])
+            .await
+            .unwrap_err(),
+        exceeds_balance_percentage
+    );
+
+    // and skipped through a trusted one, along with the tracking
+    process_transaction(&context, &[route(&router, 502)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 0);
+}
+
+#[tokio::test]
+async fn execute_with_extra_account_metas_version() {
+    use spl_transfer_hook_example::processor::EXTRA_ACCOUNT_METAS_VERSION;
+
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // a validation account written for this program resolves
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            extra_account_metas_version: EXTRA_ACCOUNT_METAS_VERSION,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1);
+
+    // one written for another version is rejected before its metas are
+    // resolved
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            extra_account_metas_version: EXTRA_ACCOUNT_METAS_VERSION + 1,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::ExtraAccountMetasVersionMismatch as u32)
+        )
+    );
+
+    // and an unset version is not checked
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+}
+
+#[tokio::test]
+async fn success_health_check() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    let health_check = health_check(&tracked.program_id, &tracked.mint_address);
+
+    assert_eq!(
+        simulate_return_data(&context, &[health_check.clone()]).await,
+        Some(vec![0])
+    );
+
+    // only the validation account
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let init_extra_account_metas = tracked.init_extra_account_metas();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    process_transaction(
+        &context,
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent.minimum_balance(
+                    ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap(),
+                ),
+            ),
+            initialize_extra_account_meta_list(
+                &tracked.program_id,
+                &extra_account_metas_address,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &init_extra_account_metas,
+            ),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        simulate_return_data(&context, &[health_check.clone()]).await,
+        Some(vec![
+            health_status::VALIDATION_ACCOUNT_INITIALIZED | health_status::VALIDATION_ACCOUNT_VALID
+        ])
+    );
+
+    // fully configured
+    process_transaction(
+        &context,
+        &[initialize_mint_config(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        simulate_return_data(&context, &[health_check]).await,
+        Some(vec![
+            health_status::VALIDATION_ACCOUNT_INITIALIZED
+                | health_status::VALIDATION_ACCOUNT_VALID
+                | health_status::MINT_CONFIG_INITIALIZED
+                | health_status::MINT_CONFIG_VALID
+        ])
+    );
+}
+
+#[tokio::test]
+async fn execute_updates_transfer_root() {
+    use spl_transfer_tracking_interface::{
+        merkle::{inclusion_proof, transfer_leaf, transfer_root, verify_transfer_inclusion},
+        solana_pubkey::Pubkey as InterfacePubkey,
+        state::{decode_program_account, ProgramAccount, TransferRoot},
+    };
+
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_transfer_root = true;
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let transfer_root_address =
+        get_transfer_root_address(&tracked.mint_address, &tracked.program_id);
+
+    // the root is only updated once initialized
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    process_transaction(
+        &context,
+        &[initialize_transfer_root(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let owner = InterfacePubkey::new_from_array(tracked.wallet.pubkey().to_bytes());
+    let mut leaves = vec![];
+    let first_slot = context.banks_client.get_root_slot().await.unwrap() + 10;
+    for (i, amount) in [2, 3, 5].into_iter().enumerate() {
+        let slot = first_slot + i as u64;
+        context.warp_to_slot(slot).unwrap();
+        process_transaction(&context, &[tracked.execute(amount)], &[])
+            .await
+            .unwrap();
+        leaves.push(transfer_leaf(&owner, amount, slot));
+
+        let account = context
+            .banks_client
+            .get_account(transfer_root_address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            decode_program_account(&account.data),
+            ProgramAccount::TransferRoot(TransferRoot {
+                count: leaves.len() as u64,
+                root: transfer_root(&leaves),
+            })
+        );
+    }
+
+    // each transfer is proven against the latest root
+    let root = transfer_root(&leaves);
+    for (index, leaf) in leaves.iter().enumerate() {
+        let proof = inclusion_proof(&leaves, index as u64).unwrap();
+        assert!(verify_transfer_inclusion(&root, leaf, index as u64, &proof));
+    }
+    let proof = inclusion_proof(&leaves, 1).unwrap();
+    assert!(!verify_transfer_inclusion(
+        &root,
+        &transfer_leaf(&owner, 4, first_slot + 1),
+        1,
+        &proof
+    ));
+}
+
+#[tokio::test]
+async fn success_teardown_mint() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let payer = context.payer.pubkey();
+    let authority = tracked.mint_authority.pubkey();
+    let owner = Pubkey::new_unique();
+    let allowlist_page = get_allowlist_page(&owner);
+    let treasury = get_treasury_address(&tracked.mint_address, &tracked.program_id);
+    process_transaction(
+        &context,
+        &[
+            initialize_mint_stats(&tracked.program_id, &payer, &tracked.mint_address),
+            freeze_owner(
+                &tracked.program_id,
+                &payer,
+                &tracked.source,
+                &tracked.mint_address,
+                &authority,
+            ),
+            add_exempt_owner(
+                &tracked.program_id,
+                &payer,
+                &tracked.mint_address,
+                &authority,
+                &owner,
+            ),
+            add_allowlisted_owner(
+                &tracked.program_id,
+                &payer,
+                &tracked.mint_address,
+                &authority,
+                &owner,
+                0,
+            ),
+            snapshot_stats(
+                &tracked.program_id,
+                &payer,
+                &tracked.mint_address,
+                &authority,
+            ),
+            initialize_transfer_root(&tracked.program_id, &payer, &tracked.mint_address),
+            initialize_fee_ledger(&tracked.program_id, &payer, &tracked.mint_address, 3),
+            system_instruction::transfer(&payer, &treasury, 1_000_000_000),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let addresses = [
+        get_mint_config_address(&tracked.mint_address, &tracked.program_id),
+        get_mint_stats_address(&tracked.mint_address, &tracked.program_id),
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+        get_blocklist_address(&tracked.mint_address, &tracked.program_id),
+        get_exempt_owners_address(&tracked.mint_address, &tracked.program_id),
+        get_allowlist_address(&tracked.mint_address, allowlist_page, &tracked.program_id),
+        get_stats_history_address(&tracked.mint_address, &tracked.program_id),
+        get_transfer_root_address(&tracked.mint_address, &tracked.program_id),
+        get_fee_ledger_address(&tracked.mint_address, 3, &tracked.program_id),
+        treasury,
+    ];
+    let mut rent_lamports = 0;
+    for address in addresses {
+        rent_lamports += context.banks_client.get_balance(address).await.unwrap();
+    }
+
+    // only the mint authority may tear the mint down
+    let not_authority = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[teardown_mint(
+                &tracked.program_id,
+                &recipient,
+                &tracked.mint_address,
+                &not_authority.pubkey(),
+                &[allowlist_page],
+                &[3],
+            )],
+            &[&not_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferHookError::IncorrectMintAuthority as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[teardown_mint(
+            &tracked.program_id,
+            &recipient,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &[allowlist_page],
+            &[3],
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    for address in addresses {
+        assert_eq!(
+            context.banks_client.get_account(address).await.unwrap(),
+            None
+        );
+    }
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        rent_lamports
+    );
+    assert_eq!(
+        simulate_return_data(
+            &context,
+            &[health_check(&tracked.program_id, &tracked.mint_address)]
+        )
+        .await,
+        Some(vec![0])
+    );
+
+    // accounts that don't exist are skipped
+    let other_recipient = Pubkey::new_unique();
+    process_transaction(
+        &context,
+        &[teardown_mint(
+            &tracked.program_id,
+            &other_recipient,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &[allowlist_page],
+            &[3],
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(other_recipient)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn teardown_mint_with_signer_threshold() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        signer_threshold: 2,
+        ..MintConfig::default()
+    };
+    for (slot, signer) in config.signers.iter_mut().zip(&signers) {
+        *slot = signer.pubkey();
+    }
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let teardown = |signers: &[&Pubkey]| {
+        with_config_signers(
+            teardown_mint(
+                &tracked.program_id,
+                &recipient,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &[],
+                &[],
+            ),
+            signers,
+        )
+    };
+    let mint_config_address = get_mint_config_address(&tracked.mint_address, &tracked.program_id);
+
+    // the mint authority alone no longer suffices, nor does it with a single
+    // config signer
+    let signer_pubkey = signers[0].pubkey();
+    for (config_signers, all_signers) in [
+        (vec![], vec![&tracked.mint_authority]),
+        (
+            vec![&signer_pubkey],
+            vec![&tracked.mint_authority, &signers[0]],
+        ),
+    ] {
+        assert_eq!(
+            process_transaction(&context, &[teardown(&config_signers)], &all_signers)
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(ExampleError::NotEnoughConfigSigners as u32)
+            )
+        );
+        assert!(context
+            .banks_client
+            .get_account(mint_config_address)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    process_transaction(
+        &context,
+        &[teardown(&[&signers[0].pubkey(), &signers[2].pubkey()])],
+        &[&tracked.mint_authority, &signers[0], &signers[2]],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(mint_config_address)
+            .await
+            .unwrap(),
+        None
+    );
+}
+#[tokio::test]
+async fn success_withdraw_treasury() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let recipient = Pubkey::new_unique();
+    add_funded_wallet(&mut program_test, &recipient);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let treasury = get_treasury_address(&tracked.mint_address, &tracked.program_id);
+    let rent_lamports = context
+        .banks_client
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(0);
+    process_transaction(
+        &context,
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &treasury,
+            rent_lamports + 5_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let withdraw = |authority: &Keypair, amount| {
+        withdraw_treasury(
+            &tracked.program_id,
+            &recipient,
+            &tracked.mint_address,
+            &authority.pubkey(),
+            amount,
+        )
+    };
+
+    // only the config authority may withdraw
+    let not_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[withdraw(&not_authority, 1_000)],
+            &[&not_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+
+    // the treasury stays rent exempt
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[withdraw(&tracked.mint_authority, 5_001)],
+            &[&tracked.mint_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+    process_transaction(
+        &context,
+        &[withdraw(&tracked.mint_authority, 3_000)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(
+        &context,
+        &[withdraw(&tracked.mint_authority, 2_000)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(treasury).await.unwrap(),
+        rent_lamports
+    );
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        1_000_005_000
+    );
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[withdraw(&tracked.mint_authority, 1)],
+            &[&tracked.mint_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+}
+
+#[tokio::test]
+async fn execute_zero_amount() {
+    for zero_amount_mode in [
+        ZeroAmountMode::Track,
+        ZeroAmountMode::SkipTracking,
+        ZeroAmountMode::Reject,
+    ] {
+        let (program_test, tracked) = setup_tracked_mint(1_000);
+        let context = program_test.start_with_context().await;
+        tracked.initialize(&context).await;
+
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                zero_amount_mode,
+                ..MintConfig::default()
+            })],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+
+        let result = process_transaction(&context, &[tracked.execute(0)], &[]).await;
+        let transfer_account = tracked.transfer_account(&context).await;
+        assert_eq!(transfer_account.transfered, 0);
+        match zero_amount_mode {
+            ZeroAmountMode::Track => {
+                result.unwrap();
+                assert_ne!(transfer_account.last_transfer_timestamp, 0);
+            }
+            ZeroAmountMode::SkipTracking => {
+                result.unwrap();
+                assert_eq!(transfer_account.last_transfer_timestamp, 0);
+            }
+            ZeroAmountMode::Reject => assert_eq!(
+                result.unwrap_err(),
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(ExampleError::ZeroAmountTransfer as u32)
+                )
+            ),
+        }
+    }
+}
+
+#[tokio::test]
+async fn execute_zero_amount_skips_transfer_account() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            zero_amount_mode: ZeroAmountMode::SkipTracking,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the transfer account isn't loaded, so an unreadable one goes unnoticed
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let unreadable = SolanaAccount {
+        lamports: 1_000_000_000,
+        data: vec![0xff; 3],
+        owner: tracked.program_id,
+        ..SolanaAccount::default()
+    };
+    context.set_account(&transfer_account_address, &unreadable.clone().into());
+    process_transaction(&context, &[tracked.execute(0)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+    let account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data, unreadable.data);
+
+    // the blocklist still applies
+    process_transaction(
+        &context,
+        &[freeze_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.source,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    // a new blockhash, for the transaction to differ from the one above
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(0)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerBlocklisted as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn execute_zero_amount_skipping_tracking_is_checked() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            zero_amount_mode: ZeroAmountMode::SkipTracking,
+            require_setup: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the validation account is checked
+    let mut execute = tracked.execute(0);
+    execute.accounts[4].pubkey = Pubkey::new_unique();
+    assert_eq!(
+        process_transaction(&context, &[execute], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    // so is the setup of the mint
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(0)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::SetupNotFinalized as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn reinitialize_corrupted_transfer_account() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+
+    // a healthy account keeps its limits
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[reinitialize_transfer_account(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+            )],
+            &[&tracked.wallet],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+
+    let corrupted_account = SolanaAccount {
+        lamports: 1_000_000_000,
+        data: vec![0xff; TransferAccount::LEN - 13],
+        owner: tracked.program_id,
+        ..SolanaAccount::default()
+    };
+    context.set_account(&transfer_account_address, &corrupted_account.clone().into());
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // only the owner can reset the account
+    let other = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[{
+                let mut instruction = reinitialize_transfer_account(
+                    &tracked.program_id,
+                    &other.pubkey(),
+                    &tracked.mint_address,
+                );
+                instruction.accounts[1].pubkey = transfer_account_address;
+                instruction
+            }],
+            &[&other],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    context.set_account(&transfer_account_address, &corrupted_account.into());
+    // a new blockhash, for the transaction to differ from the rejected one
+    context.get_new_latest_blockhash().await.unwrap();
+    process_transaction(
+        &context,
+        &[reinitialize_transfer_account(
+            &tracked.program_id,
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&tracked.wallet],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.lamports, rent.minimum_balance(TransferAccount::LEN));
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(
+        TransferAccount::unpack(&account.data).unwrap(),
+        TransferAccount {
+            owner: tracked.wallet.pubkey(),
+            first_received_timestamp: clock.unix_timestamp,
+            first_received_slot: clock.slot,
+            ..TransferAccount::default()
+        }
+    );
+
+    // tracking starts over
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 2);
+}
+
+#[tokio::test]
+async fn reset_accounts_batch_for_new_season() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    for (owner, transfered) in owners.iter().zip([100, 200]) {
+        let mut data = vec![0; TransferAccount::LEN];
+        TransferAccount {
+            owner: *owner,
+            transfered,
+            received: 50,
+            label: *b"season one\0\0\0\0\0\0",
+            allowance_remaining: 7,
+            ..TransferAccount::default()
+        }
+        .pack(&mut data);
+        program_test.add_account(
+            get_transfer_account_address(owner, &tracked.mint_address, &tracked.program_id),
+            SolanaAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner: tracked.program_id,
+                ..SolanaAccount::default()
+            },
+        );
+    }
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(&context, &[tracked.execute(300)], &[])
+        .await
+        .unwrap();
+
+    let all_owners = [owners[0], owners[1], tracked.wallet.pubkey()];
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[reset_accounts_batch(
+                &tracked.program_id,
+                &tracked.mint_address,
+                &tracked.wallet.pubkey(),
+                &all_owners,
+            )],
+            &[&tracked.wallet],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+    process_transaction(
+        &context,
+        &[reset_accounts_batch(
+            &tracked.program_id,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &all_owners,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    for owner in all_owners {
+        let account = context
+            .banks_client
+            .get_account(get_transfer_account_address(
+                &owner,
+                &tracked.mint_address,
+                &tracked.program_id,
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        let transfer_account = TransferAccount::unpack(&account.data).unwrap();
+        assert_eq!(transfer_account.owner, owner);
+        assert_eq!(transfer_account.transfered, 0);
+        assert_eq!(transfer_account.received, 0);
+    }
+    let account = context
+        .banks_client
+        .get_account(get_transfer_account_address(
+            &owners[0],
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let transfer_account = TransferAccount::unpack(&account.data).unwrap();
+    assert_eq!(transfer_account.label, *b"season one\0\0\0\0\0\0");
+    assert_eq!(transfer_account.allowance_remaining, 7);
+
+    // accounts the program doesn't own are rejected
+    let mut reset = reset_accounts_batch(
+        &tracked.program_id,
+        &tracked.mint_address,
+        &tracked.mint_authority.pubkey(),
+        &owners,
+    );
+    reset.accounts[4].pubkey = tracked.source;
+    assert_eq!(
+        process_transaction(&context, &[reset], &[&tracked.mint_authority])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn success_reassign_transfer_account() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    // the new owner already tracked some transfers
+    let new_owner = Keypair::new();
+    add_funded_wallet(&mut program_test, &new_owner.pubkey());
+    let new_transfer_account_address = get_transfer_account_address(
+        &new_owner.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let mut new_transfer_account_data = vec![0; TransferAccount::LEN];
+    TransferAccount {
+        owner: new_owner.pubkey(),
+        transfered: 500,
+        label: *b"new wallet\0\0\0\0\0\0",
+        ..TransferAccount::default()
+    }
+    .pack(&mut new_transfer_account_data);
+    program_test.add_account(
+        new_transfer_account_address,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: new_transfer_account_data,
+            owner: tracked.program_id,
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(&context, &[tracked.execute(42)], &[])
+        .await
+        .unwrap();
+
+    let reassign = reassign_transfer_account(
+        &tracked.program_id,
+        &tracked.wallet.pubkey(),
+        &new_owner.pubkey(),
+        &tracked.mint_address,
+    );
+
+    // both owners must sign
+    for missing_signer in [0, 2] {
+        let mut instruction = reassign.clone();
+        instruction.accounts[missing_signer].is_signer = false;
+        let signer = if missing_signer == 0 {
+            &new_owner
+        } else {
+            &tracked.wallet
+        };
+        assert_eq!(
+            process_transaction(&context, &[instruction], &[signer])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+
+    process_transaction(&context, &[reassign], &[&tracked.wallet, &new_owner])
+        .await
+        .unwrap();
+
+    // the old account stays, keeping the limit state of the old owner
+    let old_transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(old_transfer_account.owner, tracked.wallet.pubkey());
+    assert_eq!(old_transfer_account.transfered, 0);
+    assert_ne!(old_transfer_account.last_transfer_timestamp, 0);
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &tracked.program_id,
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&tracked.wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        tracked.transfer_account(&context).await,
+        old_transfer_account
+    );
+
+    let new_transfer_account = context
+        .banks_client
+        .get_account(new_transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let new_transfer_account = TransferAccount::unpack(&new_transfer_account.data).unwrap();
+    assert_eq!(new_transfer_account.owner, new_owner.pubkey());
+    assert_eq!(new_transfer_account.transfered, 542);
+    assert_eq!(&new_transfer_account.label, b"new wallet\0\0\0\0\0\0");
+    assert_ne!(new_transfer_account.last_transfer_timestamp, 0);
+}
+
+/// Mock notify program, recording the notification as return data
+fn process_notification(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    solana_program::program::set_return_data(input);
+    Ok(())
+}
+
+#[tokio::test]
+async fn execute_with_notify_program() {
+    let notify_program = Pubkey::new_unique();
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    program_test.add_program(
+        "mock_notify_program",
+        notify_program,
+        processor!(process_notification),
+    );
+    tracked.notify_program = Some(notify_program);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // not notified until configured
+    assert_eq!(
+        simulate_return_data(&context, &[tracked.execute(7)]).await,
+        None
+    );
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            notify_program,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    let mut notification = tracked.wallet.pubkey().to_bytes().to_vec();
+    notification.extend_from_slice(&7u64.to_le_bytes());
+    assert_eq!(
+        simulate_return_data(&context, &[tracked.execute(7)]).await,
+        Some(notification)
+    );
+}
+
+#[tokio::test]
+async fn success_initialize_transfer_account_with_non_default_rent() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    let rent = Rent {
+        lamports_per_byte_year: Rent::default().lamports_per_byte_year * 3,
+        ..Rent::default()
+    };
+    context.set_sysvar(&rent);
+
+    tracked.initialize(&context).await;
+
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(rent.is_exempt(account.lamports, account.data.len()));
+    assert_eq!(account.lamports, rent.minimum_balance(TransferAccount::LEN));
+}
+
+#[tokio::test]
+async fn success_initialize_transfer_account_with_payer() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let funded_owner = Keypair::new();
+    add_funded_wallet(&mut program_test, &funded_owner.pubkey());
+    let context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let transfer_account = |owner: &Keypair| {
+        get_transfer_account_address(&owner.pubkey(), &tracked.mint_address, &tracked.program_id)
+    };
+    let lamports = |address| {
+        let banks_client = context.banks_client.clone();
+        async move { banks_client.get_balance(address).await.unwrap() }
+    };
+
+    // the owner pays the rent by default
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &tracked.program_id,
+            &funded_owner.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&funded_owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        lamports(funded_owner.pubkey()).await,
+        1_000_000_000 - rent.minimum_balance(TransferAccount::LEN)
+    );
+
+    // or a distinct payer, for an owner without lamports
+    let owner = Keypair::new();
+    let payer = Keypair::new();
+    process_transaction(
+        &context,
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &payer.pubkey(),
+            1_000_000_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let mut unsigned_payer = initialize_transfer_account_with_payer(
+        &tracked.program_id,
+        &payer.pubkey(),
+        &owner.pubkey(),
+        &tracked.mint_address,
+    );
+    unsigned_payer.accounts[4].is_signer = false;
+    assert_eq!(
+        process_transaction(&context, &[unsigned_payer], &[&owner])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+    process_transaction(
+        &context,
+        &[initialize_transfer_account_with_payer(
+            &tracked.program_id,
+            &payer.pubkey(),
+            &owner.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&payer, &owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(lamports(owner.pubkey()).await, 0);
+    assert_eq!(
+        lamports(payer.pubkey()).await,
+        1_000_000_000 - rent.minimum_balance(TransferAccount::LEN)
+    );
+    let account = context
+        .banks_client
+        .get_account(transfer_account(&owner))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        TransferAccount::unpack(&account.data).unwrap().owner,
+        owner.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn initialize_transfer_account_with_creation_cap() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let payer = Keypair::new();
+    add_funded_wallet(&mut program_test, &payer.pubkey());
+    let owners = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let destination = Pubkey::new_unique();
+    program_test.add_account(
+        destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &owners[2].pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            max_transfer_accounts_per_payer: 2,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let initialize = |owner: &Keypair| {
+        initialize_transfer_account_with_payer(
+            &tracked.program_id,
+            &payer.pubkey(),
+            &owner.pubkey(),
+            &tracked.mint_address,
+        )
+    };
+    let creation_count_address =
+        get_creation_count_address(&payer.pubkey(), &tracked.mint_address, &tracked.program_id);
+
+    for owner in &owners[..2] {
+        process_transaction(&context, &[initialize(owner)], &[&payer, owner])
+            .await
+            .unwrap();
+    }
+    let creation_count = context
+        .banks_client
+        .get_account(creation_count_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        CreationCount::unpack(&creation_count.data).unwrap(),
+        CreationCount { created: 2 }
+    );
+    assert_eq!(
+        process_transaction(&context, &[initialize(&owners[2])], &[&payer, &owners[2]])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::CreationCapExceeded as u32)
+        )
+    );
+
+    // the mint config can't be left out to skip the cap
+    let mut without_config = initialize(&owners[2]);
+    without_config.accounts[5].pubkey = Pubkey::new_unique();
+    assert_eq!(
+        process_transaction(&context, &[without_config], &[&payer, &owners[2]])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    // nor the mint swapped for one without a cap, when transfer accounts are
+    // shared by every mint
+    #[cfg(not(feature = "per-mint-tracking"))]
+    {
+        let other_mint = Pubkey::new_unique();
+        let mut with_other_mint = initialize(&owners[2]);
+        with_other_mint.accounts[3].pubkey = other_mint;
+        with_other_mint.accounts[5].pubkey =
+            get_mint_config_address(&other_mint, &tracked.program_id);
+        assert_eq!(
+            process_transaction(&context, &[with_other_mint], &[&payer, &owners[2]])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+        );
+    }
+
+    // nor the transfer account created as the one of a destination
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[initialize_destination_account(
+                &tracked.program_id,
+                &payer.pubkey(),
+                &destination,
+                &owners[2].pubkey(),
+                &tracked.mint_address,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::CreationCapExceeded as u32)
+        )
+    );
+
+    // existing accounts aren't counted again, and other payers have their
+    // own count
+    process_transaction(&context, &[initialize(&owners[0])], &[&payer, &owners[0]])
+        .await
+        .unwrap();
+    let other_payer = Keypair::new();
+    process_transaction(
+        &context,
+        &[system_instruction::transfer(
+            &context.payer.pubkey(),
+            &other_payer.pubkey(),
+            1_000_000_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    process_transaction(
+        &context,
+        &[initialize_transfer_account_with_payer(
+            &tracked.program_id,
+            &other_payer.pubkey(),
+            &owners[2].pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&other_payer, &owners[2]],
+    )
+    .await
+    .unwrap();
+
+    // an owner funding its own transfer account isn't counted, past the cap
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &tracked.program_id,
+            &payer.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&payer],
+    )
+    .await
+    .unwrap();
+    // nor does it need the mint when transfer accounts are shared
+    #[cfg(not(feature = "per-mint-tracking"))]
+    {
+        let mut without_mint = initialize_transfer_account(
+            &tracked.program_id,
+            &other_payer.pubkey(),
+            &tracked.mint_address,
+        );
+        without_mint.accounts.truncate(3);
+        process_transaction(&context, &[without_mint], &[&other_payer])
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn execute_with_extra_account_metas_cap() {
+    use spl_transfer_hook_example::processor::MAX_EXTRA_ACCOUNT_METAS;
+
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let base_len = tracked.init_extra_account_metas().len();
+    tracked.filler_accounts = (base_len..=MAX_EXTRA_ACCOUNT_METAS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+
+    // the list above the cap cannot be initialized, so write it directly
+    let init_extra_account_metas = tracked.init_extra_account_metas();
+    assert_eq!(init_extra_account_metas.len(), MAX_EXTRA_ACCOUNT_METAS + 1);
+    let mut data = vec![0; ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap()];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &init_extra_account_metas).unwrap();
+    program_test.add_account(
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data,
+            owner: tracked.program_id,
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &tracked.program_id,
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&tracked.wallet],
+    )
+    .await
+    .unwrap();
+
+    let error = process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TrackingHookError::TooManyExtraAccountMetas as u32)
+        )
+    );
+
+    // at the cap, through the regular initialization
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.filler_accounts = (base_len..MAX_EXTRA_ACCOUNT_METAS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    assert_eq!(
+        tracked.init_extra_account_metas().len(),
+        MAX_EXTRA_ACCOUNT_METAS
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 2);
+}
+
+#[tokio::test]
+async fn initialize_extra_account_meta_list_above_cap() {
+    use spl_transfer_hook_example::processor::MAX_EXTRA_ACCOUNT_METAS;
+
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let init_extra_account_metas = (0..=MAX_EXTRA_ACCOUNT_METAS)
+        .map(|_| ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap())
+        .collect::<Vec<_>>();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let rent_lamports = rent
+        .minimum_balance(ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap());
+
+    let error = process_transaction(
+        &context,
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent_lamports,
+            ),
+            initialize_extra_account_meta_list(
+                &tracked.program_id,
+                &extra_account_metas_address,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &init_extra_account_metas,
+            ),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(TrackingHookError::TooManyExtraAccountMetas as u32)
+        )
+    );
+    let account = context
+        .banks_client
+        .get_account(extra_account_metas_address)
+        .await
+        .unwrap();
+    assert_eq!(account, None);
+}
+
+#[tokio::test]
+async fn update_extra_account_meta_list_above_cap() {
+    use spl_transfer_hook_example::processor::MAX_EXTRA_ACCOUNT_METAS;
+
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let account_before = context
+        .banks_client
+        .get_account(extra_account_metas_address)
+        .await
+        .unwrap();
+
+    let updated_extra_account_metas = (0..=MAX_EXTRA_ACCOUNT_METAS)
+        .map(|_| ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap())
+        .collect::<Vec<_>>();
+    let error = process_transaction(
+        &context,
+        &[update_extra_account_meta_list(
+            &tracked.program_id,
+            &extra_account_metas_address,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &updated_extra_account_metas,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TrackingHookError::TooManyExtraAccountMetas as u32)
+        )
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(extra_account_metas_address)
+            .await
+            .unwrap(),
+        account_before
+    );
+
+    // at the cap, the list is updated
+    let rent = context.banks_client.get_rent().await.unwrap();
+    process_transaction(
+        &context,
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent.minimum_balance(
+                    ExtraAccountMetaList::size_of(MAX_EXTRA_ACCOUNT_METAS).unwrap(),
+                ),
+            ),
+            update_extra_account_meta_list(
+                &tracked.program_id,
+                &extra_account_metas_address,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &updated_extra_account_metas[1..],
+            ),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(extra_account_metas_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data.len(),
+        ExtraAccountMetaList::size_of(MAX_EXTRA_ACCOUNT_METAS).unwrap()
+    );
+}
+
+/// Extra account metas resolving the wallet's transfer account, followed by
+/// one meta for each of `keys` cycling through the seed types, along with the
+/// accounts they resolve to for a transfer of `amount`. The keys back the
+/// pubkey metas, and the programs of the external PDAs following them.
+fn varied_extra_account_metas(
+    tracked: &TrackedMint,
+    keys: &[Pubkey],
+    amount: u64,
+) -> (Vec<ExtraAccountMeta>, Vec<AccountMeta>) {
+    let mut extra_account_metas = vec![transfer_account_extra_meta()];
+    let mut account_metas = vec![AccountMeta::new(
+        get_transfer_account_address(
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+            &tracked.program_id,
+        ),
+        false,
+    )];
+    for (index, key) in keys.iter().enumerate() {
+        let literal = format!("stress-{}", index).into_bytes();
+        let (extra_account_meta, address) = match index % 5 {
+            0 => (
+                ExtraAccountMeta::new_with_pubkey(key, false, false).unwrap(),
+                *key,
+            ),
+            1 => (
+                ExtraAccountMeta::new_with_seeds(
+                    &[
+                        Seed::Literal {
+                            bytes: literal.clone(),
+                        },
+                        Seed::AccountKey { index: 1 },
+                    ],
+                    false,
+                    false,
+                )
+                .unwrap(),
+                Pubkey::find_program_address(
+                    &[&literal, tracked.mint_address.as_ref()],
+                    &tracked.program_id,
+                )
+                .0,
+            ),
+            // the amount follows the 8-byte discriminator of `Execute`
+            2 => (
+                ExtraAccountMeta::new_with_seeds(
+                    &[
+                        Seed::Literal {
+                            bytes: literal.clone(),
+                        },
+                        Seed::InstructionData {
+                            index: 8,
+                            length: 8,
+                        },
+                    ],
+                    false,
+                    false,
+                )
+                .unwrap(),
+                Pubkey::find_program_address(
+                    &[&literal, &amount.to_le_bytes()],
+                    &tracked.program_id,
+                )
+                .0,
+            ),
+            3 => (
+                ExtraAccountMeta::new_with_seeds(
+                    &[
+                        Seed::Literal {
+                            bytes: literal.clone(),
+                        },
+                        Seed::AccountData {
+                            account_index: 2,
+                            data_index: 32,
+                            length: 32,
+                        },
+                    ],
+                    false,
+                    false,
+                )
+                .unwrap(),
+                Pubkey::find_program_address(
+                    &[&literal, tracked.destination_owner.as_ref()],
+                    &tracked.program_id,
+                )
+                .0,
+            ),
+            // the program is the pubkey meta four metas back, among the
+            // accounts of `Execute` after the five base accounts and the
+            // transfer account
+            _ => (
+                ExtraAccountMeta::new_external_pda_with_seeds(
+                    u8::try_from(index).unwrap().wrapping_add(2),
+                    &[
+                        Seed::Literal {
+                            bytes: literal.clone(),
+                        },
+                        Seed::AccountKey { index: 3 },
+                    ],
+                    false,
+                    false,
+                )
+                .unwrap(),
+                Pubkey::find_program_address(
+                    &[&literal, tracked.wallet.pubkey().as_ref()],
+                    &keys[index.wrapping_sub(4)],
+                )
+                .0,
+            ),
+        };
+        extra_account_metas.push(extra_account_meta);
+        account_metas.push(AccountMeta::new_readonly(address, false));
+    }
+    (extra_account_metas, account_metas)
+}
+
+#[tokio::test]
+async fn execute_resolves_varied_extra_account_metas() {
+    use spl_transfer_hook_example::processor::MAX_EXTRA_ACCOUNT_METAS;
+
+    for count in [1, 5, MAX_EXTRA_ACCOUNT_METAS] {
+        let (program_test, tracked) = setup_tracked_mint(1_000);
+        let context = program_test.start_with_context().await;
+        let keys: Vec<_> = (1..count).map(|_| Pubkey::new_unique()).collect();
+        let (init_extra_account_metas, _) = varied_extra_account_metas(&tracked, &keys, 0);
+        assert_eq!(init_extra_account_metas.len(), count);
+
+        let extra_account_metas_address =
+            get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+        let rent = context.banks_client.get_rent().await.unwrap();
+        process_transaction(
+            &context,
+            &[
+                system_instruction::transfer(
+                    &context.payer.pubkey(),
+                    &extra_account_metas_address,
+                    rent.minimum_balance(ExtraAccountMetaList::size_of(count).unwrap()),
+                ),
+                initialize_extra_account_meta_list(
+                    &tracked.program_id,
+                    &extra_account_metas_address,
+                    &tracked.mint_address,
+                    &tracked.mint_authority.pubkey(),
+                    &init_extra_account_metas,
+                ),
+                initialize_transfer_account(
+                    &tracked.program_id,
+                    &tracked.wallet.pubkey(),
+                    &tracked.mint_address,
+                ),
+            ],
+            &[&tracked.mint_authority, &tracked.wallet],
+        )
+        .await
+        .unwrap();
+
+        let execute = |amount, account_metas: &[AccountMeta]| {
+            execute_with_extra_account_metas(
+                &tracked.program_id,
+                &tracked.source,
+                &tracked.mint_address,
+                &tracked.destination,
+                &tracked.wallet.pubkey(),
+                &extra_account_metas_address,
+                account_metas,
+                amount,
+            )
+        };
+        for amount in [3, 5] {
+            let (_, account_metas) = varied_extra_account_metas(&tracked, &keys, amount);
+            process_transaction(&context, &[execute(amount, &account_metas)], &[])
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            tracked.transfer_account(&context).await.transfered,
+            8,
+            "{} metas",
+            count
+        );
+
+        // a wrong account in place of the last meta
+        let (_, mut account_metas) = varied_extra_account_metas(&tracked, &keys, 7);
+        account_metas.last_mut().unwrap().pubkey = Pubkey::new_unique();
+        assert_eq!(
+            process_transaction(&context, &[execute(7, &account_metas)], &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(AccountResolutionError::IncorrectAccount as u32)
+            ),
+            "{} metas",
+            count
+        );
+        // or the instruction data seed resolved for another amount
+        if count > 3 {
+            let (_, account_metas) = varied_extra_account_metas(&tracked, &keys, 5);
+            assert_eq!(
+                process_transaction(&context, &[execute(9, &account_metas)], &[])
+                    .await
+                    .unwrap_err(),
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(AccountResolutionError::IncorrectAccount as u32)
+                ),
+                "{} metas",
+                count
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn success_setup_mint() {
+    let (program_test, tracked) = setup_tracked_mint(900);
+    let context = program_test.start_with_context().await;
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        // 10% of the holdings before the transfer
+        max_pct_bps: 1_000,
+        ..MintConfig::default()
+    };
+
+    process_transaction(
+        &context,
+        &[
+            setup_mint(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &tracked.init_extra_account_metas(),
+                &config,
+            ),
+            initialize_transfer_account(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+            ),
+        ],
+        &[&tracked.mint_authority, &tracked.wallet],
+    )
+    .await
+    .unwrap();
+
+    let mint_config = context
+        .banks_client
+        .get_account(get_mint_config_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(MintConfig::unpack(&mint_config.data).unwrap(), config);
+
+    // the config applies right away
+    let error = process_transaction(&context, &[tracked.execute(101)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+        )
+    );
+    process_transaction(&context, &[tracked.execute(100)], &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn fail_setup_mint_rolls_back() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    // the payer can only fund the validation account, not the mint config
+    let init_extra_account_metas = tracked.init_extra_account_metas();
+    let validation_lamports = Rent::default()
+        .minimum_balance(ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap());
+    let payer = Keypair::new();
+    program_test.add_account(
+        payer.pubkey(),
+        SolanaAccount {
+            lamports: validation_lamports + Rent::default().minimum_balance(0),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let mint_config_address = get_mint_config_address(&tracked.mint_address, &tracked.program_id);
+
+    let error = process_transaction(
+        &context,
+        &[setup_mint(
+            &tracked.program_id,
+            &payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &init_extra_account_metas,
+            &MintConfig::default(),
+        )],
+        &[&payer, &tracked.mint_authority],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, TransactionError::InstructionError(0, _)));
+
+    // neither account was created
+    for address in [extra_account_metas_address, mint_config_address] {
+        assert_eq!(
+            context.banks_client.get_account(address).await.unwrap(),
+            None
+        );
+    }
+
+    // the setup can be retried
+    process_transaction(
+        &context,
+        &[setup_mint(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &init_extra_account_metas,
+            &MintConfig::default(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        simulate_return_data(
+            &context,
+            &[health_check(&tracked.program_id, &tracked.mint_address)]
+        )
+        .await,
+        Some(vec![
+            health_status::VALIDATION_ACCOUNT_INITIALIZED
+                | health_status::VALIDATION_ACCOUNT_VALID
+                | health_status::MINT_CONFIG_INITIALIZED
+                | health_status::MINT_CONFIG_VALID
+        ])
+    );
+}
+
+#[tokio::test]
+async fn fail_initialize_duplicate_extra_account_metas() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let mut init_extra_account_metas = tracked.init_extra_account_metas();
+    init_extra_account_metas.push(mint_config_extra_meta());
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let error = process_transaction(
+        &context,
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent.minimum_balance(
+                    ExtraAccountMetaList::size_of(init_extra_account_metas.len()).unwrap(),
+                ),
+            ),
+            initialize_extra_account_meta_list(
+                &tracked.program_id,
+                &extra_account_metas_address,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &init_extra_account_metas,
+            ),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(ExampleError::DuplicateExtraAccountMeta as u32)
+        )
+    );
+
+    // the same list without the duplicate is accepted
+    tracked.initialize(&context).await;
+}
+
+#[tokio::test]
+async fn execute_with_limit_exempt_amount() {
+    let (program_test, tracked) = setup_tracked_mint(900);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            // 10% of the holdings before the transfer, i.e. 100
+            max_pct_bps: 1_000,
+            window_slots: 1_000,
+            max_amount_per_window_slots: 150,
+            limit_exempt_amount: 120,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // above the percentage, but exempt
+    process_transaction(&context, &[tracked.execute(120)], &[])
+        .await
+        .unwrap();
+    // exempt transfers don't count towards the window
+    process_transaction(&context, &[tracked.execute(119)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.window_amount, 0);
+
+    // just above the exempt amount
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(121)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+        )
+    );
+
+    // the window limit applies once the percentage is raised
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            window_slots: 1_000,
+            max_amount_per_window_slots: 150,
+            limit_exempt_amount: 120,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(151)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::WindowLimitExceeded as u32)
+        )
+    );
+    process_transaction(&context, &[tracked.execute(118)], &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn execute_with_incoming_policy() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Keypair::new();
+    add_funded_wallet(&mut program_test, &recipient.pubkey());
+    tracked.destination = Pubkey::new_unique();
+    tracked.destination_owner = recipient.pubkey();
+    program_test.add_account(
+        tracked.destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    // the mint authority moves tokens as the permanent delegate
+    let mint_authority = tracked.mint_authority.pubkey();
+    program_test.add_account(
+        tracked.mint_address,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: mint_data_with_extensions(
+                Mint {
+                    mint_authority: COption::Some(mint_authority),
+                    supply: 1_000,
+                    decimals: 2,
+                    is_initialized: true,
+                    freeze_authority: COption::Some(mint_authority),
+                },
+                &tracked.program_id,
+                &[ExtensionType::PermanentDelegate],
+                |state| {
+                    let extension = state.init_extension::<PermanentDelegate>(true).unwrap();
+                    extension.delegate = Some(mint_authority).try_into().unwrap();
+                },
+            ),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // recipients without a policy accept transfers
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    let set_policy = |reject_incoming| {
+        set_incoming_policy(
+            &tracked.program_id,
+            &recipient.pubkey(),
+            &tracked.mint_address,
+            reject_incoming,
+        )
+    };
+    process_transaction(&context, &[set_policy(true)], &[&recipient])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncomingTransfersRejected as u32)
+        )
+    );
+
+    // the mint authority bypasses the policy
+    let execute_by_mint_authority = execute_with_extra_account_metas(
+        &tracked.program_id,
+        &tracked.source,
+        &tracked.mint_address,
+        &tracked.destination,
+        &tracked.mint_authority.pubkey(),
+        &get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+        &tracked.extra_account_metas(),
+        3,
+    );
+    process_transaction(&context, &[execute_by_mint_authority], &[])
+        .await
+        .unwrap();
+
+    process_transaction(&context, &[set_policy(false)], &[&recipient])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(4)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 8);
+}
+
+#[tokio::test]
+async fn execute_with_receive_only_owner() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    program_test.add_account(
+        sender_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &sender.pubkey(), 100, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    add_funded_wallet(&mut program_test, &sender.pubkey());
+    tracked.with_receive_only = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &tracked.program_id,
+            &sender.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[&sender],
+    )
+    .await
+    .unwrap();
+
+    // the sender sends to the wallet
+    let inbound = TrackedMint {
+        program_id: tracked.program_id,
+        mint_address: tracked.mint_address,
+        mint_authority: tracked.mint_authority.insecure_clone(),
+        wallet: sender.insecure_clone(),
+        source: sender_account,
+        destination: tracked.source,
+        destination_owner: tracked.wallet.pubkey(),
+        with_clock_sysvar: true,
+        notify_program: None,
+        fee_categories: vec![],
+        with_destination_transfer_account: false,
+        with_burn_ledger: false,
+        with_vesting_schedule: false,
+        with_allowlist_pages: false,
+        filler_accounts: vec![],
+        without_transfer_account: false,
+        with_instructions_sysvar: false,
+        governance_program: None,
+        with_receive_only: true,
+        with_transfer_root: false,
+    };
+    let set_flag = |authority: &Keypair, receive_only| {
+        set_receive_only(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+            &authority.pubkey(),
+            receive_only,
+        )
+    };
+
+    // only the config authority may turn the wallet into a lockbox
+    let not_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[set_flag(&not_authority, true)],
+            &[&not_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+    process_transaction(
+        &context,
+        &[set_flag(&tracked.mint_authority, true)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerReceiveOnly as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(2)]).await,
+        Some(FailedCheck::ReceiveOnly)
+    );
+
+    // it still receives
+    process_transaction(&context, &[inbound.execute(5)], &[])
+        .await
+        .unwrap();
+    assert_eq!(inbound.transfer_account(&context).await.transfered, 5);
+
+    process_transaction(
+        &context,
+        &[set_flag(&tracked.mint_authority, false)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+}
+
+#[tokio::test]
+async fn execute_counts_accepted_transfers() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // transfers are not counted until the stats exist
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    process_transaction(
+        &context,
+        &[initialize_mint_stats(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(2), tracked.execute(3)], &[])
+        .await
+        .unwrap();
+
+    // rejected transfers are rolled back along with their count
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_pct_bps: 1,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(500)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+        )
+    );
+
+    let account = context
+        .banks_client
+        .get_account(get_mint_stats_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        MintStats::unpack(&account.data).unwrap(),
+        MintStats {
+            accepted_transfers: 2,
+            accepted_amount: 5,
+            epoch: 0,
+            epoch_volume: 5,
+        }
+    );
+}
+
+#[tokio::test]
+async fn execute_caps_volume_per_epoch() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_epoch_volume: 10,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the stats track the volume, so the cap requires them
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+    process_transaction(
+        &context,
+        &[initialize_mint_stats(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let epoch_volume_exceeded = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::EpochVolumeExceeded as u32),
+    );
+    process_transaction(&context, &[tracked.execute(4), tracked.execute(6)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(3)], &[])
+            .await
+            .unwrap_err(),
+        epoch_volume_exceeded
+    );
+
+    // the volume restarts with the next epoch
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.epoch += 1;
+    context.set_sysvar(&clock);
+    context.get_new_latest_blockhash().await.unwrap();
+    process_transaction(&context, &[tracked.execute(1), tracked.execute(9)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        epoch_volume_exceeded
+    );
+
+    let account = context
+        .banks_client
+        .get_account(get_mint_stats_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        MintStats::unpack(&account.data).unwrap(),
+        MintStats {
+            accepted_transfers: 4,
+            accepted_amount: 20,
+            epoch: clock.epoch,
+            epoch_volume: 10,
+        }
+    );
+}
+
+#[tokio::test]
+async fn snapshot_stats_into_history() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[initialize_mint_stats(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let payer = context.payer.pubkey();
+    let snapshot = |authority: &Pubkey| {
+        snapshot_stats(
+            &tracked.program_id,
+            &payer,
+            &tracked.mint_address,
+            authority,
+        )
+    };
+
+    // only the config authority can take snapshots
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[snapshot(&wrong_authority.pubkey())],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    process_transaction(
+        &context,
+        &[snapshot(&tracked.mint_authority.pubkey())],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let first_slot = context.banks_client.get_root_slot().await.unwrap();
+
+    context.warp_to_slot(first_slot + 10).unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    process_transaction(
+        &context,
+        &[snapshot(&tracked.mint_authority.pubkey())],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(get_stats_history_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    let snapshots = StatsHistory::unpack(&account.data).unwrap();
+    assert_eq!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot.stats)
+            .collect::<Vec<_>>(),
+        [
+            MintStats {
+                accepted_transfers: 1,
+                accepted_amount: 2,
+                epoch: 0,
+                epoch_volume: 2,
+            },
+            MintStats {
+                accepted_transfers: 2,
+                accepted_amount: 5,
+                epoch: 0,
+                epoch_volume: 5,
+            },
+        ]
+    );
+    assert!(snapshots[1].slot >= first_slot + 10);
+    assert!(snapshots[0].slot < snapshots[1].slot);
+
+    // the interface decodes the same history
+    let decoded = spl_transfer_tracking_interface::state::unpack_stats_history(&account.data)
+        .unwrap()
+        .into_iter()
+        .map(|snapshot| StatsSnapshot {
+            stats: MintStats {
+                accepted_transfers: snapshot.accepted_transfers,
+                accepted_amount: snapshot.accepted_amount,
+                epoch: snapshot.epoch,
+                epoch_volume: snapshot.epoch_volume,
+            },
+            slot: snapshot.slot,
+            unix_timestamp: snapshot.unix_timestamp,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, snapshots);
+}
+
+#[tokio::test]
+async fn update_mint_config_with_signer_threshold() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        signer_threshold: 2,
+        ..MintConfig::default()
+    };
+    for (slot, signer) in config.signers.iter_mut().zip(&signers) {
+        *slot = signer.pubkey();
+    }
+
+    // the threshold must be reachable
+    let unreachable = MintConfig {
+        signer_threshold: 4,
+        ..config
+    };
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[tracked.update_mint_config(&unreachable)],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    config.max_pct_bps = 1;
+    let update = |authority: &Keypair, signers: &[&Pubkey]| {
+        with_config_signers(
+            update_mint_config(
+                &tracked.program_id,
+                &tracked.mint_address,
+                &authority.pubkey(),
+                &config,
+            ),
+            signers,
+        )
+    };
+    let not_enough_signers = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::NotEnoughConfigSigners as u32),
+    );
+
+    // the single authority no longer suffices
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[update(&tracked.mint_authority, &[])],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        not_enough_signers
+    );
+    // a signer listed twice counts once
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[update(&signers[0], &[&signers[0].pubkey()])],
+            &[&signers[0]],
+        )
+        .await
+        .unwrap_err(),
+        not_enough_signers
+    );
+    process_transaction(
+        &context,
+        &[update(&signers[0], &[&signers[2].pubkey()])],
+        &[&signers[0], &signers[2]],
+    )
+    .await
+    .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(get_mint_config_address(
+            &tracked.mint_address,
+            &tracked.program_id,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(MintConfig::unpack(&account.data).unwrap(), config);
+}
+
+#[tokio::test]
+async fn execute_accrues_split_fees() {
+    let (program_test, mut tracked) = setup_tracked_mint(100_000);
+    // treasury, burn and staking
+    tracked.fee_categories = vec![0, 1, 2];
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let mut config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        fee_bps: 300,
+        ..MintConfig::default()
+    };
+    for (split, (category, bps)) in config
+        .fee_splits
+        .iter_mut()
+        .zip([(0, 150), (1, 100), (2, 50)])
+    {
+        *split = FeeSplit { category, bps };
+    }
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the ledgers must exist
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1_000)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+    let initialize_ledgers: Vec<_> = tracked
+        .fee_categories
+        .iter()
+        .map(|category| {
+            initialize_fee_ledger(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                *category,
+            )
+        })
+        .collect();
+    process_transaction(&context, &initialize_ledgers, &[])
+        .await
+        .unwrap();
+
+    // fee of 30, then of 3 with shares of 1, 1 and 0 and a remainder of 1
+    process_transaction(
+        &context,
+        &[tracked.execute(1_000), tracked.execute(133)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let mut accrued = vec![];
+    for category in &tracked.fee_categories {
+        let account = context
+            .banks_client
+            .get_account(get_fee_ledger_address(
+                &tracked.mint_address,
+                *category,
+                &tracked.program_id,
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        accrued.push(FeeLedger::unpack(&account.data).unwrap().accrued);
+    }
+    assert_eq!(accrued, [15 + 2, 10 + 1, 5]);
+}
+
+/// Mock token program, checking that a burn is signed by its authority and
+/// recording the instruction as return data
+fn process_mock_burn(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let Ok(TokenInstruction::Burn { .. }) = TokenInstruction::unpack(input) else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    if !accounts[2].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    solana_program::program::set_return_data(input);
+    Ok(())
+}
+
+/// Set up a tracked mint whose transfers owe a burn of 1%, with the burn
+/// ledger initialized and 3 tokens owed
+async fn setup_owed_burn(program_test: ProgramTest, tracked: &TrackedMint) -> ProgramTestContext {
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            burn_bps: 100,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the ledger must exist
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(250)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+    process_transaction(
+        &context,
+        &[initialize_burn_ledger(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.wallet.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // burns of 2 and 1, rounded down
+    process_transaction(&context, &[tracked.execute(250), tracked.execute(199)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.owed_burn(&context).await, 3);
+    context
+}
+
+#[tokio::test]
+async fn settle_owed_burn_through_token_program() {
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_burn_ledger = true;
+    let context = setup_owed_burn(program_test, &tracked).await;
+
+    // only the owner settles, from their own account
+    let settle = settle_burn(
+        &tracked.program_id,
+        &tracked.source,
+        &tracked.mint_address,
+        &tracked.wallet.pubkey(),
+    );
+    process_transaction(&context, &[settle], &[&tracked.wallet])
+        .await
+        .unwrap();
+    assert_eq!(tracked.owed_burn(&context).await, 0);
+
+    let source = context
+        .banks_client
+        .get_account(tracked.source)
+        .await
+        .unwrap()
+        .unwrap();
+    let source = StateWithExtensions::<Account>::unpack(&source.data).unwrap();
+    assert_eq!(source.base.amount, 997);
+    let mint = context
+        .banks_client
+        .get_account(tracked.mint_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = StateWithExtensions::<Mint>::unpack(&mint.data).unwrap();
+    assert_eq!(mint.base.supply, 997);
+}
+
+#[tokio::test]
+async fn settle_owed_burn_with_mock_token_program() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    program_test.add_program(
+        "mock_token_program",
+        spl_token_2022::id(),
+        processor!(process_mock_burn),
+    );
+    tracked.with_burn_ledger = true;
+    let context = setup_owed_burn(program_test, &tracked).await;
+
+    let settle = settle_burn(
+        &tracked.program_id,
+        &tracked.source,
+        &tracked.mint_address,
+        &tracked.wallet.pubkey(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[settle.clone()],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &tracked.wallet],
+        context.last_blockhash,
+    );
+    let return_data = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap()
+        .simulation_details
+        .unwrap()
+        .return_data
+        .map(|return_data| return_data.data);
+    assert_eq!(
+        return_data,
+        Some(TokenInstruction::Burn { amount: 3 }.pack())
+    );
+
+    // the token account must be the owner's
+    let other_owner = Keypair::new();
+    process_transaction(
+        &context,
+        &[initialize_burn_ledger(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &other_owner.pubkey(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[settle_burn(
+                &tracked.program_id,
+                &tracked.source,
+                &tracked.mint_address,
+                &other_owner.pubkey(),
+            )],
+            &[&other_owner],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    process_transaction(&context, &[settle], &[&tracked.wallet])
+        .await
+        .unwrap();
+    assert_eq!(tracked.owed_burn(&context).await, 0);
+}
+
+#[tokio::test]
+async fn execute_checks_paginated_allowlist() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    // the recipient falls on the page after the wallet's
+    let wallet_page = get_allowlist_page(&tracked.wallet.pubkey());
+    let recipient = Pubkey::new_from_array([wallet_page.wrapping_add(1); 32]);
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient, 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient;
+    tracked.with_allowlist_pages = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            access_mode: AccessMode::Allowlist,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let allow = |owner: &Pubkey| {
+        add_allowlisted_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            owner,
+            0,
+        )
+    };
+    let not_allowlisted = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::OwnerNotAllowlisted as u32),
+    );
+
+    // neither page exists yet
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        not_allowlisted
+    );
+
+    process_transaction(
+        &context,
+        &[allow(&tracked.wallet.pubkey())],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        not_allowlisted
+    );
+
+    process_transaction(&context, &[allow(&recipient)], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+
+    // each owner is on its own page
+    for (owner, other) in [
+        (tracked.wallet.pubkey(), recipient),
+        (recipient, tracked.wallet.pubkey()),
+    ] {
+        let page = context
+            .banks_client
+            .get_account(get_allowlist_address(
+                &tracked.mint_address,
+                get_allowlist_page(&owner),
+                &tracked.program_id,
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(AllowlistPage::unpack(&page.data).unwrap(), vec![(owner, 0)]);
+        assert!(!AllowlistPage::is_allowed(&page.data, &other, 0).unwrap());
+    }
+
+    process_transaction(
+        &context,
+        &[remove_allowlisted_owner(
+            &tracked.program_id,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &recipient,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(4)], &[])
+            .await
+            .unwrap_err(),
+        not_allowlisted
+    );
+}
+
+#[tokio::test]
+async fn execute_with_expiring_allowlist_entries() {
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_allowlist_pages = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            access_mode: AccessMode::Allowlist,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let now = clock.unix_timestamp;
+    let allow = |expires_at| {
+        add_allowlisted_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &tracked.wallet.pubkey(),
+            expires_at,
+        )
+    };
+
+    // the entry hasn't expired yet
+    process_transaction(&context, &[allow(now + 100)], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    // the expired entry is treated as absent
+    clock.unix_timestamp = now + 100;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerNotAllowlisted as u32)
+        )
+    );
+
+    // an entry added again without expiry never expires
+    process_transaction(&context, &[allow(0)], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    clock.unix_timestamp = i64::MAX;
+    context.set_sysvar(&clock);
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+}
+
+#[tokio::test]
+async fn execute_with_access_modes() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    // the recipient falls on the page after the wallet's
+    let wallet_page = get_allowlist_page(&tracked.wallet.pubkey());
+    let recipient = Pubkey::new_from_array([wallet_page.wrapping_add(1); 32]);
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient, 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient;
+    tracked.with_allowlist_pages = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let set_access_mode = |access_mode| {
+        tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            access_mode,
+            ..MintConfig::default()
+        })
+    };
+
+    // no restriction by default while the lists are empty
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    process_transaction(
+        &context,
+        &[freeze_owner(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.source,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    for owner in [tracked.wallet.pubkey(), recipient] {
+        process_transaction(
+            &context,
+            &[add_allowlisted_owner(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &owner,
+                0,
+            )],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+    }
+    // the wallet is now on both lists, only the one of the mode applies
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerBlocklisted as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[set_access_mode(AccessMode::Allowlist)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+
+    process_transaction(
+        &context,
+        &[remove_allowlisted_owner(
+            &tracked.program_id,
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &recipient,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(4)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::OwnerNotAllowlisted as u32)
+        )
+    );
+
+    // neither list applies
+    process_transaction(
+        &context,
+        &[set_access_mode(AccessMode::None)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(5)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 9);
+}
+
+#[tokio::test]
+async fn execute_rejects_program_owned_destinations() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let mint_config_address = get_mint_config_address(&tracked.mint_address, &tracked.program_id);
+    let program_owned_account = Pubkey::new_unique();
+    program_test.add_account(
+        program_owned_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &mint_config_address, 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        reject_program_destinations: true,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    let normal_destination = tracked.destination;
+    tracked.destination = program_owned_account;
+    tracked.destination_owner = mint_config_address;
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::DestinationOwnedByProgram as u32)
+        )
+    );
+
+    // accepted once the check is disabled
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            reject_program_destinations: false,
+            ..config
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(3)], &[])
+        .await
+        .unwrap();
+    tracked.destination = normal_destination;
+    tracked.destination_owner = tracked.wallet.pubkey();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 4);
+}
+
+#[tokio::test]
+async fn execute_without_transfer_tracking() {
+    let config = |tracked: &TrackedMint, skip_transfer_tracking| MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        skip_transfer_tracking,
+        ..MintConfig::default()
+    };
+
+    // a passed transfer account is left untouched
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config(&tracked, true))],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(5)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 0);
+
+    // the transfer account is required while transfers are tracked
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.without_transfer_account = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(5)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config(&tracked, true))],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(6)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 0);
+}
+
+/// Instruction of the ed25519 program verifying a transfer ticket signed by
+/// `signer`
+fn ticket_instruction(signer: &Keypair, ticket: &TransferTicket) -> Instruction {
+    let message = ticket.pack();
+    let signature = signer.sign_message(&message);
+    solana_ed25519_program::new_ed25519_instruction_with_signature(
+        &message,
+        signature.as_ref().try_into().unwrap(),
+        &signer.pubkey().to_bytes(),
+    )
+}
+
+#[tokio::test]
+async fn execute_requires_transfer_ticket() {
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_instructions_sysvar = true;
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let ticket_signer = Keypair::new();
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        ticket_signer: ticket_signer.pubkey(),
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let ticket = |amount, nonce| TransferTicket {
+        source_owner: tracked.wallet.pubkey(),
+        mint: tracked.mint_address,
+        amount,
+        nonce,
+    };
+    let missing_ticket = TransactionError::InstructionError(
+        1,
+        InstructionError::Custom(ExampleError::MissingTransferTicket as u32),
+    );
+
+    // no ticket
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(5)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::MissingTransferTicket as u32)
+        )
+    );
+    // signed by another signer, or for another amount
+    for instruction in [
+        ticket_instruction(&Keypair::new(), &ticket(5, 1)),
+        ticket_instruction(&ticket_signer, &ticket(6, 1)),
+    ] {
+        assert_eq!(
+            process_transaction(&context, &[instruction, tracked.execute(5)], &[])
+                .await
+                .unwrap_err(),
+            missing_ticket
+        );
+    }
+    // the ed25519 program rejects an invalid signature
+    let mut forged = ticket_instruction(&ticket_signer, &ticket(5, 1));
+    let last = forged.data.len() - 1;
+    forged.data[last] ^= 1;
+    assert!(
+        process_transaction(&context, &[forged, tracked.execute(5)], &[])
+            .await
+            .is_err()
+    );
+
+    process_transaction(
+        &context,
+        &[
+            ticket_instruction(&ticket_signer, &ticket(5, 1)),
+            tracked.execute(5),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.last_ticket_nonce, 1);
+    assert_eq!(transfer_account.transfered, 5);
+
+    // a used nonce can't be replayed
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[
+                ticket_instruction(&ticket_signer, &ticket(5, 1)),
+                tracked.execute(5),
+            ],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        missing_ticket
+    );
+    process_transaction(
+        &context,
+        &[
+            ticket_instruction(&ticket_signer, &ticket(5, 3)),
+            tracked.execute(5),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        tracked.transfer_account(&context).await.last_ticket_nonce,
+        3
+    );
+}
+
+#[tokio::test]
+async fn execute_rejects_transfer_ticket_of_another_mint() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_instructions_sysvar = true;
+    let ticket_signer = Keypair::new();
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        ticket_signer: ticket_signer.pubkey(),
+        ..MintConfig::default()
+    };
+
+    // the wallet also holds another mint requiring tickets of the same
+    // signer. Its validation account and config are written by hand, since
+    // the instructions creating them only accept one mint.
+    let other = TrackedMint {
+        program_id: tracked.program_id,
+        mint_address: Pubkey::new_unique(),
+        mint_authority: tracked.mint_authority.insecure_clone(),
+        wallet: tracked.wallet.insecure_clone(),
+        source: Pubkey::new_unique(),
+        destination: Pubkey::new_unique(),
+        destination_owner: tracked.wallet.pubkey(),
+        with_clock_sysvar: true,
+        notify_program: None,
+        fee_categories: vec![],
+        with_destination_transfer_account: false,
+        with_burn_ledger: false,
+        with_vesting_schedule: false,
+        with_allowlist_pages: false,
+        filler_accounts: vec![],
+        without_transfer_account: false,
+        with_instructions_sysvar: true,
+        governance_program: None,
+        with_receive_only: false,
+        with_transfer_root: false,
+    };
+    let other_mint = Mint {
+        mint_authority: COption::Some(other.mint_authority.pubkey()),
+        supply: 1_000,
+        decimals: 2,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut other_config = vec![0; MintConfig::LEN];
+    config.pack(&mut other_config);
+    for (address, data, owner) in [
+        (
+            other.mint_address,
+            mint_data(other_mint, &other.program_id),
+            spl_token_2022::id(),
+        ),
+        (
+            other.source,
+            token_account_data(&other.mint_address, &other.wallet.pubkey(), 1_000, true),
+            spl_token_2022::id(),
+        ),
+        (
+            other.destination,
+            token_account_data(&other.mint_address, &other.wallet.pubkey(), 0, true),
+            spl_token_2022::id(),
+        ),
+        (
+            get_extra_account_metas_address(&other.mint_address, &other.program_id),
+            example_data(&other.init_extra_account_metas()).unwrap(),
+            other.program_id,
+        ),
+        (
+            get_mint_config_address(&other.mint_address, &other.program_id),
+            other_config,
+            other.program_id,
+        ),
+    ] {
+        program_test.add_account(
+            address,
+            SolanaAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner,
+                ..SolanaAccount::default()
+            },
+        );
+    }
+
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    // the transfer account is otherwise shared by both mints
+    #[cfg(feature = "per-mint-tracking")]
+    process_transaction(
+        &context,
+        &[initialize_transfer_account(
+            &other.program_id,
+            &other.wallet.pubkey(),
+            &other.mint_address,
+        )],
+        &[&other.wallet],
+    )
+    .await
+    .unwrap();
+    let ticket = |mint_address, nonce| TransferTicket {
+        source_owner: tracked.wallet.pubkey(),
+        mint: mint_address,
+        amount: 5,
+        nonce,
+    };
+
+    let used_ticket = ticket_instruction(&ticket_signer, &ticket(tracked.mint_address, 1));
+    process_transaction(&context, &[used_ticket.clone(), tracked.execute(5)], &[])
+        .await
+        .unwrap();
+
+    // the ticket can't be replayed on the other mint, even when its transfer
+    // account hasn't used the nonce
+    assert_eq!(
+        process_transaction(&context, &[used_ticket, other.execute(5)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(ExampleError::MissingTransferTicket as u32)
+        )
+    );
+    process_transaction(
+        &context,
+        &[
+            ticket_instruction(&ticket_signer, &ticket(other.mint_address, 2)),
+            other.execute(5),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(other.transfer_account(&context).await.last_ticket_nonce, 2);
+}
+
+#[tokio::test]
+async fn execute_checks_transfer_hook_program() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // the mint's hook is the program
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    let mint = Mint {
+        mint_authority: COption::Some(tracked.mint_authority.pubkey()),
+        supply: 1_000,
+        decimals: 2,
+        is_initialized: true,
+        freeze_authority: COption::Some(tracked.mint_authority.pubkey()),
+    };
+    let mut mint_without_hook = vec![0; Mint::LEN];
+    Mint::pack(mint, &mut mint_without_hook).unwrap();
+    for (amount, data) in [
+        (2, mint_data(mint, &Pubkey::new_unique())),
+        (3, mint_without_hook),
+    ] {
+        context.set_account(
+            &tracked.mint_address,
+            &SolanaAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner: spl_token_2022::id(),
+                ..SolanaAccount::default()
+            }
+            .into(),
+        );
+        assert_eq!(
+            process_transaction(&context, &[tracked.execute(amount)], &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(ExampleError::TransferHookProgramMismatch as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn success_read_totals() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    let other_owners = [Keypair::new(), Keypair::new()];
+    for owner in &other_owners {
+        add_funded_wallet(&mut program_test, &owner.pubkey());
+    }
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    for owner in &other_owners {
+        process_transaction(
+            &context,
+            &[initialize_transfer_account(
+                &tracked.program_id,
+                &owner.pubkey(),
+                &tracked.mint_address,
+            )],
+            &[owner],
+        )
+        .await
+        .unwrap();
+    }
+    process_transaction(&context, &[tracked.execute(5), tracked.execute(7)], &[])
+        .await
+        .unwrap();
+
+    let transfer_accounts = [&other_owners[0], &tracked.wallet, &other_owners[1]].map(|owner| {
+        get_transfer_account_address(&owner.pubkey(), &tracked.mint_address, &tracked.program_id)
+    });
+    let return_data = simulate_return_data(
+        &context,
+        &[read_totals(&tracked.program_id, &transfer_accounts)],
+    )
+    .await
+    .unwrap();
+    let totals: Vec<u64> = return_data
+        .chunks_exact(8)
+        .map(|total| u64::from_le_bytes(total.try_into().unwrap()))
+        .collect();
+    assert_eq!(totals, [0, 12, 0]);
+
+    // only transfer accounts of the program can be read
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[read_totals(
+                &tracked.program_id,
+                &[transfer_accounts[1], tracked.source]
+            )],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn success_get_mint_config() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_pct_bps: 2_500,
+        cooldown_seconds: 60,
+        owner_checked_metas: 0b10,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let return_data = simulate_return_data(
+        &context,
+        &[get_mint_config(&tracked.program_id, &tracked.mint_address)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(MintConfig::unpack(&return_data).unwrap(), config);
+
+    // a config at an older version is returned at the current one
+    let v1_len = MintConfig::layout_len(1).unwrap();
+    let v1_config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_pct_bps: 2_500,
+        ..MintConfig::default()
+    };
+    let mut data = vec![0; MintConfig::LEN];
+    v1_config.pack(&mut data);
+    data[0] = 1;
+    data.truncate(v1_len);
+    context.set_account(
+        &get_mint_config_address(&tracked.mint_address, &tracked.program_id),
+        &SolanaAccount {
+            lamports: 1_000_000_000,
+            data,
+            owner: tracked.program_id,
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+    let return_data = simulate_return_data(
+        &context,
+        &[get_mint_config(&tracked.program_id, &tracked.mint_address)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(return_data.len(), MintConfig::LEN);
+    assert_eq!(MintConfig::unpack(&return_data).unwrap(), v1_config);
+
+    // mints without a config have none to report
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[get_mint_config(&tracked.program_id, &Pubkey::new_unique())],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::UninitializedAccount)
+    );
+}
+
+#[tokio::test]
+async fn execute_with_non_transferring_destination() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    program_test.add_account(
+        tracked.destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 0, false),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // strict by default
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferHookError::ProgramCalledOutsideOfTransfer as u32)
+        )
+    );
+
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        source_transferring_only: true,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 2);
+}
+
+#[tokio::test]
+async fn success_replace_extra_account_meta_list() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let two_metas = [transfer_account_extra_meta(), mint_config_extra_meta()];
+    process_transaction(
+        &context,
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &extra_account_metas_address,
+                rent.minimum_balance(ExtraAccountMetaList::size_of(two_metas.len()).unwrap()),
+            ),
+            initialize_extra_account_meta_list(
+                &tracked.program_id,
+                &extra_account_metas_address,
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                &two_metas,
+            ),
+        ],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    let four_metas = [
+        transfer_account_extra_meta(),
+        mint_config_extra_meta(),
+        blocklist_extra_meta(),
+        exempt_owners_extra_meta(),
+    ];
+    // the new list grows the account, then shrinks it
+    for metas in [&four_metas[..], &four_metas[3..]] {
+        process_transaction(
+            &context,
+            &[replace_extra_account_meta_list(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+                metas,
+            )],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap();
+
+        let account = context
+            .banks_client
+            .get_account(extra_account_metas_address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.data, example_data(metas).unwrap());
+        assert_eq!(account.lamports, rent.minimum_balance(account.data.len()));
+    }
+
+    // only the mint authority can replace the list
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[replace_extra_account_meta_list(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+                &two_metas,
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferHookError::IncorrectMintAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn verify_matching_extra_account_meta_list() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    assert_eq!(
+        simulate_return_data(
+            &context,
+            &[verify_extra_account_meta_list(
+                &tracked.program_id,
+                &tracked.mint_address
+            )],
+        )
+        .await,
+        Some(vec![meta_list_status::MATCHING])
+    );
+}
+
+#[tokio::test]
+async fn repair_drifted_extra_account_meta_list() {
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.without_transfer_account = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let verify = verify_extra_account_meta_list(&tracked.program_id, &tracked.mint_address);
+    let repair = |authority: &Pubkey| {
+        repair_extra_account_meta_list(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            authority,
+        )
+    };
+
+    // the transfer account meta is missing
+    assert_eq!(
+        simulate_return_data(&context, &[verify.clone()]).await,
+        Some(vec![0])
+    );
+
+    // only the mint authority can repair the list
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[repair(&wrong_authority.pubkey())],
+            &[&wrong_authority]
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(TransferHookError::IncorrectMintAuthority as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[repair(&tracked.mint_authority.pubkey())],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        simulate_return_data(&context, &[verify]).await,
+        Some(vec![meta_list_status::MATCHING])
+    );
+
+    // the other metas keep their order after the transfer account meta
+    tracked.without_transfer_account = false;
+    let extra_account_metas_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    let account = context
+        .banks_client
+        .get_account(extra_account_metas_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data,
+        example_data(&tracked.init_extra_account_metas()).unwrap()
+    );
+    let rent = context.banks_client.get_rent().await.unwrap();
+    assert_eq!(account.lamports, rent.minimum_balance(account.data.len()));
+
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1);
+}
+
+#[tokio::test]
+async fn execute_with_same_owner_transfers() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Pubkey::new_unique();
+    let other_destination = Pubkey::new_unique();
+    program_test.add_account(
+        other_destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient, 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    tracked.destination_owner = recipient;
+    let other_owner_metas = tracked.extra_account_metas();
+    tracked.destination_owner = tracked.wallet.pubkey();
+    let execute_to_other_owner = |amount| {
+        execute_with_extra_account_metas(
+            &tracked.program_id,
+            &tracked.source,
+            &tracked.mint_address,
+            &other_destination,
+            &tracked.wallet.pubkey(),
+            &get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id),
+            &other_owner_metas,
+            amount,
+        )
+    };
+
+    // counted by default
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    process_transaction(&context, &[execute_to_other_owner(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_pct_bps: 1_000,
+        skip_same_owner_transfers: true,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // same-owner transfers skip tracking and the limits
+    process_transaction(&context, &[tracked.execute(500)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+    assert_eq!(
+        process_transaction(&context, &[execute_to_other_owner(500)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+        )
+    );
+    process_transaction(&context, &[execute_to_other_owner(4)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 7);
+}
+
+#[tokio::test]
+async fn execute_with_amount_consistency_check() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    program_test.add_account(
+        tracked.destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 100, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // the amount is trusted by default
+    process_transaction(&context, &[tracked.execute(101)], &[])
+        .await
+        .unwrap();
+
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        check_amount_consistency: true,
+        ..MintConfig::default()
+    };
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&config)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the destination holds 100 tokens once they moved
+    process_transaction(&context, &[tracked.execute(100)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(102)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::InconsistentTransferAmount as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(103)]).await,
+        Some(FailedCheck::AmountConsistency)
+    );
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 201);
+}
+
+#[tokio::test]
+async fn migrate_v1_mint_config() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // overwrite the config with one of the version 1 layout
+    let v1_len = MintConfig::layout_len(1).unwrap();
+    let config = MintConfig {
+        authority: tracked.mint_authority.pubkey(),
+        max_pct_bps: 1_000,
+        zero_amount_mode: ZeroAmountMode::Reject,
+        limit_exempt_amount: 10,
+        ..MintConfig::default()
+    };
+    let mut data = vec![0; MintConfig::LEN];
+    config.pack(&mut data);
+    data[0] = 1;
+    data.truncate(v1_len);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mint_config_address = get_mint_config_address(&tracked.mint_address, &tracked.program_id);
+    context.set_account(
+        &mint_config_address,
+        &SolanaAccount {
+            lamports: rent.minimum_balance(v1_len),
+            data,
+            owner: tracked.program_id,
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[migrate_mint_config(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[migrate_mint_config(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(mint_config_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), MintConfig::LEN);
+    assert_eq!(account.lamports, rent.minimum_balance(MintConfig::LEN));
+    // the version 1 fields are preserved, the appended ones left to defaults
+    assert_eq!(MintConfig::unpack(&account.data).unwrap(), config);
+
+    // the config is already migrated
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[migrate_mint_config(
+                &tracked.program_id,
+                &tracked.mint_authority.pubkey(),
+                &tracked.mint_address,
+                &tracked.mint_authority.pubkey(),
+            )],
+            &[&tracked.mint_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(200)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::TransferExceedsBalancePercentage as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn migrate_outdated_transfer_account() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    // overwrite the transfer account with one of the original layout, the
+    // owner and the transferred amount
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let mut data = vec![0; TransferAccount::LEN];
+    TransferAccount {
+        owner: tracked.wallet.pubkey(),
+        transfered: 3,
+        ..TransferAccount::default()
+    }
+    .pack(&mut data);
+    data.truncate(32 + 8);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    context.set_account(
+        &transfer_account_address,
+        &SolanaAccount {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: tracked.program_id,
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+    let outdated = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::OutdatedTransferAccount as u32),
+    );
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        outdated
+    );
+    // resetting it would drop what it tracks
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[reinitialize_transfer_account(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+            )],
+            &[&tracked.wallet],
+        )
+        .await
+        .unwrap_err(),
+        outdated
+    );
+
+    let other = Pubkey::new_unique();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[{
+                let mut instruction = migrate_transfer_account(
+                    &tracked.program_id,
+                    &context.payer.pubkey(),
+                    &other,
+                    &tracked.mint_address,
+                );
+                instruction.accounts[1].pubkey = transfer_account_address;
+                instruction
+            }],
+            &[],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    let migrate = migrate_transfer_account(
+        &tracked.program_id,
+        &context.payer.pubkey(),
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+    );
+    process_transaction(&context, &[migrate.clone()], &[])
+        .await
+        .unwrap();
+    let account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), TransferAccount::LEN);
+    assert_eq!(account.lamports, rent.minimum_balance(TransferAccount::LEN));
+    // the tracked amount is preserved, and the account is held since the
+    // migration
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    assert_eq!(
+        TransferAccount::unpack(&account.data).unwrap(),
+        TransferAccount {
+            owner: tracked.wallet.pubkey(),
+            transfered: 3,
+            first_received_timestamp: clock.unix_timestamp,
+            first_received_slot: clock.slot,
+            ..TransferAccount::default()
+        }
+    );
+
+    // the account is already migrated
+    context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        process_transaction(&context, &[migrate], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 5);
+}
+
+#[tokio::test]
+async fn execute_with_holding_period() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Keypair::new();
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    add_funded_wallet(&mut program_test, &recipient.pubkey());
+    tracked.with_destination_transfer_account = true;
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[
+            initialize_transfer_account(
+                &tracked.program_id,
+                &recipient.pubkey(),
+                &tracked.mint_address,
+            ),
+            tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                holding_period_seconds: 100,
+                ..MintConfig::default()
+            }),
+        ],
+        &[&recipient, &tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the recipient sends back to the wallet
+    let outbound = TrackedMint {
+        program_id: tracked.program_id,
+        mint_address: tracked.mint_address,
+        mint_authority: tracked.mint_authority.insecure_clone(),
+        wallet: recipient.insecure_clone(),
+        source: recipient_account,
+        destination: tracked.destination,
+        destination_owner: tracked.wallet.pubkey(),
+        with_clock_sysvar: true,
+        notify_program: None,
+        fee_categories: vec![],
+        with_destination_transfer_account: true,
+        with_burn_ledger: false,
+        with_vesting_schedule: false,
+        with_allowlist_pages: false,
+        filler_accounts: vec![],
+        without_transfer_account: false,
+        with_instructions_sysvar: false,
+        governance_program: None,
+        with_receive_only: false,
+        with_transfer_root: false,
+    };
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient.pubkey();
+
+    // the tokens minted to the wallet count as received at its initialization,
+    // as do those of the recipient
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let initialized_timestamp = clock.unix_timestamp;
+    for tracked in [&tracked, &outbound] {
+        assert_eq!(
+            tracked
+                .transfer_account(&context)
+                .await
+                .first_received_timestamp,
+            initialized_timestamp
+        );
+    }
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(10)]).await,
+        Some(FailedCheck::HoldingPeriod)
+    );
+
+    clock.unix_timestamp = initialized_timestamp + 99;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::HoldingPeriodActive as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(3)]).await,
+        Some(FailedCheck::HoldingPeriod)
+    );
+
+    // a dust transfer to the recipient doesn't restart its holding period
+    clock.unix_timestamp = initialized_timestamp + 100;
+    context.set_sysvar(&clock);
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    let recipient_account = outbound.transfer_account(&context).await;
+    assert_eq!(recipient_account.received, 1);
+    assert_eq!(
+        recipient_account.first_received_timestamp,
+        initialized_timestamp
+    );
+    process_transaction(&context, &[outbound.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(outbound.transfer_account(&context).await.transfered, 3);
+
+    // nor does the transfer back restart the one of the wallet
+    assert_eq!(
+        tracked
+            .transfer_account(&context)
+            .await
+            .first_received_timestamp,
+        initialized_timestamp
+    );
+    process_transaction(&context, &[tracked.execute(12)], &[])
+        .await
+        .unwrap();
+
+    // without a known receive, the holding period is never over
+    let transfer_account_address = get_transfer_account_address(
+        &tracked.wallet.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let mut account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    TransferAccount {
+        first_received_timestamp: 0,
+        ..TransferAccount::unpack(&account.data).unwrap()
+    }
+    .pack(&mut account.data);
+    context.set_account(&transfer_account_address, &account.into());
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(13)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::HoldingPeriodActive as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn execute_with_min_account_age() {
+    let (mut program_test, mut tracked) = setup_tracked_mint(1_000);
+    let recipient = Keypair::new();
+    let recipient_account = Pubkey::new_unique();
+    program_test.add_account(
+        recipient_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &recipient.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    add_funded_wallet(&mut program_test, &recipient.pubkey());
+    tracked.with_destination_transfer_account = true;
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    process_transaction(
+        &context,
+        &[
+            initialize_transfer_account(
+                &tracked.program_id,
+                &recipient.pubkey(),
+                &tracked.mint_address,
+            ),
+            tracked.update_mint_config(&MintConfig {
+                authority: tracked.mint_authority.pubkey(),
+                min_account_age_slots: 50,
+                ..MintConfig::default()
+            }),
+        ],
+        &[&recipient, &tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the recipient sends back to the wallet
+    let outbound = TrackedMint {
+        program_id: tracked.program_id,
+        mint_address: tracked.mint_address,
+        mint_authority: tracked.mint_authority.insecure_clone(),
+        wallet: recipient.insecure_clone(),
+        source: recipient_account,
+        destination: tracked.destination,
+        destination_owner: tracked.wallet.pubkey(),
+        with_clock_sysvar: true,
+        notify_program: None,
+        fee_categories: vec![],
+        with_destination_transfer_account: true,
+        with_burn_ledger: false,
+        with_vesting_schedule: false,
+        with_allowlist_pages: false,
+        filler_accounts: vec![],
+        without_transfer_account: false,
+        with_instructions_sysvar: false,
+        governance_program: None,
+        with_receive_only: false,
+        with_transfer_root: false,
+    };
+    tracked.destination = recipient_account;
+    tracked.destination_owner = recipient.pubkey();
+
+    // the wallet and the recipient never received tokens, so their age counts
+    // from their initialization
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let initialized_slot = clock.slot;
+    for tracked in [&tracked, &outbound] {
+        assert_eq!(
+            tracked.transfer_account(&context).await.first_received_slot,
+            initialized_slot
+        );
+    }
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(10)]).await,
+        Some(FailedCheck::AccountAge)
+    );
+
+    clock.slot = initialized_slot + 49;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::AccountTooYoung as u32)
+        )
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(3)]).await,
+        Some(FailedCheck::AccountAge)
+    );
+
+    // a dust transfer from a third party doesn't reset the age of the
+    // recipient
+    clock.slot = initialized_slot + 50;
+    context.set_sysvar(&clock);
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+    let recipient_account = outbound.transfer_account(&context).await;
+    assert_eq!(recipient_account.received, 1);
+    assert_eq!(recipient_account.first_received_slot, initialized_slot);
+    process_transaction(&context, &[outbound.execute(3)], &[])
+        .await
+        .unwrap();
+    assert_eq!(outbound.transfer_account(&context).await.transfered, 3);
+
+    // an account without a known receive is always too young
+    let transfer_account_address = get_transfer_account_address(
+        &recipient.pubkey(),
+        &tracked.mint_address,
+        &tracked.program_id,
+    );
+    let mut account = context
+        .banks_client
+        .get_account(transfer_account_address)
+        .await
+        .unwrap()
+        .unwrap();
+    TransferAccount {
+        first_received_slot: 0,
+        ..TransferAccount::unpack(&account.data).unwrap()
+    }
+    .pack(&mut account.data);
+    context.set_account(&transfer_account_address, &account.into());
+    clock.slot = initialized_slot + 1_000;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        process_transaction(&context, &[outbound.execute(4)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::AccountTooYoung as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn execute_with_vesting_schedule() {
+    let (program_test, mut tracked) = setup_tracked_mint(1_000);
+    tracked.with_vesting_schedule = true;
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let start_timestamp = clock.unix_timestamp;
+    let schedule = VestingSchedule {
+        total: 1_500,
+        start_timestamp,
+        cliff_timestamp: start_timestamp + 100,
+        duration_seconds: 1_000,
+    };
+
+    // no schedule, nothing locked
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    // only the config authority sets schedules
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[set_vesting_schedule(
+                &tracked.program_id,
+                &context.payer.pubkey(),
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+                &schedule,
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+    process_transaction(
+        &context,
+        &[set_vesting_schedule(
+            &tracked.program_id,
+            &context.payer.pubkey(),
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            &schedule,
+        )],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the source keeps 1_000 tokens after each transfer, which must cover
+    // the amount still locked
+    for (amount, elapsed, locked) in [
+        // before the cliff, all of it
+        (2, 50, Some(1_500)),
+        (3, 200, Some(1_200)),
+        (4, 600, None),
+        // fully vested
+        (5, 1_000, None),
+    ] {
+        clock.unix_timestamp = start_timestamp + elapsed;
+        context.set_sysvar(&clock);
+        match locked {
+            Some(locked) => {
+                assert_eq!(schedule.locked_amount(clock.unix_timestamp), locked);
+                assert_eq!(
+                    simulate_failed_check(&context, &[tracked.execute(amount)]).await,
+                    Some(FailedCheck::Vesting)
+                );
+                assert_eq!(
+                    process_transaction(&context, &[tracked.execute(amount)], &[])
+                        .await
+                        .unwrap_err(),
+                    TransactionError::InstructionError(
+                        0,
+                        InstructionError::Custom(ExampleError::TransferExceedsVestedAmount as u32)
+                    ),
+                    "{}s elapsed",
+                    elapsed
+                );
+            }
+            None => process_transaction(&context, &[tracked.execute(amount)], &[])
+                .await
+                .unwrap(),
+        }
+    }
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 10);
+}
+
+#[tokio::test]
+async fn execute_with_allowance() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let allowance = |amount, top_up| {
+        set_allowance(
+            &tracked.program_id,
+            &tracked.wallet.pubkey(),
+            &tracked.mint_address,
+            &tracked.mint_authority.pubkey(),
+            amount,
+            top_up,
+        )
+    };
+    let allowance_exceeded = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ExampleError::AllowanceExceeded as u32),
+    );
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            enforce_allowances: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1)], &[])
+            .await
+            .unwrap_err(),
+        allowance_exceeded
+    );
+
+    let wrong_authority = Keypair::new();
+    assert_eq!(
+        process_transaction(
+            &context,
+            &[set_allowance(
+                &tracked.program_id,
+                &tracked.wallet.pubkey(),
+                &tracked.mint_address,
+                &wrong_authority.pubkey(),
+                10,
+                false,
+            )],
+            &[&wrong_authority],
+        )
+        .await
+        .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::IncorrectConfigAuthority as u32)
+        )
+    );
+
+    process_transaction(
+        &context,
+        &[allowance(10, false)],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(4)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        tracked.transfer_account(&context).await.allowance_remaining,
+        6
+    );
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(7)], &[])
+            .await
+            .unwrap_err(),
+        allowance_exceeded
+    );
+    assert_eq!(
+        simulate_failed_check(&context, &[tracked.execute(8)]).await,
+        Some(FailedCheck::Allowance)
+    );
+
+    process_transaction(&context, &[allowance(5, true)], &[&tracked.mint_authority])
+        .await
+        .unwrap();
+    process_transaction(&context, &[tracked.execute(11)], &[])
+        .await
+        .unwrap();
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.allowance_remaining, 0);
+    assert_eq!(transfer_account.transfered, 15);
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(2)], &[])
+            .await
+            .unwrap_err(),
+        allowance_exceeded
+    );
+}
+
+#[tokio::test]
+async fn execute_with_active_mint_check() {
+    let (program_test, tracked) = setup_tracked_mint(1_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let mint = Mint {
+        mint_authority: COption::Some(tracked.mint_authority.pubkey()),
+        supply: 1_000,
+        decimals: 2,
+        is_initialized: true,
+        freeze_authority: COption::Some(tracked.mint_authority.pubkey()),
+    };
+    let set_mint = |context: &mut ProgramTestContext, data: Vec<u8>| {
+        context.set_account(
+            &tracked.mint_address,
+            &SolanaAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner: spl_token_2022::id(),
+                ..SolanaAccount::default()
+            }
+            .into(),
+        );
+    };
+    let revoked_mint = mint_data(
+        Mint {
+            mint_authority: COption::None,
+            ..mint
+        },
+        &tracked.program_id,
+    );
+    let paused_mint = mint_data_with_extensions(
+        mint,
+        &tracked.program_id,
+        &[ExtensionType::Pausable],
+        |state| {
+            let extension = state.init_extension::<PausableConfig>(true).unwrap();
+            extension.paused = true.into();
+        },
+    );
+
+    // the mint state is ignored by default
+    set_mint(&mut context, revoked_mint.clone());
+    process_transaction(&context, &[tracked.execute(1)], &[])
+        .await
+        .unwrap();
+
+    set_mint(&mut context, mint_data(mint, &tracked.program_id));
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            require_active_mint: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(2)], &[])
+        .await
+        .unwrap();
+
+    for (amount, data) in [(3, revoked_mint), (4, paused_mint)] {
+        set_mint(&mut context, data);
+        assert_eq!(
+            process_transaction(&context, &[tracked.execute(amount)], &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(ExampleError::MintHalted as u32)
+            )
+        );
+        assert_eq!(
+            simulate_failed_check(&context, &[tracked.execute(amount + 10)]).await,
+            Some(FailedCheck::MintState)
+        );
+    }
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 3);
+}
+
+#[tokio::test]
+async fn execute_with_amount_consistency_check_net_of_transfer_fee() {
+    let (mut program_test, tracked) = setup_tracked_mint(10_000);
+    program_test.add_account(
+        tracked.destination,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 990, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let mint = Mint {
+        mint_authority: COption::Some(tracked.mint_authority.pubkey()),
+        supply: 10_990,
+        decimals: 2,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    // 1% fee, capped at 15
+    let transfer_fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: 15.into(),
+        transfer_fee_basis_points: 100.into(),
+    };
+    context.set_account(
+        &tracked.mint_address,
+        &SolanaAccount {
+            lamports: 1_000_000_000,
+            data: mint_data_with_extensions(
+                mint,
+                &tracked.program_id,
+                &[ExtensionType::TransferFeeConfig],
+                |state| {
+                    let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                    extension.older_transfer_fee = transfer_fee;
+                    extension.newer_transfer_fee = transfer_fee;
+                },
+            ),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            check_amount_consistency: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+
+    // the destination holds the 990 tokens it was credited, the fee of 10
+    // withheld
+    process_transaction(&context, &[tracked.execute(1_000)], &[])
+        .await
+        .unwrap();
+    // a fee of 11 would have left it 991
+    assert_eq!(
+        process_transaction(&context, &[tracked.execute(1_002)], &[])
+            .await
+            .unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ExampleError::InconsistentTransferAmount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn execute_net_of_transfer_fee() {
+    let (program_test, tracked) = setup_tracked_mint(10_000);
+    let mut context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+    let mint = Mint {
+        mint_authority: COption::Some(tracked.mint_authority.pubkey()),
+        supply: 10_000,
+        decimals: 2,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    // 1% fee, capped at 15
+    let transfer_fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: 15.into(),
+        transfer_fee_basis_points: 100.into(),
+    };
+    context.set_account(
+        &tracked.mint_address,
+        &SolanaAccount {
+            lamports: 1_000_000_000,
+            data: mint_data_with_extensions(
+                mint,
+                &tracked.program_id,
+                &[ExtensionType::TransferFeeConfig],
+                |state| {
+                    let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                    extension.older_transfer_fee = transfer_fee;
+                    extension.newer_transfer_fee = transfer_fee;
+                },
+            ),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        }
+        .into(),
+    );
+
+    // the gross amount is tracked by default
+    process_transaction(&context, &[tracked.execute(1_000)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1_000);
+
+    process_transaction(
+        &context,
+        &[tracked.update_mint_config(&MintConfig {
+            authority: tracked.mint_authority.pubkey(),
+            track_net_of_transfer_fee: true,
+            ..MintConfig::default()
+        })],
+        &[&tracked.mint_authority],
+    )
+    .await
+    .unwrap();
+    process_transaction(&context, &[tracked.execute(900)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 1_891);
+    // the fee is capped at its maximum
+    process_transaction(&context, &[tracked.execute(2_000)], &[])
+        .await
+        .unwrap();
+    let transfer_account = tracked.transfer_account(&context).await;
+    assert_eq!(transfer_account.transfered, 3_876);
+    assert_eq!(transfer_account.max_single_transfer, 1_985);
+}
+
+#[tokio::test]
+async fn execute_with_accounts_in_wrong_positions() {
+    let (mut program_test, tracked) = setup_tracked_mint(1_000);
+    // any authority is accepted from a source without a delegate, so give it
+    // one for the authority to be checked
+    let mut source_data =
+        token_account_data(&tracked.mint_address, &tracked.wallet.pubkey(), 1_000, true);
+    let mut state = StateWithExtensionsMut::<Account>::unpack(&mut source_data).unwrap();
+    state.base.delegate = COption::Some(Pubkey::new_unique());
+    state.base.delegated_amount = 1_000;
+    state.pack_base();
+    program_test.add_account(
+        tracked.source,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: source_data,
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let other_mint_account = Pubkey::new_unique();
+    program_test.add_account(
+        other_mint_account,
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data: token_account_data(&Pubkey::new_unique(), &tracked.wallet.pubkey(), 0, true),
+            owner: spl_token_2022::id(),
+            ..SolanaAccount::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+    tracked.initialize(&context).await;
+
+    let execute_with = |amount, position: usize, address: Pubkey| {
+        let mut instruction = tracked.execute(amount);
+        instruction.accounts[position].pubkey = address;
+        instruction
+    };
+    let validation_address =
+        get_extra_account_metas_address(&tracked.mint_address, &tracked.program_id);
+    for (amount, instruction, error) in [
+        // source and mint swapped
+        (
+            1,
+            {
+                let mut instruction = tracked.execute(1);
+                instruction.accounts.swap(0, 1);
+                instruction
+            },
+            ExampleError::InvalidMintAccount,
+        ),
+        (
+            2,
+            execute_with(2, 0, other_mint_account),
+            ExampleError::SourceAccountMismatch,
+        ),
+        (
+            3,
+            execute_with(3, 2, tracked.mint_address),
+            ExampleError::DestinationAccountMismatch,
+        ),
+        (
+            4,
+            execute_with(4, 2, other_mint_account),
+            ExampleError::DestinationAccountMismatch,
+        ),
+        // validation account and authority swapped
+        (
+            5,
+            {
+                let mut instruction = tracked.execute(5);
+                instruction.accounts.swap(3, 4);
+                instruction
+            },
+            ExampleError::UnexpectedTransferAuthority,
+        ),
+        (
+            6,
+            execute_with(6, 3, validation_address),
+            ExampleError::UnexpectedTransferAuthority,
+        ),
+    ] {
+        assert_eq!(
+            process_transaction(&context, &[instruction], &[])
+                .await
+                .unwrap_err(),
+            TransactionError::InstructionError(0, InstructionError::Custom(error as u32)),
+            "amount {}",
+            amount
+        );
+    }
+
+    process_transaction(&context, &[tracked.execute(7)], &[])
+        .await
+        .unwrap();
+    assert_eq!(tracked.transfer_account(&context).await.transfered, 7);
+}
@@ -13,7 +13,10 @@ import * as fs from 'fs';
 const INITIALIZE_TRANSFER_ACCOUNT_DISCRIMINATOR = 255;
 
 /**
- * Create an instruction to initialize a transfer account
+ * Create an instruction to initialize a transfer account funded by its owner.
+ * The mint only follows with the `per-mint-tracking` feature, and the mint
+ * config and creation count only with a separate payer, counted against the
+ * cap of the config on the transfer accounts created per payer.
  */
 function createInitializeTransferAccountInstruction(
     programId: PublicKey,